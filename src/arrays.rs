@@ -0,0 +1,511 @@
+use crate::diff::{floats_equal, Key, Path};
+use crate::minimize::get;
+use crate::paths::__to_value;
+use crate::{check_nesting, Config};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+type Comparator = dyn Fn(&Value, &Value) -> Ordering + Send + Sync + RefUnwindSafe;
+
+/// The order that the items of an array are expected to be in, checked by
+/// [`assert_json_sorted!`](crate::assert_json_sorted).
+///
+/// Items are ordered by the value at a key, or by the items themselves. By default numbers are
+/// compared by their value, strings by their bytes and booleans with `false` first. Values of
+/// other types, or of different types, can only be ordered with a custom comparator.
+///
+/// ```
+/// use serde_json_assert::{assert_json_sorted, SortedBy};
+/// use serde_json::json;
+///
+/// let response = json!({
+///     "posts": [
+///         { "id": 3, "title": "Zebras", "published": "2024-03-01" },
+///         { "id": 1, "title": "apples", "published": "2024-01-15" },
+///     ]
+/// });
+///
+/// assert_json_sorted!(response, ".posts", SortedBy::key(".published").descending());
+/// assert_json_sorted!(
+///     response,
+///     ".posts",
+///     SortedBy::key(".title").descending().compare_with(|a, b| {
+///         let key = |value: &serde_json::Value| value.as_str().unwrap_or("").to_lowercase();
+///         key(a).cmp(&key(b))
+///     })
+/// );
+/// ```
+#[derive(Clone)]
+pub struct SortedBy {
+    key: Path,
+    descending: bool,
+    compare: Option<Arc<Comparator>>,
+}
+
+impl SortedBy {
+    /// Orders the items by the value at `key`, relative to each item, such as `.user.name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` can't be parsed.
+    #[track_caller]
+    pub fn key(key: &str) -> Self {
+        Self {
+            key: key.parse().unwrap_or_else(|err| panic!("{}", err)),
+            descending: false,
+            compare: None,
+        }
+    }
+
+    /// Orders the items by their own values.
+    pub fn items() -> Self {
+        Self {
+            key: Path::Root,
+            descending: false,
+            compare: None,
+        }
+    }
+
+    /// Expects the smallest value first. This is the default.
+    pub fn ascending(mut self) -> Self {
+        self.descending = false;
+        self
+    }
+
+    /// Expects the largest value first.
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+
+    /// Compares values with `compare` instead of their natural order.
+    pub fn compare_with<F>(mut self, compare: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> Ordering + Send + Sync + RefUnwindSafe + 'static,
+    {
+        self.compare = Some(Arc::new(compare));
+        self
+    }
+
+    fn order(&self) -> &'static str {
+        if self.descending {
+            "descending"
+        } else {
+            "ascending"
+        }
+    }
+}
+
+impl fmt::Debug for SortedBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SortedBy")
+            .field("key", &self.key)
+            .field("descending", &self.descending)
+            .field("compare", &self.compare.as_ref().map(|_| "custom"))
+            .finish()
+    }
+}
+
+/// Checks that the items of the array at `path` are sorted, without panicking.
+///
+/// Returns a `Result` where the error is the message that would be passed to `panic!`, which
+/// shows the first pair of items that are out of order. See [`SortedBy`] for details.
+///
+/// # Panics
+///
+/// Panics if `path` can't be parsed, or if `actual` can't be serialized.
+#[track_caller]
+pub fn assert_json_sorted_no_panic<T>(
+    actual: &T,
+    path: &str,
+    sorted: &SortedBy,
+) -> Result<(), String>
+where
+    T: Serialize + ?Sized,
+{
+    let actual = __to_value(actual);
+    let path: Path = path.parse().unwrap_or_else(|err| panic!("{}", err));
+    let items = array_at(&actual, &path)?;
+
+    let keys = items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| key_at(item, &path, idx, &sorted.key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (idx, pair) in keys.windows(2).enumerate() {
+        let [(lhs_path, lhs), (rhs_path, rhs)] = pair else {
+            unreachable!()
+        };
+        let ordering = match &sorted.compare {
+            Some(compare) => compare(lhs, rhs),
+            None => natural_order(lhs, rhs).ok_or_else(|| {
+                format!(
+                    "json values at paths \"{}\" and \"{}\" can't be compared:\n    {}\n    {}",
+                    lhs_path, rhs_path, lhs, rhs
+                )
+            })?,
+        };
+        let ordering = if sorted.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        if ordering == Ordering::Greater {
+            let by = match &sorted.key {
+                Path::Root => String::new(),
+                key => format!(" by \"{}\"", key),
+            };
+            return Err(format!(
+                "json array at path \"{}\" is not sorted in {} order{}, at index {}:\n    {}: {}\n    {}: {}",
+                path, sorted.order(), by, idx + 1, lhs_path, lhs, rhs_path, rhs
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no two items of the array at `path` have equal values at `key`, without panicking.
+///
+/// `key` is relative to each item, such as `.id`. Use `$` to compare the items themselves. Values
+/// are compared for exact equality. Returns a `Result` where the error is the message that would be
+/// passed to `panic!`, which lists the paths of all duplicates.
+///
+/// ```
+/// use serde_json_assert::assert_json_unique_no_panic;
+/// use serde_json::json;
+///
+/// let users = json!({ "users": [{ "id": 1 }, { "id": 2 }, { "id": 1 }] });
+///
+/// assert_eq!(
+///     assert_json_unique_no_panic(&users, ".users", ".id").unwrap_err(),
+///     "json array at path \".users\" has duplicate values at \".id\":\n    1 at .users[0].id, .users[2].id"
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `path` or `key` can't be parsed, or if `actual` can't be serialized.
+#[track_caller]
+pub fn assert_json_unique_no_panic<T>(actual: &T, path: &str, key: &str) -> Result<(), String>
+where
+    T: Serialize + ?Sized,
+{
+    let actual = __to_value(actual);
+    let path: Path = path.parse().unwrap_or_else(|err| panic!("{}", err));
+    let key: Path = key.parse().unwrap_or_else(|err| panic!("{}", err));
+    let items = array_at(&actual, &path)?;
+
+    // Groups the paths of equal values, in the order the values first appear.
+    let mut groups: Vec<(&Value, Vec<Path>)> = vec![];
+    let mut group_by_value = HashMap::new();
+    for (idx, item) in items.iter().enumerate() {
+        let (value_path, value) = key_at(item, &path, idx, &key)?;
+        let group = *group_by_value.entry(value.to_string()).or_insert_with(|| {
+            groups.push((value, vec![]));
+            groups.len() - 1
+        });
+        groups[group].1.push(value_path);
+    }
+
+    let duplicates = groups
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect::<Vec<_>>();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = match key {
+        Path::Root => format!("json array at path \"{}\" has duplicate items:", path),
+        key => format!(
+            "json array at path \"{}\" has duplicate values at \"{}\":",
+            path, key
+        ),
+    };
+    for (value, paths) in duplicates {
+        let paths = paths.iter().map(Path::to_string).collect::<Vec<_>>();
+        message.push_str(&format!("\n    {} at {}", value, paths.join(", ")));
+    }
+    Err(message)
+}
+
+/// A value computed from the numbers of an array, checked by
+/// [`assert_json_aggregate!`](crate::assert_json_aggregate).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Aggregate {
+    /// The sum of the numbers, which is 0 for an empty array.
+    Sum,
+    /// The smallest number.
+    Min,
+    /// The largest number.
+    Max,
+    /// The arithmetic mean of the numbers.
+    Average,
+}
+
+impl Aggregate {
+    fn name(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Min => "minimum",
+            Aggregate::Max => "maximum",
+            Aggregate::Average => "average",
+        }
+    }
+
+    /// Returns the aggregate of `numbers`, if it is defined.
+    pub(crate) fn apply(self, numbers: &[f64]) -> Option<f64> {
+        match self {
+            Aggregate::Sum => Some(numbers.iter().sum()),
+            Aggregate::Min => numbers.iter().copied().reduce(f64::min),
+            Aggregate::Max => numbers.iter().copied().reduce(f64::max),
+            Aggregate::Average => {
+                (!numbers.is_empty()).then(|| numbers.iter().sum::<f64>() / numbers.len() as f64)
+            }
+        }
+    }
+}
+
+/// Checks an aggregate of the numbers at `key` in the items of the array at `path`, without
+/// panicking.
+///
+/// `key` is relative to each item, such as `.amount`. Use `$` for arrays of numbers. The aggregate
+/// is computed using floating point arithmetic and compared to `expected` according to the
+/// [`FloatCompareMode`](crate::FloatCompareMode) of `config`. Returns a `Result` where the error is
+/// the message that would be passed to `panic!`.
+///
+/// ```
+/// use serde_json_assert::{
+///     assert_json_aggregate_no_panic, Aggregate, CompareMode, Config, FloatCompareMode,
+/// };
+/// use serde_json::json;
+///
+/// let invoice = json!({ "total": 30.3, "lines": [{ "amount": 10.1 }, { "amount": 20.2 }] });
+/// let config = Config::new(CompareMode::Strict).float_compare_mode(FloatCompareMode::Epsilon(1e-9));
+///
+/// let total = invoice["total"].as_f64().unwrap();
+/// let result = assert_json_aggregate_no_panic(&invoice, ".lines", ".amount", Aggregate::Sum, total, &config);
+/// assert_eq!(result, Ok(()));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `path` or `key` can't be parsed, or if `actual` can't be serialized.
+#[track_caller]
+pub fn assert_json_aggregate_no_panic<T>(
+    actual: &T,
+    path: &str,
+    key: &str,
+    aggregate: Aggregate,
+    expected: f64,
+    config: &Config,
+) -> Result<(), String>
+where
+    T: Serialize + ?Sized,
+{
+    check_nesting(&[("actual", &actual)], config).map_err(|limit| limit.to_string())?;
+    let actual = __to_value(actual);
+    let path: Path = path.parse().unwrap_or_else(|err| panic!("{}", err));
+    let key: Path = key.parse().unwrap_or_else(|err| panic!("{}", err));
+    let items = array_at(&actual, &path)?;
+
+    let mut numbers = Vec::with_capacity(items.len());
+    for (idx, item) in items.iter().enumerate() {
+        let (number_path, number) = key_at(item, &path, idx, &key)?;
+        match number.as_f64() {
+            Some(number) => numbers.push(number),
+            None => {
+                return Err(format!(
+                    "json atom at path \"{}\" is not a number:\n    {}",
+                    number_path, number
+                ))
+            }
+        }
+    }
+
+    let value = aggregate.apply(&numbers);
+    let of = match key {
+        Path::Root => String::new(),
+        key => format!(" of \"{}\"", key),
+    };
+    match value {
+        Some(value) if floats_equal(value, expected, config.float_compare_mode) => Ok(()),
+        Some(value) => Err(format!(
+            "{}{} in json array at path \"{}\" is not equal to expected:\n    actual:   {}\n    expected: {}",
+            aggregate.name(),
+            of,
+            path,
+            value,
+            expected
+        )),
+        None => Err(format!(
+            "{}{} in json array at path \"{}\" is undefined, because the array is empty",
+            aggregate.name(),
+            of,
+            path
+        )),
+    }
+}
+
+/// Returns the items of the array at `path`.
+pub(crate) fn array_at<'a>(actual: &'a Value, path: &Path) -> Result<&'a Vec<Value>, String> {
+    match get(actual, path.keys()) {
+        Some(Value::Array(items)) => Ok(items),
+        Some(value) => Err(format!(
+            "json atom at path \"{}\" is not an array:\n    {}",
+            path, value
+        )),
+        None => Err(format!("json atom at path \"{}\" is missing", path)),
+    }
+}
+
+/// Returns the value at `key` in the item at index `idx` of the array at `path`, and its path.
+pub(crate) fn key_at<'a>(
+    item: &'a Value,
+    path: &Path,
+    idx: usize,
+    key: &Path,
+) -> Result<(Path, &'a Value), String> {
+    let key_path = key.nest_under(&Path::from_keys(vec![Key::Idx(idx)]).nest_under(path));
+    match get(item, key.keys()) {
+        Some(value) => Ok((key_path, value)),
+        None => Err(format!("json atom at path \"{}\" is missing", key_path)),
+    }
+}
+
+/// Orders numbers by their value, strings by their bytes and booleans with `false` first.
+pub(crate) fn natural_order(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Number(lhs), Value::Number(rhs)) => match (lhs.as_i64(), rhs.as_i64()) {
+            (Some(lhs), Some(rhs)) => Some(lhs.cmp(&rhs)),
+            _ => match (lhs.as_u64(), rhs.as_u64()) {
+                (Some(lhs), Some(rhs)) => Some(lhs.cmp(&rhs)),
+                _ => lhs.as_f64()?.partial_cmp(&rhs.as_f64()?),
+            },
+        },
+        (Value::String(lhs), Value::String(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::Bool(lhs), Value::Bool(rhs)) => Some(lhs.cmp(rhs)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompareMode, FloatCompareMode};
+    use serde_json::json;
+
+    #[test]
+    fn sorted_arrays() {
+        let actual =
+            json!({ "items": [{ "n": 1 }, { "n": 2.5 }, { "n": 2.5 }, { "n": u64::MAX }] });
+        let by_n = SortedBy::key(".n");
+        assert_eq!(
+            assert_json_sorted_no_panic(&actual, ".items", &by_n),
+            Ok(())
+        );
+
+        assert_eq!(
+            assert_json_sorted_no_panic(&actual, ".items", &by_n.descending()).unwrap_err(),
+            r#"json array at path ".items" is not sorted in descending order by ".n", at index 1:
+    .items[0].n: 1
+    .items[1].n: 2.5"#
+        );
+
+        let actual = json!(["b", "a", "c"]);
+        assert_eq!(
+            assert_json_sorted_no_panic(&actual, "$", &SortedBy::items()).unwrap_err(),
+            r#"json array at path "(root)" is not sorted in ascending order, at index 1:
+    [0]: "b"
+    [1]: "a""#
+        );
+    }
+
+    #[test]
+    fn unique_items() {
+        let actual = json!({ "tags": ["a", "b", { "c": [1] }, "a", { "c": [1] }, "a"] });
+        assert_eq!(
+            assert_json_unique_no_panic(&actual, ".tags", "$").unwrap_err(),
+            r#"json array at path ".tags" has duplicate items:
+    "a" at .tags[0], .tags[3], .tags[5]
+    {"c":[1]} at .tags[2], .tags[4]"#
+        );
+        assert_eq!(
+            assert_json_unique_no_panic(&json!([1, 1.0, "1"]), "", ""),
+            Ok(())
+        );
+        assert_eq!(
+            assert_json_unique_no_panic(&json!([{ "id": 1 }, 1]), "", ".id").unwrap_err(),
+            "json atom at path \"[1].id\" is missing"
+        );
+    }
+
+    #[test]
+    fn aggregates() {
+        let config = Config::new(CompareMode::Strict);
+        let actual = json!({ "lines": [{ "n": 2 }, { "n": -1.5 }, { "n": 5 }] });
+        let check = |aggregate, expected| {
+            assert_json_aggregate_no_panic(&actual, ".lines", ".n", aggregate, expected, &config)
+        };
+        assert_eq!(check(Aggregate::Sum, 5.5), Ok(()));
+        assert_eq!(check(Aggregate::Min, -1.5), Ok(()));
+        assert_eq!(check(Aggregate::Max, 5.0), Ok(()));
+        assert_eq!(check(Aggregate::Average, 5.5 / 3.0), Ok(()));
+        assert_eq!(
+            check(Aggregate::Sum, 5.0).unwrap_err(),
+            r#"sum of ".n" in json array at path ".lines" is not equal to expected:
+    actual:   5.5
+    expected: 5"#
+        );
+
+        let sum = |actual: &Value, expected| {
+            assert_json_aggregate_no_panic(actual, "", "$", Aggregate::Sum, expected, &config)
+        };
+        assert_eq!(sum(&json!([0.1, 0.2]), 0.3).unwrap_err().lines().count(), 3);
+        let approx = config
+            .clone()
+            .float_compare_mode(FloatCompareMode::Epsilon(1e-9));
+        let actual = json!([0.1, 0.2]);
+        assert_eq!(
+            assert_json_aggregate_no_panic(&actual, "", "$", Aggregate::Sum, 0.3, &approx),
+            Ok(())
+        );
+        assert_eq!(sum(&json!([]), 0.0), Ok(()));
+        assert_eq!(
+            sum(&json!([1, "2"]), 3.0).unwrap_err(),
+            "json atom at path \"[1]\" is not a number:\n    \"2\""
+        );
+        assert_eq!(
+            assert_json_aggregate_no_panic(&json!([]), "", "$", Aggregate::Max, 0.0, &config)
+                .unwrap_err(),
+            "maximum in json array at path \"(root)\" is undefined, because the array is empty"
+        );
+    }
+
+    #[test]
+    fn unsortable_arrays() {
+        let by_n = SortedBy::key(".n");
+        assert_eq!(
+            assert_json_sorted_no_panic(&json!({ "a": {} }), ".a", &by_n).unwrap_err(),
+            "json atom at path \".a\" is not an array:\n    {}"
+        );
+        assert_eq!(
+            assert_json_sorted_no_panic(&json!([{ "n": 1 }, {}]), "", &by_n).unwrap_err(),
+            "json atom at path \"[1].n\" is missing"
+        );
+        assert_eq!(
+            assert_json_sorted_no_panic(&json!([{ "n": 1 }, { "n": "2" }]), "", &by_n).unwrap_err(),
+            "json values at paths \"[0].n\" and \"[1].n\" can't be compared:\n    1\n    \"2\""
+        );
+
+        let by_length = by_n.compare_with(|a, b| a.to_string().len().cmp(&b.to_string().len()));
+        let actual = json!([{ "n": 1 }, { "n": "2" }]);
+        assert_eq!(assert_json_sorted_no_panic(&actual, "", &by_length), Ok(()));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::diff::PathRef;
+use crate::pattern::PathPattern;
+use serde_json::Value;
+use std::fmt;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+type CompareFn = dyn Fn(&Value, &Value) -> bool + Send + Sync + RefUnwindSafe;
+
+/// A custom comparison for the values matching a pattern, added with
+/// [`Config::compare_with`](crate::Config::compare_with).
+///
+/// Comparators are shared between clones of a [`Config`](crate::Config), which is why they must be
+/// thread and unwind safe.
+#[derive(Clone)]
+pub struct PathComparator {
+    /// The values the comparator applies to.
+    pub pattern: PathPattern,
+    compare: Arc<CompareFn>,
+}
+
+impl PathComparator {
+    pub(crate) fn new<F>(pattern: PathPattern, compare: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + RefUnwindSafe + 'static,
+    {
+        Self {
+            pattern,
+            compare: Arc::new(compare),
+        }
+    }
+
+    /// Returns true if the comparator accepts `actual` for `expected`.
+    pub fn compare(&self, actual: &Value, expected: &Value) -> bool {
+        (self.compare)(actual, expected)
+    }
+}
+
+impl fmt::Debug for PathComparator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PathComparator")
+            .field("pattern", &self.pattern)
+            .field("compare", &"custom")
+            .finish()
+    }
+}
+
+/// Comparators are equal if they apply the same function to the same values.
+impl PartialEq for PathComparator {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && Arc::ptr_eq(&self.compare, &other.compare)
+    }
+}
+
+/// Returns the comparator for `path`. The most specific pattern wins, and among equally specific
+/// ones the one added last.
+pub(crate) fn find<'a>(
+    comparators: &'a [PathComparator],
+    path: &PathRef,
+) -> Option<&'a PathComparator> {
+    if comparators.is_empty() {
+        return None;
+    }
+    let keys = path.keys();
+    comparators
+        .iter()
+        .filter(|comparator| comparator.pattern.matches_refs(keys.iter().copied()))
+        .max_by_key(|comparator| comparator.pattern.specificity())
+}
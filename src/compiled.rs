@@ -0,0 +1,126 @@
+use crate::matchers;
+use crate::normalize;
+use crate::paths::__to_value;
+use crate::report::Output;
+use crate::{check_nesting, check_values, prepare_expected, Config, Mismatch, Path};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// An expected value prepared once for comparing many actual values to it.
+///
+/// Assertions serialize the expected value, resolve its references and apply the normalizers of
+/// the config on every call. When the same expectation is checked in a loop, such as against every
+/// message of a stream, this preparation is done once here instead. Values that are equal to the
+/// expected value and contain no matchers are also accepted without walking them.
+///
+/// ```
+/// use serde_json_assert::{CompareMode, CompiledExpectation, Config};
+/// use serde_json::json;
+///
+/// let expected = CompiledExpectation::new(
+///     &json!({ "kind": "heartbeat" }),
+///     &Config::new(CompareMode::Inclusive),
+/// );
+///
+/// let messages = (0..1000).map(|seq| json!({ "kind": "heartbeat", "seq": seq }));
+/// assert!(messages.into_iter().all(|message| expected.matches(&message)));
+///
+/// let mismatch = expected.diff(&json!({ "kind": "error" })).unwrap_err();
+/// assert_eq!(mismatch.differences().len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledExpectation {
+    expected: Value,
+    config: Config,
+    has_markers: bool,
+}
+
+impl CompiledExpectation {
+    /// Prepares `expected` for comparisons using `config`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `expected` can't be serialized or is nested too deeply, see
+    /// [`Config::max_nesting`].
+    #[track_caller]
+    pub fn new<T: Serialize + ?Sized>(expected: &T, config: &Config) -> Self {
+        check_nesting(&[("expected", &expected)], config)
+            .unwrap_or_else(|limit| panic!("{}", limit));
+        let expected = prepare_expected(__to_value(expected), config);
+        Self {
+            has_markers: matchers::contains_markers(&expected),
+            expected,
+            config: config.clone(),
+        }
+    }
+
+    /// Returns the prepared expected value.
+    pub fn expected(&self) -> &Value {
+        &self.expected
+    }
+
+    /// Returns the config used for comparisons.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns true if `actual` matches the expected value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `actual` can't be serialized.
+    #[track_caller]
+    pub fn matches<T: Serialize + ?Sized>(&self, actual: &T) -> bool {
+        self.diff(actual).is_ok()
+    }
+
+    /// Compares `actual` to the expected value, like
+    /// [`check_json_matches`](crate::check_json_matches), returning the paths of values allowed
+    /// without being expected if they match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `actual` can't be serialized.
+    #[track_caller]
+    pub fn diff<T: Serialize + ?Sized>(&self, actual: &T) -> Result<Vec<Path>, Mismatch> {
+        check_nesting(&[("actual", &actual)], &self.config)
+            .map_err(|limit| Mismatch::limit_exceeded(limit, Output::from_env(&self.config)))?;
+        let actual = normalize::normalize(__to_value(actual), &self.config);
+        if !self.has_markers && self.config.max_nodes.is_none() && actual == self.expected {
+            return Ok(vec![]);
+        }
+        check_values(Cow::Owned(actual), &self.expected, &self.config).map(|matched| matched.extras)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matchers, normalizers, CompareMode};
+    use serde_json::json;
+
+    #[test]
+    fn prepares_expected_value_once() {
+        let config = Config::new(CompareMode::Strict)
+            .normalizer(normalizers::CollapseWhitespace)
+            .context(&json!({ "id": 7 }));
+        let expected = CompiledExpectation::new(
+            &json!({ "id": matchers::context_value(".id"), "name": " Ann  Lee ", "age": matchers::any_number() }),
+            &config,
+        );
+        assert_eq!(expected.expected()["id"], 7);
+        assert_eq!(expected.expected()["name"], "Ann Lee");
+
+        assert!(expected.matches(&json!({ "id": 7, "name": "Ann Lee\n", "age": 30 })));
+        assert!(!expected.matches(&json!({ "id": 7, "name": "Ann Lee", "age": "30" })));
+
+        let error = expected
+            .diff(&json!({ "id": 8, "name": "Ann Lee", "age": 30 }))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "json atoms at path \".id\" are not equal:\n    lhs:\n        8\n    rhs:\n        7"
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+/// A value whose nesting depth can be measured before it's serialized, for
+/// [`check_nesting`](crate::check_nesting).
+pub(crate) trait Measure {
+    /// Measures how deeply the value is nested as JSON, stopping as soon as it's deeper than
+    /// `limit`.
+    fn depth(&self, limit: usize) -> usize;
+}
+
+impl<T: Serialize + ?Sized> Measure for T {
+    fn depth(&self, limit: usize) -> usize {
+        match self.serialize(Probe { depth: 0, limit }) {
+            Ok(depth) => depth,
+            Err(Error::TooDeep) => limit + 1,
+            // Serializing the value fails the same way later on, with a proper message.
+            Err(Error::Custom) => 0,
+        }
+    }
+}
+
+/// A serializer that only tracks the depth of values, mirroring how `serde_json` lays them out,
+/// and gives up once they're nested more than `limit` levels. That bounds its recursion, unlike
+/// serializing to a [`Value`](serde_json::Value).
+struct Probe {
+    depth: usize,
+    limit: usize,
+}
+
+impl Probe {
+    /// Returns a probe for the values nested one level below this one.
+    fn nested(&self) -> Result<Probe, Error> {
+        if self.depth >= self.limit {
+            return Err(Error::TooDeep);
+        }
+        Ok(Probe {
+            depth: self.depth + 1,
+            limit: self.limit,
+        })
+    }
+
+    /// Starts a container that is `levels` deep itself, such as `{ "Variant": [items] }`.
+    fn compound(self, levels: usize) -> Result<Compound, Error> {
+        let mut container = self;
+        for _ in 1..levels {
+            container = container.nested()?;
+        }
+        Ok(Compound {
+            deepest: container.depth,
+            container,
+        })
+    }
+}
+
+/// The innermost array or object of a container, and the depth of its deepest item so far.
+struct Compound {
+    container: Probe,
+    deepest: usize,
+}
+
+impl Compound {
+    fn item<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let depth = value.serialize(self.container.nested()?)?;
+        self.deepest = self.deepest.max(depth);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    TooDeep,
+    Custom,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TooDeep => write!(f, "value is nested too deeply"),
+            Error::Custom => write!(f, "value can't be serialized"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        Error::Custom
+    }
+}
+
+macro_rules! scalars {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method(self, _: $ty) -> Result<usize, Error> {
+                Ok(self.depth)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for Probe {
+    type Ok = usize;
+    type Error = Error;
+    type SerializeSeq = Compound;
+    type SerializeTuple = Compound;
+    type SerializeTupleStruct = Compound;
+    type SerializeTupleVariant = Compound;
+    type SerializeMap = Compound;
+    type SerializeStruct = Compound;
+    type SerializeStructVariant = Compound;
+
+    scalars! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_bytes(self, bytes: &[u8]) -> Result<usize, Error> {
+        // Bytes become an array of numbers.
+        if bytes.is_empty() {
+            Ok(self.depth)
+        } else {
+            Ok(self.nested()?.depth)
+        }
+    }
+
+    fn serialize_none(self) -> Result<usize, Error> {
+        Ok(self.depth)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<usize, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<usize, Error> {
+        Ok(self.depth)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<usize, Error> {
+        Ok(self.depth)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<usize, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<usize, Error> {
+        // `{ "Variant": value }`
+        value.serialize(self.nested()?)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Compound, Error> {
+        self.compound(1)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Compound, Error> {
+        self.compound(1)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Compound, Error> {
+        self.compound(1)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Compound, Error> {
+        // `{ "Variant": [items] }`
+        self.compound(2)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Compound, Error> {
+        self.compound(1)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Compound, Error> {
+        self.compound(1)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Compound, Error> {
+        // `{ "Variant": { fields } }`
+        self.compound(2)
+    }
+}
+
+impl ser::SerializeSeq for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+impl ser::SerializeTuple for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+impl ser::SerializeMap for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    // Keys are always strings in JSON.
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, _: &T) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+impl ser::SerializeStruct for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound {
+    type Ok = usize;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<usize, Error> {
+        Ok(self.deepest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    #[derive(serde::Serialize)]
+    enum Shape {
+        Unit,
+        Newtype(Vec<u8>),
+        Tuple(u8, Vec<u8>),
+        Struct { items: Vec<u8> },
+    }
+
+    #[test]
+    fn measures_depth_like_serialized_json() {
+        let cases = [
+            json!(1),
+            json!([]),
+            json!({}),
+            json!([1, [2, [3]]]),
+            json!({ "a": [{ "b": {} }], "c": null }),
+        ];
+        for value in &cases {
+            let expected = crate::stats::DocumentStats::of(value).depth;
+            assert_eq!(value.depth(64), expected, "{}", value);
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert("a", Some(vec![vec![1]]));
+        let shapes = [
+            (Shape::Unit, 0),
+            (Shape::Newtype(vec![]), 1),
+            (Shape::Newtype(vec![1]), 2),
+            (Shape::Tuple(1, vec![]), 2),
+            (Shape::Tuple(1, vec![2]), 3),
+            (Shape::Struct { items: vec![] }, 2),
+            (Shape::Struct { items: vec![1] }, 3),
+        ];
+        for (shape, expected) in shapes {
+            let value = serde_json::to_value(&shape).unwrap();
+            assert_eq!(shape.depth(64), expected, "{}", value);
+            assert_eq!(crate::stats::DocumentStats::of(&value).depth, expected);
+        }
+        assert_eq!(map.depth(64), 3);
+    }
+
+    #[test]
+    fn stops_past_the_limit() {
+        let value = json!([[[1]], 2]);
+        assert_eq!(value.depth(3), 3);
+        assert_eq!(value.depth(2), 3);
+        assert_eq!(value.depth(0), 1);
+        assert_eq!(json!([]).depth(0), 0);
+
+        let mut deep = Value::Null;
+        for _ in 0..10_000 {
+            deep = Value::Array(vec![deep]);
+        }
+        assert_eq!(deep.depth(100), 101);
+        // Dropping the value would overflow the stack.
+        std::mem::forget(deep);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::diff::{Key, Path};
+use crate::paths::__to_value;
+use crate::{assert_json_matches_no_panic, Config};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::type_name;
+
+/// Deserializes `value` into a `T`, or panics with the path of the value that couldn't be
+/// deserialized.
+///
+/// This checks that a payload can be consumed by the types of a client, which comparing it to an
+/// expected value doesn't. The deserialized value is returned for further assertions.
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_json::json;
+/// use serde_json_assert::assert_json_deserializes_to;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     id: u64,
+///     roles: Vec<String>,
+/// }
+///
+/// let user: User = assert_json_deserializes_to(&json!({ "id": 1, "roles": ["admin"] }));
+/// assert_eq!(user.roles, ["admin"]);
+/// ```
+///
+/// Use [`assert_raw_json_deserializes_to`] for JSON text.
+#[track_caller]
+pub fn assert_json_deserializes_to<T, V>(value: &V) -> T
+where
+    T: DeserializeOwned,
+    V: Serialize + ?Sized,
+{
+    assert_json_deserializes_to_no_panic(value)
+        .unwrap_or_else(|error| panic!("\n{}\n\nat {}", error, std::panic::Location::caller()))
+}
+
+/// Deserializes `value` into a `T` without panicking.
+///
+/// Returns a `Result` where the error is the message that [`assert_json_deserializes_to`] would
+/// panic with.
+///
+/// ```
+/// use serde_json::json;
+/// use serde_json_assert::assert_json_deserializes_to_no_panic;
+///
+/// let result = assert_json_deserializes_to_no_panic::<Vec<(u8, bool)>, _>(&json!([[1, true], [2, "no"]]));
+/// assert_eq!(
+///     result.unwrap_err(),
+///     "json value can't be deserialized into `alloc::vec::Vec<(u8, bool)>`:\n    at path \"[1][1]\": invalid type: string \"no\", expected a boolean"
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized.
+#[track_caller]
+pub fn assert_json_deserializes_to_no_panic<T, V>(value: &V) -> Result<T, String>
+where
+    T: DeserializeOwned,
+    V: Serialize + ?Sized,
+{
+    let value = __to_value(value);
+    serde_path_to_error::deserialize(value).map_err(|err| describe::<T>(err))
+}
+
+/// Parses `text` as JSON and deserializes it into a `T`, or panics with the path of the value that
+/// couldn't be deserialized.
+///
+/// Like [`assert_json_deserializes_to`], but for JSON text. Text that isn't valid JSON is reported
+/// with the line and column of the syntax error.
+#[track_caller]
+pub fn assert_raw_json_deserializes_to<T>(text: impl AsRef<[u8]>) -> T
+where
+    T: DeserializeOwned,
+{
+    assert_raw_json_deserializes_to_no_panic(text)
+        .unwrap_or_else(|error| panic!("\n{}\n\nat {}", error, std::panic::Location::caller()))
+}
+
+/// Parses `text` as JSON and deserializes it into a `T` without panicking.
+///
+/// Returns a `Result` where the error is the message that [`assert_raw_json_deserializes_to`]
+/// would panic with.
+pub fn assert_raw_json_deserializes_to_no_panic<T>(text: impl AsRef<[u8]>) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(text.as_ref());
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(describe::<T>)?;
+    deserializer.end().map_err(|err| {
+        format!(
+            "json text can't be deserialized into `{}`:\n    {}",
+            type_name::<T>(),
+            err
+        )
+    })?;
+    Ok(value)
+}
+
+/// Checks that `value` survives a round trip through JSON without panicking.
+///
+/// `value` is serialized, deserialized into a new `T` and serialized again, and the two JSON
+/// documents are compared using `config`. This catches serde implementations that don't read
+/// what they write, like fields renamed on one side only or skipped when deserializing. The
+/// re-serialized document is the lhs (or actual value) of the comparison.
+///
+/// Returns a `Result` where the error is the message that
+/// [`assert_json_round_trips`](crate::assert_json_round_trips) would panic with.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_json_assert::{assert_json_round_trips_no_panic, CompareMode, Config};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Session {
+///     id: u64,
+///     #[serde(skip_deserializing)]
+///     token: String,
+/// }
+///
+/// let session = Session { id: 1, token: "abc".to_string() };
+/// let config = Config::new(CompareMode::Strict);
+/// let error = assert_json_round_trips_no_panic(&session, &config).unwrap_err();
+/// assert!(error.ends_with(r#"json atoms at path ".token" are not equal:
+///     lhs:
+///         ""
+///     rhs:
+///         "abc""#));
+/// ```
+pub fn assert_json_round_trips_no_panic<T>(value: &T, config: &Config) -> Result<(), String>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let serialize = |value: &T| {
+        serde_json::to_value(value)
+            .map_err(|err| format!("`{}` can't be serialized: {}", type_name::<T>(), err))
+    };
+    let first = serialize(value)?;
+    let copy = assert_json_deserializes_to_no_panic::<T, _>(&first)?;
+    let second = serialize(&copy)?;
+    assert_json_matches_no_panic(&second, &first, config).map_err(|error| {
+        format!(
+            "`{}` doesn't round-trip through json:\n\n{}",
+            type_name::<T>(),
+            error
+        )
+    })
+}
+
+fn describe<T>(err: serde_path_to_error::Error<serde_json::Error>) -> String {
+    let path = Path::from_keys(
+        err.path()
+            .iter()
+            .filter_map(|segment| match segment {
+                serde_path_to_error::Segment::Seq { index } => Some(Key::Idx(*index)),
+                serde_path_to_error::Segment::Map { key } => Some(Key::Field(key.clone())),
+                serde_path_to_error::Segment::Enum { variant } => Some(Key::Field(variant.clone())),
+                serde_path_to_error::Segment::Unknown => None,
+            })
+            .collect(),
+    );
+    format!(
+        "json value can't be deserialized into `{}`:\n    at path \"{}\": {}",
+        type_name::<T>(),
+        path,
+        err.inner()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Shape {
+        Circle { radius: f64 },
+    }
+
+    #[test]
+    fn reports_paths() {
+        let result = assert_json_deserializes_to_no_panic::<HashMap<String, Vec<Shape>>, _>(
+            &json!({ "a": [{ "Circle": { "radius": 1 } }, { "Circle": { "radius": "2" } }] }),
+        );
+        assert!(result
+            .unwrap_err()
+            .ends_with(r#"at path ".a[1].Circle.radius": invalid type: string "2", expected f64"#));
+
+        let shape: Shape = assert_json_deserializes_to(&json!({ "Circle": { "radius": 1.5 } }));
+        assert_eq!(shape, Shape::Circle { radius: 1.5 });
+    }
+
+    #[test]
+    fn round_trips() {
+        #[derive(Serialize, Deserialize)]
+        struct Renamed {
+            #[serde(rename(serialize = "userId"))]
+            user_id: u64,
+        }
+
+        let config = Config::new(crate::CompareMode::Strict);
+        let error = assert_json_round_trips_no_panic(&Renamed { user_id: 1 }, &config).unwrap_err();
+        assert!(error.ends_with(r#"at path "(root)": missing field `user_id`"#));
+
+        assert_json_round_trips_no_panic(&json!({ "a": [1, 2.5, null] }), &config).unwrap();
+        assert_json_round_trips_no_panic(&Some(vec![Shape::Circle { radius: 1.0 }]), &config)
+            .unwrap();
+    }
+
+    #[test]
+    fn raw_json() {
+        let result = assert_raw_json_deserializes_to_no_panic::<Vec<u8>>("[1, 256]");
+        assert_eq!(
+            result.unwrap_err(),
+            "json value can't be deserialized into `alloc::vec::Vec<u8>`:\n    at path \"[1]\": invalid value: integer `256`, expected u8 at line 1 column 7"
+        );
+
+        let result = assert_raw_json_deserializes_to_no_panic::<Vec<u8>>("[1] 2");
+        assert_eq!(
+            result.unwrap_err(),
+            "json text can't be deserialized into `alloc::vec::Vec<u8>`:\n    trailing characters at line 1 column 5"
+        );
+
+        assert_eq!(
+            assert_raw_json_deserializes_to::<Vec<u8>>(b"[1, 2]"),
+            [1, 2]
+        );
+    }
+}
@@ -1,78 +1,380 @@
+use crate::comparator;
 use crate::core_ext::{Indent, Indexes};
-use crate::{ArraySortingMode, CompareMode, Config, FloatCompareMode, NumericMode};
+use crate::explain::Decision;
+use crate::matchers::{self, Matcher};
+use crate::minimize::get;
+use crate::normalize;
+use crate::pattern::{parse_segments, Segment};
+use crate::render::{self, Line};
+use crate::report::{Output, LHS_COLOR, RHS_COLOR};
+use crate::timestamp;
+use crate::{
+    ArrayDiffMode, ArraySortingMode, CompareMode, Config, FloatCompareMode, LineEndingMode,
+    MaxDepthMode, NumericMode, StringCompareMode, Verbosity,
+};
+use bumpalo::Bump;
 use float_cmp::{ApproxEq, F64Margin, FloatMargin};
-use serde_json::Value;
-use std::{collections::HashSet, fmt};
+use serde::de::Error as _;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::{fmt, str::FromStr};
+
+pub(crate) fn diff(lhs: &Value, rhs: &Value, config: &Config) -> Vec<Difference> {
+    diff_with_extras(lhs, rhs, config).0
+}
 
-pub(crate) fn diff<'a>(
-    lhs: &'a Value,
-    rhs: &'a Value,
-    config: &'a Config,
-) -> Vec<DifferenceRef<'a>> {
-    let mut acc = vec![];
-    diff_with(lhs, rhs, config, PathRef::Root, &mut acc);
-    acc
+/// Like [`diff`], but also returns the paths of values that were allowed in `lhs` without being
+/// expected. These are only collected if [`Config::report_extras`] is set.
+pub(crate) fn diff_with_extras(
+    lhs: &Value,
+    rhs: &Value,
+    config: &Config,
+) -> (Vec<Difference>, Vec<Path>) {
+    let comparison = compare(lhs, rhs, config);
+    (comparison.differences, comparison.extras)
+}
+
+/// The outcome of comparing two values.
+#[derive(Debug)]
+pub(crate) struct Comparison {
+    pub(crate) differences: Vec<Difference>,
+    pub(crate) extras: Vec<Path>,
+    /// The number of nodes that were compared, not counting those below trivially equal values.
+    pub(crate) compared: usize,
+    /// The values that matched [`matchers::capture`] markers, by name, in the order they were
+    /// found.
+    pub(crate) captures: Vec<(String, Value)>,
+}
+
+/// Like [`diff_with_extras`], but also returns how many nodes were compared.
+pub(crate) fn compare(lhs: &Value, rhs: &Value, config: &Config) -> Comparison {
+    if trivially_equal(lhs, rhs) {
+        return Comparison {
+            differences: vec![],
+            extras: vec![],
+            compared: 1,
+            captures: vec![],
+        };
+    }
+
+    // Paths built during the walk live in an arena that is freed at once when the comparison is
+    // done, after the findings have been copied out of it.
+    let arena = Bump::new();
+    let mut findings = Findings::new(config.fail_fast);
+    diff_with(lhs, rhs, config, PathRef::Root, &arena, &mut findings);
+    if config.fail_fast {
+        // Missing and unexpected items are still collected by the loop that found the first
+        // difference, since they aren't visited.
+        findings.differences.truncate(1);
+    }
+    Comparison {
+        differences: Difference::from_refs(findings.differences),
+        extras: findings.extras.into_iter().map(Path::from).collect(),
+        compared: findings.compared,
+        captures: findings
+            .captures
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect(),
+    }
+}
+
+/// Returns true if `lhs` matches `rhs`, stopping at the first difference whatever
+/// [`Config::fail_fast`] is set to.
+pub(crate) fn is_match(lhs: &Value, rhs: &Value, config: &Config) -> bool {
+    if trivially_equal(lhs, rhs) {
+        return true;
+    }
+    let arena = Bump::new();
+    let mut findings = Findings::new(true);
+    diff_with(lhs, rhs, config, PathRef::Root, &arena, &mut findings);
+    findings.differences.is_empty()
+}
+
+/// Returns true if `lhs` differs from `rhs` when they are compared as the values at `keys`, so
+/// the settings for paths apply as they would within the whole documents.
+pub(crate) fn differs_at(lhs: &Value, rhs: &Value, config: &Config, keys: &[Key]) -> bool {
+    let arena = Bump::new();
+    let path = keys
+        .iter()
+        .fold(PathRef::Root, |path, key| path.append(&arena, key.into()));
+    let mut findings = Findings::new(true);
+    diff_with(lhs, rhs, config, path, &arena, &mut findings);
+    !findings.differences.is_empty()
+}
+
+/// Compares two values like [`diff`], borrowing the differing values instead of cloning them.
+pub(crate) fn diff_borrowed<'v>(
+    lhs: &'v Value,
+    rhs: &'v Value,
+    config: &Config,
+) -> Vec<BorrowedDifference<'v>> {
+    if trivially_equal(lhs, rhs) {
+        return vec![];
+    }
+
+    let arena = Bump::new();
+    let mut findings = Findings::new(config.fail_fast);
+    diff_with(lhs, rhs, config, PathRef::Root, &arena, &mut findings);
+    if config.fail_fast {
+        findings.differences.truncate(1);
+    }
+    let mut shared: Option<Arc<Config>> = None;
+    findings
+        .differences
+        .into_iter()
+        .map(|diff| BorrowedDifference {
+            path: Path::from(diff.path),
+            lhs: diff.lhs.map(Cow::Borrowed),
+            rhs: diff.rhs.map(Cow::Borrowed),
+            kind: DifferenceKind::of(diff.lhs, diff.rhs, config),
+            config: Arc::clone(shared.get_or_insert_with(|| Arc::new(config.clone()))),
+        })
+        .collect()
+}
+
+/// Compares two values like [`compare`], recording the rule that decided each visited node.
+pub(crate) fn explain(lhs: &Value, rhs: &Value, config: &Config) -> Vec<Decision> {
+    let arena = Bump::new();
+    let mut findings = Findings {
+        decisions: Some(vec![]),
+        ..Findings::default()
+    };
+    diff_with(lhs, rhs, config, PathRef::Root, &arena, &mut findings);
+    findings
+        .decisions
+        .unwrap_or_default()
+        .into_iter()
+        .map(|decision| Decision::new(decision.path.into(), decision.matched, decision.rule))
+        .collect()
+}
+
+/// Returns true if `lhs` and `rhs` are known to match without walking them, whatever the config.
+///
+/// Equal values always match, unless the expected value contains matchers.
+fn trivially_equal(lhs: &Value, rhs: &Value) -> bool {
+    std::ptr::eq(lhs, rhs) || (lhs == rhs && !matchers::contains_markers(rhs))
+}
+
+/// Returns true if `lhs` and `rhs` are equal according to `mode`.
+pub(crate) fn floats_equal(lhs: f64, rhs: f64, mode: FloatCompareMode) -> bool {
+    if let FloatCompareMode::Epsilon(epsilon) = mode {
+        lhs.approx_eq(rhs, F64Margin::default().epsilon(epsilon))
+    } else {
+        lhs == rhs
+    }
+}
+
+/// Returns true if `lhs` and `rhs` are equal according to `mode`.
+pub(crate) fn strings_equal(lhs: &str, rhs: &str, mode: StringCompareMode) -> bool {
+    let (lhs, rhs) = (
+        lhs.chars().collect::<Vec<_>>(),
+        rhs.chars().collect::<Vec<_>>(),
+    );
+    let max_distance = match mode {
+        StringCompareMode::Exact => return lhs == rhs,
+        StringCompareMode::EditDistance(max_distance) => max_distance,
+        StringCompareMode::Similarity(similarity) => {
+            let len = lhs.len().max(rhs.len());
+            ((1.0 - similarity) * len as f64 + 1e-9).floor() as usize
+        }
+    };
+    lhs.len().abs_diff(rhs.len()) <= max_distance && edit_distance(&lhs, &rhs) <= max_distance
+}
+
+/// Returns the Levenshtein distance between `lhs` and `rhs`.
+fn edit_distance(lhs: &[char], rhs: &[char]) -> usize {
+    let mut previous = (0..=rhs.len()).collect::<Vec<_>>();
+    let mut current = vec![0; rhs.len() + 1];
+    for (i, lhs) in lhs.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, rhs) in rhs.iter().enumerate() {
+            let replace = previous[j] + usize::from(lhs != rhs);
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[rhs.len()]
+}
+
+fn as_integer(value: &Value) -> Option<i128> {
+    value
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| value.as_u64().map(i128::from))
+}
+
+#[derive(Debug, Default)]
+struct Findings<'v, 'a> {
+    differences: Vec<DifferenceRef<'v, 'a>>,
+    extras: Vec<PathRef<'a>>,
+    compared: usize,
+    captures: Vec<(&'v str, &'v Value)>,
+    /// The rules that decided each node, only recorded when explaining a comparison.
+    decisions: Option<Vec<DecisionRef<'a>>>,
+    /// Stop comparing at the first difference.
+    fail_fast: bool,
+}
+
+impl Findings<'_, '_> {
+    fn new(fail_fast: bool) -> Self {
+        Self {
+            fail_fast,
+            ..Self::default()
+        }
+    }
 }
 
-fn diff_with<'a>(
-    lhs: &'a Value,
-    rhs: &'a Value,
+#[derive(Debug)]
+struct DecisionRef<'a> {
+    path: PathRef<'a>,
+    matched: bool,
+    rule: String,
+}
+
+fn diff_with<'v: 'a, 'a>(
+    lhs: &'v Value,
+    rhs: &'v Value,
     config: &'a Config,
     path: PathRef<'a>,
-    acc: &mut Vec<DifferenceRef<'a>>,
+    arena: &'a Bump,
+    acc: &mut Findings<'v, 'a>,
 ) {
+    if acc.fail_fast && !acc.differences.is_empty() {
+        return;
+    }
+    acc.compared += 1;
+    let path_mode = config.compare_mode_at_path(&path);
     let mut folder = DiffFolder {
         rhs,
         path,
         acc,
         config,
+        arena,
+        path_mode,
     };
+    if folder.ignored(path) {
+        return;
+    }
+    if let Some(comparator) = comparator::find(&config.comparators, &path) {
+        let matched = comparator.compare(lhs, rhs);
+        folder.explain(matched, || {
+            format!(
+                "compared by Config::compare_with for {}",
+                comparator.pattern
+            )
+        });
+        if !matched {
+            folder.push_mismatch(lhs);
+        }
+        return;
+    }
 
-    fold_json(lhs, &mut folder);
+    match Matcher::find(rhs, config) {
+        Some(matcher) => folder.on_matcher(lhs, matcher),
+        None if folder.at_max_depth() && (lhs.is_array() || lhs.is_object()) => {
+            match config.max_depth_mode {
+                MaxDepthMode::Atomic => {
+                    folder.explain(lhs == rhs, || {
+                        "compared as a whole below the maximum depth".to_string()
+                    });
+                    if lhs != rhs {
+                        folder.push_mismatch(lhs);
+                    }
+                }
+                MaxDepthMode::Ignore => {
+                    folder.explain(true, || "ignored below the maximum depth".to_string())
+                }
+            }
+        }
+        None => fold_json(lhs, &mut folder),
+    }
 }
 
 #[derive(Debug)]
-struct DiffFolder<'a, 'b> {
-    rhs: &'a Value,
+struct DiffFolder<'v, 'a, 'b> {
+    rhs: &'v Value,
     path: PathRef<'a>,
-    acc: &'b mut Vec<DifferenceRef<'a>>,
+    acc: &'b mut Findings<'v, 'a>,
     config: &'a Config,
+    /// Allocates the paths of nested values.
+    arena: &'a Bump,
+    /// The compare mode of the current node, if set for its path.
+    path_mode: Option<CompareMode>,
 }
 
 macro_rules! direct_compare {
     ($name:ident) => {
-        fn $name(&mut self, lhs: &'a Value) {
+        fn $name(&mut self, lhs: &'v Value) {
+            let rhs = self.rhs;
+            self.explain(rhs == lhs, || leaf_rule(lhs, rhs, "compared exactly"));
             if self.rhs != lhs {
-                self.acc.push(DifferenceRef {
+                self.acc.differences.push(DifferenceRef {
                     lhs: Some(lhs),
                     rhs: Some(&self.rhs),
-                    path: self.path.clone(),
-                    config: self.config.clone(),
+                    path: self.path,
+                    config: self.config,
                 });
             }
         }
     };
 }
 
-impl<'a> DiffFolder<'a, '_> {
+impl<'v: 'a, 'a> DiffFolder<'v, 'a, '_> {
     direct_compare!(on_null);
     direct_compare!(on_bool);
-    direct_compare!(on_string);
 
-    fn on_number(&mut self, lhs: &'a Value) {
+    fn on_string(&mut self, lhs: &'v Value) {
+        let is_equal = match (lhs, self.rhs) {
+            (Value::String(lhs), Value::String(rhs)) if lhs != rhs => {
+                let mode = self.config.line_ending_mode;
+                let (lhs, rhs) = (
+                    normalize::line_endings(lhs, mode),
+                    normalize::line_endings(rhs, mode),
+                );
+                lhs == rhs
+                    || strings_equal(&lhs, &rhs, self.config.string_compare_mode)
+                    || self.config.timestamps
+                        && matches!(
+                            (timestamp::parse(&lhs), timestamp::parse(&rhs)),
+                            (Some(lhs), Some(rhs)) if lhs == rhs
+                        )
+            }
+            _ => lhs == self.rhs,
+        };
+        let (rhs, config) = (self.rhs, self.config);
+        self.explain(is_equal, || string_rule(lhs, rhs, config, is_equal));
+        if !is_equal {
+            self.push_mismatch(lhs);
+        }
+    }
+
+    fn on_number(&mut self, lhs: &'v Value) {
         let is_equal = match self.config.numeric_mode {
             NumericMode::Strict => self.eq_values(lhs, self.rhs),
-            NumericMode::AssumeFloat => match (lhs.as_f64(), self.rhs.as_f64()) {
-                (Some(lhs), Some(rhs)) => self.eq_floats(lhs, rhs),
-                (lhs, rhs) => lhs == rhs,
+            NumericMode::AssumeFloat => match (as_integer(lhs), as_integer(self.rhs)) {
+                // Integers beyond 2^53 lose precision when converted to floats, so only their
+                // exact difference is converted.
+                (Some(lhs), Some(rhs)) => self.eq_floats((lhs - rhs) as f64, 0.0),
+                _ => match (lhs.as_f64(), self.rhs.as_f64()) {
+                    (Some(lhs), Some(rhs)) => self.eq_floats(lhs, rhs),
+                    (lhs, rhs) => lhs == rhs,
+                },
             },
         };
+        let (rhs, config) = (self.rhs, self.config);
+        self.explain(is_equal, || number_rule(lhs, rhs, config));
         if !is_equal {
-            self.acc.push(DifferenceRef {
+            self.acc.differences.push(DifferenceRef {
                 lhs: Some(lhs),
                 rhs: Some(self.rhs),
-                path: self.path.clone(),
-                config: self.config.clone(),
+                path: self.path,
+                config: self.config,
             });
         }
     }
@@ -91,25 +393,224 @@ impl<'a> DiffFolder<'a, '_> {
     }
 
     fn eq_floats(&self, lhs: f64, rhs: f64) -> bool {
-        if let FloatCompareMode::Epsilon(epsilon) = self.config.float_compare_mode {
-            lhs.approx_eq(rhs, F64Margin::default().epsilon(epsilon))
-        } else {
-            lhs == rhs
+        floats_equal(lhs, rhs, self.config.float_compare_mode)
+    }
+    fn on_matcher(&mut self, lhs: &'v Value, matcher: Matcher<'v>) {
+        let rhs = self.rhs;
+        let rule = || match rhs {
+            Value::String(sentinel) => format!("checked by sentinel `{}`", sentinel),
+            rhs => format!(
+                "checked by matcher `{}`",
+                rhs[matchers::MATCHER_KEY].as_str().unwrap_or_default()
+            ),
+        };
+        match matcher {
+            Matcher::Partial(expected) => self.explained(rule, |folder| {
+                folder.rhs = expected;
+                match (lhs, expected) {
+                    (Value::Array(_), Value::Array(_)) => folder.compare_arrays(lhs, true),
+                    (Value::Object(_), Value::Object(_)) => folder.compare_objects(lhs, true),
+                    _ => fold_json(lhs, folder),
+                }
+            }),
+            Matcher::Exactly(expected) => self.explained(rule, |folder| {
+                folder.rhs = expected;
+                fold_json(lhs, folder);
+            }),
+            Matcher::Capture { name, expected } => self.explained(rule, |folder| {
+                let differences = folder.acc.differences.len();
+                diff_with(
+                    lhs,
+                    expected,
+                    folder.config,
+                    folder.path,
+                    folder.arena,
+                    folder.acc,
+                );
+                if folder.acc.differences.len() == differences {
+                    folder.acc.captures.push((name, lhs));
+                }
+            }),
+            matcher => {
+                let differences = self.acc.differences.len();
+                self.check_matcher(lhs, matcher);
+                let matched = self.acc.differences.len() == differences;
+                self.explain(matched, rule);
+            }
+        }
+    }
+
+    /// Checks `lhs` against a matcher that decides on its own, without comparing nested values.
+    fn check_matcher(&mut self, lhs: &'v Value, matcher: Matcher<'v>) {
+        match matcher {
+            Matcher::Not(unexpected) => {
+                if self.matches(lhs, unexpected, self.path) {
+                    self.push_mismatch(lhs);
+                }
+            }
+            Matcher::NoneOf(unexpected) => {
+                if unexpected
+                    .iter()
+                    .any(|unexpected| self.matches(lhs, unexpected, self.path))
+                {
+                    self.push_mismatch(lhs);
+                }
+            }
+            Matcher::Like(example) => {
+                if !self.is_like(lhs, example, self.path) {
+                    self.push_mismatch(lhs);
+                }
+            }
+            Matcher::ArrayContaining(expected) => {
+                let contains = lhs.as_array().is_some_and(|items| {
+                    expected.iter().all(|expected| {
+                        items.iter().enumerate().any(|(idx, item)| {
+                            self.matches(
+                                item,
+                                expected,
+                                self.path.append(self.arena, KeyRef::Idx(idx)),
+                            )
+                        })
+                    })
+                });
+                if !contains {
+                    self.push_mismatch(lhs);
+                }
+            }
+            Matcher::ArrayLike { item, min, max } => {
+                let is_like = lhs.as_array().is_some_and(|items| {
+                    min.is_none_or(|min| items.len() >= min)
+                        && max.is_none_or(|max| items.len() <= max)
+                        && items.iter().enumerate().all(|(idx, lhs)| {
+                            self.is_like(lhs, item, self.path.append(self.arena, KeyRef::Idx(idx)))
+                        })
+                });
+                if !is_like {
+                    self.push_mismatch(lhs);
+                }
+            }
+            matcher => {
+                if !matcher.test(lhs) {
+                    self.push_mismatch(lhs);
+                }
+            }
+        }
+    }
+
+    /// Returns true if `lhs` matches `rhs` when compared at `path`.
+    fn matches(&self, lhs: &'v Value, rhs: &'v Value, path: PathRef<'a>) -> bool {
+        if trivially_equal(lhs, rhs) {
+            return true;
+        }
+        let mut findings = Findings::new(true);
+        diff_with(lhs, rhs, self.config, path, self.arena, &mut findings);
+        findings.differences.is_empty()
+    }
+
+    /// Returns true if `lhs` has the same shape as `example`. See [`matchers::like`].
+    ///
+    /// [`matchers::like`]: matchers::like
+    fn is_like(&self, lhs: &'v Value, example: &'v Value, path: PathRef<'a>) -> bool {
+        if Matcher::find(example, self.config).is_some() {
+            return self.matches(lhs, example, path);
+        }
+        match (lhs, example) {
+            (Value::Array(items), Value::Array(examples)) => {
+                examples.first().is_none_or(|example| {
+                    items.iter().enumerate().all(|(idx, item)| {
+                        self.is_like(item, example, path.append(self.arena, KeyRef::Idx(idx)))
+                    })
+                })
+            }
+            (Value::Object(fields), Value::Object(examples)) => {
+                examples.iter().all(|(key, example)| {
+                    fields.get(key).is_some_and(|field| {
+                        self.is_like(field, example, path.append(self.arena, KeyRef::Field(key)))
+                    })
+                })
+            }
+            (Value::Null, Value::Null)
+            | (Value::Bool(_), Value::Bool(_))
+            | (Value::Number(_), Value::Number(_))
+            | (Value::String(_), Value::String(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the value at `path` is skipped because of [`Config::ignore_paths`] or a
+    /// similar setting, recording it as matched.
+    fn ignored(&mut self, path: PathRef<'a>) -> bool {
+        let Some(setting) = self.config.ignored_by(&path) else {
+            return false;
+        };
+        self.explain_at(path, true, || format!("ignored by {}", setting));
+        true
+    }
+
+    /// Records the rule that decided whether the current node matched, if the comparison is
+    /// explained.
+    fn explain(&mut self, matched: bool, rule: impl FnOnce() -> String) {
+        self.explain_at(self.path, matched, rule);
+    }
+
+    fn explain_at(&mut self, path: PathRef<'a>, matched: bool, rule: impl FnOnce() -> String) {
+        if let Some(decisions) = &mut self.acc.decisions {
+            decisions.push(DecisionRef {
+                path,
+                matched,
+                rule: rule(),
+            });
+        }
+    }
+
+    /// Runs `compare` on the current node, recording `rule` as the rule that decided it. The node
+    /// matched if `compare` found no differences, which is only known afterwards.
+    fn explained(&mut self, rule: impl FnOnce() -> String, compare: impl FnOnce(&mut Self)) {
+        let differences = self.acc.differences.len();
+        let decision = self.acc.decisions.as_mut().map(|decisions| {
+            decisions.push(DecisionRef {
+                path: self.path,
+                matched: true,
+                rule: rule(),
+            });
+            decisions.len() - 1
+        });
+        compare(self);
+        if let (Some(decision), Some(decisions)) = (decision, &mut self.acc.decisions) {
+            decisions[decision].matched = self.acc.differences.len() == differences;
         }
     }
-    fn on_array_contains(&mut self, lhs: &'a Value) {
+
+    fn at_max_depth(&self) -> bool {
+        self.config
+            .max_depth
+            .is_some_and(|max_depth| self.path.depth() >= max_depth)
+    }
+
+    fn push_mismatch(&mut self, lhs: &'v Value) {
+        self.acc.differences.push(DifferenceRef {
+            lhs: Some(lhs),
+            rhs: Some(self.rhs),
+            path: self.path,
+            config: self.config,
+        });
+    }
+
+    /// Checks that `lhs` contains the items of the expected array in any order. Unless `open` is
+    /// set, `lhs` can't contain any other items.
+    fn on_array_contains(&mut self, lhs: &'v Value, open: bool) {
         if let Some(rhs) = self.rhs.as_array() {
             let lhs_array = lhs.as_array().unwrap();
 
             let lhs_len = lhs_array.len();
             let rhs_len = rhs.len();
 
-            if self.config.compare_mode == CompareMode::Strict && lhs_len != rhs_len {
-                self.acc.push(DifferenceRef {
+            if !open && lhs_len != rhs_len {
+                self.acc.differences.push(DifferenceRef {
                     lhs: Some(lhs),
                     rhs: Some(self.rhs),
-                    path: self.path.clone(),
-                    config: self.config.clone(),
+                    path: self.path,
+                    config: self.config,
                 });
                 return;
             }
@@ -119,167 +620,705 @@ impl<'a> DiffFolder<'a, '_> {
                 // (expected) array.
                 let rhs_item_count = rhs
                     .iter()
-                    .filter(|i| diff(rhs_item, i, self.config).is_empty())
+                    .filter(|i| *i == rhs_item || self.matches(rhs_item, i, self.path))
                     .count();
                 // Make sure that lhs (actual) has at least as many items matching the rhs
                 // (expected) item.
                 let lhs_matching_items_count = lhs_array
                     .iter()
-                    .filter(|lhs_item| diff(lhs_item, rhs_item, self.config).is_empty())
+                    .enumerate()
+                    .filter(|(idx, lhs_item)| {
+                        self.matches(
+                            lhs_item,
+                            rhs_item,
+                            self.path.append(self.arena, KeyRef::Idx(*idx)),
+                        )
+                    })
                     .count();
                 if lhs_matching_items_count < rhs_item_count {
-                    self.acc.push(DifferenceRef {
+                    self.acc.differences.push(DifferenceRef {
                         lhs: Some(lhs),
                         rhs: Some(self.rhs),
-                        path: self.path.clone(),
-                        config: self.config.clone(),
+                        path: self.path,
+                        config: self.config,
                     });
-                    break;
+                    return;
                 }
             }
+
+            if open && self.config.report_extras {
+                self.note_unmatched_items(lhs_array, rhs);
+            }
         } else {
-            self.acc.push(DifferenceRef {
+            self.acc.differences.push(DifferenceRef {
                 lhs: Some(lhs),
                 rhs: Some(self.rhs),
-                path: self.path.clone(),
-                config: self.config.clone(),
+                path: self.path,
+                config: self.config,
             });
         }
     }
 
-    fn on_array(&mut self, lhs: &'a Value) {
-        if self.config.array_sorting_mode == ArraySortingMode::Ignore {
-            return self.on_array_contains(lhs);
+    /// Notes the items of `lhs` left over after pairing each expected item with a matching one.
+    fn note_unmatched_items(&mut self, lhs: &'v [Value], rhs: &'v [Value]) {
+        let mut unmatched = (0..lhs.len()).collect::<Vec<_>>();
+        for rhs_item in rhs {
+            let matching = unmatched.iter().position(|idx| {
+                self.matches(
+                    &lhs[*idx],
+                    rhs_item,
+                    self.path.append(self.arena, KeyRef::Idx(*idx)),
+                )
+            });
+            if let Some(position) = matching {
+                unmatched.remove(position);
+            }
+        }
+        for idx in unmatched {
+            self.acc
+                .extras
+                .push(self.path.append(self.arena, KeyRef::Idx(idx)));
+        }
+    }
+
+    /// Compares arrays as sets, ignoring order and duplicates. Items only found on one side are
+    /// reported at their index on that side. Unless `open` is set, `lhs` can't contain items that
+    /// aren't expected.
+    fn on_array_set(&mut self, lhs: &'v Value, open: bool) {
+        let (Some(lhs_items), Some(rhs_items)) = (lhs.as_array(), self.rhs.as_array()) else {
+            return self.push_mismatch(lhs);
+        };
+
+        for (idx, rhs) in rhs_items.iter().enumerate() {
+            let found = lhs_items.iter().enumerate().any(|(lhs_idx, lhs)| {
+                self.matches(lhs, rhs, self.path.append(self.arena, KeyRef::Idx(lhs_idx)))
+            });
+            if !found {
+                self.acc.differences.push(DifferenceRef {
+                    lhs: None,
+                    rhs: Some(rhs),
+                    path: self.path.append(self.arena, KeyRef::Idx(idx)),
+                    config: self.config,
+                });
+            }
+        }
+
+        for (idx, lhs) in lhs_items.iter().enumerate() {
+            let path = self.path.append(self.arena, KeyRef::Idx(idx));
+            let expected = rhs_items.iter().any(|rhs| self.matches(lhs, rhs, path));
+            if expected {
+                continue;
+            }
+            if open {
+                if self.config.report_extras {
+                    self.acc.extras.push(path);
+                }
+            } else {
+                self.acc.differences.push(DifferenceRef {
+                    lhs: Some(lhs),
+                    rhs: None,
+                    path,
+                    config: self.config,
+                });
+            }
+        }
+    }
+
+    /// Checks that `lhs` and the expected array contain the same items in any order, each item
+    /// being paired with a different item of the other array. If `open` is set, `lhs` may contain
+    /// additional items.
+    fn on_array_multiset(&mut self, lhs: &'v Value, open: bool) {
+        let (Some(lhs_items), Some(rhs_items)) = (lhs.as_array(), self.rhs.as_array()) else {
+            return self.push_mismatch(lhs);
+        };
+
+        let candidates = rhs_items
+            .iter()
+            .map(|rhs| {
+                (0..lhs_items.len())
+                    .filter(|&idx| {
+                        let path = self.path.append(self.arena, KeyRef::Idx(idx));
+                        self.matches(&lhs_items[idx], rhs, path)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut paired = vec![false; lhs_items.len()];
+        for (rhs_idx, lhs_idx) in pair_items(&candidates, lhs_items.len())
+            .into_iter()
+            .enumerate()
+        {
+            match lhs_idx {
+                Some(lhs_idx) => paired[lhs_idx] = true,
+                None => self.push_missing_item(rhs_idx, &rhs_items[rhs_idx]),
+            }
+        }
+        for (idx, lhs) in lhs_items.iter().enumerate() {
+            if !paired[idx] {
+                self.push_unexpected_item(idx, lhs, open);
+            }
+        }
+    }
+
+    fn on_array(&mut self, lhs: &'v Value) {
+        let mode = self
+            .path_mode
+            .or(self.config.array_compare_mode)
+            .unwrap_or(self.config.compare_mode);
+        let sorting_mode = self.config.array_sorting_mode_for(lhs, self.rhs);
+        let open = match sorting_mode {
+            ArraySortingMode::Prefix => true,
+            ArraySortingMode::IgnoreStrict => false,
+            _ => mode == CompareMode::Inclusive,
+        };
+        let (rhs, path_mode) = (self.rhs, self.path_mode);
+        let key = self.config.array_key_at(&self.path);
+        let diff_mode = self.config.array_diff_mode;
+        let rule = || {
+            let order = match (key, sorting_mode) {
+                (Some(key), _) => format!("by key {}", key),
+                (None, ArraySortingMode::Consider) => match diff_mode {
+                    ArrayDiffMode::Index => "in order".to_string(),
+                    ArrayDiffMode::Sequence => "as a sequence".to_string(),
+                },
+                (None, ArraySortingMode::Ignore) => "ignoring order".to_string(),
+                (None, ArraySortingMode::Prefix) => "as a prefix".to_string(),
+                (None, ArraySortingMode::Set) => "as a set".to_string(),
+                (None, ArraySortingMode::IgnoreStrict) => "as a multiset".to_string(),
+            };
+            container_rule(rhs, format!("array compared {}", order), mode, path_mode)
+        };
+        self.explained(rule, |folder| folder.compare_arrays(lhs, open));
+    }
+
+    /// Compares arrays by pairing their items by the value at `key`. If `open` is set, `lhs` may
+    /// contain additional items.
+    fn on_array_by_key(&mut self, lhs: &'v Value, key: &'a Path, open: bool) {
+        let (Some(lhs_items), Some(rhs_items)) = (lhs.as_array(), self.rhs.as_array()) else {
+            return self.push_mismatch(lhs);
+        };
+
+        let mut paired = vec![false; lhs_items.len()];
+        for (rhs_idx, rhs) in rhs_items.iter().enumerate() {
+            let lhs_idx = get(rhs, key.keys()).and_then(|rhs_key| {
+                (0..lhs_items.len())
+                    .find(|&idx| !paired[idx] && get(&lhs_items[idx], key.keys()) == Some(rhs_key))
+            });
+            match lhs_idx {
+                Some(lhs_idx) => {
+                    paired[lhs_idx] = true;
+                    let path = self.path.append(self.arena, KeyRef::Idx(lhs_idx));
+                    diff_with(
+                        &lhs_items[lhs_idx],
+                        rhs,
+                        self.config,
+                        path,
+                        self.arena,
+                        self.acc,
+                    );
+                }
+                None => self.push_missing_item(rhs_idx, rhs),
+            }
+        }
+
+        for (idx, lhs) in lhs_items.iter().enumerate() {
+            if !paired[idx] {
+                self.push_unexpected_item(idx, lhs, open);
+            }
+        }
+    }
+
+    /// Compares arrays in order, aligning their items along the longest common subsequence of
+    /// matching items. If `open` is set, `lhs` may contain additional items anywhere.
+    ///
+    /// Runs of items between aligned ones are paired up and compared item by item, so a changed
+    /// item is reported as such rather than as removed and inserted. The items left over in a run
+    /// are reported as inserted, at their index in `rhs`, or as removed, at their index in `lhs`.
+    fn on_array_sequence(&mut self, lhs: &'v Value, open: bool) {
+        let (Some(lhs_items), Some(rhs_items)) = (lhs.as_array(), self.rhs.as_array()) else {
+            return self.push_mismatch(lhs);
+        };
+        let (lhs_len, rhs_len) = (lhs_items.len(), rhs_items.len());
+
+        // `lengths[i][j]` is the length of the longest common subsequence of `lhs_items[i..]` and
+        // `rhs_items[j..]`.
+        let mut matching = vec![vec![false; rhs_len]; lhs_len];
+        let mut lengths = vec![vec![0; rhs_len + 1]; lhs_len + 1];
+        for i in (0..lhs_len).rev() {
+            let path = self.path.append(self.arena, KeyRef::Idx(i));
+            for j in (0..rhs_len).rev() {
+                matching[i][j] = self.matches(&lhs_items[i], &rhs_items[j], path);
+                lengths[i][j] = if matching[i][j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        let (mut removed, mut inserted) = (vec![], vec![]);
+        loop {
+            let aligned = i < lhs_len && j < rhs_len && matching[i][j];
+            if aligned || (i == lhs_len && j == rhs_len) {
+                for (&lhs_idx, &rhs_idx) in removed.iter().zip(&inserted) {
+                    let path = self.path.append(self.arena, KeyRef::Idx(lhs_idx));
+                    let (lhs, rhs) = (&lhs_items[lhs_idx], &rhs_items[rhs_idx]);
+                    diff_with(lhs, rhs, self.config, path, self.arena, self.acc);
+                }
+                let paired = removed.len().min(inserted.len());
+                for &lhs_idx in &removed[paired..] {
+                    self.push_unexpected_item(lhs_idx, &lhs_items[lhs_idx], open);
+                }
+                for &rhs_idx in &inserted[paired..] {
+                    self.push_missing_item(rhs_idx, &rhs_items[rhs_idx]);
+                }
+                removed.clear();
+                inserted.clear();
+            }
+            if aligned {
+                let path = self.path.append(self.arena, KeyRef::Idx(i));
+                diff_with(
+                    &lhs_items[i],
+                    &rhs_items[j],
+                    self.config,
+                    path,
+                    self.arena,
+                    self.acc,
+                );
+                (i, j) = (i + 1, j + 1);
+            } else if i < lhs_len && (j == rhs_len || lengths[i + 1][j] >= lengths[i][j + 1]) {
+                removed.push(i);
+                i += 1;
+            } else if j < rhs_len {
+                inserted.push(j);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Reports the item at `idx` of the expected array as missing from `lhs`.
+    fn push_missing_item(&mut self, idx: usize, rhs: &'v Value) {
+        let path = self.path.append(self.arena, KeyRef::Idx(idx));
+        if self.ignored(path) {
+            return;
+        }
+        self.explain_at(path, false, missing_rule);
+        self.acc.differences.push(DifferenceRef {
+            lhs: None,
+            rhs: Some(rhs),
+            path,
+            config: self.config,
+        });
+    }
+
+    /// Reports the item at `idx` of `lhs` as unexpected, or notes it as an extra if `open` is set.
+    fn push_unexpected_item(&mut self, idx: usize, lhs: &'v Value, open: bool) {
+        let path = self.path.append(self.arena, KeyRef::Idx(idx));
+        if self.ignored(path) {
+            return;
+        }
+        if open {
+            self.explain_at(path, true, || "extra item allowed".to_string());
+            if self.config.report_extras {
+                self.acc.extras.push(path);
+            }
+        } else {
+            self.explain_at(path, false, unexpected_rule);
+            self.acc.differences.push(DifferenceRef {
+                lhs: Some(lhs),
+                rhs: None,
+                path,
+                config: self.config,
+            });
+        }
+    }
+
+    /// Compares arrays item by item. If `open` is set, `lhs` may contain additional items.
+    fn compare_arrays(&mut self, lhs: &'v Value, open: bool) {
+        if let Some(key) = self.config.array_key_at(&self.path) {
+            return self.on_array_by_key(lhs, key, open);
+        }
+        match self.config.array_sorting_mode_for(lhs, self.rhs) {
+            ArraySortingMode::Ignore => return self.on_array_contains(lhs, open),
+            ArraySortingMode::Set => return self.on_array_set(lhs, open),
+            ArraySortingMode::IgnoreStrict => return self.on_array_multiset(lhs, open),
+            ArraySortingMode::Consider
+                if self.config.array_diff_mode == ArrayDiffMode::Sequence =>
+            {
+                return self.on_array_sequence(lhs, open)
+            }
+            ArraySortingMode::Consider | ArraySortingMode::Prefix => {}
         }
 
         if let Some(rhs) = self.rhs.as_array() {
             let lhs = lhs.as_array().unwrap();
 
-            match self.config.compare_mode {
-                CompareMode::Inclusive => {
-                    for (idx, rhs) in rhs.iter().enumerate() {
-                        let path = self.path.append(KeyRef::Idx(idx));
-
-                        if let Some(lhs) = lhs.get(idx) {
-                            diff_with(lhs, rhs, self.config, path, self.acc)
-                        } else {
-                            self.acc.push(DifferenceRef {
+            if open {
+                if self.config.report_extras || self.acc.decisions.is_some() {
+                    for idx in rhs.len()..lhs.len() {
+                        let path = self.path.append(self.arena, KeyRef::Idx(idx));
+                        if self.ignored(path) {
+                            continue;
+                        }
+                        self.explain_at(path, true, || "extra item allowed".to_string());
+                        if self.config.report_extras {
+                            self.acc.extras.push(path);
+                        }
+                    }
+                }
+                for (idx, rhs) in rhs.iter().enumerate() {
+                    let path = self.path.append(self.arena, KeyRef::Idx(idx));
+
+                    if let Some(lhs) = lhs.get(idx) {
+                        diff_with(lhs, rhs, self.config, path, self.arena, self.acc)
+                    } else if !self.ignored(path) {
+                        self.explain_at(path, false, missing_rule);
+                        self.acc.differences.push(DifferenceRef {
+                            lhs: None,
+                            rhs: Some(rhs),
+                            path,
+                            config: self.config,
+                        });
+                    }
+                }
+            } else {
+                let all_keys = rhs
+                    .indexes()
+                    .into_iter()
+                    .chain(lhs.indexes())
+                    .collect::<HashSet<_>>();
+                for key in all_keys {
+                    let path = self.path.append(self.arena, KeyRef::Idx(key));
+
+                    match (lhs.get(key), rhs.get(key)) {
+                        (None, Some(_)) | (Some(_), None) if self.ignored(path) => {}
+                        (Some(lhs), Some(rhs)) => {
+                            diff_with(lhs, rhs, self.config, path, self.arena, self.acc);
+                        }
+                        (None, Some(rhs)) => {
+                            self.explain_at(path, false, missing_rule);
+                            self.acc.differences.push(DifferenceRef {
                                 lhs: None,
-                                rhs: Some(self.rhs),
+                                rhs: Some(rhs),
                                 path,
-                                config: self.config.clone(),
+                                config: self.config,
                             });
                         }
-                    }
-                }
-                CompareMode::Strict => {
-                    let all_keys = rhs
-                        .indexes()
-                        .into_iter()
-                        .chain(lhs.indexes())
-                        .collect::<HashSet<_>>();
-                    for key in all_keys {
-                        let path = self.path.append(KeyRef::Idx(key));
-
-                        match (lhs.get(key), rhs.get(key)) {
-                            (Some(lhs), Some(rhs)) => {
-                                diff_with(lhs, rhs, self.config, path, self.acc);
-                            }
-                            (None, Some(rhs)) => {
-                                self.acc.push(DifferenceRef {
-                                    lhs: None,
-                                    rhs: Some(rhs),
-                                    path,
-                                    config: self.config.clone(),
-                                });
-                            }
-                            (Some(lhs), None) => {
-                                self.acc.push(DifferenceRef {
-                                    lhs: Some(lhs),
-                                    rhs: None,
-                                    path,
-                                    config: self.config.clone(),
-                                });
-                            }
-                            (None, None) => {
-                                unreachable!("at least one of the maps should have the key")
-                            }
+                        (Some(lhs), None) => {
+                            self.explain_at(path, false, unexpected_rule);
+                            self.acc.differences.push(DifferenceRef {
+                                lhs: Some(lhs),
+                                rhs: None,
+                                path,
+                                config: self.config,
+                            });
+                        }
+                        (None, None) => {
+                            unreachable!("at least one of the maps should have the key")
                         }
                     }
                 }
             }
         } else {
-            self.acc.push(DifferenceRef {
+            self.acc.differences.push(DifferenceRef {
                 lhs: Some(lhs),
                 rhs: Some(self.rhs),
-                path: self.path.clone(),
-                config: self.config.clone(),
+                path: self.path,
+                config: self.config,
             });
         }
     }
 
-    fn on_object(&mut self, lhs: &'a Value) {
+    fn on_object(&mut self, lhs: &'v Value) {
+        let mode = self
+            .path_mode
+            .or(self.config.object_compare_mode)
+            .unwrap_or(self.config.compare_mode);
+        let (rhs, path_mode) = (self.rhs, self.path_mode);
+        let rule = || container_rule(rhs, "object compared".to_string(), mode, path_mode);
+        self.explained(rule, |folder| {
+            folder.compare_objects(lhs, mode == CompareMode::Inclusive)
+        });
+    }
+
+    /// Compares objects field by field. If `open` is set, `lhs` may contain additional fields.
+    fn compare_objects(&mut self, lhs: &'v Value, open: bool) {
         if let Some(rhs) = self.rhs.as_object() {
             let lhs = lhs.as_object().unwrap();
 
-            match self.config.compare_mode {
-                CompareMode::Inclusive => {
-                    for (key, rhs) in rhs.iter() {
-                        let path = self.path.append(KeyRef::Field(key));
+            for (key, lhs, rhs) in self.object_entries(lhs, rhs, open) {
+                let path = self.path.append(self.arena, KeyRef::Field(key));
 
-                        if let Some(lhs) = lhs.get(key) {
-                            diff_with(lhs, rhs, self.config, path, self.acc)
-                        } else {
-                            self.acc.push(DifferenceRef {
-                                lhs: None,
-                                rhs: Some(self.rhs),
-                                path,
-                                config: self.config.clone(),
-                            });
-                        }
+                match (lhs, rhs) {
+                    (None, Some(_)) | (Some(_), None) if self.ignored(path) => {}
+                    (Some(lhs), Some(rhs)) => {
+                        diff_with(lhs, rhs, self.config, path, self.arena, self.acc);
                     }
-                }
-                CompareMode::Strict => {
-                    let all_keys = rhs.keys().chain(lhs.keys()).collect::<HashSet<_>>();
-                    for key in all_keys {
-                        let path = self.path.append(KeyRef::Field(key));
-
-                        match (lhs.get(key), rhs.get(key)) {
-                            (Some(lhs), Some(rhs)) => {
-                                diff_with(lhs, rhs, self.config, path, self.acc);
-                            }
-                            (None, Some(rhs)) => {
-                                self.acc.push(DifferenceRef {
-                                    lhs: None,
-                                    rhs: Some(rhs),
-                                    path,
-                                    config: self.config.clone(),
-                                });
-                            }
-                            (Some(lhs), None) => {
-                                self.acc.push(DifferenceRef {
-                                    lhs: Some(lhs),
-                                    rhs: None,
-                                    path,
-                                    config: self.config.clone(),
-                                });
-                            }
-                            (None, None) => {
-                                unreachable!("at least one of the maps should have the key")
-                            }
-                        }
+                    (None, Some(rhs)) => {
+                        self.explain_at(path, false, missing_rule);
+                        self.acc.differences.push(DifferenceRef {
+                            lhs: None,
+                            rhs: Some(rhs),
+                            path,
+                            config: self.config,
+                        });
+                    }
+                    (Some(lhs), None) => {
+                        self.explain_at(path, false, unexpected_rule);
+                        self.acc.differences.push(DifferenceRef {
+                            lhs: Some(lhs),
+                            rhs: None,
+                            path,
+                            config: self.config,
+                        });
+                    }
+                    (None, None) => {
+                        unreachable!("at least one of the maps should have the key")
                     }
                 }
             }
         } else {
-            self.acc.push(DifferenceRef {
+            self.acc.differences.push(DifferenceRef {
                 lhs: Some(lhs),
                 rhs: Some(self.rhs),
-                path: self.path.clone(),
-                config: self.config.clone(),
+                path: self.path,
+                config: self.config,
             });
         }
     }
+
+    /// Pairs up the fields of two objects.
+    ///
+    /// Fields only present in `lhs` are included unless `open` is set, in which case they are noted
+    /// as extras if requested. With `numeric_keys` enabled,
+    /// numeric-looking keys are paired by value and the entries are sorted numerically.
+    fn object_entries(
+        &mut self,
+        lhs: &'v Map<String, Value>,
+        rhs: &'v Map<String, Value>,
+        open: bool,
+    ) -> Vec<ObjectEntry<'v>> {
+        let numeric_lhs_keys = if self.config.numeric_keys {
+            lhs.keys()
+                .filter(|key| !rhs.contains_key(*key))
+                .filter_map(|key| Some((numeric_key(key)?.to_bits(), key.as_str())))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut matched = HashSet::new();
+        let mut entries = vec![];
+        for (key, rhs) in rhs {
+            let lhs_key = if lhs.contains_key(key) {
+                Some(key.as_str())
+            } else {
+                numeric_key(key).and_then(|n| numeric_lhs_keys.get(&n.to_bits()).copied())
+            };
+
+            match lhs_key {
+                Some(lhs_key) if matched.insert(lhs_key) => {
+                    entries.push((lhs_key, lhs.get(lhs_key), Some(rhs)));
+                }
+                _ => entries.push((key.as_str(), None, Some(rhs))),
+            }
+        }
+
+        if !open {
+            for (key, lhs) in lhs {
+                if !matched.contains(key.as_str()) {
+                    entries.push((key, Some(lhs), None));
+                }
+            }
+        } else if self.config.report_extras || self.acc.decisions.is_some() {
+            for key in lhs.keys() {
+                if !matched.contains(key.as_str()) {
+                    let path = self.path.append(self.arena, KeyRef::Field(key));
+                    if self.ignored(path) {
+                        continue;
+                    }
+                    self.explain_at(path, true, || "extra field allowed".to_string());
+                    if self.config.report_extras {
+                        self.acc.extras.push(path);
+                    }
+                }
+            }
+        }
+
+        if self.config.numeric_keys {
+            entries.sort_by(|(lhs, _, _), (rhs, _, _)| {
+                match (numeric_key(lhs), numeric_key(rhs)) {
+                    (Some(lhs), Some(rhs)) => lhs.total_cmp(&rhs),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            });
+        }
+
+        entries
+    }
+}
+
+/// Returns `rule` if `lhs` and `rhs` have the same type, since values of different types never
+/// match.
+fn leaf_rule(lhs: &Value, rhs: &Value, rule: &str) -> String {
+    if std::mem::discriminant(lhs) == std::mem::discriminant(rhs) {
+        rule.to_string()
+    } else {
+        "values have different types".to_string()
+    }
+}
+
+fn string_rule(lhs: &Value, rhs: &Value, config: &Config, matched: bool) -> String {
+    let (Value::String(lhs), Value::String(rhs)) = (lhs, rhs) else {
+        return leaf_rule(lhs, rhs, "compared exactly");
+    };
+    let line_endings = format!(
+        "compared with line ending mode {:?}",
+        config.line_ending_mode
+    );
+    let string_mode = format!(
+        "compared with string compare mode {:?}",
+        config.string_compare_mode
+    );
+    if lhs == rhs {
+        return "compared exactly".to_string();
+    }
+    if matched {
+        let (lhs, rhs) = (
+            normalize::line_endings(lhs, config.line_ending_mode),
+            normalize::line_endings(rhs, config.line_ending_mode),
+        );
+        return if lhs == rhs {
+            line_endings
+        } else if strings_equal(&lhs, &rhs, config.string_compare_mode) {
+            string_mode
+        } else {
+            "compared as timestamps".to_string()
+        };
+    }
+
+    let mut rules = vec![];
+    if config.line_ending_mode != LineEndingMode::Exact {
+        rules.push(line_endings);
+    }
+    if config.string_compare_mode != StringCompareMode::Exact {
+        rules.push(string_mode);
+    }
+    if config.timestamps {
+        rules.push("compared as timestamps".to_string());
+    }
+    if rules.is_empty() {
+        "compared exactly".to_string()
+    } else {
+        rules.join(", ")
+    }
+}
+
+fn number_rule(lhs: &Value, rhs: &Value, config: &Config) -> String {
+    let float_mode = match config.float_compare_mode {
+        FloatCompareMode::Exact => String::new(),
+        mode => format!(" with float compare mode {:?}", mode),
+    };
+    let rule = match config.numeric_mode {
+        NumericMode::AssumeFloat => format!("compared as floats{}", float_mode),
+        NumericMode::Strict if lhs.is_f64() && rhs.is_f64() => {
+            format!("compared exactly{}", float_mode)
+        }
+        NumericMode::Strict => "compared exactly".to_string(),
+    };
+    leaf_rule(lhs, rhs, &rule)
+}
+
+/// Describes how an array or object was compared, given how its items or fields were compared.
+fn container_rule(
+    rhs: &Value,
+    rule: String,
+    mode: CompareMode,
+    path_mode: Option<CompareMode>,
+) -> String {
+    if Matcher::parse(rhs).is_none() && !rhs.is_array() && !rhs.is_object() {
+        return "values have different types".to_string();
+    }
+    let mode = match mode {
+        CompareMode::Inclusive => "inclusive",
+        CompareMode::Strict => "strict",
+    };
+    let source = if path_mode.is_some() {
+        " set for its path"
+    } else {
+        ""
+    };
+    format!("{} in {} mode{}", rule, mode, source)
+}
+
+/// Pairs as many expected items as possible with different items of the actual array, given the
+/// indexes of the actual items each expected item matches. Returns the index paired with each
+/// expected item.
+fn pair_items(candidates: &[Vec<usize>], lhs_len: usize) -> Vec<Option<usize>> {
+    // Finds an actual item for `rhs_idx`, moving the expected items already paired to other
+    // candidates if needed.
+    fn augment(
+        rhs_idx: usize,
+        candidates: &[Vec<usize>],
+        owners: &mut [Option<usize>],
+        visited: &mut [bool],
+    ) -> bool {
+        for &lhs_idx in &candidates[rhs_idx] {
+            if visited[lhs_idx] {
+                continue;
+            }
+            visited[lhs_idx] = true;
+            if owners[lhs_idx].is_none_or(|owner| augment(owner, candidates, owners, visited)) {
+                owners[lhs_idx] = Some(rhs_idx);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut owners = vec![None; lhs_len];
+    for rhs_idx in 0..candidates.len() {
+        augment(rhs_idx, candidates, &mut owners, &mut vec![false; lhs_len]);
+    }
+    let mut pairs = vec![None; candidates.len()];
+    for (lhs_idx, owner) in owners.into_iter().enumerate() {
+        if let Some(rhs_idx) = owner {
+            pairs[rhs_idx] = Some(lhs_idx);
+        }
+    }
+    pairs
+}
+
+fn missing_rule() -> String {
+    "missing from the actual value".to_string()
+}
+
+fn unexpected_rule() -> String {
+    "not in the expected value".to_string()
+}
+
+type ObjectEntry<'a> = (&'a str, Option<&'a Value>, Option<&'a Value>);
+
+/// Returns the value of an object key that looks like a number, such as `"2"`, `"-1"` or `"1.5"`.
+fn numeric_key(key: &str) -> Option<f64> {
+    let is_numeric = key.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+    if !is_numeric {
+        return None;
+    }
+    // Adding zero turns negative zero into zero, so both keys compare equal.
+    key.parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .map(|n| n + 0.0)
 }
 
 /// Represents a difference between two JSON values.
@@ -288,7 +1327,7 @@ pub struct Difference {
     path: Path,
     lhs: Option<Value>,
     rhs: Option<Value>,
-    config: Config,
+    config: Arc<Config>,
 }
 
 impl Difference {
@@ -302,124 +1341,417 @@ impl Difference {
         &self.lhs
     }
 
-    /// Get the right-hand side, or "expected", value of the difference.
-    pub fn expected(&self) -> &Option<Value> {
-        &self.rhs
+    /// Get the right-hand side, or "expected", value of the difference.
+    pub fn expected(&self) -> &Option<Value> {
+        &self.rhs
+    }
+
+    /// Returns the left-hand side value of the difference, which is the actual value in inclusive
+    /// comparisons, or `None` if it is missing.
+    pub fn lhs(&self) -> Option<&Value> {
+        self.lhs.as_ref()
+    }
+
+    /// Returns the right-hand side value of the difference, which is the expected value in
+    /// inclusive comparisons, or `None` if it is missing.
+    pub fn rhs(&self) -> Option<&Value> {
+        self.rhs.as_ref()
+    }
+
+    /// Returns the kind of the difference.
+    ///
+    /// ```
+    /// use serde_json_assert::{try_assert_json_matches, CompareMode, Config, DifferenceKind};
+    /// use serde_json::json;
+    ///
+    /// let lhs = json!({ "a": 1, "b": "2", "c": true });
+    /// let rhs = json!({ "a": 2, "b": 2, "d": null });
+    /// let differences = try_assert_json_matches(&lhs, &rhs, &Config::new(CompareMode::Strict))
+    ///     .unwrap_err();
+    ///
+    /// let kinds = differences.iter().map(|diff| (diff.path().to_string(), diff.kind()));
+    /// assert_eq!(
+    ///     kinds.collect::<Vec<_>>(),
+    ///     [
+    ///         (".a".to_string(), DifferenceKind::ValueMismatch),
+    ///         (".b".to_string(), DifferenceKind::TypeMismatch),
+    ///         (".d".to_string(), DifferenceKind::Removed),
+    ///         (".c".to_string(), DifferenceKind::Added),
+    ///     ]
+    /// );
+    /// ```
+    pub fn kind(&self) -> DifferenceKind {
+        DifferenceKind::of(self.lhs(), self.rhs(), &self.config)
+    }
+
+    /// Returns the configuration used to generate this difference.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+}
+
+/// The kind of a [`Difference`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DifferenceKind {
+    /// The value is only present on the left-hand side, which is the actual value in inclusive
+    /// comparisons.
+    Added,
+    /// The value is only present on the right-hand side, which is the expected value in inclusive
+    /// comparisons.
+    Removed,
+    /// Both values have the same type but aren't equal, or the right-hand side is a matcher that
+    /// the left-hand side doesn't satisfy.
+    ValueMismatch,
+    /// The values have different types, such as a string and a number.
+    TypeMismatch,
+}
+
+impl DifferenceKind {
+    fn of(lhs: Option<&Value>, rhs: Option<&Value>, config: &Config) -> Self {
+        match (lhs, rhs) {
+            (Some(_), None) => DifferenceKind::Added,
+            (None, _) => DifferenceKind::Removed,
+            (Some(lhs), Some(rhs)) => {
+                if Matcher::find(rhs, config).is_none()
+                    && std::mem::discriminant(lhs) != std::mem::discriminant(rhs)
+                {
+                    DifferenceKind::TypeMismatch
+                } else {
+                    DifferenceKind::ValueMismatch
+                }
+            }
+        }
+    }
+}
+
+/// A difference between two JSON values that borrows the differing values instead of owning
+/// them where it can, returned by [`diff`](crate::diff).
+///
+/// It's displayed and serialized like a [`Difference`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorrowedDifference<'a> {
+    path: Path,
+    lhs: Option<Cow<'a, Value>>,
+    rhs: Option<Cow<'a, Value>>,
+    kind: DifferenceKind,
+    config: Arc<Config>,
+}
+
+impl BorrowedDifference<'_> {
+    /// Copies the differing values, for differences found in values that don't outlive them.
+    pub(crate) fn into_owned(self) -> BorrowedDifference<'static> {
+        BorrowedDifference {
+            path: self.path,
+            lhs: self.lhs.map(|lhs| Cow::Owned(lhs.into_owned())),
+            rhs: self.rhs.map(|rhs| Cow::Owned(rhs.into_owned())),
+            kind: self.kind,
+            config: self.config,
+        }
+    }
+
+    /// Returns the path to the difference.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the left-hand side value of the difference, which is the actual value in inclusive
+    /// comparisons, or `None` if it is missing.
+    pub fn lhs(&self) -> Option<&Value> {
+        self.lhs.as_deref()
+    }
+
+    /// Returns the right-hand side value of the difference, which is the expected value in
+    /// inclusive comparisons, or `None` if it is missing.
+    pub fn rhs(&self) -> Option<&Value> {
+        self.rhs.as_deref()
+    }
+
+    /// Returns the kind of the difference.
+    pub fn kind(&self) -> DifferenceKind {
+        self.kind
+    }
+}
+
+impl Difference {
+    pub(crate) fn new(path: Path, lhs: Option<Value>, rhs: Option<Value>, config: Config) -> Self {
+        Difference {
+            path,
+            lhs,
+            rhs,
+            config: Arc::new(config),
+        }
+    }
+
+    /// Converts the differences found by a single comparison, which all share its config.
+    fn from_refs(diffs: Vec<DifferenceRef>) -> Vec<Self> {
+        let mut config: Option<Arc<Config>> = None;
+        diffs
+            .into_iter()
+            .map(|diff| {
+                let config = config.get_or_insert_with(|| Arc::new(diff.config.clone()));
+                Difference {
+                    path: Path::from(diff.path),
+                    lhs: diff.lhs.cloned(),
+                    rhs: diff.rhs.cloned(),
+                    config: Arc::clone(config),
+                }
+            })
+            .collect()
+    }
+
+    /// Moves the difference below `prefix`, for differences found in a subtree.
+    pub(crate) fn nest_under(mut self, prefix: &Path) -> Self {
+        self.path = self.path.nest_under(prefix);
+        self
+    }
+}
+
+impl Difference {
+    pub(crate) fn fmt_with(&self, f: &mut fmt::Formatter, output: &Output) -> fmt::Result {
+        let (lhs, rhs) = (self.lhs.as_ref(), self.rhs.as_ref());
+        fmt_difference(f, output, &self.path, lhs, rhs, &self.config)
+    }
+}
+
+/// Describes the difference at `path` between `lhs` and `rhs`, for both kinds of differences.
+fn fmt_difference(
+    f: &mut fmt::Formatter,
+    output: &Output,
+    path: &Path,
+    lhs: Option<&Value>,
+    rhs: Option<&Value>,
+    config: &Config,
+) -> fmt::Result {
+    let json_to_string = |json: &Value| render::pretty(json, path, config);
+    let (lhs_label, rhs_label) = match config.compare_mode {
+        CompareMode::Inclusive => ("actual", "expected"),
+        CompareMode::Strict => ("lhs", "rhs"),
+    };
+
+    match (config.compare_mode, lhs, rhs) {
+        (_, Some(lhs), Some(rhs)) => {
+            write!(f, "json atoms at path \"{}\" are not equal", path)?;
+            if output.verbosity == Verbosity::Quiet {
+                return Ok(());
+            }
+            writeln!(f, ":")?;
+            // Inclusive comparisons show the expected value first.
+            let sides = match config.compare_mode {
+                CompareMode::Inclusive => {
+                    [(rhs_label, rhs, RHS_COLOR), (lhs_label, lhs, LHS_COLOR)]
+                }
+                CompareMode::Strict => [(lhs_label, lhs, LHS_COLOR), (rhs_label, rhs, RHS_COLOR)],
+            };
+            // Multi-line strings are shown as a diff of their lines.
+            let [(old_label, old, old_color), (new_label, new, new_color)] = sides;
+            if let (Value::String(old), Value::String(new)) = (old, new) {
+                if (old.contains('\n') || new.contains('\n'))
+                    && !render::is_masked(path.keys(), config)
+                {
+                    write!(f, "    lines (- {}, + {}):", old_label, new_label)?;
+                    for line in render::line_diff(old, new) {
+                        let (line, color) = match line {
+                            Line::Same(text) => (format!("  {}", text), None),
+                            Line::Removed(text) => (format!("- {}", text), Some(old_color)),
+                            Line::Added(text) => (format!("+ {}", text), Some(new_color)),
+                        };
+                        match color {
+                            Some(color) => write!(f, "\n        {}", output.paint(&line, color))?,
+                            None => write!(f, "\n        {}", line)?,
+                        }
+                    }
+                    return Ok(());
+                }
+            }
+            let mut texts = sides.map(|(_, value, _)| json_to_string(value));
+            // Floats that only differ beyond the configured precision are printed in full.
+            if texts[0] == texts[1] {
+                texts = sides.map(|(_, value, _)| render::pretty_exact(value, path, config));
+            }
+            for (idx, ((label, _, color), text)) in sides.into_iter().zip(texts).enumerate() {
+                writeln!(f, "    {}:", output.paint(label, color))?;
+                write!(f, "{}", text.indent(8))?;
+                if idx == 0 {
+                    writeln!(f)?;
+                }
+            }
+            if let (Value::String(lhs), Value::String(rhs)) = (lhs, rhs) {
+                if render::is_long_string(lhs, config) || render::is_long_string(rhs, config) {
+                    write!(
+                        f,
+                        "\n    first difference at byte offset {}",
+                        render::first_difference(lhs, rhs)
+                    )?;
+                }
+            }
+        }
+        (_, None, Some(rhs)) => {
+            write!(
+                f,
+                "json atom at path \"{}\" is missing from {}",
+                path, lhs_label
+            )?;
+            if output.verbosity == Verbosity::Verbose {
+                writeln!(f, ":")?;
+                writeln!(f, "    {}:", output.paint(rhs_label, RHS_COLOR))?;
+                write!(f, "{}", json_to_string(rhs).indent(8))?;
+            }
+        }
+        (_, Some(lhs), None) => {
+            write!(
+                f,
+                "json atom at path \"{}\" is missing from {}",
+                path, rhs_label
+            )?;
+            if output.verbosity == Verbosity::Verbose {
+                writeln!(f, ":")?;
+                writeln!(f, "    {}:", output.paint(lhs_label, LHS_COLOR))?;
+                write!(f, "{}", json_to_string(lhs).indent(8))?;
+            }
+        }
+        (_, None, None) => unreachable!("can't both be missing"),
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with(f, &Output::default())
+    }
+}
+
+/// Differences are serialized as objects with the `path`, `kind` and `compare_mode` of the
+/// difference, and its `lhs` and `rhs` values unless they are missing.
+///
+/// ```
+/// use serde_json_assert::{try_assert_json_matches, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Strict);
+/// let differences = try_assert_json_matches(&json!({ "a": 1 }), &json!({ "a": "1" }), &config)
+///     .unwrap_err();
+/// assert_eq!(
+///     serde_json::to_value(&differences).unwrap(),
+///     json!([{
+///         "path": ".a",
+///         "kind": "type_mismatch",
+///         "compare_mode": "strict",
+///         "lhs": 1,
+///         "rhs": "1",
+///     }])
+/// );
+/// ```
+impl Serialize for Difference {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (lhs, rhs) = (self.lhs.as_ref(), self.rhs.as_ref());
+        serialize_difference(serializer, &self.path, self.kind(), lhs, rhs, &self.config)
+    }
+}
+
+impl fmt::Display for BorrowedDifference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let output = Output::default();
+        fmt_difference(f, &output, &self.path, self.lhs(), self.rhs(), &self.config)
     }
+}
 
-    /// Returns the configuration used to generate this difference.
-    pub fn config(&self) -> &Config {
-        &self.config
+/// Borrowed differences are serialized like [`Difference`]s.
+impl Serialize for BorrowedDifference<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (lhs, rhs) = (self.lhs(), self.rhs());
+        serialize_difference(serializer, &self.path, self.kind, lhs, rhs, &self.config)
     }
 }
 
-impl<'a> From<DifferenceRef<'a>> for Difference {
-    fn from(diff: DifferenceRef<'a>) -> Self {
-        Difference {
-            path: Path::from(diff.path),
-            lhs: diff.lhs.cloned(),
-            rhs: diff.rhs.cloned(),
-            config: diff.config.clone(),
-        }
+fn serialize_difference<S: Serializer>(
+    serializer: S,
+    path: &Path,
+    kind: DifferenceKind,
+    lhs: Option<&Value>,
+    rhs: Option<&Value>,
+    config: &Config,
+) -> Result<S::Ok, S::Error> {
+    let len = 3 + usize::from(lhs.is_some()) + usize::from(rhs.is_some());
+    let mut map = serializer.serialize_map(Some(len))?;
+    map.serialize_entry("path", path)?;
+    map.serialize_entry("kind", &kind)?;
+    map.serialize_entry("compare_mode", compare_mode_name(config.compare_mode))?;
+    if let Some(lhs) = lhs {
+        map.serialize_entry("lhs", lhs)?;
     }
+    if let Some(rhs) = rhs {
+        map.serialize_entry("rhs", rhs)?;
+    }
+    map.end()
 }
 
-impl fmt::Display for Difference {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let json_to_string = |json: &Value| serde_json::to_string_pretty(json).unwrap();
-
-        match (&self.config.compare_mode, &self.lhs, &self.rhs) {
-            (CompareMode::Inclusive, Some(actual), Some(expected)) => {
-                writeln!(f, "json atoms at path \"{}\" are not equal:", self.path)?;
-                writeln!(f, "    expected:")?;
-                writeln!(f, "{}", json_to_string(expected).indent(8))?;
-                writeln!(f, "    actual:")?;
-                write!(f, "{}", json_to_string(actual).indent(8))?;
-            }
-            (CompareMode::Inclusive, None, Some(_expected)) => {
-                write!(
-                    f,
-                    "json atom at path \"{}\" is missing from actual",
-                    self.path
-                )?;
-            }
-            (CompareMode::Inclusive, Some(_actual), None) => {
-                unreachable!("stuff missing actual wont produce an error")
-            }
-            (CompareMode::Inclusive, None, None) => unreachable!("can't both be missing"),
-
-            (CompareMode::Strict, Some(lhs), Some(rhs)) => {
-                writeln!(f, "json atoms at path \"{}\" are not equal:", self.path)?;
-                writeln!(f, "    lhs:")?;
-                writeln!(f, "{}", json_to_string(lhs).indent(8))?;
-                writeln!(f, "    rhs:")?;
-                write!(f, "{}", json_to_string(rhs).indent(8))?;
-            }
-            (CompareMode::Strict, None, Some(_)) => {
-                write!(f, "json atom at path \"{}\" is missing from lhs", self.path)?;
-            }
-            (CompareMode::Strict, Some(_), None) => {
-                write!(f, "json atom at path \"{}\" is missing from rhs", self.path)?;
-            }
-            (CompareMode::Strict, None, None) => unreachable!("can't both be missing"),
+/// Differences are deserialized from the objects they are serialized as. The `kind` is ignored,
+/// since it follows from the values, and the difference gets a default config with its
+/// `compare_mode`, which is strict if missing.
+impl<'de> Deserialize<'de> for Difference {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut fields = Map::deserialize(deserializer)?;
+        let path = match fields.remove("path") {
+            Some(Value::String(path)) => path.parse().map_err(D::Error::custom)?,
+            _ => return Err(D::Error::custom("difference has no path")),
+        };
+        let compare_mode = match fields.remove("compare_mode") {
+            None => CompareMode::Strict,
+            Some(mode) if mode == "strict" => CompareMode::Strict,
+            Some(mode) if mode == "inclusive" => CompareMode::Inclusive,
+            Some(mode) => return Err(D::Error::custom(format!("unknown compare mode {}", mode))),
+        };
+        let (lhs, rhs) = (fields.remove("lhs"), fields.remove("rhs"));
+        if lhs.is_none() && rhs.is_none() {
+            return Err(D::Error::custom("difference has neither lhs nor rhs"));
         }
+        Ok(Difference::new(path, lhs, rhs, Config::new(compare_mode)))
+    }
+}
 
-        Ok(())
+fn compare_mode_name(compare_mode: CompareMode) -> &'static str {
+    match compare_mode {
+        CompareMode::Inclusive => "inclusive",
+        CompareMode::Strict => "strict",
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub(crate) struct DifferenceRef<'a> {
-    path: PathRef<'a>,
-    lhs: Option<&'a Value>,
-    rhs: Option<&'a Value>,
-    config: Config,
+/// Kinds are serialized as `"added"`, `"removed"`, `"value_mismatch"` and `"type_mismatch"`.
+impl Serialize for DifferenceKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            DifferenceKind::Added => "added",
+            DifferenceKind::Removed => "removed",
+            DifferenceKind::ValueMismatch => "value_mismatch",
+            DifferenceKind::TypeMismatch => "type_mismatch",
+        })
+    }
 }
 
-impl fmt::Display for DifferenceRef<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let json_to_string = |json: &Value| serde_json::to_string_pretty(json).unwrap();
-
-        match (&self.config.compare_mode, &self.lhs, &self.rhs) {
-            (CompareMode::Inclusive, Some(actual), Some(expected)) => {
-                writeln!(f, "json atoms at path \"{}\" are not equal:", self.path)?;
-                writeln!(f, "    expected:")?;
-                writeln!(f, "{}", json_to_string(expected).indent(8))?;
-                writeln!(f, "    actual:")?;
-                write!(f, "{}", json_to_string(actual).indent(8))?;
-            }
-            (CompareMode::Inclusive, None, Some(_expected)) => {
-                write!(
-                    f,
-                    "json atom at path \"{}\" is missing from actual",
-                    self.path
-                )?;
-            }
-            (CompareMode::Inclusive, Some(_actual), None) => {
-                unreachable!("stuff missing actual wont produce an error")
-            }
-            (CompareMode::Inclusive, None, None) => unreachable!("can't both be missing"),
-
-            (CompareMode::Strict, Some(lhs), Some(rhs)) => {
-                writeln!(f, "json atoms at path \"{}\" are not equal:", self.path)?;
-                writeln!(f, "    lhs:")?;
-                writeln!(f, "{}", json_to_string(lhs).indent(8))?;
-                writeln!(f, "    rhs:")?;
-                write!(f, "{}", json_to_string(rhs).indent(8))?;
-            }
-            (CompareMode::Strict, None, Some(_)) => {
-                write!(f, "json atom at path \"{}\" is missing from lhs", self.path)?;
-            }
-            (CompareMode::Strict, Some(_), None) => {
-                write!(f, "json atom at path \"{}\" is missing from rhs", self.path)?;
-            }
-            (CompareMode::Strict, None, None) => unreachable!("can't both be missing"),
+impl<'de> Deserialize<'de> for DifferenceKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "added" => Ok(DifferenceKind::Added),
+            "removed" => Ok(DifferenceKind::Removed),
+            "value_mismatch" => Ok(DifferenceKind::ValueMismatch),
+            "type_mismatch" => Ok(DifferenceKind::TypeMismatch),
+            kind => Err(D::Error::custom(format!(
+                "unknown difference kind {:?}",
+                kind
+            ))),
         }
-
-        Ok(())
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DifferenceRef<'v, 'a> {
+    path: PathRef<'a>,
+    lhs: Option<&'v Value>,
+    rhs: Option<&'v Value>,
+    config: &'a Config,
+}
+
 /// Represents a path to a JSON value in a tree structure.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Path {
@@ -429,11 +1761,34 @@ pub enum Path {
     Keys(Vec<Key>),
 }
 
+impl Path {
+    pub(crate) fn from_keys(keys: Vec<Key>) -> Self {
+        if keys.is_empty() {
+            Path::Root
+        } else {
+            Path::Keys(keys)
+        }
+    }
+
+    pub(crate) fn keys(&self) -> &[Key] {
+        match self {
+            Path::Root => &[],
+            Path::Keys(keys) => keys,
+        }
+    }
+
+    /// Returns this path relative to `prefix` as an absolute path.
+    pub(crate) fn nest_under(&self, prefix: &Path) -> Path {
+        let keys = prefix.keys().iter().chain(self.keys()).cloned().collect();
+        Path::from_keys(keys)
+    }
+}
+
 impl<'a> From<PathRef<'a>> for Path {
     fn from(path: PathRef<'a>) -> Self {
         match path {
             PathRef::Root => Path::Root,
-            PathRef::Keys(keys) => Path::Keys(keys.into_iter().map(Key::from).collect()),
+            PathRef::Keys(_) => Path::Keys(path.keys().into_iter().map(Key::from).collect()),
         }
     }
 }
@@ -452,22 +1807,89 @@ impl fmt::Display for Path {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum PathRef<'a> {
+/// Parses paths in the format used by difference reports, such as `.data.users[0].name`.
+///
+/// A leading `$` is accepted and ignored, so `$.a[1]` and `.a[1]` are the same path, as is the
+/// leading field written without a dot (`a[1]`). Fields containing `.` or `[` can be written as
+/// quoted JSON strings in brackets: `["a.b"]` (or `.["a.b"]`), which is also how such fields are
+/// displayed. An empty string, `$` and `(root)` all parse as [`Path::Root`].
+impl FromStr for Path {
+    type Err = String;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let keys = parse_segments(path)?
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => Ok(key),
+                Segment::Wildcard => Err(format!(
+                    "Invalid path {:?}: wildcards are only allowed in patterns",
+                    path
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Path::from_keys(keys))
+    }
+}
+
+/// Paths are serialized as strings in the format of failure messages, such as `.a[1]`.
+impl Serialize for Path {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Paths are deserialized from strings, see the [`FromStr`] implementation.
+impl<'de> Deserialize<'de> for Path {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A path used while walking values.
+///
+/// Paths share their ancestors with the paths of their siblings, so appending a key only
+/// allocates a single node in the arena of the comparison, whatever the depth.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum PathRef<'a> {
     Root,
-    Keys(Vec<KeyRef<'a>>),
+    Keys(&'a PathNode<'a>),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct PathNode<'a> {
+    parent: PathRef<'a>,
+    key: KeyRef<'a>,
+    depth: usize,
 }
 
 impl<'a> PathRef<'a> {
-    fn append(&self, next: KeyRef<'a>) -> PathRef<'a> {
+    fn depth(&self) -> usize {
         match self {
-            PathRef::Root => PathRef::Keys(vec![next]),
-            PathRef::Keys(list) => {
-                let mut copy = list.clone();
-                copy.push(next);
-                PathRef::Keys(copy)
-            }
+            PathRef::Root => 0,
+            PathRef::Keys(node) => node.depth,
+        }
+    }
+
+    /// Returns the keys of the path, starting from the root.
+    pub(crate) fn keys(&self) -> Vec<KeyRef<'a>> {
+        let mut keys = Vec::with_capacity(self.depth());
+        let mut path = self;
+        while let PathRef::Keys(node) = path {
+            keys.push(node.key);
+            path = &node.parent;
         }
+        keys.reverse();
+        keys
+    }
+
+    fn append(self, arena: &'a Bump, next: KeyRef<'a>) -> PathRef<'a> {
+        PathRef::Keys(arena.alloc(PathNode {
+            parent: self,
+            key: next,
+            depth: self.depth() + 1,
+        }))
     }
 }
 
@@ -475,8 +1897,8 @@ impl fmt::Display for PathRef<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             PathRef::Root => write!(f, "(root)"),
-            PathRef::Keys(keys) => {
-                for key in keys {
+            PathRef::Keys(_) => {
+                for key in self.keys() {
                     write!(f, "{}", key)?;
                 }
                 Ok(())
@@ -494,6 +1916,15 @@ pub enum Key {
     Field(String),
 }
 
+impl<'a> From<&'a Key> for KeyRef<'a> {
+    fn from(key: &'a Key) -> Self {
+        match key {
+            Key::Idx(idx) => KeyRef::Idx(*idx),
+            Key::Field(field) => KeyRef::Field(field),
+        }
+    }
+}
+
 impl<'a> From<KeyRef<'a>> for Key {
     fn from(key: KeyRef<'a>) -> Self {
         match key {
@@ -507,13 +1938,40 @@ impl fmt::Display for Key {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Key::Idx(idx) => write!(f, "[{}]", idx),
-            Key::Field(key) => write!(f, ".{}", key),
+            Key::Field(key) => write_field(f, key),
+        }
+    }
+}
+
+/// Indexes are serialized as numbers and fields as strings.
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Key::Idx(idx) => serializer.serialize_u64(*idx as u64),
+            Key::Field(field) => serializer.serialize_str(field),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(field) => Ok(Key::Field(field)),
+            Value::Number(idx) => idx
+                .as_u64()
+                .and_then(|idx| usize::try_from(idx).ok())
+                .map(Key::Idx)
+                .ok_or_else(|| D::Error::custom(format!("invalid array index {}", idx))),
+            key => Err(D::Error::custom(format!(
+                "expected a field name or an array index, found {}",
+                key
+            ))),
         }
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum KeyRef<'a> {
+pub(crate) enum KeyRef<'a> {
     Idx(usize),
     Field(&'a str),
 }
@@ -522,12 +1980,27 @@ impl fmt::Display for KeyRef<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             KeyRef::Idx(idx) => write!(f, "[{}]", idx),
-            KeyRef::Field(key) => write!(f, ".{}", key),
+            KeyRef::Field(key) => write_field(f, key),
         }
     }
 }
 
-fn fold_json<'a>(json: &'a Value, folder: &mut DiffFolder<'a, '_>) {
+/// Writes a field as `.field`, or as a quoted JSON string in brackets if it wouldn't be parsed
+/// back as the same field, such as `["a.b"]`, `[""]` or `["*"]`.
+fn write_field(f: &mut fmt::Formatter, field: &str) -> fmt::Result {
+    let plain = !field.is_empty()
+        && field != "*"
+        && !field.chars().any(|c| {
+            matches!(c, '.' | '[' | ']' | '"' | '\\') || c.is_whitespace() || c.is_control()
+        });
+    if plain {
+        write!(f, ".{}", field)
+    } else {
+        write!(f, "[{}]", Value::from(field))
+    }
+}
+
+fn fold_json<'v: 'a, 'a>(json: &'v Value, folder: &mut DiffFolder<'v, 'a, '_>) {
     match json {
         Value::Null => folder.on_null(json),
         Value::Bool(_) => folder.on_bool(json),
@@ -630,6 +2103,102 @@ mod test {
         assert_eq!(diffs.len(), 1);
     }
 
+    #[test]
+    fn test_string_compare_mode() {
+        let chars = |text: &str| text.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("héllo"), &chars("hello")), 1);
+
+        let config = Config::new(CompareMode::Strict)
+            .string_compare_mode(StringCompareMode::EditDistance(1));
+        assert_eq!(
+            diff(&json!(["cat", "dog"]), &json!(["cut", "dig"]), &config),
+            vec![]
+        );
+        assert_eq!(diff(&json!("cat"), &json!("cart!"), &config).len(), 1);
+        assert_eq!(diff(&json!("1"), &json!(1), &config).len(), 1);
+
+        let config = Config::new(CompareMode::Strict)
+            .string_compare_mode(StringCompareMode::Similarity(0.8));
+        assert_eq!(diff(&json!("abcde"), &json!("abcdX"), &config), vec![]);
+        assert_eq!(diff(&json!("abcd"), &json!("abcX"), &config).len(), 1);
+        assert_eq!(diff(&json!(""), &json!(""), &config), vec![]);
+    }
+
+    #[test]
+    fn test_array_sorting_mode_by_item_type() {
+        let config = Config::new(CompareMode::Strict)
+            .scalar_array_sorting_mode(ArraySortingMode::Ignore)
+            .container_array_sorting_mode(ArraySortingMode::Consider);
+        assert_eq!(
+            diff(
+                &json!({ "a": [2, 1], "b": [] }),
+                &json!({ "a": [1, 2], "b": [] }),
+                &config
+            ),
+            vec![]
+        );
+        assert_eq!(
+            diff(
+                &json!([{ "a": 2 }, { "a": 1 }]),
+                &json!([{ "a": 1 }, { "a": 2 }]),
+                &config
+            )
+            .len(),
+            2
+        );
+        assert_eq!(diff(&json!([[1], 2]), &json!([2, [1]]), &config).len(), 2);
+
+        let config = Config::new(CompareMode::Strict)
+            .array_sorting_mode(ArraySortingMode::Ignore)
+            .scalar_array_sorting_mode(ArraySortingMode::Prefix);
+        assert_eq!(diff(&json!([1, 2, 3]), &json!([1, 2]), &config), vec![]);
+        assert_eq!(diff(&json!([2, 1]), &json!([1, 2]), &config).len(), 2);
+        assert_eq!(
+            diff(
+                &json!([{ "a": 2 }, { "a": 1 }]),
+                &json!([{ "a": 1 }, { "a": 2 }]),
+                &config
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_assume_float_large_integers() {
+        let config = Config::new(CompareMode::Strict).numeric_mode(NumericMode::AssumeFloat);
+
+        let diffs = diff(
+            &json!(9007199254740993_u64),
+            &json!(9007199254740992_u64),
+            &config,
+        );
+        assert_eq!(diffs.len(), 1);
+        let diffs = diff(&json!(u64::MAX), &json!(u64::MAX - 1), &config);
+        assert_eq!(diffs.len(), 1);
+        let diffs = diff(&json!(i64::MIN), &json!(i64::MIN + 1), &config);
+        assert_eq!(diffs.len(), 1);
+        let diffs = diff(&json!(u64::MAX), &json!(-1), &config);
+        assert_eq!(diffs.len(), 1);
+        let diffs = diff(&json!(u64::MAX), &json!(u64::MAX), &config);
+        assert_eq!(diffs, vec![]);
+
+        // As soon as one side is a float, both are compared as floats.
+        let diffs = diff(
+            &json!(9007199254740993_u64),
+            &json!(9007199254740992.0),
+            &config,
+        );
+        assert_eq!(diffs, vec![]);
+
+        let config = config.float_compare_mode(FloatCompareMode::Epsilon(2.0));
+        let diffs = diff(&json!(u64::MAX), &json!(u64::MAX - 2), &config);
+        assert_eq!(diffs, vec![]);
+        let diffs = diff(&json!(u64::MAX), &json!(u64::MAX - 3), &config);
+        assert_eq!(diffs.len(), 1);
+    }
+
     #[test]
     fn test_diffing_array() {
         let config = Config::new(CompareMode::Inclusive);
@@ -760,4 +2329,665 @@ mod test {
         let diffs = diff(&json, &json, &config);
         assert_eq!(diffs, vec![]);
     }
+
+    #[test]
+    fn test_parse_path() {
+        let field = |name: &str| Key::Field(name.to_owned());
+
+        for root in ["", "$", "(root)"] {
+            assert_eq!(root.parse(), Ok(Path::Root));
+        }
+        let expected = Path::Keys(vec![field("a"), Key::Idx(1), field("b")]);
+        for path in [".a[1].b", "$.a[1].b", "a[1].b", "$a[1].b"] {
+            assert_eq!(path.parse(), Ok(expected.clone()));
+        }
+        assert_eq!(
+            r#"["a.b"][0]["quote\"d"]"#.parse(),
+            Ok(Path::Keys(vec![
+                field("a.b"),
+                Key::Idx(0),
+                field("quote\"d")
+            ]))
+        );
+
+        // Display and FromStr round-trip.
+        let path: Path = ".data.users[12].name".parse().unwrap();
+        assert_eq!(path.to_string(), ".data.users[12].name");
+
+        for invalid in [".a..b", ".a[", ".a[-1]", ".a[x]", r#"["a"#, ".a.[0]"] {
+            assert!(
+                invalid.parse::<Path>().is_err(),
+                "{} should not parse",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_numeric_keys() {
+        let lhs = json!({ "1": "a", "2": "b", "10": "c", "x": 0 });
+        let rhs = json!({ "1.0": "a", "+2": "b", "10": "d", "x": 0 });
+
+        let config = Config::new(CompareMode::Strict);
+        assert_eq!(diff(&lhs, &rhs, &config).len(), 5);
+
+        let config = config.numeric_keys(true);
+        let diffs = diff(&lhs, &rhs, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".10");
+
+        let config = Config::new(CompareMode::Inclusive).numeric_keys(true);
+        assert_eq!(diff(&lhs, &json!({ "02": "b" }), &config), vec![]);
+
+        // Differences are reported in numeric order rather than string order.
+        let lhs = json!({ "2": 1, "10": 1, "-1": 1, "a": 1 });
+        let rhs = json!({ "2": 2, "10": 2, "-1": 2, "a": 2 });
+        let paths = diff(&lhs, &rhs, &config)
+            .iter()
+            .map(|diff| diff.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![".-1", ".2", ".10", ".a"]);
+    }
+
+    #[test]
+    fn test_numeric_key() {
+        assert_eq!(numeric_key("12"), Some(12.0));
+        assert_eq!(numeric_key("-0"), Some(0.0));
+        assert_eq!(numeric_key("1e2"), Some(100.0));
+        for key in ["", "a1", "1a", "inf", "NaN", "e1", "1..0"] {
+            assert_eq!(numeric_key(key), None, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let lhs = json!({ "a": { "b": { "c": 1 } }, "d": [[1]] });
+        let rhs = json!({ "a": { "b": { "c": 2 } }, "d": [[1]] });
+
+        let config = Config::new(CompareMode::Strict).max_depth(2);
+        let diffs = diff(&lhs, &rhs, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".a.b");
+
+        let config = config.max_depth_mode(MaxDepthMode::Ignore);
+        assert_eq!(diff(&lhs, &rhs, &config), vec![]);
+
+        // Atoms are compared at any depth.
+        let config = config.max_depth(0);
+        assert_eq!(diff(&json!(1), &json!(2), &config).len(), 1);
+        assert_eq!(diff(&lhs, &json!([]), &config), vec![]);
+    }
+
+    #[test]
+    fn test_compare_mode_at() {
+        let actual = json!({ "a": { "b": 1, "c": 2 }, "d": { "e": 1, "f": 2 }, "g": 3 });
+        let expected = json!({ "a": { "b": 1 }, "d": { "e": 1 } });
+
+        let config = Config::new(CompareMode::Inclusive).compare_mode_at(".a", CompareMode::Strict);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".a.c");
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atom at path ".a.c" is missing from expected"#
+        );
+
+        // The most specific pattern wins.
+        let config = config.compare_mode_at(".*", CompareMode::Inclusive);
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+        let config = config.compare_mode_at(".a", CompareMode::Inclusive);
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+        let config = config.compare_mode_at(".a.*", CompareMode::Strict);
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        let config = Config::new(CompareMode::Strict).compare_mode_at(".*", CompareMode::Inclusive);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".g");
+    }
+
+    #[test]
+    fn test_ignore_paths() {
+        let actual = json!({
+            "meta": { "timestamp": 1, "id": "a" },
+            "data": [{ "id": 1, "updated_at": "x" }, { "id": 2 }],
+            "tail": [1, 2, 3],
+        });
+        let expected = json!({
+            "meta": { "id": "b" },
+            "data": [{ "id": 1, "updated_at": "y" }, { "id": 2, "updated_at": "z" }],
+            "tail": [1, 2],
+        });
+
+        let config = Config::new(CompareMode::Strict).ignore_paths([
+            "$.meta.timestamp",
+            "$.data.*.updated_at",
+            ".tail[2]",
+        ]);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".meta.id");
+
+        let config = config.ignore_paths(["meta"]);
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        // Ignored extras aren't reported either.
+        let config = Config::new(CompareMode::Inclusive)
+            .report_extras(true)
+            .ignore_paths([".meta"]);
+        let comparison = compare(&actual, &json!({ "tail": [1] }), &config);
+        assert_eq!(comparison.differences, vec![]);
+        let extras = comparison
+            .extras
+            .iter()
+            .map(Path::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(extras, [".data", ".tail[1]", ".tail[2]"]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_ignore_paths_matching() {
+        let actual = json!({ "created_at": 1, "items": [{ "id": 1, "seen_at": 2 }], "at": 3 });
+        let expected = json!({ "created_at": 2, "items": [{ "id": 1 }], "at": 4 });
+
+        let regex = regex::Regex::new(r"_at$").unwrap();
+        let config = Config::new(CompareMode::Strict).ignore_paths_matching(regex);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".at");
+
+        let regex = regex::Regex::new(r"^\.items\[\d+\]$").unwrap();
+        let config = config.ignore_paths_matching(regex);
+        assert_eq!(
+            diff(&actual, &json!({ "at": 3, "items": [] }), &config),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_match_array_by_key() {
+        let actual = json!({
+            "users": [
+                { "id": 0, "name": "Eve" },
+                { "id": 1, "name": "Ann" },
+                { "id": 2, "name": "Bob" },
+                { "name": "Joe" },
+            ],
+        });
+        let expected = json!({
+            "users": [
+                { "id": 2, "name": "Bob" },
+                { "id": 1, "name": "Ada" },
+                { "id": 3, "name": "Sam" },
+            ],
+        });
+        let paths = |config: &Config| {
+            diff(&actual, &expected, config)
+                .iter()
+                .map(|diff| diff.path.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let config = Config::new(CompareMode::Strict).match_array_by_key(".users", "id");
+        assert_eq!(
+            paths(&config),
+            [".users[1].name", ".users[2]", ".users[0]", ".users[3]"]
+        );
+
+        // Unpaired actual items are allowed in inclusive mode.
+        let config = Config::new(CompareMode::Inclusive).match_array_by_key(".*", "id");
+        assert_eq!(paths(&config), [".users[1].name", ".users[2]"]);
+
+        // The pattern must match the array itself.
+        let config = Config::new(CompareMode::Inclusive).match_array_by_key(".users[*]", "id");
+        assert_eq!(paths(&config).len(), 5);
+    }
+
+    #[test]
+    fn test_array_diff_mode_sequence() {
+        let paths = |actual: Value, expected: Value, config: &Config| {
+            diff(&actual, &expected, config)
+                .iter()
+                .map(|diff| (diff.path.to_string(), diff.kind()))
+                .collect::<Vec<_>>()
+        };
+        let config = Config::new(CompareMode::Strict).array_diff_mode(ArrayDiffMode::Sequence);
+
+        assert_eq!(
+            paths(json!([1, 2, 3, 4, 5]), json!([1, 3, 4, 5, 6]), &config),
+            [
+                ("[1]".to_string(), DifferenceKind::Added),
+                ("[4]".to_string(), DifferenceKind::Removed),
+            ]
+        );
+        assert_eq!(
+            paths(
+                json!([0, { "a": 1 }, 2]),
+                json!([{ "a": 2 }, 2, 3]),
+                &config
+            ),
+            [
+                ("[0]".to_string(), DifferenceKind::TypeMismatch),
+                ("[1]".to_string(), DifferenceKind::Added),
+                ("[2]".to_string(), DifferenceKind::Removed),
+            ]
+        );
+        // Changed items between aligned ones are compared with each other.
+        assert_eq!(
+            paths(
+                json!([{ "id": 1 }, { "id": 2, "a": 1 }, { "id": 3 }]),
+                json!([{ "id": 1 }, { "id": 2, "a": 2 }, { "id": 3 }]),
+                &config
+            ),
+            [("[1].a".to_string(), DifferenceKind::ValueMismatch)]
+        );
+        assert_eq!(paths(json!([]), json!([]), &config), []);
+
+        let config = Config::new(CompareMode::Inclusive).array_diff_mode(ArrayDiffMode::Sequence);
+        assert_eq!(paths(json!([0, 1, 2, 3]), json!([1, 3]), &config), []);
+        assert_eq!(
+            paths(json!([0, 1, 2]), json!([1, 3]), &config),
+            [("[2]".to_string(), DifferenceKind::ValueMismatch)]
+        );
+    }
+
+    #[test]
+    fn test_diff_borrowed() {
+        let lhs = json!({ "a": [1, { "b": 2 }], "c": "x", "d": null });
+        let rhs = json!({ "a": [1, { "b": 3 }, 4], "c": 1 });
+        let config = Config::new(CompareMode::Strict);
+
+        let mut borrowed = diff_borrowed(&lhs, &rhs, &config);
+        let mut owned = diff(&lhs, &rhs, &config);
+        borrowed.sort_by_key(|diff| diff.path().to_string());
+        owned.sort_by_key(|diff| diff.path().to_string());
+        assert_eq!(borrowed.len(), owned.len());
+        for (borrowed, owned) in borrowed.iter().zip(&owned) {
+            assert_eq!(borrowed.path(), owned.path());
+            assert_eq!(borrowed.lhs(), owned.lhs());
+            assert_eq!(borrowed.rhs(), owned.rhs());
+            assert_eq!(borrowed.kind(), owned.kind());
+        }
+        let b = borrowed
+            .iter()
+            .find(|diff| diff.path().to_string() == ".a[1].b")
+            .unwrap();
+        assert!(std::ptr::eq(b.lhs().unwrap(), &lhs["a"][1]["b"]));
+        assert_eq!(diff_borrowed(&lhs, &lhs, &config), vec![]);
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let lhs = json!({ "a": [1, 2, 3], "b": { "c": 1 }, "d": 1 });
+        let rhs = json!({ "a": [4, 5, 6], "b": { "c": 2 }, "e": 1 });
+
+        let config = Config::new(CompareMode::Strict);
+        let comparison = compare(&lhs, &rhs, &config);
+        assert_eq!(comparison.differences.len(), 6);
+        assert_eq!(comparison.compared, 7);
+
+        let comparison = compare(&lhs, &rhs, &config.fail_fast(true));
+        assert_eq!(comparison.differences.len(), 1);
+        assert!(comparison.compared < 7);
+    }
+
+    #[test]
+    fn test_compare_with() {
+        let actual = json!({ "a": { "at": 12 }, "b": { "at": 20, "id": 1 }, "c": [1, 2] });
+        let expected = json!({ "a": { "at": 10 }, "b": { "at": 10, "id": 2 }, "c": [2, 1] });
+
+        let within = |margin: i64| {
+            move |lhs: &Value, rhs: &Value| {
+                (lhs.as_i64().unwrap() - rhs.as_i64().unwrap()).abs() <= margin
+            }
+        };
+        let config = Config::new(CompareMode::Strict)
+            .compare_with(".*.at", within(5))
+            .compare_with(".c", |lhs, rhs| {
+                lhs.as_array().map(Vec::len) == rhs.as_array().map(Vec::len)
+            });
+        let diffs = diff(&actual, &expected, &config);
+        let paths = diffs
+            .iter()
+            .map(|diff| diff.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [".b.at", ".b.id"]);
+
+        // The most specific pattern wins.
+        let config = config.compare_with(".b.at", within(10));
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+
+        // Missing values are still reported.
+        let diffs = diff(&json!({ "a": {} }), &json!({ "a": { "at": 1 } }), &config);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atom at path ".a.at" is missing from lhs"#
+        );
+    }
+
+    #[test]
+    fn test_difference_kinds() {
+        let config = Config::new(CompareMode::Strict);
+        let lhs = json!({ "a": [1, 2], "b": 1.5, "c": "x", "d": { "e": 1 } });
+        let rhs = json!({ "a": [1], "b": 1, "c": matchers::any_number(), "d": [] });
+        let kinds = diff(&lhs, &rhs, &config)
+            .into_iter()
+            .map(|diff| (diff.path().to_string(), diff.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (".a[1]".to_string(), DifferenceKind::Added),
+                (".b".to_string(), DifferenceKind::ValueMismatch),
+                (".c".to_string(), DifferenceKind::ValueMismatch),
+                (".d".to_string(), DifferenceKind::TypeMismatch),
+            ]
+        );
+
+        let diffs = diff(&json!({}), &json!({ "a": null }), &config);
+        assert_eq!(diffs[0].kind(), DifferenceKind::Removed);
+        assert_eq!((diffs[0].lhs(), diffs[0].rhs()), (None, Some(&json!(null))));
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let config = Config::new(CompareMode::Inclusive);
+        let diffs = diff(
+            &json!({ "a.b": [1, { "c": null }] }),
+            &json!({ "a.b": [2, { "c": null, "d": 1 }] }),
+            &config,
+        );
+        let serialized = serde_json::to_value(&diffs).unwrap();
+        assert_eq!(serialized[1]["path"], r#"["a.b"][1].d"#);
+        assert_eq!(serialized[1]["kind"], "removed");
+        assert_eq!(serialized[1].get("lhs"), None);
+        let deserialized: Vec<Difference> = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, diffs);
+
+        let keys: Vec<Key> = serde_json::from_value(json!(["a", 1])).unwrap();
+        assert_eq!(keys, [Key::Field("a".to_string()), Key::Idx(1)]);
+        assert_eq!(serde_json::to_value(&keys).unwrap(), json!(["a", 1]));
+        assert!(serde_json::from_value::<Key>(json!(-1)).is_err());
+
+        for invalid in [
+            json!({ "lhs": 1 }),
+            json!({ "path": ".a" }),
+            json!({ "path": ".a[x]", "lhs": 1 }),
+            json!({ "path": ".a", "lhs": 1, "compare_mode": "loose" }),
+        ] {
+            assert!(serde_json::from_value::<Difference>(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_object_and_array_compare_modes() {
+        let actual = json!({ "a": [1, 2], "b": { "c": [1], "d": 1 } });
+        let expected = json!({ "a": [1], "b": { "c": [1] } });
+
+        let config = Config::new(CompareMode::Strict).array_compare_mode(CompareMode::Inclusive);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".b.d");
+
+        let config = Config::new(CompareMode::Strict).object_compare_mode(CompareMode::Inclusive);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path.to_string(), ".a[1]");
+
+        // Per-path modes take precedence.
+        let config = config.compare_mode_at(".a", CompareMode::Inclusive);
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+    }
+
+    #[test]
+    fn test_extras() {
+        let lhs = json!({ "a": 1, "b": [1, 2, 3], "c": { "d": 1 }, "e": [3, 1, 2] });
+        let rhs = json!({ "b": [1], "c": {}, "e": matchers::partial(json!([3])) });
+        let extras = |lhs: &Value, rhs: &Value, config: &Config| {
+            let (diffs, extras) = diff_with_extras(lhs, rhs, config);
+            assert_eq!(diffs, vec![]);
+            extras
+                .iter()
+                .map(|path| path.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let config = Config::new(CompareMode::Inclusive);
+        assert_eq!(extras(&lhs, &rhs, &config), Vec::<String>::new());
+
+        let config = config.report_extras(true);
+        assert_eq!(
+            extras(&lhs, &rhs, &config),
+            vec![".a", ".b[1]", ".b[2]", ".c.d", ".e[1]", ".e[2]"]
+        );
+
+        let config = config.array_sorting_mode(ArraySortingMode::Ignore);
+        assert_eq!(
+            extras(&json!([3, 1, 2, 1]), &json!([1, 2]), &config),
+            vec!["[0]", "[3]"]
+        );
+    }
+
+    #[test]
+    fn test_array_prefix() {
+        let config = Config::new(CompareMode::Strict).array_sorting_mode(ArraySortingMode::Prefix);
+        let actual = json!({ "events": [{ "id": 1 }, { "id": 2 }, { "id": 3 }] });
+
+        let expected = json!({ "events": [{ "id": 1 }, { "id": 2 }] });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        // The prefix must match exactly and in order.
+        let expected = json!({ "events": [{ "id": 2 }] });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+        let expected = json!({ "events": [{}] });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+        let expected = json!({ "events": [{ "id": 1 }, { "id": 2 }, { "id": 3 }, { "id": 4 }] });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_array_set() {
+        let config = Config::new(CompareMode::Strict).array_sorting_mode(ArraySortingMode::Set);
+
+        let lhs = json!(["read", "write", "read"]);
+        assert_eq!(diff(&lhs, &json!(["write", "read"]), &config), vec![]);
+
+        let rhs = json!(["read", "admin"]);
+        let diffs = diff(&lhs, &rhs, &config)
+            .into_iter()
+            .map(|diff| (diff.path.to_string(), diff.lhs, diff.rhs))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diffs,
+            vec![
+                ("[1]".to_string(), None, Some(json!("admin"))),
+                ("[1]".to_string(), Some(json!("write")), None),
+            ]
+        );
+
+        let config = Config::new(CompareMode::Inclusive).array_sorting_mode(ArraySortingMode::Set);
+        assert_eq!(diff(&lhs, &json!(["read", "read"]), &config), vec![]);
+        assert_eq!(diff(&lhs, &json!(["admin"]), &config).len(), 1);
+    }
+
+    #[test]
+    fn test_array_ignore_strict() {
+        let config =
+            Config::new(CompareMode::Inclusive).array_sorting_mode(ArraySortingMode::IgnoreStrict);
+
+        let lhs = json!([{ "id": 1, "a": true }, { "id": 2 }, { "id": 1 }]);
+        assert_eq!(
+            diff(
+                &lhs,
+                &json!([{ "id": 1 }, { "id": 2 }, { "id": 1 }]),
+                &config
+            ),
+            vec![]
+        );
+        // Each item is paired with a different one, trying all pairings.
+        let rhs = json!([{ "id": 1 }, { "id": 1, "a": true }, {}]);
+        assert_eq!(diff(&lhs, &rhs, &config), vec![]);
+
+        let diffs = diff(
+            &lhs,
+            &json!([{ "id": 1 }, { "id": 2 }, { "id": 2 }]),
+            &config,
+        )
+        .into_iter()
+        .map(|diff| (diff.path.to_string(), diff.kind()))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            diffs,
+            [
+                ("[2]".to_string(), DifferenceKind::Removed),
+                ("[2]".to_string(), DifferenceKind::Added),
+            ]
+        );
+
+        let config = Config::new(CompareMode::Strict).array_mode(crate::ArrayMode::UnorderedExact);
+        assert_eq!(diff(&json!([1, 2, 1]), &json!([1, 1, 2]), &config), vec![]);
+        assert_eq!(diff(&json!([1, 2, 2]), &json!([1, 1, 2]), &config).len(), 2);
+        assert_eq!(diff(&json!([1, 2]), &json!([2, 1, 1]), &config).len(), 1);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let lhs = json!({ "at": "2024-01-01T10:00:00+02:00", "name": "2024" });
+        let rhs = json!({ "at": "2024-01-01T08:00:00Z", "name": "2024" });
+
+        let config = Config::new(CompareMode::Strict);
+        assert_eq!(diff(&lhs, &rhs, &config).len(), 1);
+
+        let config = config.timestamps(true);
+        assert_eq!(diff(&lhs, &rhs, &config), vec![]);
+        assert_eq!(
+            diff(
+                &lhs,
+                &json!({ "at": "2024-01-01T10:00:00Z", "name": "2024" }),
+                &config
+            )
+            .len(),
+            1
+        );
+        assert_eq!(diff(&json!("a"), &json!("b"), &config).len(), 1);
+    }
+
+    #[test]
+    fn test_multi_line_strings() {
+        let lhs = json!({ "sql": "SELECT *\nFROM users\nWHERE id = 1" });
+        let rhs = json!({ "sql": "SELECT *\nFROM accounts\nWHERE id = 1" });
+
+        let config = Config::new(CompareMode::Strict);
+        let diffs = diff(&lhs, &rhs, &config);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path ".sql" are not equal:
+    lines (- lhs, + rhs):
+          SELECT *
+        - FROM users
+        + FROM accounts
+          WHERE id = 1"#
+        );
+
+        let config = Config::new(CompareMode::Inclusive);
+        let diffs = diff(&lhs, &rhs, &config);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path ".sql" are not equal:
+    lines (- expected, + actual):
+          SELECT *
+        - FROM accounts
+        + FROM users
+          WHERE id = 1"#
+        );
+
+        let config = Config::new(CompareMode::Strict).mask_output(".sql");
+        let diffs = diff(&lhs, &rhs, &config);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path ".sql" are not equal:
+    lhs:
+        ***
+    rhs:
+        ***"#
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let lhs = json!({ "text": "a\r\nb\r\n" });
+        let rhs = json!({ "text": "a\nb" });
+
+        let config = Config::new(CompareMode::Strict);
+        assert_eq!(diff(&lhs, &rhs, &config).len(), 1);
+        let config = config.line_ending_mode(LineEndingMode::Normalize);
+        assert_eq!(diff(&lhs, &rhs, &config).len(), 1);
+        assert_eq!(diff(&lhs, &json!({ "text": "a\nb\n" }), &config), vec![]);
+        let config = config.line_ending_mode(LineEndingMode::NormalizeIgnoringTrailing);
+        assert_eq!(diff(&lhs, &rhs, &config), vec![]);
+        assert_eq!(diff(&lhs, &json!({ "text": "a\n\nb" }), &config).len(), 1);
+    }
+
+    #[test]
+    fn test_equal_values_with_matchers() {
+        // A marker compared to itself is still applied as a matcher.
+        let value = matchers::not(json!({ "a": 1 }));
+        let config = Config::new(CompareMode::Strict);
+        assert_eq!(diff(&value, &value, &config), vec![]);
+
+        let value = matchers::of_type(matchers::JsonType::Null);
+        assert_eq!(diff(&value, &value.clone(), &config).len(), 1);
+        assert_eq!(
+            diff(&json!([value.clone()]), &json!([value]), &config).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_differences_share_config() {
+        let config = Config::new(CompareMode::Strict).mask_output(".secret");
+        let diffs = diff(&json!([1, 2]), &json!([3, 4]), &config);
+        assert_eq!(diffs.len(), 2);
+        assert!(Arc::ptr_eq(&diffs[0].config, &diffs[1].config));
+        assert_eq!(diffs[0].config(), &config);
+    }
+
+    #[test]
+    fn test_path_ref_shares_ancestors() {
+        let arena = Bump::new();
+        let parent = PathRef::Root.append(&arena, KeyRef::Field("a"));
+        let first = parent.append(&arena, KeyRef::Idx(0));
+        let second = parent
+            .append(&arena, KeyRef::Idx(1))
+            .append(&arena, KeyRef::Field("b"));
+
+        assert_eq!(first.depth(), 2);
+        assert_eq!(
+            second.keys(),
+            vec![KeyRef::Field("a"), KeyRef::Idx(1), KeyRef::Field("b")]
+        );
+        assert_eq!(second.to_string(), ".a[1].b");
+        assert_eq!(Path::from(first).to_string(), ".a[0]");
+        assert_eq!(PathRef::Root.to_string(), "(root)");
+    }
+
+    #[test]
+    fn test_special_keys_are_quoted() {
+        let config = Config::new(CompareMode::Strict)
+            .compare_mode_at(r#"["weird.key"]["*"]"#, CompareMode::Inclusive);
+        let diffs = diff(
+            &json!({ "weird.key": { "*": { "extra": 1 }, "a b": 1 }, "": 2 }),
+            &json!({ "weird.key": { "*": {}, "a b": 2 }, "": 3 }),
+            &config,
+        );
+        let paths = diffs
+            .iter()
+            .map(|diff| diff.path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [r#"[""]"#, r#"["weird.key"]["a b"]"#]);
+        for path in paths {
+            assert_eq!(path.parse::<Path>().unwrap().to_string(), path);
+        }
+    }
 }
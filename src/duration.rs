@@ -0,0 +1,167 @@
+use serde_json::Number;
+use std::time::Duration;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Parses a humanized duration, such as `90s`, `1.5h` or `1h 30min`.
+///
+/// A duration is a sequence of numbers with units, optionally separated by spaces. The units are
+/// `ns`, `us` (or `µs`), `ms`, `s`, `m` and `h`, `d` and `w`, and their usual long forms such as
+/// `secs`, `minutes` or `days`.
+pub(crate) fn parse_humanized(text: &str) -> Option<Duration> {
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut nanos = 0u128;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, after_number) = rest.split_at(number_len);
+        let after_number = after_number.trim_start();
+        let unit_len = after_number
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(after_number.len());
+        let (unit, after_unit) = after_number.split_at(unit_len);
+        let unit_nanos = match unit {
+            "ns" | "nanos" | "nanosecond" | "nanoseconds" => 1,
+            "us" | "µs" | "micros" | "microsecond" | "microseconds" => 1_000,
+            "ms" | "millis" | "millisecond" | "milliseconds" => 1_000_000,
+            "s" | "sec" | "secs" | "second" | "seconds" => NANOS_PER_SEC,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60 * NANOS_PER_SEC,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3_600 * NANOS_PER_SEC,
+            "d" | "day" | "days" => 86_400 * NANOS_PER_SEC,
+            "w" | "week" | "weeks" => 604_800 * NANOS_PER_SEC,
+            _ => return None,
+        };
+        nanos = nanos.checked_add(scaled(number, unit_nanos)?)?;
+        rest = after_unit.trim_start();
+    }
+    from_nanos(nanos)
+}
+
+/// Parses an ISO 8601 duration, such as `PT1H30M` or `P1DT0.5S`.
+///
+/// Years and months are rejected, since their length varies.
+pub(crate) fn parse_iso8601(text: &str) -> Option<Duration> {
+    let rest = text.strip_prefix('P')?;
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return None,
+        Some((date, time)) => (date, Some(time)),
+        None => (rest, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return None;
+    }
+
+    let mut nanos = 0u128;
+    for (part, units) in [(date, &[('W', 604_800), ('D', 86_400)][..])]
+        .into_iter()
+        .chain(time.map(|time| (time, &[('H', 3_600), ('M', 60), ('S', 1)][..])))
+    {
+        let mut rest = part;
+        let mut units = units.iter();
+        while !rest.is_empty() {
+            let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')?;
+            let designator = rest[number_len..].chars().next()?;
+            // Designators must appear in order, each at most once.
+            let (_, unit_secs) = units.find(|(unit, _)| *unit == designator)?;
+            let number = rest[..number_len].replace(',', ".");
+            nanos = nanos.checked_add(scaled(&number, unit_secs * NANOS_PER_SEC)?)?;
+            rest = &rest[number_len + 1..];
+        }
+    }
+    from_nanos(nanos)
+}
+
+/// Returns the duration of `number` units of `unit_nanos` nanoseconds, if it isn't negative.
+pub(crate) fn parse_number(number: &Number, unit_nanos: u128) -> Option<Duration> {
+    // The text of a number keeps its exact digits, which converting it to a float wouldn't.
+    let text = number.to_string();
+    match text.split_once(['e', 'E']) {
+        Some(_) => {
+            let nanos = number.as_f64()? * unit_nanos as f64;
+            if nanos < 0.0 {
+                return None;
+            }
+            from_nanos(nanos.round() as u128)
+        }
+        None => from_nanos(scaled(&text, unit_nanos)?),
+    }
+}
+
+/// Returns the decimal `number` multiplied by `unit_nanos`, truncated to whole nanoseconds.
+fn scaled(number: &str, unit_nanos: u128) -> Option<u128> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<u128>().ok()?
+    };
+    // Digits beyond the precision of nanoseconds can't change the result.
+    let fraction = &fraction[..fraction.len().min(18)];
+    let fraction_nanos = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>().ok()? * unit_nanos / 10u128.pow(fraction.len() as u32)
+    };
+    whole.checked_mul(unit_nanos)?.checked_add(fraction_nanos)
+}
+
+fn from_nanos(nanos: u128) -> Option<Duration> {
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).ok()?;
+    Some(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn humanized() {
+        let hour = Some(Duration::from_secs(3600));
+        assert_eq!(parse_humanized("1h"), hour);
+        assert_eq!(parse_humanized("60m"), hour);
+        assert_eq!(parse_humanized("59min 60s"), hour);
+        assert_eq!(parse_humanized("0.5h30m"), hour);
+        assert_eq!(parse_humanized(" 1 hour "), hour);
+        assert_eq!(parse_humanized("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_humanized("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_humanized("0.1s"), Some(Duration::from_millis(100)));
+        assert_eq!(parse_humanized("7µs"), Some(Duration::from_micros(7)));
+
+        for invalid in ["", "1", "h", "1x", "1.2.3s", "-1s", "1h-"] {
+            assert_eq!(parse_humanized(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn iso8601() {
+        let hour = Some(Duration::from_secs(3600));
+        assert_eq!(parse_iso8601("PT1H"), hour);
+        assert_eq!(parse_iso8601("PT60M"), hour);
+        assert_eq!(parse_iso8601("PT3600S"), hour);
+        assert_eq!(parse_iso8601("PT0.5H30M"), hour);
+        assert_eq!(parse_iso8601("PT1,5S"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_iso8601("P1D"), Some(Duration::from_secs(86_400)));
+        assert_eq!(parse_iso8601("P1W"), Some(Duration::from_secs(604_800)));
+        assert_eq!(parse_iso8601("P1DT1S"), Some(Duration::from_secs(86_401)));
+
+        for invalid in [
+            "", "P", "PT", "1H", "PT1", "P1H", "PT1S1M", "P1M", "P1Y", "PT1H1H",
+        ] {
+            assert_eq!(parse_iso8601(invalid), None, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::paths::__to_value;
+use crate::{assert_json_matches_no_panic, check_nesting, Config};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// How often and how long [`assert_json_eventually`](crate::assert_json_eventually) tries to get
+/// a matching value.
+///
+/// By default, values are polled every 100 milliseconds for up to 5 seconds.
+///
+/// ```
+/// use serde_json_assert::Polling;
+/// use std::time::Duration;
+///
+/// // Wait 10ms, 20ms, 40ms, ... between attempts, at most 1s, for up to 30s.
+/// let polling = Polling::new()
+///     .timeout(Duration::from_secs(30))
+///     .interval(Duration::from_millis(10))
+///     .backoff(2.0)
+///     .max_interval(Duration::from_secs(1));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Polling {
+    timeout: Duration,
+    interval: Duration,
+    backoff: f64,
+    max_interval: Duration,
+}
+
+impl Default for Polling {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            interval: Duration::from_millis(100),
+            backoff: 1.0,
+            max_interval: Duration::MAX,
+        }
+    }
+}
+
+impl Polling {
+    /// Creates the default polling settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Change how long values are polled before failing. A value is always polled at least once.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Change how long to wait after the first failed attempt.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Multiply the time to wait by `factor` after each failed attempt. The default factor of 1
+    /// waits the same time between all attempts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is less than 1 or not finite.
+    #[track_caller]
+    pub fn backoff(mut self, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor >= 1.0,
+            "polling backoff must be a finite factor of at least 1, got {}",
+            factor
+        );
+        self.backoff = factor;
+        self
+    }
+
+    /// Change the longest time to wait between attempts, which limits the backoff.
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+}
+
+/// The attempts made so far, and when to make the next one.
+struct Attempts<'a> {
+    polling: &'a Polling,
+    start: Instant,
+    interval: Duration,
+    count: usize,
+}
+
+impl<'a> Attempts<'a> {
+    fn new(polling: &'a Polling) -> Self {
+        Self {
+            polling,
+            start: Instant::now(),
+            interval: polling.interval.min(polling.max_interval),
+            count: 0,
+        }
+    }
+
+    /// Records a failed attempt, returning how long to wait before the next one, or the final
+    /// error if the timeout has passed.
+    fn failed(&mut self, error: String) -> Result<Duration, String> {
+        self.count += 1;
+        let remaining = self.polling.timeout.saturating_sub(self.start.elapsed());
+        if remaining.is_zero() {
+            return Err(format!(
+                "json value didn't match within {:?}, after {} attempts. The last attempt failed with:\n\n{}",
+                self.polling.timeout, self.count, error
+            ));
+        }
+        let wait = self.interval.min(remaining);
+        self.interval = self
+            .interval
+            .mul_f64(self.polling.backoff)
+            .min(self.polling.max_interval);
+        Ok(wait)
+    }
+}
+
+/// Polls `actual` until the value it returns matches `expected`, without panicking.
+///
+/// `actual` is called again after each mismatch, waiting between attempts as set by `polling`.
+/// If no value matches before the timeout, the error shows the differences of the last attempt.
+/// See [`assert_json_eventually`](crate::assert_json_eventually) for the panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eventually_no_panic, CompareMode, Config, Polling};
+/// use serde_json::json;
+/// use std::time::Duration;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let polling = Polling::new().interval(Duration::from_millis(1));
+///
+/// let mut polls = 0;
+/// let job = || {
+///     polls += 1;
+///     json!({ "id": 7, "status": if polls < 3 { "running" } else { "done" } })
+/// };
+///
+/// assert_json_eventually_no_panic(job, &json!({ "status": "done" }), &config, &polling).unwrap();
+/// ```
+#[track_caller]
+pub fn assert_json_eventually_no_panic<F, T, Rhs>(
+    mut actual: F,
+    expected: &Rhs,
+    config: &Config,
+    polling: &Polling,
+) -> Result<(), String>
+where
+    F: FnMut() -> T,
+    T: Serialize,
+    Rhs: Serialize + ?Sized,
+{
+    check_nesting(&[("expected", &expected)], config).map_err(|limit| limit.to_string())?;
+    let expected = __to_value(expected);
+    let mut attempts = Attempts::new(polling);
+    loop {
+        let error = match assert_json_matches_no_panic(&actual(), &expected, config) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        std::thread::sleep(attempts.failed(error)?);
+    }
+}
+
+/// Polls the futures returned by `actual` until the value of one of them matches `expected`,
+/// without panicking.
+///
+/// Like [`assert_json_eventually_no_panic`], but waits between attempts without blocking the
+/// thread, using the timer of the [tokio](https://docs.rs/tokio) runtime. See
+/// [`assert_json_eventually_async`](crate::assert_json_eventually_async) for the panicking
+/// version.
+///
+/// Requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub async fn assert_json_eventually_async_no_panic<F, Fut, T, Rhs>(
+    mut actual: F,
+    expected: &Rhs,
+    config: &Config,
+    polling: &Polling,
+) -> Result<(), String>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = T>,
+    T: Serialize,
+    Rhs: Serialize + ?Sized,
+{
+    check_nesting(&[("expected", &expected)], config).map_err(|limit| limit.to_string())?;
+    let expected = __to_value(expected);
+    let mut attempts = Attempts::new(polling);
+    loop {
+        let error = match assert_json_matches_no_panic(&actual().await, &expected, config) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        tokio::time::sleep(attempts.failed(error)?).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn backs_off() {
+        let polling = Polling::new()
+            .timeout(Duration::from_secs(60))
+            .interval(Duration::from_millis(10))
+            .backoff(3.0)
+            .max_interval(Duration::from_millis(50));
+        let mut attempts = Attempts::new(&polling);
+        let waits = (0..4)
+            .map(|_| attempts.failed(String::new()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(waits, [10, 30, 50, 50].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn reports_last_attempt() {
+        let config = Config::new(CompareMode::Strict);
+        let polling = Polling::new()
+            .timeout(Duration::from_millis(20))
+            .interval(Duration::from_millis(5));
+        let mut count = 0;
+        let error = assert_json_eventually_no_panic(
+            || {
+                count += 1;
+                count
+            },
+            &0,
+            &config,
+            &polling,
+        )
+        .unwrap_err();
+
+        assert!(error.starts_with("json value didn't match within 20ms, after "));
+        assert!(error.ends_with(&format!(
+            "attempts. The last attempt failed with:\n\njson atoms at path \"(root)\" are not equal:\n    lhs:\n        {}\n    rhs:\n        0",
+            count
+        )));
+
+        let polling = Polling::new().timeout(Duration::ZERO);
+        let error = assert_json_eventually_no_panic(|| json!(1), &json!(2), &config, &polling);
+        assert!(error.unwrap_err().contains("after 1 attempts"));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn polls_futures() {
+        let config = Config::new(CompareMode::Strict);
+        let polling = Polling::new().interval(Duration::from_millis(1));
+        let mut count = 0;
+        let result = assert_json_eventually_async_no_panic(
+            || {
+                count += 1;
+                let value = count.min(3);
+                async move { json!([value]) }
+            },
+            &json!([3]),
+            &config,
+            &polling,
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+        assert_eq!(count, 3);
+    }
+}
@@ -0,0 +1,84 @@
+//! Integration with [expect-test](https://docs.rs/expect-test) snapshots.
+//!
+//! The difference report of a comparison is checked against an inline or file snapshot, so
+//! `UPDATE_EXPECT=1 cargo test` can be used to update expectations about how two documents differ.
+//! A report for values that match is empty.
+//!
+//! Requires the `expect-test` feature.
+
+use crate::{check_json_matches, Config};
+use ::expect_test::{Expect, ExpectFile};
+use serde::Serialize;
+
+/// Asserts that the difference report of comparing `lhs` and `rhs` matches an inline snapshot.
+///
+/// ```
+/// use expect_test::expect;
+/// use serde_json::json;
+/// use serde_json_assert::{expect_test::assert_diff, CompareMode, Config};
+///
+/// let config = Config::new(CompareMode::Inclusive);
+///
+/// assert_diff(&json!({ "a": 1, "b": 2 }), &json!({ "b": 2 }), &config, expect![[""]]);
+/// assert_diff(
+///     &json!({ "a": 1 }),
+///     &json!({ "b": 2 }),
+///     &config,
+///     expect![[r#"json atom at path ".b" is missing from actual"#]],
+/// );
+/// ```
+#[track_caller]
+pub fn assert_diff<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config, expect: Expect)
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    expect.assert_eq(&report(lhs, rhs, config));
+}
+
+/// Asserts that the difference report of comparing `lhs` and `rhs` matches a file snapshot.
+#[track_caller]
+pub fn assert_diff_file<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config, expect: ExpectFile)
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    expect.assert_eq(&report(lhs, rhs, config));
+}
+
+fn report<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> String
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    match check_json_matches(lhs, rhs, config) {
+        Ok(_) => String::new(),
+        Err(mismatch) => mismatch.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use ::expect_test::expect;
+    use serde_json::json;
+
+    #[test]
+    fn snapshots_difference_report() {
+        let config = Config::new(CompareMode::Strict);
+
+        assert_diff(&json!([1, 2]), &json!([1, 2]), &config, expect![[""]]);
+        assert_diff(
+            &json!({ "a": [1, 2] }),
+            &json!({ "a": [1, 3] }),
+            &config,
+            expect![[r#"
+                json atoms at path ".a[1]" are not equal:
+                    lhs:
+                        2
+                    rhs:
+                        3"#]],
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::diff::{self, Path};
+use crate::{to_values, Config};
+use serde::Serialize;
+use std::fmt;
+
+/// The rule that decided whether the values at a path matched, recorded by [`explain_json`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    path: Path,
+    matched: bool,
+    rule: String,
+}
+
+impl Decision {
+    pub(crate) fn new(path: Path, matched: bool, rule: String) -> Self {
+        Self {
+            path,
+            matched,
+            rule,
+        }
+    }
+
+    /// Returns the path of the compared values.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the values at this path matched, including any nested values.
+    pub fn matched(&self) -> bool {
+        self.matched
+    }
+
+    /// Describes the rule that decided the comparison, such as `compared with float compare mode
+    /// Epsilon(0.01)` or ``checked by matcher `of_type` ``.
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outcome = if self.matched {
+            "matched"
+        } else {
+            "didn't match"
+        };
+        write!(f, "{}: {}, {}", self.path, outcome, self.rule)
+    }
+}
+
+/// The decisions taken while comparing two values, in the order the values were visited.
+///
+/// Created by [`explain_json`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    decisions: Vec<Decision>,
+}
+
+impl Explanation {
+    /// Returns the decisions, parents before their children.
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+
+    /// Returns the decision for the values at `path`, if they were visited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` isn't a valid path, see [`Path`].
+    #[track_caller]
+    pub fn at(&self, path: &str) -> Option<&Decision> {
+        let path = path
+            .parse::<Path>()
+            .unwrap_or_else(|error| panic!("{}", error));
+        self.decisions.iter().find(|decision| decision.path == path)
+    }
+
+    /// Returns true if the compared values matched.
+    pub fn is_match(&self) -> bool {
+        self.decisions.iter().all(Decision::matched)
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "json comparison decisions:")?;
+        for decision in &self.decisions {
+            write!(f, "\n    {}", decision)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two JSON values and returns why each visited value matched or not.
+///
+/// When a lenient config unexpectedly accepts or rejects a document, the explanation shows which
+/// rule decided each value: an epsilon, a matcher, an extra field allowed by inclusive mode, and so
+/// on. Values inside arrays compared ignoring order are only tried against each other, so the
+/// explanation stops at such arrays.
+///
+/// ```
+/// use serde_json_assert::{explain_json, matchers, CompareMode, Config, FloatCompareMode};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive)
+///     .float_compare_mode(FloatCompareMode::Epsilon(0.01));
+/// let explanation = explain_json(
+///     &json!({ "id": "a1", "price": 9.999, "debug": true }),
+///     &json!({ "id": matchers::any_string(), "price": 10.0 }),
+///     &config,
+/// );
+///
+/// assert!(explanation.is_match());
+/// assert_eq!(
+///     explanation.to_string(),
+///     r#"json comparison decisions:
+///     (root): matched, object compared in inclusive mode
+///     .debug: matched, extra field allowed
+///     .id: matched, checked by matcher `of_type`
+///     .price: matched, compared exactly with float compare mode Epsilon(0.01)"#
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn explain_json<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> Explanation
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config).unwrap_or_else(|limit| panic!("{}", limit));
+    Explanation {
+        decisions: diff::explain(&lhs, &rhs, config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matchers, ArraySortingMode, CompareMode, MaxDepthMode, NumericMode};
+    use serde_json::json;
+
+    fn rules(explanation: &Explanation) -> Vec<String> {
+        explanation
+            .decisions()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn explains_decisions() {
+        let config = Config::new(CompareMode::Strict)
+            .numeric_mode(NumericMode::AssumeFloat)
+            .array_sorting_mode(ArraySortingMode::Prefix);
+        let explanation = explain_json(
+            &json!({ "a": [1, 2.0, 3], "b": "x", "c": null }),
+            &json!({ "a": [1.0, 2], "b": matchers::not(json!("x")), "d": false }),
+            &config,
+        );
+
+        assert!(!explanation.is_match());
+        assert_eq!(
+            rules(&explanation),
+            [
+                "(root): didn't match, object compared in strict mode",
+                ".a: matched, array compared as a prefix in strict mode",
+                ".a[2]: matched, extra item allowed",
+                ".a[0]: matched, compared as floats",
+                ".a[1]: matched, compared as floats",
+                ".b: didn't match, checked by matcher `not`",
+                ".d: didn't match, missing from the actual value",
+                ".c: didn't match, not in the expected value",
+            ]
+        );
+        assert!(explanation.at(".a[1]").unwrap().matched());
+        assert_eq!(explanation.at(".e"), None);
+    }
+
+    #[test]
+    fn explains_leaves() {
+        let config = Config::new(CompareMode::Strict)
+            .max_depth(2)
+            .max_depth_mode(MaxDepthMode::Ignore)
+            .compare_mode_at(".s", CompareMode::Inclusive);
+        let explanation = explain_json(
+            &json!({ "n": 1, "deep": { "a": { "b": 1 } }, "s": { "x": 1 }, "t": "1" }),
+            &json!({ "n": "1", "deep": { "a": { "b": 2 } }, "s": {}, "t": "1" }),
+            &config,
+        );
+        assert_eq!(
+            rules(&explanation)[1..],
+            [
+                ".deep: matched, object compared in strict mode",
+                ".deep.a: matched, ignored below the maximum depth",
+                ".n: didn't match, values have different types",
+                ".s: matched, object compared in inclusive mode set for its path",
+                ".s.x: matched, extra field allowed",
+                ".t: matched, compared exactly",
+            ]
+        );
+    }
+
+    #[test]
+    fn explains_ignored_paths() {
+        let config = Config::new(CompareMode::Strict).ignore_paths([".at", ".extra"]);
+        let explanation = explain_json(
+            &json!({ "at": 1, "extra": true }),
+            &json!({ "at": 2 }),
+            &config,
+        );
+        assert!(explanation.is_match());
+        assert_eq!(
+            rules(&explanation)[1..],
+            [
+                ".at: matched, ignored by Config::ignore_paths",
+                ".extra: matched, ignored by Config::ignore_paths",
+            ]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! A test harness running one test per pair of fixture files.
+//!
+//! Tests comparing many documents to expected ones are often a loop over a directory of fixtures,
+//! which stops at the first failure and reports all of them as a single test. [`Fixtures`] finds
+//! the pairs of files in a directory and turns each pair into its own test case, run by
+//! [libtest-mimic](https://docs.rs/libtest-mimic) with the usual filtering and reporting of
+//! `cargo test`.
+//!
+//! The harness replaces the one of the test target, so it needs `harness = false` in
+//! `Cargo.toml`:
+//!
+//! ```toml
+//! [[test]]
+//! name = "fixtures"
+//! harness = false
+//! ```
+//!
+//! And `tests/fixtures.rs` runs the fixtures in its `main` function:
+//!
+//! ```no_run
+//! use serde_json_assert::fixtures::Fixtures;
+//! use serde_json_assert::{CompareMode, Config};
+//!
+//! fn main() {
+//!     Fixtures::new("tests/fixtures")
+//!         .config(Config::new(CompareMode::Inclusive))
+//!         .run();
+//! }
+//! ```
+//!
+//! Requires the `fixtures` feature.
+
+use crate::parse::parse;
+use crate::{assert_json_matches_no_panic, CompareMode, Config};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub use libtest_mimic::{Arguments, Conclusion, Trial};
+
+type Transform = Arc<dyn Fn(Value) -> Value + Send + Sync>;
+
+/// Pairs of actual and expected documents in a directory, run as one test case per pair.
+///
+/// By default, a pair is made of `<name>.actual.json` and `<name>.expected.json`, searched
+/// recursively, and the test case is named after `<name>` relative to the directory. The documents
+/// are compared strictly unless a [`Config`] is set. A file without its counterpart is a failing
+/// test case, so fixtures can't be skipped by accident.
+///
+/// See the [module documentation](self) for how to set up the harness.
+#[derive(Clone)]
+pub struct Fixtures {
+    dir: PathBuf,
+    config: Config,
+    actual_suffix: String,
+    expected_suffix: String,
+    transform: Option<Transform>,
+}
+
+impl Fixtures {
+    /// Finds fixtures in `dir`, which is relative to the working directory of the test: the root
+    /// of the crate when run by `cargo test`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            config: Config::new(CompareMode::Strict),
+            actual_suffix: ".actual.json".to_string(),
+            expected_suffix: ".expected.json".to_string(),
+            transform: None,
+        }
+    }
+
+    /// Compares the documents of every pair using `config`.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Changes the suffixes of the file names that make up a pair, which are `.actual.json` and
+    /// `.expected.json` by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the suffixes are equal or empty.
+    #[track_caller]
+    pub fn suffixes(mut self, actual: &str, expected: &str) -> Self {
+        assert!(
+            !actual.is_empty() && !expected.is_empty() && actual != expected,
+            "fixture suffixes must be different and not empty, got {:?} and {:?}",
+            actual,
+            expected
+        );
+        self.actual_suffix = actual.to_string();
+        self.expected_suffix = expected.to_string();
+        self
+    }
+
+    /// Computes the actual document from the first file of each pair, for fixtures made of an
+    /// input and the golden output it should produce.
+    ///
+    /// ```no_run
+    /// use serde_json_assert::fixtures::Fixtures;
+    /// use serde_json::{json, Value};
+    ///
+    /// fn handle(request: Value) -> Value {
+    ///     json!({ "echo": request })
+    /// }
+    ///
+    /// fn main() {
+    ///     Fixtures::new("tests/golden")
+    ///         .suffixes(".input.json", ".golden.json")
+    ///         .actual_from(handle)
+    ///         .run();
+    /// }
+    /// ```
+    pub fn actual_from<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        self.transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Returns one test case per pair of files, sorted by name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the directory can't be read.
+    #[track_caller]
+    pub fn trials(&self) -> Vec<Trial> {
+        let mut files = vec![];
+        collect_files(&self.dir, &mut files);
+
+        let mut names = files
+            .iter()
+            .filter_map(|file| {
+                let relative = file.strip_prefix(&self.dir).ok()?.to_str()?;
+                let relative = relative.replace('\\', "/");
+                [&self.actual_suffix, &self.expected_suffix]
+                    .into_iter()
+                    .find_map(|suffix| relative.strip_suffix(suffix.as_str()))
+                    .map(str::to_string)
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let actual = self.dir.join(format!("{}{}", name, self.actual_suffix));
+                let expected = self.dir.join(format!("{}{}", name, self.expected_suffix));
+                let config = self.config.clone();
+                let transform = self.transform.clone();
+                Trial::test(name, move || {
+                    check_pair(&actual, &expected, &config, transform.as_deref())
+                        .map_err(Into::into)
+                })
+            })
+            .collect()
+    }
+
+    /// Runs the test cases with the arguments passed to the test binary, and exits the process
+    /// with the outcome.
+    #[track_caller]
+    pub fn run(&self) -> ! {
+        libtest_mimic::run(&Arguments::from_args(), self.trials()).exit()
+    }
+}
+
+impl fmt::Debug for Fixtures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fixtures")
+            .field("dir", &self.dir)
+            .field("config", &self.config)
+            .field("actual_suffix", &self.actual_suffix)
+            .field("expected_suffix", &self.expected_suffix)
+            .field("transform", &self.transform.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+#[track_caller]
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Couldn't read fixtures in {}: {}", dir.display(), err));
+    for entry in entries {
+        let path = entry
+            .unwrap_or_else(|err| panic!("Couldn't read fixtures in {}: {}", dir.display(), err))
+            .path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+fn check_pair(
+    actual: &Path,
+    expected: &Path,
+    config: &Config,
+    transform: Option<&(dyn Fn(Value) -> Value + Send + Sync)>,
+) -> Result<(), String> {
+    let read = |path: &Path| {
+        let text =
+            fs::read(path).map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        parse(&text).map_err(|err| format!("Couldn't parse {}: {}", path.display(), err))
+    };
+    let mut actual_value = read(actual)?;
+    let expected_value = read(expected)?;
+    if let Some(transform) = transform {
+        actual_value = transform(actual_value);
+    }
+    assert_json_matches_no_panic(&actual_value, &expected_value, config).map_err(|error| {
+        format!(
+            "{} doesn't match {}:\n\n{}",
+            actual.display(),
+            expected.display(),
+            error
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn runs_one_trial_per_pair() {
+        let dir = std::env::temp_dir().join("serde-json-assert-fixtures");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(dir.join("users")).unwrap();
+        let files = [
+            ("a.actual.json", r#"{ "id": 1, "extra": true }"#),
+            ("a.expected.json", r#"{ "id": 1 }"#),
+            ("users/b.actual.json", r#"[1, 2]"#),
+            ("users/b.expected.json", r#"[1, 3]"#),
+            ("c.actual.json", r#"{}"#),
+            ("notes.txt", "not a fixture"),
+        ];
+        for (name, text) in files {
+            fs::write(dir.join(name), text).unwrap();
+        }
+
+        let fixtures = Fixtures::new(&dir).config(Config::new(CompareMode::Inclusive));
+        let trials = fixtures.trials();
+        assert_eq!(
+            trials.iter().map(Trial::name).collect::<Vec<_>>(),
+            ["a", "c", "users/b"]
+        );
+
+        let check = |name: &str, transform: Option<&(dyn Fn(Value) -> Value + Send + Sync)>| {
+            check_pair(
+                &dir.join(format!("{}.actual.json", name)),
+                &dir.join(format!("{}.expected.json", name)),
+                &fixtures.config,
+                transform,
+            )
+        };
+        assert_eq!(check("a", None), Ok(()));
+        assert!(check("c", None).unwrap_err().starts_with("Couldn't read"));
+        assert!(check("users/b", None)
+            .unwrap_err()
+            .contains("users/b.actual.json doesn't match"));
+        assert_eq!(
+            check("users/b", Some(&|value: Value| json!([value[0], 3]))),
+            Ok(())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
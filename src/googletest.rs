@@ -0,0 +1,143 @@
+//! Integration with the [googletest](https://docs.rs/googletest) matcher framework.
+//!
+//! Requires the `googletest` feature.
+
+use crate::core_ext::Indent;
+use crate::{assert_json_matches_no_panic, check_nesting, CompareMode, Config};
+use ::googletest::description::Description;
+use ::googletest::matcher::{Matcher, MatcherBase, MatcherResult};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+
+/// A [googletest](https://docs.rs/googletest) matcher that compares JSON values according to a
+/// [`Config`].
+///
+/// The value passed to `assert_that!` or `expect_that!` is the left-hand side, or "actual", value
+/// of the comparison. Create one with [`json_eq`], [`json_include`] or [`json_matches`].
+///
+/// ```
+/// use googletest::prelude::*;
+/// use serde_json::json;
+/// use serde_json_assert::googletest::{json_eq, json_include};
+///
+/// let actual = json!({ "a": { "b": 1 }, "c": true });
+///
+/// assert_that!(actual, json_include(json!({ "a": {} })));
+/// assert_that!(actual, json_eq(json!({ "a": { "b": 1 }, "c": true })));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonMatcher {
+    expected: Value,
+    config: Config,
+}
+
+/// Matches values that are exactly equal to `expected` when serialized to JSON.
+///
+/// The googletest counterpart of [`assert_json_eq`](crate::assert_json_eq).
+#[track_caller]
+pub fn json_eq<T>(expected: T) -> JsonMatcher
+where
+    T: Serialize,
+{
+    json_matches(expected, Config::new(CompareMode::Strict))
+}
+
+/// Matches values that include `expected` when serialized to JSON.
+///
+/// The googletest counterpart of [`assert_json_include`](crate::assert_json_include).
+#[track_caller]
+pub fn json_include<T>(expected: T) -> JsonMatcher
+where
+    T: Serialize,
+{
+    json_matches(expected, Config::new(CompareMode::Inclusive))
+}
+
+/// Matches values that match `expected` according to `config` when serialized to JSON.
+///
+/// The googletest counterpart of [`assert_json_matches`](crate::assert_json_matches).
+///
+/// # Panics
+///
+/// Panics if `expected` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn json_matches<T>(expected: T, config: Config) -> JsonMatcher
+where
+    T: Serialize,
+{
+    check_nesting(&[("expected", &expected)], &config).unwrap_or_else(|limit| panic!("{}", limit));
+    let expected = serde_json::to_value(expected).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't convert expected value to JSON. Serde error: {}",
+            err
+        )
+    });
+    JsonMatcher { expected, config }
+}
+
+impl MatcherBase for JsonMatcher {}
+
+impl<T> Matcher<&T> for JsonMatcher
+where
+    T: Serialize + Debug + ?Sized,
+{
+    fn matches(&self, actual: &T) -> MatcherResult {
+        assert_json_matches_no_panic(&actual, &self.expected, &self.config)
+            .is_ok()
+            .into()
+    }
+
+    fn describe(&self, matcher_result: MatcherResult) -> Description {
+        let relation = match (matcher_result, self.config.compare_mode) {
+            (MatcherResult::Match, CompareMode::Inclusive) => "includes",
+            (MatcherResult::Match, CompareMode::Strict) => "is equal to",
+            (MatcherResult::NoMatch, CompareMode::Inclusive) => "doesn't include",
+            (MatcherResult::NoMatch, CompareMode::Strict) => "isn't equal to",
+        };
+        let expected = serde_json::to_string_pretty(&self.expected).unwrap();
+        format!("{} JSON\n{}", relation, expected.indent(2)).into()
+    }
+
+    fn explain_match(&self, actual: &T) -> Description {
+        match assert_json_matches_no_panic(&actual, &self.expected, &self.config) {
+            Ok(()) => "which matches the expected JSON".into(),
+            Err(error) => format!("which has differences:\n{}", error.indent(2)).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::googletest::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn matches_json() {
+        let actual = json!({ "a": 1, "b": [1, 2] });
+
+        assert_that!(actual, json_include(json!({ "b": [1] })));
+        assert_that!(actual, json_eq(json!({ "a": 1, "b": [1, 2] })));
+        assert_that!(actual, not(json_eq(json!({ "a": 1 }))));
+        assert_that!(
+            actual,
+            json_matches(
+                json!({ "a": 1.0 }),
+                Config::new(CompareMode::Inclusive).numeric_mode(crate::NumericMode::AssumeFloat)
+            )
+        );
+    }
+
+    #[test]
+    fn explains_differences() {
+        let actual = json!({ "a": 1 });
+        let failure = verify_that!(actual, json_include(json!({ "a": 2 }))).unwrap_err();
+
+        assert!(failure.description.contains("includes JSON"));
+        assert!(failure
+            .description
+            .contains(r#"json atoms at path ".a" are not equal:"#));
+    }
+}
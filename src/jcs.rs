@@ -0,0 +1,293 @@
+//! Canonical JSON as defined by [RFC 8785](https://www.rfc-editor.org/rfc/rfc8785), the JSON
+//! Canonicalization Scheme (JCS).
+//!
+//! Signatures over JSON are computed over its canonical form, so tests of signing code need to
+//! produce it, and to check that signed payloads are in it. In canonical form, object keys are
+//! sorted by their UTF-16 code units, there is no whitespace, and strings and numbers are written
+//! like `JSON.stringify` does in JavaScript.
+//!
+//! Requires the `jcs` feature, which also makes serde_json parse floats exactly.
+//!
+//! ```
+//! use serde_json_assert::{assert_json_canonical, jcs};
+//! use serde_json::json;
+//!
+//! let payload = jcs::canonicalize(&json!({ "b": [1.0, 1e21], "a": "\u{20ac}" }));
+//! assert_eq!(payload, r#"{"a":"€","b":[1,1e+21]}"#);
+//!
+//! assert_json_canonical!(payload);
+//! ```
+
+use crate::paths::__to_value;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Returns the canonical form of `value`.
+///
+/// All numbers are written as IEEE 754 doubles, so integers beyond 2<sup>53</sup> may lose
+/// precision, as they would in JavaScript.
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized.
+#[track_caller]
+pub fn canonicalize<T: Serialize + ?Sized>(value: &T) -> String {
+    let mut canonical = String::new();
+    write_value(&mut canonical, &__to_value(value));
+    canonical
+}
+
+/// Checks that `text` is JSON in canonical form without panicking.
+///
+/// The error shows where `text` first differs from its canonical form. See
+/// [`assert_json_canonical`](crate::assert_json_canonical) for the panicking version.
+///
+/// ```
+/// use serde_json_assert::jcs;
+///
+/// assert!(jcs::assert_canonical_no_panic(r#"{"a":1,"b":[true,null]}"#).is_ok());
+///
+/// let error = jcs::assert_canonical_no_panic(r#"{"b":1,"a":2}"#).unwrap_err();
+/// assert_eq!(
+///     error,
+///     r#"json text isn't in canonical form, it differs from it at byte 2:
+///     actual:    {"b":1,"a":2}
+///     canonical: {"a":2,"b":1}"#
+/// );
+/// ```
+pub fn assert_canonical_no_panic(text: impl AsRef<[u8]>) -> Result<(), String> {
+    let text = std::str::from_utf8(text.as_ref())
+        .map_err(|err| format!("Couldn't parse json text. Error: {}", err))?;
+    let value: Value = serde_json::from_str(text)
+        .map_err(|err| format!("Couldn't parse json text. Error: {}", err))?;
+    let canonical = canonicalize(&value);
+
+    let offset = match text
+        .char_indices()
+        .zip(canonical.chars())
+        .find(|((_, actual), canonical)| actual != canonical)
+    {
+        Some(((offset, _), _)) => offset,
+        None if text.len() == canonical.len() => return Ok(()),
+        None => text.len().min(canonical.len()),
+    };
+    // Show some of the text before the difference, starting at a character boundary.
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(idx, _)| idx);
+    Err(format!(
+        "json text isn't in canonical form, it differs from it at byte {}:\n    actual:    {}\n    canonical: {}",
+        offset,
+        snippet(text, start),
+        snippet(&canonical, start),
+    ))
+}
+
+/// The number of characters shown before the first difference.
+const SNIPPET_CONTEXT: usize = 20;
+/// The number of characters shown in total.
+const SNIPPET_LEN: usize = 60;
+
+fn snippet(text: &str, start: usize) -> String {
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    let rest = &text[start..];
+    // Whitespace that isn't allowed in canonical form would be invisible otherwise.
+    for c in rest.chars().take(SNIPPET_LEN) {
+        if c.is_control() {
+            snippet.extend(c.escape_default());
+        } else {
+            snippet.push(c);
+        }
+    }
+    if rest.chars().nth(SNIPPET_LEN).is_some() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => {
+            // Numbers always fit in a double, at worst losing precision.
+            write_number(out, number.as_f64().unwrap_or_default())
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            let mut fields = fields.iter().collect::<Vec<_>>();
+            fields.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (idx, (key, field)) in fields.into_iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_value(out, field);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Writes `number` like the ECMAScript `Number.prototype.toString`.
+fn write_number(out: &mut String, number: f64) {
+    if number == 0.0 {
+        out.push('0');
+        return;
+    }
+    if number < 0.0 {
+        out.push('-');
+    }
+
+    // Rust prints the shortest digits that round-trip, like ECMAScript does.
+    let scientific = format!("{:e}", number.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("floats are printed with an exponent");
+    let digits = mantissa.replace('.', "");
+    let exponent: i32 = exponent.parse().expect("exponents are integers");
+    // The position of the decimal point relative to the start of the digits.
+    let point = exponent + 1;
+    let len = digits.len() as i32;
+
+    if len <= point && point <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((point - len) as usize));
+    } else if 0 < point && point <= 21 {
+        let (whole, fraction) = digits.split_at(point as usize);
+        let _ = write!(out, "{}.{}", whole, fraction);
+    } else if -6 < point && point <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-point as usize));
+        out.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            let _ = write!(out, ".{}", rest);
+        }
+        let _ = write!(
+            out,
+            "e{}{}",
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn canonicalizes_rfc_example() {
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            canonicalize(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn sorts_keys_by_utf16() {
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis",
+        });
+        let keys = canonicalize(&value)
+            .split(',')
+            .map(|field| field.split('"').nth(1).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "\\r",
+                "1",
+                "\u{80}",
+                "\u{f6}",
+                "\u{20ac}",
+                "\u{1f600}",
+                "\u{fb33}"
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_numbers() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (1e-6, "0.000001"),
+            (1e-7, "1e-7"),
+            (123.456e-10, "1.23456e-8"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (f64::from_bits(1), "5e-324"),
+        ];
+        for (number, expected) in cases {
+            let mut out = String::new();
+            write_number(&mut out, number);
+            assert_eq!(out, expected, "{:e}", number);
+        }
+        assert_eq!(canonicalize(&u64::MAX), "18446744073709552000");
+    }
+
+    #[test]
+    fn reports_first_difference() {
+        assert_eq!(assert_canonical_no_panic(r#"{"a":[1,"x"]}"#), Ok(()));
+        assert_eq!(
+            assert_canonical_no_panic("[1.0]").unwrap_err(),
+            "json text isn't in canonical form, it differs from it at byte 2:\n    actual:    [1.0]\n    canonical: [1]"
+        );
+        assert_eq!(
+            assert_canonical_no_panic("{\"a\":1}\n").unwrap_err(),
+            "json text isn't in canonical form, it differs from it at byte 7:\n    actual:    {\"a\":1}\\n\n    canonical: {\"a\":1}"
+        );
+
+        let long = format!(r#"{{"key":"{}","value":[1, 2]}}"#, "x".repeat(100));
+        assert_eq!(
+            assert_canonical_no_panic(long).unwrap_err(),
+            format!(
+                "json text isn't in canonical form, it differs from it at byte 121:\n    actual:    ...{x},\"value\":[1, 2]}}\n    canonical: ...{x},\"value\":[1,2]}}",
+                x = "x".repeat(7) + "\""
+            )
+        );
+        assert!(assert_canonical_no_panic("[1,")
+            .unwrap_err()
+            .starts_with("Couldn't parse json text."));
+    }
+}
@@ -0,0 +1,80 @@
+//! Expected documents written in [JSON5](https://json5.org), or JSON with comments.
+//!
+//! Large expected documents are easier to maintain with comments explaining the values, and
+//! without having to quote every key or remove the last trailing comma. JSON5 allows all of these,
+//! and is a superset of JSONC and of JSON itself. The documents are parsed into a [`Value`] before
+//! they're compared, so they work with every assertion.
+//!
+//! Requires the `json5` feature.
+//!
+//! ```
+//! use serde_json_assert::{assert_json_eq, json5};
+//! use serde_json::json;
+//!
+//! let expected = json5::parse(r#"
+//!     // The user created by the fixture.
+//!     {
+//!         id: 1,
+//!         name: 'Bob',
+//!         roles: ["admin", "dev",], /* sorted by name */
+//!     }
+//! "#);
+//!
+//! assert_json_eq!(json!({ "id": 1, "name": "Bob", "roles": ["admin", "dev"] }), expected);
+//! ```
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Parses a JSON5 document.
+///
+/// # Panics
+///
+/// Panics if `text` isn't valid JSON5.
+#[track_caller]
+pub fn parse(text: &str) -> Value {
+    try_parse(text).unwrap_or_else(|error| panic!("Couldn't parse JSON5 document: {}", error))
+}
+
+/// Parses a JSON5 document without panicking.
+pub fn try_parse(text: &str) -> Result<Value, String> {
+    ::json5::from_str(text).map_err(|err| err.to_string())
+}
+
+/// Reads and parses the JSON5 document in the file at `path`, such as a fixture.
+///
+/// # Panics
+///
+/// Panics if the file can't be read, or isn't valid JSON5.
+#[track_caller]
+pub fn read(path: impl AsRef<Path>) -> Value {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Couldn't read {}: {}", path.display(), err));
+    try_parse(&text).unwrap_or_else(|error| panic!("Couldn't parse {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_json5() {
+        assert_eq!(
+            try_parse("{ a: [1, -2.5, +3, 0x10, .5,], 'b': null, \"c\": 'it\\'s', } // end"),
+            Ok(json!({ "a": [1, -2.5, 3, 16, 0.5], "b": null, "c": "it's" }))
+        );
+        assert_eq!(try_parse(r#"{ "a": [true] }"#), Ok(json!({ "a": [true] })));
+        assert!(try_parse("{ a: }").is_err());
+    }
+
+    #[test]
+    fn reads_files() {
+        let path = std::env::temp_dir().join("serde-json-assert-json5-fixture.json5");
+        fs::write(&path, "/* fixture */ [1, 2,]").unwrap();
+        assert_eq!(read(&path), json!([1, 2]));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+//! Assertions on the header and claims of JSON Web Tokens.
+//!
+//! Tokens are split into their parts and the header and claims are decoded, so they can be
+//! compared like any other JSON value. Signatures aren't verified.
+//!
+//! Requires the `jwt` feature.
+//!
+//! ```
+//! use serde_json_assert::{assert_jwt_claims_include, assert_jwt_header_include};
+//! use serde_json::json;
+//!
+//! // {"alg":"HS256","typ":"JWT"}.{"sub":"1234567890","name":"John Doe","iat":1516239022}
+//! let token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9\
+//!     .eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ\
+//!     .SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c";
+//!
+//! assert_jwt_header_include!(token, json!({ "alg": "HS256" }));
+//! assert_jwt_claims_include!(token, json!({ "sub": "1234567890", "name": "John Doe" }));
+//! ```
+
+use crate::matchers::decode_base64;
+use crate::{assert_json_matches_no_panic, Config};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Decodes the header of `token`.
+pub fn header(token: &str) -> Result<Value, String> {
+    decode_part(parts(token)?[0], "header")
+}
+
+/// Decodes the claims of `token`.
+pub fn claims(token: &str) -> Result<Value, String> {
+    decode_part(parts(token)?[1], "claims")
+}
+
+/// Compares the header of `token` to `expected` without panicking.
+///
+/// Fails if the token can't be decoded, or its header doesn't match.
+pub fn assert_header_no_panic<T>(token: &str, expected: &T, config: &Config) -> Result<(), String>
+where
+    T: Serialize,
+{
+    assert_json_matches_no_panic(&header(token)?, expected, config)
+        .map_err(|err| format!("JWT header doesn't match:\n\n{}", err))
+}
+
+/// Compares the claims of `token` to `expected` without panicking.
+///
+/// Fails if the token can't be decoded, or its claims don't match.
+pub fn assert_claims_no_panic<T>(token: &str, expected: &T, config: &Config) -> Result<(), String>
+where
+    T: Serialize,
+{
+    assert_json_matches_no_panic(&claims(token)?, expected, config)
+        .map_err(|err| format!("JWT claims don't match:\n\n{}", err))
+}
+
+fn parts(token: &str) -> Result<[&str; 3], String> {
+    let mut parts = token.trim().split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(claims), Some(signature), None) => Ok([header, claims, signature]),
+        _ => Err("Invalid JWT: expected three parts separated by `.`".to_owned()),
+    }
+}
+
+fn decode_part(part: &str, name: &str) -> Result<Value, String> {
+    let json = decode_base64(part)
+        .ok_or_else(|| format!("Invalid JWT: the {} isn't valid base64url", name))?;
+    serde_json::from_slice(&json)
+        .map_err(|err| format!("Invalid JWT: the {} isn't valid JSON. Error: {}", name, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    const TOKEN: &str = "eyJhbGciOiJub25lIn0.eyJzdWIiOiJib2IiLCJzY29wZSI6WyJyZWFkIl19.";
+
+    #[test]
+    fn decodes_parts() {
+        assert_eq!(header(TOKEN).unwrap(), json!({ "alg": "none" }));
+        assert_eq!(
+            claims(TOKEN).unwrap(),
+            json!({ "sub": "bob", "scope": ["read"] })
+        );
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        assert_eq!(
+            claims("a.b").unwrap_err(),
+            "Invalid JWT: expected three parts separated by `.`"
+        );
+        assert_eq!(
+            header("!.b.c").unwrap_err(),
+            "Invalid JWT: the header isn't valid base64url"
+        );
+        assert!(claims("eyJhbGciOiJub25lIn0.YWJj.")
+            .unwrap_err()
+            .starts_with("Invalid JWT: the claims isn't valid JSON"));
+    }
+
+    #[test]
+    fn compares_claims() {
+        let config = Config::new(CompareMode::Inclusive);
+        assert!(assert_claims_no_panic(TOKEN, &json!({ "sub": "bob" }), &config).is_ok());
+
+        let error = assert_claims_no_panic(TOKEN, &json!({ "sub": "eve" }), &config).unwrap_err();
+        assert!(error.starts_with("JWT claims don't match:"), "{}", error);
+        assert!(assert_header_no_panic(TOKEN, &json!({ "alg": "none" }), &config).is_ok());
+    }
+}
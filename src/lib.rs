@@ -157,6 +157,60 @@
 //! )
 //! ```
 //!
+//! ## Open objects and arrays
+//!
+//! Inclusive and strict matching apply to the whole comparison. To allow additional fields or
+//! items in only some places, end those objects or arrays with `..` using [`json_partial!`]:
+//!
+//! ```
+//! use serde_json_assert::{assert_json_eq, json_partial};
+//! use serde_json::json;
+//!
+//! assert_json_eq!(
+//!     json!({ "id": 1, "meta": { "etag": "abc", "version": 2 } }),
+//!     json_partial!({ "id": 1, "meta": { "version": 2, .. } })
+//! );
+//! ```
+//!
+//! See the [`matchers`] module for other ways to customize how individual values are matched.
+//!
+//! ## Checking individual paths
+//!
+//! [`assert_json_paths`] compares the values at several paths of a document in one go. All
+//! failing paths are reported together.
+//!
+//! ```
+//! use serde_json_assert::assert_json_paths;
+//! use serde_json::json;
+//!
+//! let response = json!({ "data": { "users": [{ "id": 1, "name": "Bob" }] }, "total": 1 });
+//!
+//! assert_json_paths!(response, {
+//!     ".data.users[0].name" => "Bob",
+//!     ".total" => 1,
+//! });
+//! ```
+//!
+//! ## Raw JSON
+//!
+//! With the `raw_value` feature enabled, [`serde_json::value::RawValue`] can be used on either side
+//! of any assertion. The raw JSON is only parsed when the comparison is made, so request and
+//! response bodies handed out as raw values don't have to be deserialized up front.
+//!
+//! ```
+//! # #[cfg(feature = "raw_value")]
+//! # {
+//! use serde_json_assert::assert_json_include;
+//! use serde_json::{json, value::RawValue};
+//!
+//! let body = RawValue::from_string(r#"{ "id": 1, "name": "bob" }"#.to_string()).unwrap();
+//!
+//! assert_json_include!(actual: body, expected: json!({ "id": 1 }));
+//! # }
+//! ```
+//!
+//! Documents available as text or bytes can be compared using [`assert_raw_json_matches_no_panic`].
+//!
 //! ## Further customization
 //!
 //! You can use [`assert_json_matches`] to further customize the comparison.
@@ -175,13 +229,118 @@
     unknown_lints
 )]
 
-use diff::diff;
+use core_ext::Indent;
+use diff::compare;
+use report::{Extras, Output};
 use serde::Serialize;
+use serde_json::Value;
+use stats::{Limit, NestingLimit, Stats};
+use std::borrow::Cow;
+use std::collections::HashMap;
 
-pub use crate::diff::{Difference, Key, Path};
+pub use crate::arrays::{
+    assert_json_aggregate_no_panic, assert_json_sorted_no_panic, assert_json_unique_no_panic,
+    Aggregate, SortedBy,
+};
+pub use crate::comparator::PathComparator;
+pub use crate::compiled::CompiledExpectation;
+pub use crate::deserialize::{
+    assert_json_deserializes_to, assert_json_deserializes_to_no_panic,
+    assert_json_round_trips_no_panic, assert_raw_json_deserializes_to,
+    assert_raw_json_deserializes_to_no_panic,
+};
+pub use crate::diff::{BorrowedDifference, Difference, DifferenceKind, Key, Path};
+#[cfg(feature = "tokio")]
+pub use crate::eventually::assert_json_eventually_async_no_panic;
+pub use crate::eventually::{assert_json_eventually_no_panic, Polling};
+pub use crate::explain::{explain_json, Decision, Explanation};
+pub use crate::lint::{assert_json_lint_no_panic, Lint};
+pub use crate::minimize::{minimal_failing_subtree, FailingSubtree};
+pub use crate::overrides::ConfigOverrides;
+pub use crate::patch::{apply_differences, diff_as_json_patch, PatchOp};
+#[doc(hidden)]
+pub use crate::paths::__to_value;
+pub use crate::paths::{
+    assert_json_any_item_no_panic, assert_json_matching_items_no_panic, assert_json_paths_no_panic,
+    check_json_paths, leaf_paths, leaf_paths_with_types, Quantity,
+};
+pub use crate::pattern::PathPattern;
+#[cfg(feature = "regex")]
+pub use crate::pattern::PathRegex;
+#[doc(hidden)]
+pub use crate::reader::{
+    assert_json_matches_reader, assert_json_matches_reader_no_panic, ReaderError,
+};
+pub use crate::record::__recording_path;
+pub use crate::record::check_json_recording;
+pub use crate::relations::{assert_json_relation_no_panic, Relation};
+pub use crate::report::Mismatch;
+pub use crate::similarity::{assert_json_similar_no_panic, json_similarity};
+pub use crate::soft::JsonChecks;
+pub use crate::symmetric::{diff_symmetric, SymmetricDiff};
+pub use crate::template::Template;
+pub use crate::three_way::{
+    merge_json, three_way_diff, Change, ChangeKind, MergeStrategy, ThreeWayDiff,
+};
+pub use crate::to_json::{__Document, __Json, __ViaSerialize, __ViaValue, ToJsonValue};
+pub use crate::writer::{write_json_diff, DiffWriter, IoDiffWriter, PrintDiffWriter};
+#[doc(hidden)]
+pub use serde_json as __serde_json;
 
+mod arrays;
+mod comparator;
+mod compiled;
 mod core_ext;
+mod depth;
+mod deserialize;
 mod diff;
+mod duration;
+mod eventually;
+#[cfg(feature = "expect-test")]
+pub mod expect_test;
+mod explain;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "googletest")]
+pub mod googletest;
+#[cfg(feature = "jcs")]
+pub mod jcs;
+#[cfg(feature = "json5")]
+pub mod json5;
+#[cfg(feature = "jwt")]
+pub mod jwt;
+mod lint;
+pub mod matchers;
+mod minimize;
+mod normalize;
+pub mod normalizers;
+mod overrides;
+pub mod pact;
+mod parse;
+mod patch;
+mod paths;
+mod pattern;
+#[cfg(feature = "predicates")]
+pub mod predicates;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+mod reader;
+mod record;
+mod relations;
+mod render;
+mod report;
+mod similarity;
+mod soft;
+mod stats;
+mod summary;
+mod symmetric;
+mod template;
+mod three_way;
+mod timestamp;
+mod to_json;
+mod tree;
+mod url;
+mod writer;
 
 /// Assert that a JSON value contains other JSON value
 ///
@@ -189,13 +348,13 @@ mod diff;
 #[macro_export]
 macro_rules! assert_json_contains {
     (container: $container:expr, contained: $contained:expr $(,)?) => {{
-        let config =
-            $crate::Config::new($crate::CompareMode::Inclusive).consider_array_sorting(false);
+        let config = $crate::Config::new($crate::CompareMode::Inclusive)
+            .array_mode($crate::ArrayMode::UnorderedSubset);
         $crate::assert_json_matches!($container, $contained, &config)
     }};
     (container: $container:expr, contained: $contained:expr, $($arg:tt)+) => {{
-        let config =
-            $crate::Config::new($crate::CompareMode::Inclusive).consider_array_sorting(false);
+        let config = $crate::Config::new($crate::CompareMode::Inclusive)
+            .array_mode($crate::ArrayMode::UnorderedSubset);
         $crate::assert_json_matches!($container, $contained, &config, $($arg)+)
     }};
 }
@@ -242,6 +401,37 @@ macro_rules! assert_json_eq {
     }};
 }
 
+/// Compare two JSON values for an exact match, ignoring the order of array items.
+///
+/// Arrays are sorted on both sides, at any depth, before the values are compared for an exact
+/// match. Failure messages show the sorted values. This is a quick way to deal with
+/// nondeterministic ordering, but unlike [`ArraySortingMode::Ignore`] the differences are found
+/// between items at the same position after sorting, which isn't always the most telling pairing.
+/// See [`normalizers::SortArrays`] for details.
+///
+/// ```
+/// use serde_json_assert::assert_json_eq_sorted;
+/// use serde_json::json;
+///
+/// assert_json_eq_sorted!(
+///     json!({ "ids": [3, 1, 2], "groups": [["b", "a"], ["c"]] }),
+///     json!({ "ids": [1, 2, 3], "groups": [["c"], ["a", "b"]] })
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq_sorted {
+    ($lhs:expr, $rhs:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict)
+            .normalizer($crate::normalizers::SortArrays);
+        $crate::assert_json_matches!($lhs, $rhs, &config)
+    }};
+    ($lhs:expr, $rhs:expr, $($arg:tt)+) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict)
+            .normalizer($crate::normalizers::SortArrays);
+        $crate::assert_json_matches!($lhs, $rhs, &config, $($arg)+)
+    }};
+}
+
 /// Compare two JSON values according to a configuration.
 ///
 /// ```
@@ -314,184 +504,3035 @@ macro_rules! assert_json_eq {
 ///     }),
 /// );
 /// ```
+///
+/// Instead of a [`Config`], the settings can also be given inline as `key = value` pairs. The
+/// comparison is strict unless `mode` says otherwise.
+///
+/// | Key             | Values                                  |
+/// |-----------------|-----------------------------------------|
+/// | `mode`          | `strict`, `inclusive`                   |
+/// | `arrays`        | `ordered`, `unordered`, `prefix`, `set` |
+/// | `numeric`       | `strict`, `float`                       |
+/// | `float_epsilon` | an `f64` expression                     |
+/// | `verbosity`     | `quiet`, `normal`, `verbose`            |
+/// | `max_diffs`     | a `usize` expression                    |
+/// | `keys`          | `exact`, `numeric`                      |
+/// | `format`        | `list`, `tree`                          |
+///
+/// ```
+/// # use serde_json_assert::assert_json_matches;
+/// # use serde_json::json;
+/// assert_json_matches!(
+///     json!({ "a": [3, 1, 2], "b": 0.1, "c": true }),
+///     json!({ "a": [1, 2, 3], "b": 0.1000001 }),
+///     mode = inclusive,
+///     arrays = unordered,
+///     float_epsilon = 1e-6,
+/// );
+/// ```
+///
+/// The panic message ends with the file and line of the assertion. When the assertion is made by a
+/// shared helper function, mark the helper with `#[track_caller]` and the reported location is
+/// that of the test calling the helper instead:
+///
+/// ```
+/// use serde_json_assert::assert_json_eq;
+/// use serde_json::{json, Value};
+///
+/// #[track_caller]
+/// fn assert_user(user: Value) {
+///     assert_json_eq!(user, json!({ "id": 1 }));
+/// }
+///
+/// assert_user(json!({ "id": 1 }));
+/// ```
 #[macro_export]
 macro_rules! assert_json_matches {
+    ($lhs:expr, $rhs:expr, $key:ident = $($settings:tt)+) => {{
+        let config = $crate::__config!(
+            $crate::Config::new($crate::CompareMode::Strict);
+            $key = $($settings)+
+        );
+        $crate::assert_json_matches!($lhs, $rhs, &config)
+    }};
     ($lhs:expr, $rhs:expr, $config:expr $(,)?) => {{
-        if let Err(error) = $crate::assert_json_matches_no_panic(&$lhs, &$rhs, $config) {
-            panic!("\n{}", error);
+        #[allow(unused_imports)]
+        use $crate::{__ViaSerialize as _, __ViaValue as _};
+        match $crate::__assert_json_values_match_no_panic(
+            (&$crate::__Json(&$lhs)).__json(),
+            (&$crate::__Json(&$rhs)).__json(),
+            $config,
+        ) {
+            Ok(extras) => $crate::__report_extras(&extras),
+            Err(error) => panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller()),
         }
     }};
     ($lhs:expr, $rhs:expr, $config:expr, $($arg:tt)+) => {{
-        if let Err(error) = $crate::assert_json_matches_no_panic(&$lhs, &$rhs, $config) {
-            panic!("\n{}\n\n{}", format_args!($($arg)+), error);
+        #[allow(unused_imports)]
+        use $crate::{__ViaSerialize as _, __ViaValue as _};
+        match $crate::__assert_json_values_match_no_panic(
+            (&$crate::__Json(&$lhs)).__json(),
+            (&$crate::__Json(&$rhs)).__json(),
+            $config,
+        ) {
+            Ok(extras) => $crate::__report_extras(&extras),
+            Err(error) => panic!(
+                "\n{}\n\n{}\n\nat {}",
+                format_args!($($arg)+),
+                error,
+                ::std::panic::Location::caller()
+            ),
+        }
+    }};
+}
+
+/// Compare each item of a JSON array to an expected fragment.
+///
+/// Every item must include the fragment, as with [`assert_json_include`], or match it using the
+/// [`Config`] passed as the last argument. This replaces loops asserting the same fields on every
+/// item of a page. On failure, the differences are grouped by the index of the item.
+///
+/// ```
+/// use serde_json_assert::assert_json_each;
+/// use serde_json::json;
+///
+/// let page = json!([
+///     { "id": 1, "tenant_id": "acme" },
+///     { "id": 2, "tenant_id": "acme", "archived": true },
+/// ]);
+///
+/// assert_json_each!(page, json!({ "tenant_id": "acme" }));
+/// ```
+///
+/// The arguments can also be named, which reads better when the array is part of a response:
+///
+/// ```
+/// use serde_json_assert::{assert_json_each, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let body = json!({ "items": [{ "id": 1, "status": "active" }, { "id": 2, "status": "active" }] });
+///
+/// assert_json_each!(actual: body["items"], template: json!({ "status": "active" }));
+/// assert_json_each!(
+///     actual: body["items"],
+///     template: json!({ "status": "active" }),
+///     config: &Config::new(CompareMode::Inclusive),
+/// );
+/// ```
+///
+/// See [`assert_json_each_no_panic`] for details.
+#[macro_export]
+macro_rules! assert_json_each {
+    (actual: $actual:expr, template: $template:expr $(,)?) => {{
+        $crate::assert_json_each!($actual, $template)
+    }};
+    (actual: $actual:expr, template: $template:expr, config: $config:expr $(,)?) => {{
+        $crate::assert_json_each!($actual, $template, $config)
+    }};
+    ($actual:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        $crate::assert_json_each!($actual, $expected, &config)
+    }};
+    ($actual:expr, $expected:expr, $config:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_each_no_panic(&$actual, &$expected, $config) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Assert that at least one item of a JSON array matches an expected value.
+///
+/// The array is found at the path given as second argument, such as `".data.users"`, or `"$"` for
+/// the root. Items must include the expected value, as with [`assert_json_include`], or match it
+/// using the [`Config`] passed as the last argument. On failure, the differences from the closest
+/// item are shown.
+///
+/// ```
+/// use serde_json_assert::{assert_json_any_item, matchers};
+/// use serde_json::json;
+///
+/// let response = json!({ "data": { "events": [
+///     { "type": "created", "at": 1 },
+///     { "type": "paid", "at": 2, "amount": 30 },
+/// ] } });
+///
+/// assert_json_any_item!(response, ".data.events", json!({ "type": "paid", "amount": matchers::any_number() }));
+/// ```
+///
+/// See [`assert_json_any_item_no_panic`] for details.
+#[macro_export]
+macro_rules! assert_json_any_item {
+    ($actual:expr, $path:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        $crate::assert_json_any_item!($actual, $path, $expected, &config)
+    }};
+    ($actual:expr, $path:expr, $expected:expr, $config:expr $(,)?) => {{
+        if let Err(error) =
+            $crate::assert_json_any_item_no_panic(&$actual, $path, &$expected, $config)
+        {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Assert how many items of a JSON array match an expected value.
+///
+/// The array is found at the path given as second argument, such as `".data.users"`, or `"$"` for
+/// the root. The number of items that must match is given as `exactly = n`, `at_least = n` or
+/// `at_most = n`. Items must include the expected value, as with [`assert_json_include`], or match
+/// it using the [`Config`] passed as the last argument. On failure, the matching items are listed.
+///
+/// ```
+/// use serde_json_assert::{assert_json_matching_items, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let results = json!({ "hits": [
+///     { "id": 1, "status": "open" },
+///     { "id": 2, "status": "closed" },
+///     { "id": 3, "status": "open" },
+/// ] });
+///
+/// assert_json_matching_items!(results, ".hits", json!({ "status": "open" }), exactly = 2);
+/// assert_json_matching_items!(results, ".hits", json!({ "status": "stale" }), at_most = 0);
+/// assert_json_matching_items!(
+///     results,
+///     ".hits",
+///     json!({ "status": "closed" }),
+///     at_least = 1,
+///     &Config::new(CompareMode::Inclusive)
+/// );
+/// ```
+///
+/// See [`assert_json_matching_items_no_panic`] for details.
+#[macro_export]
+macro_rules! assert_json_matching_items {
+    ($actual:expr, $path:expr, $expected:expr, $quantity:ident = $count:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        $crate::assert_json_matching_items!($actual, $path, $expected, $quantity = $count, &config)
+    }};
+    ($actual:expr, $path:expr, $expected:expr, $quantity:ident = $count:expr, $config:expr $(,)?) => {{
+        let quantity = $crate::__quantity!($quantity = $count);
+        if let Err(error) = $crate::assert_json_matching_items_no_panic(
+            &$actual, $path, &$expected, quantity, $config,
+        ) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quantity {
+    (exactly = $count:expr) => {
+        $crate::Quantity::Exactly($count)
+    };
+    (at_least = $count:expr) => {
+        $crate::Quantity::AtLeast($count)
+    };
+    (at_most = $count:expr) => {
+        $crate::Quantity::AtMost($count)
+    };
+}
+
+/// Assert that two JSON values are at least as similar as a threshold.
+///
+/// The threshold is a fraction between 0 and 1, see [`json_similarity`] for how the similarity is
+/// computed. The values are compared strictly, or with the [`Config`] passed as the last argument.
+/// This suits documents that are never exactly equal, such as generated or sampled ones. On
+/// failure, the similarity and the largest contributors to the dissimilarity are shown.
+///
+/// ```
+/// use serde_json_assert::assert_json_similar;
+/// use serde_json::json;
+///
+/// assert_json_similar!(
+///     json!({ "labels": ["cat", "dog", "cat", "bird"], "model": "v2" }),
+///     json!({ "labels": ["cat", "dog", "cat", "fish"], "model": "v2" }),
+///     0.75
+/// );
+/// ```
+///
+/// See [`assert_json_similar_no_panic`] for details.
+#[macro_export]
+macro_rules! assert_json_similar {
+    ($lhs:expr, $rhs:expr, $threshold:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_similar!($lhs, $rhs, $threshold, &config)
+    }};
+    ($lhs:expr, $rhs:expr, $threshold:expr, $config:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_similar_no_panic(&$lhs, &$rhs, $threshold, $config)
+        {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Compare two JSON values like [`assert_json_matches`], recording a failure in a [`JsonChecks`]
+/// instead of panicking.
+///
+/// The first argument is the `JsonChecks`, followed by the arguments of [`assert_json_matches`],
+/// including an optional message. Evaluates to true if the values match.
+///
+/// ```
+/// use serde_json_assert::{check_json, CompareMode, Config, JsonChecks};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let mut checks = JsonChecks::new();
+/// check_json!(checks, json!({ "a": 1, "b": 2 }), json!({ "a": 1 }), &config);
+/// checks.finalize();
+/// ```
+#[macro_export]
+macro_rules! check_json {
+    ($checks:expr, $lhs:expr, $rhs:expr, $config:expr $(,)?) => {{
+        $checks.record($crate::assert_json_matches_no_panic(&$lhs, &$rhs, $config))
+    }};
+    ($checks:expr, $lhs:expr, $rhs:expr, $config:expr, $($arg:tt)+) => {{
+        $checks.record(
+            $crate::assert_json_matches_no_panic(&$lhs, &$rhs, $config)
+                .map_err(|error| format!("{}\n\n{}", format_args!($($arg)+), error)),
+        )
+    }};
+}
+
+/// Compare two JSON values for equality like [`assert_json_eq`], recording a failure in a
+/// [`JsonChecks`] instead of panicking.
+///
+/// See [`JsonChecks`] for an example.
+#[macro_export]
+macro_rules! check_json_eq {
+    ($checks:expr, $lhs:expr, $rhs:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::check_json!($checks, $lhs, $rhs, &config)
+    }};
+    ($checks:expr, $lhs:expr, $rhs:expr, $($arg:tt)+) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::check_json!($checks, $lhs, $rhs, &config, $($arg)+)
+    }};
+}
+
+/// Compare two JSON values for inclusion like [`assert_json_include`], recording a failure in a
+/// [`JsonChecks`] instead of panicking.
+///
+/// See [`JsonChecks`] for an example.
+#[macro_export]
+macro_rules! check_json_include {
+    ($checks:expr, actual: $actual:expr, expected: $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        $crate::check_json!($checks, $actual, $expected, &config)
+    }};
+    ($checks:expr, expected: $expected:expr, actual: $actual:expr $(,)?) => {{
+        $crate::check_json_include!($checks, actual: $actual, expected: $expected)
+    }};
+    ($checks:expr, actual: $actual:expr, expected: $expected:expr, $($arg:tt)+) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        $crate::check_json!($checks, $actual, $expected, &config, $($arg)+)
+    }};
+    ($checks:expr, expected: $expected:expr, actual: $actual:expr, $($arg:tt)+) => {{
+        $crate::check_json_include!($checks, actual: $actual, expected: $expected, $($arg)+)
+    }};
+}
+
+/// Assert that a JSON value eventually matches, polling for it until a timeout.
+///
+/// The first argument is a closure returning the actual value, which is called until the value
+/// matches or the timeout passes. The values are compared strictly, or with the [`Config`] passed
+/// as third argument, and polled every 100 milliseconds for up to 5 seconds, or as set by the
+/// [`Polling`] passed as fourth argument. On timeout, the differences of the last attempt are
+/// shown.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eventually, CompareMode, Config, Polling};
+/// use serde_json::json;
+/// use std::time::{Duration, Instant};
+///
+/// let started = Instant::now();
+/// let status = || {
+///     let done = started.elapsed() > Duration::from_millis(20);
+///     json!({ "id": 7, "status": if done { "done" } else { "running" } })
+/// };
+///
+/// assert_json_eventually!(
+///     status,
+///     json!({ "status": "done" }),
+///     &Config::new(CompareMode::Inclusive),
+///     &Polling::new().interval(Duration::from_millis(5))
+/// );
+/// ```
+///
+/// See [`assert_json_eventually_no_panic`] for details, and [`assert_json_eventually_async`] to
+/// poll futures.
+#[macro_export]
+macro_rules! assert_json_eventually {
+    ($actual:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_eventually!($actual, $expected, &config)
+    }};
+    ($actual:expr, $expected:expr, $config:expr $(,)?) => {{
+        $crate::assert_json_eventually!($actual, $expected, $config, &$crate::Polling::new())
+    }};
+    ($actual:expr, $expected:expr, $config:expr, $polling:expr $(,)?) => {{
+        if let Err(error) =
+            $crate::assert_json_eventually_no_panic($actual, &$expected, $config, $polling)
+        {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Assert that a JSON value returned by a future eventually matches, polling for it until a
+/// timeout.
+///
+/// Like [`assert_json_eventually`], but the closure returns a future, and the macro must be used
+/// in an async function running on a [tokio](https://docs.rs/tokio) runtime.
+///
+/// ```
+/// use serde_json_assert::assert_json_eventually_async;
+/// use serde_json::json;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let fetch = || async { json!({ "status": "done" }) };
+/// assert_json_eventually_async!(fetch, json!({ "status": "done" }));
+/// # }
+/// ```
+///
+/// Requires the `tokio` feature. See [`assert_json_eventually_async_no_panic`] for details.
+#[cfg(feature = "tokio")]
+#[macro_export]
+macro_rules! assert_json_eventually_async {
+    ($actual:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_eventually_async!($actual, $expected, &config)
+    }};
+    ($actual:expr, $expected:expr, $config:expr $(,)?) => {{
+        $crate::assert_json_eventually_async!($actual, $expected, $config, &$crate::Polling::new())
+    }};
+    ($actual:expr, $expected:expr, $config:expr, $polling:expr $(,)?) => {{
+        if let Err(error) =
+            $crate::assert_json_eventually_async_no_panic($actual, &$expected, $config, $polling)
+                .await
+        {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Assert that a JSON value contains none of the constructs disallowed by a [`Lint`].
+///
+/// The whole document is scanned, and every offending path is reported.
+///
+/// ```should_panic
+/// use serde_json_assert::{assert_json_lint, Lint};
+/// use serde_json::json;
+///
+/// let response = json!({ "user": { "name": "Ann", "email": null, "roles": [] } });
+///
+/// assert_json_lint!(response, Lint::new().no_nulls().no_empty_arrays());
+/// ```
+#[macro_export]
+macro_rules! assert_json_lint {
+    ($value:expr, $lint:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_lint_no_panic(&$value, &$lint) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Compare a JSON value to several alternatives, passing if any of them matches.
+///
+/// The alternatives are given in brackets and can be of different types. They are compared
+/// strictly, or with the [`Config`] passed as the last argument. On failure, the differences from
+/// the closest alternative are shown.
+///
+/// ```
+/// use serde_json_assert::{assert_json_matches_any, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let response = json!({ "version": 2, "user": { "first_name": "Ann" } });
+///
+/// assert_json_matches_any!(
+///     response,
+///     [
+///         json!({ "user": { "name": "Ann" } }),
+///         json!({ "user": { "first_name": "Ann" } }),
+///     ],
+///     &config
+/// );
+/// ```
+///
+/// See [`assert_json_matches_any_no_panic`] for details.
+#[macro_export]
+macro_rules! assert_json_matches_any {
+    ($lhs:expr, [$($rhs:expr),+ $(,)?] $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_matches_any!($lhs, [$($rhs),+], &config)
+    }};
+    ($lhs:expr, [$($rhs:expr),+ $(,)?], $config:expr $(,)?) => {{
+        let alternatives = [$($crate::__to_value(&$rhs)),+];
+        if let Err(error) = $crate::assert_json_matches_any_no_panic(&$lhs, &alternatives, $config) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Compare two JSON values and return the values captured by [`matchers::capture`] markers.
+///
+/// Takes the same arguments as [`assert_json_matches`]: a [`Config`] or inline settings can be
+/// passed after the expected value, and the comparison is strict otherwise. Evaluates to a
+/// `HashMap<String, Value>` of the captured values. See [`check_json_captures`] for details.
+///
+/// ```
+/// use serde_json_assert::{assert_json_captures, matchers};
+/// use serde_json::json;
+///
+/// let created = json!({ "id": 7, "tags": ["new"] });
+/// let captures = assert_json_captures!(
+///     created,
+///     json!({ "id": matchers::capture("id", matchers::any_number()) }),
+///     mode = inclusive,
+/// );
+/// assert_eq!(captures["id"], 7);
+/// ```
+#[macro_export]
+macro_rules! assert_json_captures {
+    ($lhs:expr, $rhs:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_captures!($lhs, $rhs, &config)
+    }};
+    ($lhs:expr, $rhs:expr, $key:ident = $($settings:tt)+) => {{
+        let config = $crate::__config!(
+            $crate::Config::new($crate::CompareMode::Strict);
+            $key = $($settings)+
+        );
+        $crate::assert_json_captures!($lhs, $rhs, &config)
+    }};
+    ($lhs:expr, $rhs:expr, $config:expr $(,)?) => {{
+        match $crate::__check_json_captures(&$lhs, &$rhs, $config) {
+            Ok((captures, extras)) => {
+                $crate::__report_extras(&extras);
+                captures
+            }
+            Err(mismatch) => {
+                panic!("\n{}\n\nat {}", mismatch, ::std::panic::Location::caller())
+            }
+        }
+    }};
+}
+
+/// Compare the values at several paths of a JSON document.
+///
+/// Paths are written like in failure messages, such as `.data.users[0].name`. Every path is
+/// checked and all failures are reported together. Values are compared exactly unless a
+/// [`Config`] is passed as the last argument. See [`check_json_paths`] for details.
+///
+/// ```
+/// use serde_json_assert::{assert_json_paths, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let user = json!({ "id": 1, "address": { "city": "Oslo", "zip": "0150" } });
+///
+/// assert_json_paths!(user, { ".id" => 1, ".address.city" => "Oslo" });
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// assert_json_paths!(user, { "$.address" => json!({ "city": "Oslo" }) }, &config);
+/// ```
+#[macro_export]
+macro_rules! assert_json_paths {
+    ($actual:expr, { $($path:expr => $expected:expr),+ $(,)? } $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_paths!($actual, { $($path => $expected),+ }, &config)
+    }};
+    ($actual:expr, { $($path:expr => $expected:expr),+ $(,)? }, $config:expr $(,)?) => {{
+        let assertions = [$(($path, $crate::__to_value(&$expected))),+];
+        match $crate::check_json_paths(&$actual, assertions, $config) {
+            Ok(extras) => $crate::__report_extras(&extras),
+            Err(mismatch) => panic!("\n{}\n\nat {}", mismatch, ::std::panic::Location::caller()),
+        }
+    }};
+}
+
+/// Assert that the items of an array in a JSON document are sorted.
+///
+/// The arguments are the document, the path of the array and optionally a [`SortedBy`] describing
+/// the expected order. Without one, the items themselves are expected in ascending order. The
+/// failure message shows the first pair of items that are out of order. See
+/// [`assert_json_sorted_no_panic`] for details.
+///
+/// ```
+/// use serde_json_assert::{assert_json_sorted, SortedBy};
+/// use serde_json::json;
+///
+/// let page = json!({ "ids": [1, 4, 9], "users": [{ "age": 41 }, { "age": 27 }] });
+///
+/// assert_json_sorted!(page, ".ids");
+/// assert_json_sorted!(page, ".users", SortedBy::key(".age").descending());
+/// ```
+#[macro_export]
+macro_rules! assert_json_sorted {
+    ($actual:expr, $path:expr $(,)?) => {
+        $crate::assert_json_sorted!($actual, $path, $crate::SortedBy::items())
+    };
+    ($actual:expr, $path:expr, $sorted:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_sorted_no_panic(&$actual, $path, &$sorted) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Assert that the items of an array in a JSON document are unique.
+///
+/// The arguments are the document, the path of the array and optionally a key relative to each
+/// item, such as `.id`. With a key, no two items may have equal values at the key; without one, no
+/// two items may be equal. The failure message lists the paths of all duplicates. See
+/// [`assert_json_unique_no_panic`] for details.
+///
+/// ```
+/// use serde_json_assert::assert_json_unique;
+/// use serde_json::json;
+///
+/// let order = json!({ "tags": ["new", "gift"], "lines": [{ "sku": "A1" }, { "sku": "B2" }] });
+///
+/// assert_json_unique!(order, ".tags");
+/// assert_json_unique!(order, ".lines", ".sku");
+/// ```
+#[macro_export]
+macro_rules! assert_json_unique {
+    ($actual:expr, $path:expr $(,)?) => {
+        $crate::assert_json_unique!($actual, $path, "$")
+    };
+    ($actual:expr, $path:expr, $key:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_unique_no_panic(&$actual, $path, $key) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Assert that an aggregate of the numbers in an array of a JSON document equals a value.
+///
+/// The arguments are the document, the path of the array, the key of the numbers relative to each
+/// item (`$` for arrays of numbers), the [`Aggregate`] and the expected value. Aggregates are
+/// compared exactly unless a [`Config`] is passed as the last argument, whose
+/// [`FloatCompareMode`] is used. See [`assert_json_aggregate_no_panic`] for details.
+///
+/// ```
+/// use serde_json_assert::{assert_json_aggregate, Aggregate, CompareMode, Config, FloatCompareMode};
+/// use serde_json::json;
+///
+/// let invoice = json!({ "total": 30.3, "lines": [{ "amount": 10.1 }, { "amount": 20.2 }] });
+///
+/// assert_json_aggregate!(invoice, ".lines", ".amount", Aggregate::Max, 20.2);
+///
+/// let config = Config::new(CompareMode::Strict).float_compare_mode(FloatCompareMode::Epsilon(1e-9));
+/// let total = invoice["total"].as_f64().unwrap();
+/// assert_json_aggregate!(invoice, ".lines", ".amount", Aggregate::Sum, total, &config);
+/// ```
+#[macro_export]
+macro_rules! assert_json_aggregate {
+    ($actual:expr, $path:expr, $key:expr, $aggregate:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_aggregate!($actual, $path, $key, $aggregate, $expected, &config)
+    }};
+    ($actual:expr, $path:expr, $key:expr, $aggregate:expr, $expected:expr, $config:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_aggregate_no_panic(
+            &$actual, $path, $key, $aggregate, $expected, $config,
+        ) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Asserts that two values of the same document satisfy a constraint.
+///
+/// The sides are paths or functions of the values matching a pattern, written as string literals
+/// around one of `==`, `!=`, `<`, `<=`, `>` and `>=`. Numbers are compared exactly unless a
+/// [`Config`] with a [`FloatCompareMode`] is passed as the last argument. See
+/// [`assert_json_relation_no_panic`] for the supported functions.
+///
+/// ```
+/// use serde_json_assert::assert_json_relation;
+/// use serde_json::json;
+///
+/// let order = json!({
+///     "total": 29.5,
+///     "items": [{ "price": 10 }, { "price": 19.5 }],
+///     "created": "2024-04-01T10:00:00Z",
+///     "shipped": "2024-04-03T08:30:00Z",
+/// });
+///
+/// assert_json_relation!(order, ".total" == "sum(.items[*].price)");
+/// assert_json_relation!(order, ".created" <= ".shipped");
+/// ```
+#[macro_export]
+macro_rules! assert_json_relation {
+    ($actual:expr, $lhs:tt $op:tt $rhs:tt $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_relation!($actual, $lhs $op $rhs, &config)
+    }};
+    ($actual:expr, $lhs:tt $op:tt $rhs:tt, $config:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_relation_no_panic(
+            &$actual,
+            $lhs,
+            $crate::__relation!($op),
+            $rhs,
+            $config,
+        ) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __relation {
+    (==) => {
+        $crate::Relation::Equal
+    };
+    (!=) => {
+        $crate::Relation::NotEqual
+    };
+    (<) => {
+        $crate::Relation::Less
+    };
+    (<=) => {
+        $crate::Relation::LessOrEqual
+    };
+    (>) => {
+        $crate::Relation::Greater
+    };
+    (>=) => {
+        $crate::Relation::GreaterOrEqual
+    };
+}
+
+/// Asserts that a value survives a round trip through JSON.
+///
+/// The value is serialized, deserialized and serialized again, and the two JSON documents are
+/// compared strictly, or with the config passed as third argument. The differences point to the
+/// fields whose serde implementations aren't symmetric.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_json_assert::assert_json_round_trips;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     id: u64,
+///     #[serde(rename = "displayName")]
+///     name: String,
+/// }
+///
+/// assert_json_round_trips!(User { id: 1, name: "Ann".to_string() });
+/// ```
+///
+/// See [`assert_json_round_trips_no_panic`] for details.
+#[macro_export]
+macro_rules! assert_json_round_trips {
+    ($value:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_round_trips!($value, &config)
+    }};
+    ($value:expr, $config:expr $(,)?) => {{
+        if let Err(error) = $crate::assert_json_round_trips_no_panic(&$value, $config) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Like [`assert_json_eq`], but only checked in builds with debug assertions enabled.
+///
+/// In release builds the comparison is skipped entirely, so it can be used for invariant checks on
+/// JSON produced inside library code. Like [`debug_assert`], the arguments are still type checked
+/// but never evaluated.
+///
+/// ```
+/// use serde_json_assert::debug_assert_json_eq;
+/// use serde_json::json;
+///
+/// debug_assert_json_eq!(json!({ "a": 1 }), json!({ "a": 1 }));
+/// ```
+#[macro_export]
+macro_rules! debug_assert_json_eq {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::assert_json_eq!($($arg)*);
+        }
+    };
+}
+
+/// Like [`assert_json_include`], but only checked in builds with debug assertions enabled.
+///
+/// See [`debug_assert_json_eq`] for details.
+#[macro_export]
+macro_rules! debug_assert_json_include {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::assert_json_include!($($arg)*);
+        }
+    };
+}
+
+/// Like [`assert_json_contains`], but only checked in builds with debug assertions enabled.
+///
+/// See [`debug_assert_json_eq`] for details.
+#[macro_export]
+macro_rules! debug_assert_json_contains {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::assert_json_contains!($($arg)*);
+        }
+    };
+}
+
+/// Like [`assert_json_matches`], but only checked in builds with debug assertions enabled.
+///
+/// See [`debug_assert_json_eq`] for details.
+#[macro_export]
+macro_rules! debug_assert_json_matches {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::assert_json_matches!($($arg)*);
+        }
+    };
+}
+
+/// Assert that a JSON text is in canonical form, as defined by RFC 8785.
+///
+/// The text can be anything that can be viewed as bytes, such as `&str`, `String` or `Vec<u8>`.
+/// On failure, the message shows where the text first differs from its canonical form. See
+/// [`jcs`] for details.
+///
+/// ```should_panic
+/// use serde_json_assert::assert_json_canonical;
+///
+/// assert_json_canonical!(r#"{"id":1, "name":"Ann"}"#);
+/// ```
+///
+/// Requires the `jcs` feature.
+#[cfg(feature = "jcs")]
+#[macro_export]
+macro_rules! assert_json_canonical {
+    ($text:expr $(,)?) => {{
+        if let Err(error) = $crate::jcs::assert_canonical_no_panic($text) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Compare the claims of a JSON Web Token with a JSON value, allowing additional claims.
+///
+/// The arguments are the token and the expected claims. See [`jwt`] for details.
+///
+/// Requires the `jwt` feature.
+#[cfg(feature = "jwt")]
+#[macro_export]
+macro_rules! assert_jwt_claims_include {
+    ($token:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        if let Err(error) = $crate::jwt::assert_claims_no_panic(
+            ::std::convert::AsRef::<str>::as_ref(&$token),
+            &$expected,
+            &config,
+        ) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Compare the header of a JSON Web Token with a JSON value, allowing additional fields.
+///
+/// The arguments are the token and the expected header. See [`jwt`] for details.
+///
+/// Requires the `jwt` feature.
+#[cfg(feature = "jwt")]
+#[macro_export]
+macro_rules! assert_jwt_header_include {
+    ($token:expr, $expected:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Inclusive);
+        if let Err(error) = $crate::jwt::assert_header_no_panic(
+            ::std::convert::AsRef::<str>::as_ref(&$token),
+            &$expected,
+            &config,
+        ) {
+            panic!("\n{}\n\nat {}", error, ::std::panic::Location::caller());
+        }
+    }};
+}
+
+/// Compare a JSON value to a recording made by a previous run of the current test.
+///
+/// The first time the test runs, or whenever the `JSON_ASSERT_RECORD` environment variable is set,
+/// the value is recorded in `tests/recordings/<test name>.json` of the crate being tested. Later
+/// runs compare the value to the recording, exactly unless a [`Config`] is passed. Missing
+/// recordings aren't created when the `CI` environment variable is set, see
+/// [`check_json_recording`] for details.
+///
+/// Recordings are named after the thread the test harness runs the test on, so the macro can't be
+/// used on other threads.
+///
+/// ```no_run
+/// use serde_json_assert::{assert_json_recorded, CompareMode, Config};
+/// use serde_json::json;
+///
+/// # fn fetch_user() -> serde_json::Value { json!({}) }
+/// #[test]
+/// fn user_payload() {
+///     let config = Config::new(CompareMode::Inclusive);
+///     assert_json_recorded!(fetch_user(), &config);
+/// }
+/// ```
+#[macro_export]
+macro_rules! assert_json_recorded {
+    ($actual:expr $(,)?) => {{
+        let config = $crate::Config::new($crate::CompareMode::Strict);
+        $crate::assert_json_recorded!($actual, &config)
+    }};
+    ($actual:expr, $config:expr $(,)?) => {{
+        let recording = $crate::__recording_path(env!("CARGO_MANIFEST_DIR"));
+        if let Err(error) = $crate::check_json_recording(&recording, &$actual, $config) {
+            panic!(
+                "\n{}\n\nrecording: {}\nat {}",
+                error,
+                recording.display(),
+                ::std::panic::Location::caller()
+            );
+        }
+    }};
+}
+
+/// Construct an expected JSON value where some objects and arrays are open.
+///
+/// The syntax is that of [`serde_json::json!`], except that objects and arrays can end with `..`.
+/// Such objects may contain additional fields, and such arrays additional trailing items, even in
+/// a strict comparison. Every other node is compared using the mode of the comparison. See
+/// [`matchers::partial`] for details.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, json_partial};
+/// use serde_json::json;
+///
+/// let response = json!({
+///     "user": { "id": 1, "name": "Bob", "created": "2021-01-01" },
+///     "roles": ["admin", "dev"],
+/// });
+///
+/// assert_json_eq!(
+///     response,
+///     json_partial!({
+///         "user": { "id": 1, "name": "Bob", .. },
+///         "roles": ["admin", ..],
+///     })
+/// );
+/// ```
+///
+/// Only object keys written as literals are supported. Values can be any expression that
+/// [`serde_json::json!`] accepts.
+#[macro_export]
+macro_rules! json_partial {
+    ($($json:tt)+) => {
+        $crate::__json_partial!(@value $($json)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __json_partial {
+    (@value { $($fields:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut fields = $crate::__serde_json::Map::new();
+        #[allow(unused_mut)]
+        let mut open = false;
+        $crate::__json_partial!(@fields fields open () $($fields)*);
+        $crate::__json_partial!(@finish open $crate::__serde_json::Value::Object(fields))
+    }};
+    (@value [ $($items:tt)* ]) => {{
+        #[allow(unused_mut)]
+        let mut items = ::std::vec::Vec::new();
+        #[allow(unused_mut)]
+        let mut open = false;
+        $crate::__json_partial!(@items items open () $($items)*);
+        $crate::__json_partial!(@finish open $crate::__serde_json::Value::Array(items))
+    }};
+    (@value $($json:tt)+) => {
+        $crate::__serde_json::json!($($json)+)
+    };
+
+    (@finish $open:ident $value:expr) => {
+        if $open {
+            $crate::matchers::partial($value)
+        } else {
+            $value
+        }
+    };
+
+    // Object fields, as `"key": value` pairs. The value is collected token by token until the
+    // next comma.
+    (@fields $fields:ident $open:ident ()) => {};
+    (@fields $fields:ident $open:ident () .. $(,)?) => {
+        $open = true;
+    };
+    (@fields $fields:ident $open:ident () $key:literal : $($rest:tt)*) => {
+        $crate::__json_partial!(@field $fields $open ($key) () $($rest)*)
+    };
+    (@field $fields:ident $open:ident ($key:literal) ($($value:tt)+) $(, $($rest:tt)*)?) => {
+        $fields.insert(
+            ::std::string::String::from($key),
+            $crate::__json_partial!(@value $($value)+),
+        );
+        $crate::__json_partial!(@fields $fields $open () $($($rest)*)?);
+    };
+    (@field $fields:ident $open:ident ($key:literal) ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::__json_partial!(@field $fields $open ($key) ($($value)* $next) $($rest)*)
+    };
+
+    // Array items, collected token by token until the next comma.
+    (@items $items:ident $open:ident ()) => {};
+    (@items $items:ident $open:ident () .. $(,)?) => {
+        $open = true;
+    };
+    (@items $items:ident $open:ident ($($item:tt)+) $(, $($rest:tt)*)?) => {
+        $items.push($crate::__json_partial!(@value $($item)+));
+        $crate::__json_partial!(@items $items $open () $($($rest)*)?);
+    };
+    (@items $items:ident $open:ident ($($item:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::__json_partial!(@items $items $open ($($item)* $next) $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __config {
+    ($config:expr; ) => { $config };
+    ($config:expr; mode = strict $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.compare_mode($crate::CompareMode::Strict); $($($rest)*)?)
+    };
+    ($config:expr; mode = inclusive $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.compare_mode($crate::CompareMode::Inclusive); $($($rest)*)?)
+    };
+    ($config:expr; arrays = ordered $(, $($rest:tt)*)?) => {
+        $crate::__config!(
+            {
+                let mut config: $crate::Config = $config;
+                config.array_sorting_mode = $crate::ArraySortingMode::Consider;
+                config
+            };
+            $($($rest)*)?
+        )
+    };
+    ($config:expr; arrays = prefix $(, $($rest:tt)*)?) => {
+        $crate::__config!(
+            $config.array_sorting_mode($crate::ArraySortingMode::Prefix);
+            $($($rest)*)?
+        )
+    };
+    ($config:expr; arrays = set $(, $($rest:tt)*)?) => {
+        $crate::__config!(
+            $config.array_sorting_mode($crate::ArraySortingMode::Set);
+            $($($rest)*)?
+        )
+    };
+    ($config:expr; arrays = unordered $(, $($rest:tt)*)?) => {
+        $crate::__config!(
+            {
+                let mut config: $crate::Config = $config;
+                config.array_sorting_mode = $crate::ArraySortingMode::Ignore;
+                config
+            };
+            $($($rest)*)?
+        )
+    };
+    ($config:expr; numeric = strict $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.numeric_mode($crate::NumericMode::Strict); $($($rest)*)?)
+    };
+    ($config:expr; numeric = float $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.numeric_mode($crate::NumericMode::AssumeFloat); $($($rest)*)?)
+    };
+    ($config:expr; float_epsilon = $epsilon:expr $(, $($rest:tt)*)?) => {
+        $crate::__config!(
+            $config.float_compare_mode($crate::FloatCompareMode::Epsilon($epsilon));
+            $($($rest)*)?
+        )
+    };
+    ($config:expr; verbosity = quiet $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.verbosity($crate::Verbosity::Quiet); $($($rest)*)?)
+    };
+    ($config:expr; verbosity = normal $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.verbosity($crate::Verbosity::Normal); $($($rest)*)?)
+    };
+    ($config:expr; verbosity = verbose $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.verbosity($crate::Verbosity::Verbose); $($($rest)*)?)
+    };
+    ($config:expr; max_diffs = $max_diffs:expr $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.max_diffs(Some($max_diffs)); $($($rest)*)?)
+    };
+    ($config:expr; format = list $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.output_format($crate::OutputFormat::List); $($($rest)*)?)
+    };
+    ($config:expr; format = tree $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.output_format($crate::OutputFormat::Tree); $($($rest)*)?)
+    };
+    ($config:expr; keys = exact $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.numeric_keys(false); $($($rest)*)?)
+    };
+    ($config:expr; keys = numeric $(, $($rest:tt)*)?) => {
+        $crate::__config!($config.numeric_keys(true); $($($rest)*)?)
+    };
+    ($config:expr; $key:ident = $value:tt $($rest:tt)*) => {
+        compile_error!(concat!(
+            "unsupported setting `",
+            stringify!($key),
+            " = ",
+            stringify!($value),
+            "`"
+        ))
+    };
+}
+
+/// Compares two JSON values without panicking.
+///
+/// Instead it returns a `Result` where the error is the message that would be passed to `panic!`.
+/// This is might be useful if you want to control how failures are reported and don't want to deal
+/// with panics.
+#[track_caller]
+pub fn assert_json_matches_no_panic<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+) -> Result<(), String>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    check_json_matches(lhs, rhs, config)
+        .map(|_| ())
+        .map_err(|mismatch| mismatch.to_string())
+}
+
+/// Compares two JSON values without panicking.
+///
+/// Returns a `Result` containing either `Ok(())` if the values match,
+/// or an `Err` with a [`Vec<Difference>`](Difference) describing the differences.
+///
+/// # Note:
+///
+/// This function performs some cloning and may be less efficient.
+///
+/// If you only need a string error message, use [`assert_json_matches_no_panic`] or the assertion
+/// macros. To inspect the differences without cloning the values, use [`diff`].
+///
+/// # Examples
+///
+/// ```
+/// use serde_json_assert::{try_assert_json_matches, Config, CompareMode};
+/// use serde_json::json;
+///
+/// let lhs = json!({ "a": 1, "b": 2 });
+/// let rhs = json!({ "a": 1 });
+/// let config = Config::new(CompareMode::Inclusive);
+///
+/// let result = try_assert_json_matches(&lhs, &rhs, &config);
+/// assert!(result.is_ok());
+///
+/// let lhs = json!({ "a": 1 });
+/// let rhs = json!({ "a": 2 });
+/// let config = Config::new(CompareMode::Strict);
+///
+/// let result = try_assert_json_matches(&lhs, &rhs, &config);
+/// assert!(result.is_err());
+/// ```
+#[track_caller]
+pub fn try_assert_json_matches<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+) -> Result<(), Vec<Difference>>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    check_json_matches(lhs, rhs, config)
+        .map(|_| ())
+        .map_err(Mismatch::into_differences)
+}
+
+/// Compares two JSON values and returns the differences, borrowing the differing values from
+/// `lhs` and `rhs` instead of cloning them.
+///
+/// This is the cheapest way to inspect the differences between large documents, since nothing
+/// is serialized. The values are compared like [`try_assert_json_matches`] does. If `config`
+/// normalizes enums or values, or `rhs` contains [`matchers::reference`] markers, the documents
+/// have to be rewritten first, and the differing values are then copies.
+///
+/// ```
+/// use serde_json_assert::{diff, CompareMode, Config, DifferenceKind};
+/// use serde_json::json;
+///
+/// let lhs = json!({ "users": [{ "id": 1, "name": "Ann" }] });
+/// let rhs = json!({ "users": [{ "id": 1, "name": "Bob" }], "total": 1 });
+///
+/// let differences = diff(&lhs, &rhs, &Config::new(CompareMode::Strict)).unwrap();
+/// assert_eq!(differences.len(), 2);
+/// assert_eq!(differences[0].path().to_string(), ".total");
+/// assert_eq!(differences[0].kind(), DifferenceKind::Removed);
+/// assert!(std::ptr::eq(differences[1].lhs().unwrap(), &lhs["users"][0]["name"]));
+/// ```
+///
+/// Fails with a [`Mismatch`] without differences if the documents have more nodes than
+/// [`Config::max_nodes`] allows or are nested more deeply than [`Config::max_nesting`] allows.
+pub fn diff<'a>(
+    lhs: &'a Value,
+    rhs: &'a Value,
+    config: &Config,
+) -> Result<Vec<BorrowedDifference<'a>>, Mismatch> {
+    if let Some(limit) = Limit::check(lhs, rhs, config) {
+        return Err(Mismatch::limit_exceeded(limit, Output::from_env(config)));
+    }
+    Ok(
+        match prepare_values(Cow::Borrowed(lhs), Cow::Borrowed(rhs), config) {
+            (Cow::Borrowed(lhs), Cow::Borrowed(rhs)) => diff::diff_borrowed(lhs, rhs, config),
+            (lhs, rhs) => diff::diff_borrowed(&lhs, &rhs, config)
+                .into_iter()
+                .map(BorrowedDifference::into_owned)
+                .collect(),
+        },
+    )
+}
+
+/// Returns whether two JSON values match, without describing how they differ.
+///
+/// The comparison stops at the first difference, as with [`Config::fail_fast`], and no failure
+/// message is built. This is the cheapest way to compare documents when only the outcome matters.
+///
+/// ```
+/// use serde_json_assert::{is_json_match, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+///
+/// assert!(is_json_match(&json!({ "a": 1, "b": 2 }), &json!({ "a": 1 }), &config));
+/// assert!(!is_json_match(&json!({ "a": 1 }), &json!({ "a": 2 }), &config));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be serialized, or if they exceed a limit of `config`, such as
+/// [`Config::max_nesting`]. Documents that weren't compared can't be said to match or not.
+#[track_caller]
+pub fn is_json_match<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> bool
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config).unwrap_or_else(|limit| panic!("{}", limit));
+    if let Some(limit) = Limit::check(&lhs, &rhs, config) {
+        panic!("{}", limit);
+    }
+    diff::is_match(&lhs, &rhs, config)
+}
+
+/// Compares a JSON value to several alternatives without panicking, succeeding if any of them
+/// matches.
+///
+/// On failure, the error shows the differences from the closest alternative, which is the one with
+/// the fewest differences. If several are equally close, the first one is shown. Alternatives are
+/// numbered from 1. See [`assert_json_matches_any`] for the panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_matches_any_no_panic, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let v1 = json!({ "id": 1, "name": "Ann" });
+/// let v2 = json!({ "id": 1, "first_name": "Ann" });
+///
+/// let result = assert_json_matches_any_no_panic(&json!({ "id": 1, "name": "Ann" }), &[&v1, &v2], &config);
+/// assert!(result.is_ok());
+///
+/// let result = assert_json_matches_any_no_panic(&json!({ "id": 2, "first_name": "Ann" }), &[&v1, &v2], &config);
+/// assert!(result.unwrap_err().starts_with(
+///     "json value doesn't match any of 2 alternatives, the closest one is alternative 2:"
+/// ));
+/// ```
+#[track_caller]
+pub fn assert_json_matches_any_no_panic<Lhs, Rhs>(
+    lhs: &Lhs,
+    alternatives: &[Rhs],
+    config: &Config,
+) -> Result<(), String>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    check_nesting(&[("lhs", &lhs)], config).map_err(|limit| limit.to_string())?;
+    let lhs = __to_value(lhs);
+    let mut closest: Option<(usize, Mismatch)> = None;
+    for (idx, rhs) in alternatives.iter().enumerate() {
+        match check_json_matches(&lhs, rhs, config) {
+            Ok(_) => return Ok(()),
+            Err(mismatch) => {
+                let is_closer = closest.as_ref().is_none_or(|(_, closest)| {
+                    mismatch.differences().len() < closest.differences().len()
+                });
+                if is_closer {
+                    closest = Some((idx, mismatch));
+                }
+            }
+        }
+    }
+
+    match closest {
+        Some((idx, mismatch)) => Err(format!(
+            "json value doesn't match any of {} alternatives, the closest one is alternative {}:\n\n{}",
+            alternatives.len(),
+            idx + 1,
+            mismatch
+        )),
+        None => Err("json value can't match any of 0 alternatives".to_string()),
+    }
+}
+
+/// Compares each item of a JSON array to `expected` without panicking.
+///
+/// Every item of `actual` must match `expected` using `config`. An empty array passes, and a value
+/// that isn't an array fails. The error lists the differences of each failing item under its
+/// index, with paths relative to the item. See [`assert_json_each`] for the panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_each_no_panic, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let page = json!([{ "id": 1, "tenant_id": "acme" }, { "id": 2, "tenant_id": "umbrella" }]);
+///
+/// let error = assert_json_each_no_panic(&page, &json!({ "tenant_id": "acme" }), &config).unwrap_err();
+/// assert_eq!(
+///     error,
+///     r#"1 of 2 json array items don't match the expected value:
+///
+/// item [1]:
+///     json atoms at path ".tenant_id" are not equal:
+///         expected:
+///             "acme"
+///         actual:
+///             "umbrella""#
+/// );
+/// ```
+#[track_caller]
+pub fn assert_json_each_no_panic<Lhs, Rhs>(
+    actual: &Lhs,
+    expected: &Rhs,
+    config: &Config,
+) -> Result<(), String>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    check_nesting(&[("actual", &actual), ("expected", &expected)], config)
+        .map_err(|limit| limit.to_string())?;
+    let actual = __to_value(actual);
+    let expected = __to_value(expected);
+    let Some(items) = actual.as_array() else {
+        return Err(format!(
+            "json value isn't an array, so its items can't be compared:\n{}",
+            render::pretty(&actual, &Path::Root, config).indent(4)
+        ));
+    };
+
+    let failures = items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| Some((idx, check_json_matches(item, &expected, config).err()?)))
+        .collect::<Vec<_>>();
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    let mut error = format!(
+        "{} of {} json array items don't match the expected value:",
+        failures.len(),
+        items.len()
+    );
+    for (idx, mismatch) in failures {
+        error.push_str(&format!("\n\nitem [{}]:\n{}", idx, mismatch.indent(4)));
+    }
+    Err(error)
+}
+
+/// Compares two JSON values without panicking, returning a [`Mismatch`] on failure.
+///
+/// [`Mismatch`] implements [`std::error::Error`], and its `Debug` output is the same readable
+/// message the assertion macros panic with. That makes this function the most convenient one to
+/// combine with non-fatal checks from other frameworks, or with `?` in tests returning `Result`.
+///
+/// If the values match, the paths of values allowed in `lhs` without being expected are returned.
+/// They are only collected if [`Config::report_extras`] is set.
+///
+/// ```
+/// use serde_json_assert::{check_json_matches, CompareMode, Config};
+/// use serde_json::json;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = Config::new(CompareMode::Inclusive).report_extras(true);
+/// let extras = check_json_matches(&json!({ "a": 1, "b": 2 }), &json!({ "a": 1 }), &config)?;
+/// assert_eq!(extras[0].to_string(), ".b");
+///
+/// let mismatch = check_json_matches(&json!({ "a": 1 }), &json!({ "a": 2 }), &config).unwrap_err();
+/// assert_eq!(mismatch.differences().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[track_caller]
+pub fn check_json_matches<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+) -> Result<Vec<Path>, Mismatch>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config)
+        .map_err(|limit| Mismatch::limit_exceeded(limit, Output::from_env(config)))?;
+    check_values(Cow::Owned(lhs), &rhs, config).map(|matched| matched.extras)
+}
+
+/// Compares two JSON values like [`check_json_matches`], returning the values captured by
+/// [`matchers::capture`] markers in `rhs` if they match.
+///
+/// The captured values are keyed by name. If several values are captured under the same name,
+/// the last one in document order is kept.
+///
+/// ```
+/// use serde_json_assert::{check_json_captures, matchers, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let response = json!({ "order": { "id": "ord_81", "items": [{ "sku": "A-1" }] } });
+/// let expected = json!({
+///     "order": {
+///         "id": matchers::capture("order_id", matchers::any_string()),
+///         "items": [{ "sku": matchers::capture("sku", json!("A-1")) }],
+///     }
+/// });
+///
+/// let captures = check_json_captures(&response, &expected, &config).unwrap();
+/// assert_eq!(captures["order_id"], "ord_81");
+/// assert_eq!(captures["sku"], "A-1");
+/// ```
+#[track_caller]
+pub fn check_json_captures<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+) -> Result<HashMap<String, Value>, Mismatch>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    __check_json_captures(lhs, rhs, config).map(|(captures, _)| captures)
+}
+
+/// Compares two JSON values like [`check_json_captures`], also returning the paths of values
+/// allowed without being expected, for [`assert_json_captures`].
+#[doc(hidden)]
+#[track_caller]
+pub fn __check_json_captures<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+) -> Result<(HashMap<String, Value>, Vec<Path>), Mismatch>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config)
+        .map_err(|limit| Mismatch::limit_exceeded(limit, Output::from_env(config)))?;
+    check_values(Cow::Owned(lhs), &rhs, config).map(|matched| (matched.captures, matched.extras))
+}
+
+/// Compares two documents like [`assert_json_matches_no_panic`], for the assertion macros. Values
+/// that are already JSON are borrowed, and only copied if they have to be rewritten.
+///
+/// Returns the paths of values allowed without being expected if the values match.
+#[doc(hidden)]
+#[track_caller]
+pub fn __assert_json_values_match_no_panic(
+    lhs: __Document<'_>,
+    rhs: __Document<'_>,
+    config: &Config,
+) -> Result<Vec<Path>, String> {
+    check_nesting(&[("lhs", &lhs), ("rhs", &rhs)], config).map_err(|limit| limit.to_string())?;
+    let (lhs, rhs) = prepare_values(lhs.into_json(), rhs.into_json(), config);
+    check_values(lhs, &rhs, config)
+        .map(|matched| matched.extras)
+        .map_err(|mismatch| mismatch.to_string())
+}
+
+/// Prints a note listing values that were allowed without being expected, for the assertion
+/// macros once the values matched.
+///
+/// The test harness shows it for failing tests or when run with `--nocapture`. Functions
+/// returning a `Result` never print, but return these paths instead.
+#[doc(hidden)]
+pub fn __report_extras(extras: &[Path]) {
+    if !extras.is_empty() {
+        eprintln!("{}", Extras(extras));
+    }
+}
+
+/// What a successful comparison found besides the match itself.
+pub(crate) struct Matched {
+    pub(crate) captures: HashMap<String, Value>,
+    pub(crate) extras: Vec<Path>,
+}
+
+/// Compares values prepared by [`to_values`], returning the captured values and the values
+/// allowed without being expected if they match.
+fn check_values(lhs: Cow<'_, Value>, rhs: &Value, config: &Config) -> Result<Matched, Mismatch> {
+    if let Some(limit) = Limit::check(&lhs, rhs, config) {
+        return Err(Mismatch::limit_exceeded(limit, Output::from_env(config)));
+    }
+
+    let comparison = compare(&lhs, rhs, config);
+    let (diffs, extras) = (comparison.differences, comparison.extras);
+
+    if diffs.is_empty() {
+        Ok(Matched {
+            captures: comparison.captures.into_iter().collect(),
+            extras,
+        })
+    } else {
+        let mut mismatch = Mismatch::new(diffs, extras, Output::from_env(config));
+        if config.summarize_type_mismatches {
+            let summaries = summary::summarize(mismatch.differences(), &lhs);
+            mismatch = mismatch.with_summaries(summaries);
+        }
+        if config.report_stats {
+            mismatch = mismatch.with_stats(Stats::new(&lhs, rhs, comparison.compared));
+        }
+        Err(mismatch.with_document(lhs))
+    }
+}
+
+/// Compares two JSON documents given as text without panicking.
+///
+/// Both sides can be anything that can be viewed as bytes, such as `&str`, `String` or `Vec<u8>`.
+/// This avoids having to parse response bodies and fixtures before comparing them. Text that isn't
+/// valid JSON results in an error describing which side couldn't be parsed.
+///
+/// With the `simd-json` feature enabled, the documents are parsed using
+/// [simd-json](https://docs.rs/simd-json), which is considerably faster for large documents.
+///
+/// ```
+/// use serde_json_assert::{assert_raw_json_matches_no_panic, CompareMode, Config};
+///
+/// let config = Config::new(CompareMode::Inclusive);
+///
+/// let result = assert_raw_json_matches_no_panic(r#"{ "a": 1, "b": 2 }"#, r#"{ "a": 1 }"#, &config);
+/// assert!(result.is_ok());
+///
+/// let result = assert_raw_json_matches_no_panic(r#"{ "a": 1 }"#, b"{ \"a\": ", &config);
+/// assert!(result.unwrap_err().starts_with("Couldn't parse right hand side as JSON"));
+/// ```
+pub fn assert_raw_json_matches_no_panic<Lhs, Rhs>(
+    lhs: Lhs,
+    rhs: Rhs,
+    config: &Config,
+) -> Result<(), String>
+where
+    Lhs: AsRef<[u8]>,
+    Rhs: AsRef<[u8]>,
+{
+    let lhs = parse::parse(lhs.as_ref())
+        .map_err(|err| format!("Couldn't parse left hand side as JSON. Error: {}", err))?;
+    let rhs = parse::parse(rhs.as_ref())
+        .map_err(|err| format!("Couldn't parse right hand side as JSON. Error: {}", err))?;
+
+    assert_json_matches_no_panic(&lhs, &rhs, config)
+}
+
+/// Serializes two values and prepares them for comparing, unless they're nested too deeply.
+#[track_caller]
+fn to_values<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> Result<(Value, Value), Limit>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    check_nesting(&[("lhs", &lhs), ("rhs", &rhs)], config)?;
+    let lhs = serde_json::to_value(lhs).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't convert left hand side value to JSON. Serde error: {}",
+            err
+        )
+    });
+    let rhs = serde_json::to_value(rhs).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't convert right hand side value to JSON. Serde error: {}",
+            err
+        )
+    });
+    Ok((
+        normalize::normalize(lhs, config),
+        prepare_expected(rhs, config),
+    ))
+}
+
+/// Fails if any of the named values is nested more deeply than [`Config::max_nesting`] allows.
+///
+/// Serializing, normalizing and comparing values all walk them recursively, so this has to be
+/// checked on the values as given, before any of that. It stops measuring at the limit, so it
+/// can't overflow the stack itself.
+pub(crate) fn check_nesting(
+    values: &[(&'static str, &dyn depth::Measure)],
+    config: &Config,
+) -> Result<(), Limit> {
+    let Some(max_nesting) = config.max_nesting else {
+        return Ok(());
+    };
+    let depths = values
+        .iter()
+        .map(|(side, value)| (*side, value.depth(max_nesting)))
+        .collect();
+    match NestingLimit::of(max_nesting, depths) {
+        Some(limit) => Err(Limit::Nesting(limit)),
+        None => Ok(()),
+    }
+}
+
+/// Resolves the references in an expected value and normalizes it, as done before comparing.
+/// Like [`to_values`], for values that are already JSON. Borrowed values are only copied if they
+/// have to be rewritten.
+fn prepare_values<'a>(
+    lhs: Cow<'a, Value>,
+    rhs: Cow<'a, Value>,
+    config: &Config,
+) -> (Cow<'a, Value>, Cow<'a, Value>) {
+    let lhs = match lhs {
+        Cow::Borrowed(lhs) => normalize::normalized(lhs, config),
+        Cow::Owned(lhs) => Cow::Owned(normalize::normalize(lhs, config)),
+    };
+    let rhs = match rhs {
+        Cow::Borrowed(rhs) if !matchers::contains_references(rhs) => {
+            normalize::normalized(rhs, config)
+        }
+        rhs => Cow::Owned(prepare_expected(rhs.into_owned(), config)),
+    };
+    (lhs, rhs)
+}
+
+fn prepare_expected(mut rhs: Value, config: &Config) -> Value {
+    matchers::resolve_references(&mut rhs, config.context.as_ref());
+    normalize::normalize(rhs, config)
+}
+
+/// Configuration for how JSON values should be compared.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct Config {
+    /// Should array sorting be taken in consideration.
+    pub array_sorting_mode: ArraySortingMode,
+    /// The array sorting mode for arrays of scalars, instead of `array_sorting_mode`.
+    pub scalar_array_sorting_mode: Option<ArraySortingMode>,
+    /// The array sorting mode for arrays containing arrays or objects, instead of
+    /// `array_sorting_mode`.
+    pub container_array_sorting_mode: Option<ArraySortingMode>,
+    /// How are arrays compared in order diffed.
+    pub array_diff_mode: ArrayDiffMode,
+    /// How should JSON values be compared.
+    pub compare_mode: CompareMode,
+    /// How should numbers be compared.
+    pub numeric_mode: NumericMode,
+    /// How should floating point numbers be compared.
+    pub float_compare_mode: FloatCompareMode,
+    /// How much detail should failure messages contain.
+    pub verbosity: Verbosity,
+    /// The maximum number of differences listed in failure messages. `None` lists all of them.
+    pub max_diffs: Option<usize>,
+    /// Should failure messages be colored using ANSI escape codes.
+    pub color: bool,
+    /// How are the differences laid out in failure messages.
+    pub output_format: OutputFormat,
+    /// Should object keys that look like numbers be compared by their numeric value.
+    pub numeric_keys: bool,
+    /// How should serde enum representations be compared.
+    pub enum_mode: EnumMode,
+    /// Values shown as `***` in failure messages.
+    pub mask_output: Vec<PathPattern>,
+    /// Values that aren't compared at all.
+    pub ignore_paths: Vec<PathPattern>,
+    /// Values that aren't compared at all, because the text of their path matches.
+    #[cfg(feature = "regex")]
+    pub ignore_paths_matching: Vec<PathRegex>,
+    /// The depth below which arrays and objects aren't compared field by field. `None` compares
+    /// values at any depth.
+    pub max_depth: Option<usize>,
+    /// How should arrays and objects at the maximum depth be compared.
+    pub max_depth_mode: MaxDepthMode,
+    /// The number of nodes both documents may have in total for them to be compared. `None`
+    /// compares documents of any size.
+    pub max_nodes: Option<usize>,
+    /// How many levels deep documents may be nested for them to be compared. `None` compares
+    /// documents nested at any depth, which can overflow the stack.
+    pub max_nesting: Option<usize>,
+    /// The depth below which arrays and objects are collapsed in failure messages. `None` shows
+    /// values in full.
+    pub collapse_output: Option<usize>,
+    /// Should sentinel strings such as `"$any"` in expected values be treated as matchers.
+    pub sentinels: bool,
+    /// Custom comparisons used for specific subtrees instead of comparing them value by value.
+    pub comparators: Vec<PathComparator>,
+    /// Compare modes used for specific subtrees instead of `compare_mode`.
+    pub path_compare_modes: Vec<(PathPattern, CompareMode)>,
+    /// Arrays whose items are paired by the value at a key instead of by index, with the path of
+    /// the key within each item.
+    pub array_keys: Vec<(PathPattern, Path)>,
+    /// The compare mode used for objects. `None` uses `compare_mode`.
+    pub object_compare_mode: Option<CompareMode>,
+    /// The compare mode used for arrays. `None` uses `compare_mode`.
+    pub array_compare_mode: Option<CompareMode>,
+    /// Should values that are allowed without being expected be reported.
+    pub report_extras: bool,
+    /// Should failure messages include the size and shape of both documents.
+    pub report_stats: bool,
+    /// Should the comparison stop at the first difference.
+    pub fail_fast: bool,
+    /// Should type mismatches repeated across array items be shown once.
+    pub summarize_type_mismatches: bool,
+    /// The length in bytes above which strings are summarized in failure messages. `None` shows
+    /// strings in full.
+    pub long_string_threshold: Option<usize>,
+    /// The number of significant digits floats are printed with in failure messages, or `None`
+    /// for as many as needed to identify them exactly.
+    pub float_precision: Option<usize>,
+    /// Should strings containing timestamps be compared by the instant they represent.
+    pub timestamps: bool,
+    /// How should line endings in strings be compared.
+    pub line_ending_mode: LineEndingMode,
+    /// How should strings be compared.
+    pub string_compare_mode: StringCompareMode,
+    /// Normalizers applied to both sides before comparing, in order.
+    pub normalizers: Vec<normalizers::Normalization>,
+    /// The document that [`matchers::context_value`] markers refer to.
+    pub context: Option<Value>,
+}
+
+impl Config {
+    /// Create a new [`Config`] using the given [`CompareMode`].
+    ///
+    /// The default `numeric_mode` is be [`NumericMode::Strict`].
+    pub fn new(compare_mode: CompareMode) -> Self {
+        Self {
+            array_sorting_mode: ArraySortingMode::Consider,
+            scalar_array_sorting_mode: None,
+            container_array_sorting_mode: None,
+            array_diff_mode: ArrayDiffMode::Index,
+            compare_mode,
+            numeric_mode: NumericMode::Strict,
+            float_compare_mode: FloatCompareMode::Exact,
+            verbosity: Verbosity::Normal,
+            max_diffs: None,
+            color: false,
+            output_format: OutputFormat::List,
+            numeric_keys: false,
+            enum_mode: EnumMode::Exact,
+            mask_output: vec![],
+            ignore_paths: vec![],
+            #[cfg(feature = "regex")]
+            ignore_paths_matching: vec![],
+            max_depth: None,
+            max_depth_mode: MaxDepthMode::Atomic,
+            max_nodes: None,
+            max_nesting: Some(512),
+            collapse_output: None,
+            sentinels: false,
+            comparators: vec![],
+            path_compare_modes: vec![],
+            array_keys: vec![],
+            object_compare_mode: None,
+            array_compare_mode: None,
+            report_extras: false,
+            report_stats: false,
+            fail_fast: false,
+            summarize_type_mismatches: false,
+            long_string_threshold: None,
+            float_precision: None,
+            timestamps: false,
+            line_ending_mode: LineEndingMode::Exact,
+            string_compare_mode: StringCompareMode::Exact,
+            normalizers: vec![],
+            context: None,
+        }
+    }
+
+    /// Change the config's numeric mode.
+    ///
+    /// The default `numeric_mode` is be [`NumericMode::Strict`].
+    pub fn numeric_mode(mut self, numeric_mode: NumericMode) -> Self {
+        self.numeric_mode = numeric_mode;
+        self
+    }
+
+    /// Change the config's compare mode.
+    pub fn compare_mode(mut self, compare_mode: CompareMode) -> Self {
+        self.compare_mode = compare_mode;
+        self
+    }
+
+    /// Change the config's float compare mode.
+    ///
+    /// The default `float_compare_mode` is [`FloatCompareMode::Exact`].
+    pub fn float_compare_mode(mut self, float_compare_mode: FloatCompareMode) -> Self {
+        self.float_compare_mode = float_compare_mode;
+        self
+    }
+
+    /// Change how much detail failure messages contain.
+    ///
+    /// The default `verbosity` is [`Verbosity::Normal`]. It can be overridden at assertion time
+    /// with the `JSON_ASSERT_VERBOSITY` environment variable, set to `quiet`, `normal` or
+    /// `verbose`.
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Change the maximum number of differences listed in failure messages.
+    ///
+    /// By default all differences are listed. It can be overridden at assertion time with the
+    /// `JSON_ASSERT_MAX_DIFFS` environment variable, where `0` lists all differences.
+    pub fn max_diffs(mut self, max_diffs: Option<usize>) -> Self {
+        self.max_diffs = max_diffs;
+        self
+    }
+
+    /// Color failure messages using ANSI escape codes.
+    ///
+    /// Colors are disabled by default, and are always disabled if the `NO_COLOR` environment
+    /// variable is set.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Change how the differences are laid out in failure messages.
+    ///
+    /// The default `output_format` is [`OutputFormat::List`]. It can be overridden at assertion
+    /// time with the `JSON_ASSERT_FORMAT` environment variable, set to `list` or `tree`.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config, OutputFormat};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Inclusive).output_format(OutputFormat::Tree);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "id": 1, "name": "Bob", "roles": ["dev"], "team": { "id": 7 } }),
+    ///     &json!({ "id": 1, "name": "Alice", "roles": ["dev", "ops"], "team": { "id": 7 } }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     r#"tree (~ changed, + only in actual, - only in expected):
+    ///     {
+    ///       "id": 1
+    /// ~     "name": "Bob" (expected: "Alice")
+    ///       "roles": [
+    ///         "dev"
+    /// -       "ops"
+    ///       ]
+    ///       "team": {...} (1 node)
+    ///     }"#
+    /// );
+    /// ```
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// Compare object keys that look like numbers by their numeric value.
+    ///
+    /// Maps with numeric keys, like `BTreeMap<u32, T>`, serialize with string keys such as `"1"`
+    /// and `"2"`. With this enabled, keys like `"1"`, `"1.0"` and `"+1"` are considered the same
+    /// field, and differences in such objects are reported in numeric key order, so `"2"` comes
+    /// before `"10"`.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let levels = BTreeMap::from([(1_u32, "low"), (2, "high")]);
+    /// let config = Config::new(CompareMode::Strict).numeric_keys(true);
+    ///
+    /// assert_json_matches!(levels, json!({ "1.0": "low", "2.0": "high" }), &config);
+    /// ```
+    pub fn numeric_keys(mut self, numeric_keys: bool) -> Self {
+        self.numeric_keys = numeric_keys;
+        self
+    }
+
+    /// Change how serde enum representations are compared.
+    ///
+    /// The default `enum_mode` is [`EnumMode::Exact`].
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config, EnumMode};
+    ///
+    /// #[derive(Serialize)]
+    /// enum External {
+    ///     Circle { radius: u32 },
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// #[serde(tag = "kind")]
+    /// enum Internal {
+    ///     Circle { radius: u32 },
+    /// }
+    ///
+    /// let config = Config::new(CompareMode::Strict).enum_mode(EnumMode::Normalize {
+    ///     tag: "kind".to_string(),
+    ///     content: "data".to_string(),
+    /// });
+    ///
+    /// assert_json_matches!(
+    ///     External::Circle { radius: 2 },
+    ///     Internal::Circle { radius: 2 },
+    ///     &config
+    /// );
+    /// ```
+    pub fn enum_mode(mut self, enum_mode: EnumMode) -> Self {
+        self.enum_mode = enum_mode;
+        self
+    }
+
+    /// Hide values matching `pattern` in failure messages.
+    ///
+    /// Matching values are still compared, but are shown as `***` in failure messages, so secrets
+    /// and personal data don't end up in CI logs. The pattern masks every value below the paths it
+    /// matches, and `*` matches any field or index. See [`PathPattern`] for the syntax. This can be
+    /// called multiple times to mask several paths.
+    ///
+    /// The values returned by [`Difference::actual`] and [`Difference::expected`] aren't masked.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).mask_output(".credentials.*");
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "credentials": { "token": "s3cr3t" } }),
+    ///     &json!({ "credentials": { "token": "hunter2" } }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(!error.contains("s3cr3t") && !error.contains("hunter2"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid [`PathPattern`].
+    #[track_caller]
+    pub fn mask_output(mut self, pattern: &str) -> Self {
+        let pattern = pattern.parse().unwrap_or_else(|err| panic!("{}", err));
+        self.mask_output.push(pattern);
+        self
+    }
+
+    /// Skip the values matching `patterns` when comparing.
+    ///
+    /// Differences at or below the paths the patterns match are never reported, whether the
+    /// values differ, are missing from one side or are only present on the other. This is meant
+    /// for values that can't be predicted, such as timestamps generated by a server, and saves
+    /// removing them from the documents before comparing. `*` matches any field or index, see
+    /// [`PathPattern`] for the syntax. This can be called multiple times to ignore more paths.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict)
+    ///     .ignore_paths(["$.meta.timestamp", "$.data.*.updated_at"]);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "meta": { "timestamp": 1718000000 }, "data": [{ "id": 1, "updated_at": "today" }] }),
+    ///     json!({ "meta": {}, "data": [{ "id": 1, "updated_at": "yesterday" }] }),
+    ///     &config
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the patterns isn't a valid [`PathPattern`].
+    #[track_caller]
+    pub fn ignore_paths<I, P>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        for pattern in patterns {
+            let pattern = pattern
+                .as_ref()
+                .parse()
+                .unwrap_or_else(|err| panic!("{}", err));
+            self.ignore_paths.push(pattern);
+        }
+        self
+    }
+
+    /// Skip the values whose path matches `regex` when comparing.
+    ///
+    /// Like [`Config::ignore_paths`], but the text of each path, such as `.data[0].created_at`,
+    /// is matched against a regular expression. This ignores fields by name wherever they are,
+    /// without listing all their paths. The root is matched as `(root)`. This can be called
+    /// multiple times to ignore more paths.
+    ///
+    /// Requires the `regex` feature.
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict)
+    ///     .ignore_paths_matching(Regex::new(r"\.[a-z_]+_at$").unwrap());
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "created_at": 1, "order": { "lines": [{ "id": 1, "shipped_at": 2 }] } }),
+    ///     json!({ "created_at": 3, "order": { "lines": [{ "id": 1 }] } }),
+    ///     &config
+    /// );
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn ignore_paths_matching(mut self, regex: regex::Regex) -> Self {
+        self.ignore_paths_matching.push(PathRegex(regex));
+        self
+    }
+
+    /// Returns the name of the setting that skips the value at `path`, if any.
+    pub(crate) fn ignored_by(&self, path: &diff::PathRef) -> Option<&'static str> {
+        #[cfg(feature = "regex")]
+        if !self.ignore_paths_matching.is_empty() {
+            let path = Path::from(*path);
+            if self
+                .ignore_paths_matching
+                .iter()
+                .any(|regex| regex.matches(&path))
+            {
+                return Some("Config::ignore_paths_matching");
+            }
+        }
+        if self.ignore_paths.is_empty() {
+            return None;
+        }
+        let keys = path.keys();
+        self.ignore_paths
+            .iter()
+            .any(|pattern| pattern.matches_refs(keys.iter().copied()))
+            .then_some("Config::ignore_paths")
+    }
+
+    /// Stop descending into arrays and objects at `max_depth`.
+    ///
+    /// The root value is at depth 0, its fields or items at depth 1, and so on. Arrays and objects
+    /// at the maximum depth are handled according to [`Config::max_depth_mode`], which by default
+    /// compares them as a whole. This keeps comparisons of deeply recursive documents, such as
+    /// syntax tree dumps, fast and their failure messages short.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).max_depth(1);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "ast": { "body": [{ "kind": "let" }] } }),
+    ///     &json!({ "ast": { "body": [{ "kind": "const" }] } }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(error.starts_with("json atoms at path \".ast\" are not equal"));
+    /// ```
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Change how arrays and objects at the maximum depth are compared.
+    ///
+    /// The default `max_depth_mode` is [`MaxDepthMode::Atomic`]. It has no effect unless
+    /// [`Config::max_depth`] is set.
+    pub fn max_depth_mode(mut self, max_depth_mode: MaxDepthMode) -> Self {
+        self.max_depth_mode = max_depth_mode;
+        self
+    }
+
+    /// Fail without comparing if both documents have more than `max_nodes` nodes in total.
+    ///
+    /// Every value counts as a node, including arrays, objects and the values they contain.
+    /// Comparing a document that is accidentally huge, such as a whole database dump, can take
+    /// long enough to stall a test suite. With a limit, the assertion fails at once with a message
+    /// giving the size of both documents.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).max_nodes(1_000);
+    ///
+    /// let error = assert_json_matches_no_panic(&vec![0; 5_000], &json!([]), &config).unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     "json documents have more than 1000 nodes in total, the limit set by Config::max_nodes, so they weren't compared:
+    ///     lhs: more than 1000 nodes
+    ///     rhs: 1 node"
+    /// );
+    /// ```
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Fail without comparing if either document is nested more than `max_nesting` levels deep.
+    ///
+    /// The root is at depth 0 and the values in an array or object one level below it. Documents
+    /// are compared recursively, so a pathologically deep document could overflow the stack;
+    /// instead, the assertion fails with a message giving the depth of both documents. The default
+    /// limit is 512 levels, well beyond the 128 levels `serde_json` parses by default. Setting the
+    /// field to `None` lifts the limit.
+    ///
+    /// Documents are measured as given, before they're serialized or normalized. Functions
+    /// returning a `Result` fail with the message as their error. Those that can't, such as
+    /// [`explain_json`] or [`three_way_diff`], panic with it.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).max_nesting(2);
+    ///
+    /// let error = assert_json_matches_no_panic(&json!([[[1]]]), &json!([[1]]), &config).unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     "json documents are nested more than 2 levels deep, the limit set by Config::max_nesting, so they weren't compared:
+    ///     lhs: more than 2 levels
+    ///     rhs: 2 levels"
+    /// );
+    /// ```
+    pub fn max_nesting(mut self, max_nesting: usize) -> Self {
+        self.max_nesting = Some(max_nesting);
+        self
+    }
+
+    /// Collapse arrays and objects nested `depth` levels deep within values in failure messages.
+    ///
+    /// Collapsed values are shown as `{...}` or `[...]` followed by the number of values they
+    /// contain. Values are still compared in full; this only keeps failure messages for deeply
+    /// nested values readable. Depth is counted from each value shown in a message, so a depth of
+    /// 0 collapses every array and object.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).collapse_output(1);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "user": { "name": "Bob", "roles": ["admin", "dev"] } }),
+    ///     &json!(null),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(error.contains(r#""user": {...} (4 nodes)"#));
+    /// ```
+    pub fn collapse_output(mut self, depth: usize) -> Self {
+        self.collapse_output = Some(depth);
+        self
+    }
+
+    /// Use `compare_mode` for the values matching `pattern` instead of the config's compare mode.
+    ///
+    /// This allows checking some subtrees exactly in an otherwise inclusive comparison, or the
+    /// other way around. The mode applies to every value below the paths the pattern matches. If
+    /// several patterns match a value, the longest one wins. Among equally long patterns, the one
+    /// with fewer wildcards wins, and then the one added last. See [`PathPattern`] for the syntax.
+    ///
+    /// Failure messages keep using the labels of the config's compare mode. In an inclusive
+    /// comparison, values found in a strict subtree but not expected are reported as missing from
+    /// expected.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config =
+    ///     Config::new(CompareMode::Inclusive).compare_mode_at(".permissions", CompareMode::Strict);
+    ///
+    /// let expected = json!({ "permissions": { "read": true } });
+    /// let user = json!({ "name": "Bob", "permissions": { "read": true } });
+    /// assert!(assert_json_matches_no_panic(&user, &expected, &config).is_ok());
+    ///
+    /// let admin = json!({ "name": "Eve", "permissions": { "read": true, "write": true } });
+    /// assert!(assert_json_matches_no_panic(&admin, &expected, &config).is_err());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid [`PathPattern`].
+    #[track_caller]
+    pub fn compare_mode_at(mut self, pattern: &str, compare_mode: CompareMode) -> Self {
+        let pattern = pattern.parse().unwrap_or_else(|err| panic!("{}", err));
+        self.path_compare_modes.push((pattern, compare_mode));
+        self
+    }
+
+    /// Treat sentinel strings in expected values as matchers.
+    ///
+    /// This allows writing a whole expected document as a single `json!` literal, even when some
+    /// values can't be predicted. The sentinels are:
+    ///
+    /// - `"$any"` matches any value, like [`matchers::any`].
+    /// - `"$uuid"` matches any UUID string, like [`matchers::uuid`].
+    /// - `"$null"`, `"$boolean"`, `"$number"`, `"$integer"`, `"$string"`, `"$array"` and
+    ///   `"$object"` match any value of that type, like [`matchers::of_type`].
+    /// - `"$regex:<pattern>"`, such as `"$regex:^ord_[0-9a-f]{12}$"`, matches strings matching the
+    ///   regular expression after the colon, like `matchers::regex`. This one requires the `regex`
+    ///   feature, and never matches if the pattern is invalid.
+    ///
+    /// Other strings starting with `$` are compared as usual. Sentinels are disabled by default,
+    /// so expected values can contain these strings literally.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).sentinels(true);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "id": "67e55044-10b1-426f-9247-bb680e5fe0c8", "age": 42, "meta": { "etag": "x" } }),
+    ///     json!({ "id": "$uuid", "age": "$integer", "meta": "$any" }),
+    ///     &config
+    /// );
+    /// ```
+    pub fn sentinels(mut self, sentinels: bool) -> Self {
+        self.sentinels = sentinels;
+        self
+    }
+
+    /// Compare the values matching `pattern` with `compare` instead of the config's rules.
+    ///
+    /// `compare` is called with the actual (or lhs) value and the expected (or rhs) value, and
+    /// returns whether they match. The values below the paths the pattern matches aren't compared
+    /// any further, so `compare` decides for whole subtrees. Values missing from either side are
+    /// still reported as usual. If several patterns match a value, the longest one wins. Among
+    /// equally long patterns, the one with fewer wildcards wins, and then the one added last. See
+    /// [`PathPattern`] for the syntax.
+    ///
+    /// Documents that are equal as a whole match without calling any comparator.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).compare_with(".events[*].at", |lhs, rhs| {
+    ///     match (lhs.as_i64(), rhs.as_i64()) {
+    ///         (Some(lhs), Some(rhs)) => (lhs - rhs).abs() <= 5,
+    ///         _ => false,
+    ///     }
+    /// });
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "events": [{ "name": "created", "at": 1718000003 }] }),
+    ///     json!({ "events": [{ "name": "created", "at": 1718000000 }] }),
+    ///     &config
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid [`PathPattern`].
+    #[track_caller]
+    pub fn compare_with<F>(mut self, pattern: &str, compare: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + std::panic::RefUnwindSafe + 'static,
+    {
+        let pattern = pattern.parse().unwrap_or_else(|err| panic!("{}", err));
+        self.comparators.push(PathComparator::new(pattern, compare));
+        self
+    }
+
+    /// Returns the compare mode set for `path` with [`Config::compare_mode_at`], if any.
+    pub(crate) fn compare_mode_at_path(&self, path: &diff::PathRef) -> Option<CompareMode> {
+        if self.path_compare_modes.is_empty() {
+            return None;
+        }
+        let keys = path.keys();
+        self.path_compare_modes
+            .iter()
+            .filter(|(pattern, _)| pattern.matches_refs(keys.iter().copied()))
+            .max_by_key(|(pattern, _)| pattern.specificity())
+            .map(|(_, compare_mode)| *compare_mode)
+    }
+
+    /// Pair the items of the arrays matching `pattern` by the value at `key` instead of by index.
+    ///
+    /// Compared by index, an item inserted at the start of an array shifts every item after it
+    /// and each of them is reported as different. Paired by key, only the inserted item is
+    /// reported. Items are paired with the first unpaired item whose key is equal, in any order.
+    /// Expected items without a partner are reported as missing, at their index in the expected
+    /// array, and actual items without one as unexpected, unless the array is compared in
+    /// inclusive mode. Items without the key are never paired.
+    ///
+    /// `pattern` must match the array itself, see [`PathPattern`] for the syntax. If several
+    /// patterns match an array, the longest one wins. Among equally long patterns, the one with
+    /// fewer wildcards wins, and then the one added last. `key` is a path within each item, such
+    /// as `id` or `meta.id`.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).match_array_by_key(".users", "id");
+    ///
+    /// let expected = json!({ "users": [{ "id": 1, "name": "Ann" }, { "id": 2, "name": "Bob" }] });
+    /// let actual = json!({ "users": [{ "id": 3, "name": "Eve" }, { "id": 1, "name": "Ann" }, { "id": 2, "name": "Bob" }] });
+    ///
+    /// assert_eq!(
+    ///     assert_json_matches_no_panic(&actual, &expected, &config).unwrap_err(),
+    ///     r#"json atom at path ".users[0]" is missing from rhs"#
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid [`PathPattern`] or `key` isn't a valid [`Path`].
+    #[track_caller]
+    pub fn match_array_by_key(mut self, pattern: &str, key: &str) -> Self {
+        let pattern = pattern.parse().unwrap_or_else(|err| panic!("{}", err));
+        let key = key.parse().unwrap_or_else(|err| panic!("{}", err));
+        self.array_keys.push((pattern, key));
+        self
+    }
+
+    /// Returns the key set for the array at `path` with [`Config::match_array_by_key`], if any.
+    pub(crate) fn array_key_at(&self, path: &diff::PathRef) -> Option<&Path> {
+        if self.array_keys.is_empty() {
+            return None;
+        }
+        let keys = path.keys();
+        self.array_keys
+            .iter()
+            .filter(|(pattern, _)| {
+                pattern.depth() == keys.len() && pattern.matches_refs(keys.iter().copied())
+            })
+            .max_by_key(|(pattern, _)| pattern.specificity())
+            .map(|(_, key)| key)
+    }
+
+    /// Use `compare_mode` for objects instead of the config's compare mode.
+    ///
+    /// With [`CompareMode::Inclusive`], objects may contain fields that aren't expected, and with
+    /// [`CompareMode::Strict`] they may not. Modes set for specific paths with
+    /// [`Config::compare_mode_at`] take precedence.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Inclusive).object_compare_mode(CompareMode::Strict);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "id": 1, "tags": ["a", "b"] }),
+    ///     json!({ "id": 1, "tags": ["a"] }),
+    ///     &config
+    /// );
+    /// ```
+    pub fn object_compare_mode(mut self, compare_mode: CompareMode) -> Self {
+        self.object_compare_mode = Some(compare_mode);
+        self
+    }
+
+    /// Use `compare_mode` for arrays instead of the config's compare mode.
+    ///
+    /// With [`CompareMode::Inclusive`], arrays may contain items that aren't expected, and with
+    /// [`CompareMode::Strict`] they may not. Modes set for specific paths with
+    /// [`Config::compare_mode_at`] take precedence.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).array_compare_mode(CompareMode::Inclusive);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "id": 1, "tags": ["a", "b"] }),
+    ///     json!({ "id": 1, "tags": ["a"] }),
+    ///     &config
+    /// );
+    /// ```
+    pub fn array_compare_mode(mut self, compare_mode: CompareMode) -> Self {
+        self.array_compare_mode = Some(compare_mode);
+        self
+    }
+
+    /// Report values that are allowed without being expected.
+    ///
+    /// Inclusive comparisons, and partial expectations in strict ones, allow fields and items that
+    /// aren't expected. With this enabled, their paths are listed in a note at the end of failure
+    /// messages. If an assertion macro succeeds, the note is printed to stderr instead, which the
+    /// test harness shows for failing tests or when run with `--nocapture`. This helps to spot
+    /// tests that are more permissive than intended. Functions returning a `Result` never print;
+    /// [`check_json_matches`] and [`check_json_paths`] return the paths if the values match.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Inclusive).report_extras(true);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "id": 2, "admin": true }),
+    ///     &json!({ "id": 1 }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(error.ends_with("note: actual contains values that weren't expected:\n    .admin"));
+    /// ```
+    pub fn report_extras(mut self, report_extras: bool) -> Self {
+        self.report_extras = report_extras;
+        self
+    }
+
+    /// Include statistics about both documents in failure messages.
+    ///
+    /// The statistics list the number of nodes, the nesting depth and the compact serialized size
+    /// of each side, followed by the number of nodes that were compared. They help to judge
+    /// whether a failure is local or wholesale, and to spot the comparison of the wrong object,
+    /// such as an empty response.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).report_stats(true);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({}),
+    ///     &json!({ "users": [{ "id": 1 }] }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(error.ends_with(
+    ///     "stats:
+    ///     lhs:      1 node, depth 0, 2 bytes
+    ///     rhs:      4 nodes, depth 3, 20 bytes
+    ///     compared: 1 node"
+    /// ));
+    /// ```
+    pub fn report_stats(mut self, report_stats: bool) -> Self {
+        self.report_stats = report_stats;
+        self
+    }
+
+    /// Stop comparing at the first difference.
+    ///
+    /// Collecting every difference of large documents is wasted work when only the outcome of the
+    /// comparison matters, as in loops waiting for a document to change. With `fail_fast`, no
+    /// further nodes are visited once a difference is found, and only that difference is
+    /// reported. Which difference is found first isn't specified. See also [`is_json_match`].
+    ///
+    /// ```
+    /// use serde_json_assert::{try_assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).fail_fast(true);
+    ///
+    /// let differences =
+    ///     try_assert_json_matches(&json!([1, 2, 3]), &json!([4, 5, 6]), &config).unwrap_err();
+    /// assert_eq!(differences.len(), 1);
+    /// ```
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Show type mismatches repeated across array items once in failure messages.
+    ///
+    /// When a field has the wrong type in many items of an array, such as prices serialized as
+    /// strings, the failure message lists a single summary giving the number of affected items
+    /// instead of one entry per item. Mismatches found in a single item are listed as usual.
+    /// [`Mismatch::differences`] still returns every difference.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Inclusive).summarize_type_mismatches(true);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "items": [{ "price": "1.5" }, { "price": "2.5" }, { "price": 3.5 }] }),
+    ///     &json!({ "items": [{ "price": 1.5 }, { "price": 2.5 }, { "price": 3.5 }] }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     r#"json values at path ".items[*].price" are string in actual but number in expected for 2 of 3 items"#
+    /// );
+    /// ```
+    pub fn summarize_type_mismatches(mut self, summarize: bool) -> Self {
+        self.summarize_type_mismatches = summarize;
+        self
+    }
+
+    /// Summarize strings longer than `bytes` in failure messages.
+    ///
+    /// Long strings are shown by their length and a hash instead of their contents. When two long
+    /// strings differ, the byte offset of their first difference is shown as well. This keeps
+    /// failure messages usable when documents embed large strings, such as encoded files.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).long_string_threshold(1024);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "body": "a".repeat(2000) }),
+    ///     &json!({ "body": format!("{}b", "a".repeat(1999)) }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(error.contains("<string of 2000 bytes, hash "));
+    /// assert!(error.ends_with("first difference at byte offset 1999"));
+    /// ```
+    pub fn long_string_threshold(mut self, bytes: usize) -> Self {
+        self.long_string_threshold = Some(bytes);
+        self
+    }
+
+    /// Print floats with `digits` significant digits in failure messages.
+    ///
+    /// By default floats are printed with the fewest digits that identify them exactly, which
+    /// makes long computed values hard to read. Values are still compared at full precision, so
+    /// when two floats differ beyond `digits`, both are printed with as many digits as needed to
+    /// tell them apart.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).float_precision(4);
+    ///
+    /// let error = assert_json_matches_no_panic(
+    ///     &json!({ "ratio": 0.6666666666666666, "mean": 0.30000000000000004 }),
+    ///     &json!({ "ratio": 0.5, "mean": 0.3 }),
+    ///     &config,
+    /// )
+    /// .unwrap_err();
+    /// assert!(error.contains("lhs:\n        0.6667\n    rhs:\n        0.5"));
+    /// assert!(error.contains("lhs:\n        0.30000000000000004\n    rhs:\n        0.3"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digits` is 0.
+    #[track_caller]
+    pub fn float_precision(mut self, digits: usize) -> Self {
+        assert!(digits > 0, "floats must be printed with at least 1 digit");
+        self.float_precision = Some(digits);
+        self
+    }
+
+    /// Compare strings containing RFC 3339 timestamps by the instant they represent.
+    ///
+    /// With this enabled, timestamps with different UTC offsets, or with different numbers of
+    /// trailing zeros in fractional seconds, are equal if they refer to the same instant. Strings
+    /// that aren't timestamps are compared as usual.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).timestamps(true);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "created": "2024-01-01T10:00:00+02:00" }),
+    ///     json!({ "created": "2024-01-01T08:00:00Z" }),
+    ///     &config
+    /// );
+    /// ```
+    pub fn timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Change how line endings in strings are compared.
+    ///
+    /// The default `line_ending_mode` is [`LineEndingMode::Exact`].
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config, LineEndingMode};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).line_ending_mode(LineEndingMode::Normalize);
+    ///
+    /// assert_json_matches!(json!("one\r\ntwo"), json!("one\ntwo"), &config);
+    /// ```
+    pub fn line_ending_mode(mut self, line_ending_mode: LineEndingMode) -> Self {
+        self.line_ending_mode = line_ending_mode;
+        self
+    }
+
+    /// Change how strings are compared.
+    ///
+    /// The default `string_compare_mode` is [`StringCompareMode::Exact`]. The other modes tolerate
+    /// small differences between strings, such as in OCR or machine learning output, while the
+    /// structure of the documents is still compared exactly. Distances are counted in characters,
+    /// after line endings are normalized according to the [`LineEndingMode`].
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, CompareMode, Config, StringCompareMode};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).string_compare_mode(StringCompareMode::EditDistance(2));
+    /// assert_json_matches!(
+    ///     json!({ "text": "Invoice N0. 1O42", "lines": 3 }),
+    ///     json!({ "text": "Invoice No. 1042", "lines": 3 }),
+    ///     &config
+    /// );
+    ///
+    /// let config = Config::new(CompareMode::Strict).string_compare_mode(StringCompareMode::Similarity(0.9));
+    /// assert_json_matches!(json!("the quick brown fox"), json!("the quick brown fax"), &config);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`StringCompareMode::Similarity`] isn't between 0 and 1.
+    #[track_caller]
+    pub fn string_compare_mode(mut self, string_compare_mode: StringCompareMode) -> Self {
+        if let StringCompareMode::Similarity(similarity) = string_compare_mode {
+            assert!(
+                (0.0..=1.0).contains(&similarity),
+                "string similarity must be between 0 and 1, got {}",
+                similarity
+            );
+        }
+        self.string_compare_mode = string_compare_mode;
+        self
+    }
+
+    /// Normalize every value with `normalizer` before comparing.
+    ///
+    /// Normalizers run in the order they were added. See [`normalizers`] for more details.
+    pub fn normalizer<N>(mut self, normalizer: N) -> Self
+    where
+        N: normalizers::Normalizer + 'static,
+    {
+        self.normalizers.push(normalizers::Normalization {
+            pattern: None,
+            normalizer: std::sync::Arc::new(normalizer),
+        });
+        self
+    }
+
+    /// Normalize the values matching `pattern` with `normalizer` before comparing.
+    ///
+    /// Like [`Config::normalizer`], but only for values in the subtrees matching `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` isn't a valid [`PathPattern`].
+    #[track_caller]
+    pub fn normalizer_at<N>(mut self, pattern: &str, normalizer: N) -> Self
+    where
+        N: normalizers::Normalizer + 'static,
+    {
+        let pattern = pattern.parse().unwrap_or_else(|err| panic!("{}", err));
+        self.normalizers.push(normalizers::Normalization {
+            pattern: Some(pattern),
+            normalizer: std::sync::Arc::new(normalizer),
+        });
+        self
+    }
+
+    /// Set the document that [`matchers::context_value`] markers in expected values refer to.
+    ///
+    /// This is usually the request a response answers, so the response can be checked to echo
+    /// parts of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `context` can't be serialized.
+    #[track_caller]
+    pub fn context<T: Serialize + ?Sized>(mut self, context: &T) -> Self {
+        self.context = Some(__to_value(context));
+        self
+    }
+
+    /// Layer `overrides` on top of this config.
+    ///
+    /// This is useful for combining a shared base configuration with per-test tweaks. Every
+    /// setting that was set in `overrides` is taken from it, even if it was set to its default
+    /// value, and every other setting is kept from `self`. Masked paths, per-path compare modes and
+    /// the other settings that can be given several times are combined, with those from
+    /// `overrides` taking precedence.
+    ///
+    /// ```
+    /// use serde_json_assert::{CompareMode, Config, ConfigOverrides, FloatCompareMode, NumericMode};
+    ///
+    /// let base = Config::new(CompareMode::Inclusive)
+    ///     .numeric_mode(NumericMode::AssumeFloat)
+    ///     .float_compare_mode(FloatCompareMode::Epsilon(0.01));
+    /// let overrides = ConfigOverrides::new()
+    ///     .compare_mode(CompareMode::Strict)
+    ///     .max_diffs(Some(5));
+    ///
+    /// let config = base.merge(&overrides);
+    /// assert_eq!(config.compare_mode, CompareMode::Strict);
+    /// assert_eq!(config.numeric_mode, NumericMode::AssumeFloat);
+    /// assert_eq!(config.float_compare_mode, FloatCompareMode::Epsilon(0.01));
+    /// assert_eq!(config.max_diffs, Some(5));
+    /// ```
+    pub fn merge(mut self, overrides: &ConfigOverrides) -> Self {
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if overrides.is_set(stringify!($field)) {
+                        self.$field = overrides.config.$field.clone();
+                    }
+                )*
+            };
         }
-    }};
-}
 
-/// Compares two JSON values without panicking.
-///
-/// Instead it returns a `Result` where the error is the message that would be passed to `panic!`.
-/// This is might be useful if you want to control how failures are reported and don't want to deal
-/// with panics.
-pub fn assert_json_matches_no_panic<Lhs, Rhs>(
-    lhs: &Lhs,
-    rhs: &Rhs,
-    config: &Config,
-) -> Result<(), String>
-where
-    Lhs: Serialize,
-    Rhs: Serialize,
-{
-    let lhs = serde_json::to_value(lhs).unwrap_or_else(|err| {
-        panic!(
-            "Couldn't convert left hand side value to JSON. Serde error: {}",
-            err
-        )
-    });
-    let rhs = serde_json::to_value(rhs).unwrap_or_else(|err| {
-        panic!(
-            "Couldn't convert right hand side value to JSON. Serde error: {}",
-            err
-        )
-    });
+        merge!(
+            compare_mode,
+            array_sorting_mode,
+            scalar_array_sorting_mode,
+            container_array_sorting_mode,
+            array_diff_mode,
+            numeric_mode,
+            float_compare_mode,
+            verbosity,
+            max_diffs,
+            color,
+            output_format,
+            numeric_keys,
+            enum_mode,
+            max_depth,
+            max_depth_mode,
+            max_nodes,
+            max_nesting,
+            collapse_output,
+            object_compare_mode,
+            array_compare_mode,
+            report_extras,
+            report_stats,
+            fail_fast,
+            summarize_type_mismatches,
+            sentinels,
+            long_string_threshold,
+            float_precision,
+            timestamps,
+            line_ending_mode,
+            string_compare_mode,
+            context,
+        );
+        self.mask_output
+            .extend(overrides.config.mask_output.iter().cloned());
+        self.ignore_paths
+            .extend(overrides.config.ignore_paths.iter().cloned());
+        #[cfg(feature = "regex")]
+        self.ignore_paths_matching
+            .extend(overrides.config.ignore_paths_matching.iter().cloned());
+        self.path_compare_modes
+            .extend(overrides.config.path_compare_modes.iter().cloned());
+        self.array_keys
+            .extend(overrides.config.array_keys.iter().cloned());
+        self.comparators
+            .extend(overrides.config.comparators.iter().cloned());
+        self.normalizers
+            .extend(overrides.config.normalizers.iter().cloned());
+        self
+    }
 
-    let diffs = diff(&lhs, &rhs, config);
+    /// Returns warnings about settings that contradict each other or have no effect.
+    ///
+    /// A misconfigured tolerance doesn't fail any assertion, it silently weakens it, so configs
+    /// built from several sources (see [`Config::merge`]) are worth checking once. Each warning
+    /// names the setting and how to fix it. An empty list means no problems were found.
+    ///
+    /// ```
+    /// use serde_json_assert::{CompareMode, Config, MaxDepthMode};
+    ///
+    /// let config = Config::new(CompareMode::Strict).max_depth_mode(MaxDepthMode::Ignore);
+    /// assert_eq!(
+    ///     config.validate(),
+    ///     ["max_depth_mode is set, but has no effect without max_depth"]
+    /// );
+    /// assert!(Config::new(CompareMode::Strict).validate().is_empty());
+    /// ```
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = vec![];
 
-    if diffs.is_empty() {
-        Ok(())
-    } else {
-        let msg = diffs
-            .into_iter()
-            .map(|d| d.to_string())
-            .collect::<Vec<_>>()
-            .join("\n\n");
-        Err(msg)
-    }
-}
+        if let FloatCompareMode::Epsilon(epsilon) = self.float_compare_mode {
+            if epsilon < 0.0 || epsilon.is_nan() {
+                warnings.push(format!(
+                    "float_compare_mode has epsilon {}, which never accepts different floats; use a positive epsilon",
+                    epsilon
+                ));
+            }
+        }
+        if matches!(self.string_compare_mode, StringCompareMode::EditDistance(0))
+            || self.string_compare_mode == StringCompareMode::Similarity(1.0)
+        {
+            warnings.push(format!(
+                "string_compare_mode {:?} behaves like StringCompareMode::Exact",
+                self.string_compare_mode
+            ));
+        }
 
-/// Compares two JSON values without panicking.
-///
-/// Returns a `Result` containing either `Ok(())` if the values match,
-/// or an `Err` with a [`Vec<Difference>`](Difference) describing the differences.
-///
-/// # Note:
-///
-/// This function performs some cloning and may be less efficient.
-///
-/// If you only need a string error message, use [`assert_json_matches_no_panic`] or the assertion
-/// macros.
-///
-/// # Examples
-///
-/// ```
-/// use serde_json_assert::{try_assert_json_matches, Config, CompareMode};
-/// use serde_json::json;
-///
-/// let lhs = json!({ "a": 1, "b": 2 });
-/// let rhs = json!({ "a": 1 });
-/// let config = Config::new(CompareMode::Inclusive);
-///
-/// let result = try_assert_json_matches(&lhs, &rhs, &config);
-/// assert!(result.is_ok());
-///
-/// let lhs = json!({ "a": 1 });
-/// let rhs = json!({ "a": 2 });
-/// let config = Config::new(CompareMode::Strict);
-///
-/// let result = try_assert_json_matches(&lhs, &rhs, &config);
-/// assert!(result.is_err());
-/// ```
-pub fn try_assert_json_matches<Lhs, Rhs>(
-    lhs: &Lhs,
-    rhs: &Rhs,
-    config: &Config,
-) -> Result<(), Vec<Difference>>
-where
-    Lhs: Serialize,
-    Rhs: Serialize,
-{
-    let lhs = serde_json::to_value(lhs).unwrap_or_else(|err| {
-        panic!(
-            "Couldn't convert left hand side value to JSON. Serde error: {}",
-            err
-        )
-    });
-    let rhs = serde_json::to_value(rhs).unwrap_or_else(|err| {
-        panic!(
-            "Couldn't convert right hand side value to JSON. Serde error: {}",
-            err
-        )
-    });
+        match self.max_depth {
+            None if self.max_depth_mode != MaxDepthMode::Atomic => warnings
+                .push("max_depth_mode is set, but has no effect without max_depth".to_string()),
+            Some(0) if self.max_depth_mode == MaxDepthMode::Ignore => warnings.push(
+                "max_depth 0 with MaxDepthMode::Ignore accepts any array or object; use a larger depth"
+                    .to_string(),
+            ),
+            _ => {}
+        }
+        if let Some(max_nodes @ (0 | 1)) = self.max_nodes {
+            warnings.push(format!(
+                "max_nodes {} fails every comparison, since both documents have at least one node",
+                max_nodes
+            ));
+        }
 
-    let diffs = diff(&lhs, &rhs, config);
-    let diffs_buf: Vec<Difference> = diffs.into_iter().map(|d| d.into()).collect();
+        let sorting_modes = [
+            ("scalar_array_sorting_mode", self.scalar_array_sorting_mode),
+            (
+                "container_array_sorting_mode",
+                self.container_array_sorting_mode,
+            ),
+        ];
+        if self.array_sorting_mode != ArraySortingMode::Consider
+            && sorting_modes.iter().all(|(_, mode)| mode.is_some())
+        {
+            warnings.push(
+                "array_sorting_mode is overridden for all arrays by scalar_array_sorting_mode and container_array_sorting_mode"
+                    .to_string(),
+            );
+        }
+        if self.array_diff_mode == ArrayDiffMode::Sequence
+            && sorting_modes.iter().all(|(_, mode)| {
+                mode.unwrap_or(self.array_sorting_mode) != ArraySortingMode::Consider
+            })
+        {
+            warnings.push(
+                "array_diff_mode is ArrayDiffMode::Sequence, but has no effect since no arrays are compared with ArraySortingMode::Consider"
+                    .to_string(),
+            );
+        }
+        let array_compare_mode = self.array_compare_mode.unwrap_or(self.compare_mode);
+        if array_compare_mode == CompareMode::Inclusive && self.path_compare_modes.is_empty() {
+            let prefix = [("array_sorting_mode", Some(self.array_sorting_mode))]
+                .into_iter()
+                .chain(sorting_modes)
+                .filter(|(_, mode)| *mode == Some(ArraySortingMode::Prefix));
+            for (name, _) in prefix {
+                warnings.push(format!(
+                    "{} is ArraySortingMode::Prefix, which behaves like ArraySortingMode::Consider when arrays are compared in inclusive mode",
+                    name
+                ));
+            }
+        }
 
-    if diffs_buf.is_empty() {
-        Ok(())
-    } else {
-        Err(diffs_buf)
+        for (idx, (pattern, _)) in self.path_compare_modes.iter().enumerate() {
+            if self.path_compare_modes[idx + 1..]
+                .iter()
+                .any(|(other, _)| other == pattern)
+            {
+                warnings.push(format!(
+                    "compare mode for {} is set more than once, only the last one applies",
+                    pattern
+                ));
+            }
+            if let Some(max_depth) = self.max_depth {
+                if pattern.depth() >= max_depth {
+                    warnings.push(format!(
+                        "compare mode for {} never applies, since values at depth {} are compared as a whole by max_depth {}",
+                        pattern,
+                        pattern.depth(),
+                        max_depth
+                    ));
+                }
+            }
+        }
+
+        warnings
     }
-}
 
-/// Configuration for how JSON values should be compared.
-#[derive(Debug, Clone, PartialEq)]
-#[allow(missing_copy_implementations)]
-pub struct Config {
-    /// Should array sorting be taken in consideration.
-    pub array_sorting_mode: ArraySortingMode,
-    /// How should JSON values be compared.
-    pub compare_mode: CompareMode,
-    /// How should numbers be compared.
-    pub numeric_mode: NumericMode,
-    /// How should floating point numbers be compared.
-    pub float_compare_mode: FloatCompareMode,
-}
+    /// Choose how arrays are compared, independently of the config's compare mode.
+    ///
+    /// Each [`ArrayMode`] sets the [array sorting mode](Config::array_sorting_mode), the
+    /// [array diff mode](Config::array_diff_mode) and the
+    /// [array compare mode](Config::array_compare_mode) that implement it. Modes set with
+    /// [`Config::scalar_array_sorting_mode`], [`Config::container_array_sorting_mode`] and
+    /// [`Config::compare_mode_at`] still take precedence.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, ArrayMode, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).array_mode(ArrayMode::Subsequence);
+    ///
+    /// let log = json!(["created", "paid", "refunded", "shipped"]);
+    /// assert!(assert_json_matches_no_panic(&log, &json!(["created", "shipped"]), &config).is_ok());
+    /// assert!(assert_json_matches_no_panic(&log, &json!(["shipped", "created"]), &config).is_err());
+    /// ```
+    pub fn array_mode(mut self, array_mode: ArrayMode) -> Self {
+        let (sorting_mode, diff_mode, compare_mode) = match array_mode {
+            ArrayMode::Ordered => (
+                ArraySortingMode::Consider,
+                ArrayDiffMode::Index,
+                CompareMode::Strict,
+            ),
+            ArrayMode::UnorderedSubset => (
+                ArraySortingMode::Ignore,
+                ArrayDiffMode::Index,
+                CompareMode::Inclusive,
+            ),
+            ArrayMode::UnorderedExact => (
+                ArraySortingMode::IgnoreStrict,
+                ArrayDiffMode::Index,
+                CompareMode::Strict,
+            ),
+            ArrayMode::Subsequence => (
+                ArraySortingMode::Consider,
+                ArrayDiffMode::Sequence,
+                CompareMode::Inclusive,
+            ),
+        };
+        self.array_sorting_mode = sorting_mode;
+        self.array_diff_mode = diff_mode;
+        self.array_compare_mode = Some(compare_mode);
+        self
+    }
 
-impl Config {
-    /// Create a new [`Config`] using the given [`CompareMode`].
+    /// Change the config's array sorting mode.
     ///
-    /// The default `numeric_mode` is be [`NumericMode::Strict`].
-    pub fn new(compare_mode: CompareMode) -> Self {
-        Self {
-            array_sorting_mode: ArraySortingMode::Consider,
-            compare_mode,
-            numeric_mode: NumericMode::Strict,
-            float_compare_mode: FloatCompareMode::Exact,
-        }
+    /// The default `array_sorting_mode` is [`ArraySortingMode::Consider`].
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, ArraySortingMode, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).array_sorting_mode(ArraySortingMode::Prefix);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "log": ["created", "paid", "shipped"] }),
+    ///     json!({ "log": ["created", "paid"] }),
+    ///     &config
+    /// );
+    /// ```
+    pub fn array_sorting_mode(mut self, array_sorting_mode: ArraySortingMode) -> Self {
+        self.array_sorting_mode = array_sorting_mode;
+        self
     }
 
-    /// Change the config's numeric mode.
+    /// Use `array_sorting_mode` for arrays whose items are all scalars, instead of the config's
+    /// array sorting mode.
     ///
-    /// The default `numeric_mode` is be [`NumericMode::Strict`].
-    pub fn numeric_mode(mut self, numeric_mode: NumericMode) -> Self {
-        self.numeric_mode = numeric_mode;
+    /// Arrays of scalars, such as tags or ids, and arrays of objects, such as events, often have
+    /// different semantics in the same document. An array is one of scalars if none of the items
+    /// on either side is an array or object, so empty arrays are arrays of scalars. See
+    /// [`Config::container_array_sorting_mode`] for the other arrays.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches, ArraySortingMode, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).scalar_array_sorting_mode(ArraySortingMode::Set);
+    ///
+    /// assert_json_matches!(
+    ///     json!({ "tags": ["b", "a"], "events": [{ "type": "created" }, { "type": "paid" }] }),
+    ///     json!({ "tags": ["a", "b"], "events": [{ "type": "created" }, { "type": "paid" }] }),
+    ///     &config
+    /// );
+    /// ```
+    pub fn scalar_array_sorting_mode(mut self, array_sorting_mode: ArraySortingMode) -> Self {
+        self.scalar_array_sorting_mode = Some(array_sorting_mode);
         self
     }
 
-    /// Change the config's compare mode.
-    pub fn compare_mode(mut self, compare_mode: CompareMode) -> Self {
-        self.compare_mode = compare_mode;
+    /// Use `array_sorting_mode` for arrays with items that are arrays or objects, instead of the
+    /// config's array sorting mode.
+    ///
+    /// See [`Config::scalar_array_sorting_mode`] for the other arrays.
+    pub fn container_array_sorting_mode(mut self, array_sorting_mode: ArraySortingMode) -> Self {
+        self.container_array_sorting_mode = Some(array_sorting_mode);
         self
     }
 
-    /// Change the config's float compare mode.
+    /// Change how arrays compared in order are diffed.
     ///
-    /// The default `float_compare_mode` is [`FloatCompareMode::Exact`].
-    pub fn float_compare_mode(mut self, float_compare_mode: FloatCompareMode) -> Self {
-        self.float_compare_mode = float_compare_mode;
+    /// The default `array_diff_mode` is [`ArrayDiffMode::Index`]. With
+    /// [`ArrayDiffMode::Sequence`], an item inserted into or removed from an array is reported on
+    /// its own, instead of as a difference at every index after it.
+    ///
+    /// ```
+    /// use serde_json_assert::{assert_json_matches_no_panic, ArrayDiffMode, CompareMode, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::new(CompareMode::Strict).array_diff_mode(ArrayDiffMode::Sequence);
+    ///
+    /// assert_eq!(
+    ///     assert_json_matches_no_panic(&json!([1, 2, 3, 4, 5]), &json!([1, 3, 4, 5, 6]), &config)
+    ///         .unwrap_err(),
+    ///     r#"json atom at path "[1]" is missing from rhs
+    ///
+    /// json atom at path "[4]" is missing from lhs"#
+    /// );
+    /// ```
+    pub fn array_diff_mode(mut self, array_diff_mode: ArrayDiffMode) -> Self {
+        self.array_diff_mode = array_diff_mode;
         self
     }
 
-    /// configure array sorting mode
-    pub fn consider_array_sorting(mut self, consider: bool) -> Self {
-        if consider {
-            if self.compare_mode == CompareMode::Strict {
-                panic!("strict comparison does not allow array ordering to be ignored");
-            }
-            self.array_sorting_mode = ArraySortingMode::Consider;
+    /// Returns the array sorting mode for comparing the arrays `lhs` and `rhs`.
+    pub(crate) fn array_sorting_mode_for(&self, lhs: &Value, rhs: &Value) -> ArraySortingMode {
+        let is_container = |value: &Value| value.is_array() || value.is_object();
+        let has_containers = [lhs, rhs]
+            .into_iter()
+            .filter_map(Value::as_array)
+            .flatten()
+            .any(is_container);
+        let mode = if has_containers {
+            self.container_array_sorting_mode
         } else {
-            self.array_sorting_mode = ArraySortingMode::Ignore;
-        }
+            self.scalar_array_sorting_mode
+        };
+        mode.unwrap_or(self.array_sorting_mode)
+    }
+
+    /// Configure whether the order of array items matters.
+    ///
+    /// Ignoring the order uses [`ArraySortingMode::Ignore`] in inclusive comparisons and
+    /// [`ArraySortingMode::IgnoreStrict`] in strict ones, so both arrays must then contain the
+    /// same items.
+    #[deprecated(
+        since = "0.5.0",
+        note = "use `Config::array_mode`, which doesn't depend on the compare mode"
+    )]
+    pub fn consider_array_sorting(mut self, consider: bool) -> Self {
+        self.array_sorting_mode = match (consider, self.compare_mode) {
+            (true, _) => ArraySortingMode::Consider,
+            (false, CompareMode::Inclusive) => ArraySortingMode::Ignore,
+            (false, CompareMode::Strict) => ArraySortingMode::IgnoreStrict,
+        };
         self
     }
 }
@@ -510,6 +3551,22 @@ pub enum CompareMode {
     Strict,
 }
 
+/// What arrays must contain to match, see [`Config::array_mode`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ArrayMode {
+    /// The actual array contains the expected items in the same order, and nothing else.
+    Ordered,
+    /// The actual array contains each expected item at least as many times as expected, in any
+    /// order, and may contain other items.
+    UnorderedSubset,
+    /// The actual array contains the expected items as many times as expected, in any order, and
+    /// nothing else.
+    UnorderedExact,
+    /// The actual array contains the expected items in the same order, and may contain other
+    /// items before, between and after them.
+    Subsequence,
+}
+
 /// Should array sorting be taken in consideration
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ArraySortingMode {
@@ -517,6 +3574,37 @@ pub enum ArraySortingMode {
     Consider,
     /// ignore
     Ignore,
+    /// Arrays are compared in order, and the actual array may contain additional items after the
+    /// expected ones, even in strict comparisons. The expected array must match the start of the
+    /// actual array exactly.
+    Prefix,
+    /// Arrays are compared as sets: order and duplicates are ignored, and each side must contain
+    /// the same distinct items. In inclusive comparisons, the actual array only has to contain the
+    /// expected items. Items found on only one side are reported at their index on that side.
+    Set,
+    /// Order is ignored, but both arrays must contain the same items as many times, even in
+    /// inclusive comparisons, unless the array is marked with [`matchers::partial`]. Each item is
+    /// paired with a different item of the other array, so the arrays have the same length. The
+    /// items themselves are still compared according to the compare mode. Items left without a
+    /// partner are reported at their index on their side.
+    IgnoreStrict,
+}
+
+/// How arrays compared in order are diffed, see [`Config::array_diff_mode`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ArrayDiffMode {
+    /// Items are compared index by index. An item inserted into or removed from the actual array
+    /// shifts the items after it, and each of them is reported as different.
+    Index,
+    /// Items are aligned along the longest sequence of matching items both arrays have in common,
+    /// and the items in between are reported as inserted or removed. Where both arrays have items
+    /// between the same aligned ones, these are compared one by one first, so a changed item is
+    /// still reported as a changed value.
+    ///
+    /// Items missing from the actual array are reported at their index in the expected array,
+    /// and unexpected items at their index in the actual array. Aligning arrays compares every
+    /// item of one with every item of the other, which is slower for long arrays.
+    Sequence,
 }
 
 /// How should numbers be compared.
@@ -524,10 +3612,40 @@ pub enum ArraySortingMode {
 pub enum NumericMode {
     /// Different numeric types aren't considered equal.
     Strict,
-    /// All numeric types are converted to float before comparison.
+    /// Numbers are compared by value regardless of their type, so `1` equals `1.0`.
+    ///
+    /// If one side is a float, both are converted to floats before comparison. Two integers are
+    /// compared exactly, even beyond 2^53 where floats can't represent every integer, and a
+    /// [`FloatCompareMode::Epsilon`] applies to their difference.
     AssumeFloat,
 }
 
+/// How much detail failure messages contain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Verbosity {
+    /// A single line per difference, naming the path.
+    Quiet,
+    /// The path and the differing values of each difference.
+    Normal,
+    /// Like [`Verbosity::Normal`], but also shows values that are missing from one side, and
+    /// the total number of differences.
+    Verbose,
+}
+
+/// How the differences are laid out in failure messages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Each difference is described separately, with the values at its path.
+    List,
+    /// The lhs document is printed as an indented tree, where the lines of differing values are
+    /// marked with `~` if they changed, `+` if they only exist in the lhs and `-` if they only
+    /// exist in the rhs. Subtrees without differences are collapsed.
+    ///
+    /// Only available for whole documents. Other checks, such as [`check_json_paths`], fall back
+    /// to [`OutputFormat::List`].
+    Tree,
+}
+
 /// How should floating point numbers be compared.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum FloatCompareMode {
@@ -539,6 +3657,73 @@ pub enum FloatCompareMode {
 
 impl Eq for FloatCompareMode {}
 
+/// How should strings be compared.
+///
+/// See [`Config::string_compare_mode`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StringCompareMode {
+    /// Different strings are never considered equal.
+    Exact,
+    /// Strings are considered equal if at most this many characters have to be inserted, removed
+    /// or replaced to turn one into the other (their Levenshtein distance).
+    EditDistance(usize),
+    /// Strings are considered equal if their similarity is at least this fraction between 0 and
+    /// 1, where the similarity is 1 minus their edit distance divided by the length of the longer
+    /// string.
+    Similarity(f64),
+}
+
+impl Eq for StringCompareMode {}
+
+/// How should arrays and objects at the maximum depth be compared.
+///
+/// See [`Config::max_depth`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MaxDepthMode {
+    /// They are compared as a whole, and reported as a single difference if they aren't exactly
+    /// equal. Matchers and settings like float epsilons don't apply within them.
+    Atomic,
+    /// They are considered equal.
+    Ignore,
+}
+
+/// How should line endings in strings be compared.
+///
+/// See [`Config::line_ending_mode`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineEndingMode {
+    /// Line endings must be equal.
+    Exact,
+    /// `\r\n` is considered equal to `\n`.
+    Normalize,
+    /// Like [`LineEndingMode::Normalize`], and trailing line endings are ignored as well.
+    NormalizeIgnoringTrailing,
+}
+
+/// How should serde enum representations be compared.
+///
+/// See the [serde documentation](https://serde.rs/enum-representations.html) for the different
+/// representations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumMode {
+    /// Enums are compared as they are serialized.
+    Exact,
+    /// Externally, internally and adjacently tagged enums are considered equal if they have the
+    /// same variant and contents.
+    ///
+    /// Both sides are converted to the externally tagged representation before being compared, so
+    /// paths in failure messages refer to that representation. Any object with a string `tag`
+    /// field is treated as a tagged enum.
+    Normalize {
+        /// The tag field name used by internally and adjacently tagged enums, as in
+        /// `#[serde(tag = "...")]`.
+        tag: String,
+        /// The content field name used by adjacently tagged enums, as in
+        /// `#[serde(content = "...")]`.
+        content: String,
+    },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -800,6 +3985,186 @@ mod tests {
         );
     }
 
+    #[test]
+    fn merge_configs() {
+        let base = Config::new(CompareMode::Inclusive)
+            .array_sorting_mode(ArraySortingMode::Ignore)
+            .verbosity(Verbosity::Quiet)
+            .color(true);
+
+        assert_eq!(base.clone().merge(&ConfigOverrides::new()), base);
+
+        let overrides = ConfigOverrides::new()
+            .compare_mode(CompareMode::Strict)
+            .verbosity(Verbosity::Verbose)
+            .numeric_mode(NumericMode::AssumeFloat)
+            .mask_output(".token");
+        let merged = base.clone().merge(&overrides);
+        assert_eq!(merged.compare_mode, CompareMode::Strict);
+        assert_eq!(merged.array_sorting_mode, ArraySortingMode::Ignore);
+        assert_eq!(merged.verbosity, Verbosity::Verbose);
+        assert_eq!(merged.numeric_mode, NumericMode::AssumeFloat);
+        assert_eq!(merged.mask_output.len(), 1);
+        assert!(merged.color);
+
+        // settings reset to their defaults still replace customized base settings
+        let overrides = ConfigOverrides::new()
+            .array_sorting_mode(ArraySortingMode::Consider)
+            .verbosity(Verbosity::Normal)
+            .color(false);
+        let merged = base.fail_fast(true).merge(&overrides);
+        assert_eq!(merged.array_sorting_mode, ArraySortingMode::Consider);
+        assert_eq!(merged.verbosity, Verbosity::Normal);
+        assert!(!merged.color);
+        assert!(merged.fail_fast);
+    }
+
+    #[test]
+    fn diff_prepares_values_like_assertions() {
+        let config = Config::new(CompareMode::Strict)
+            .normalizer(normalizers::CollapseWhitespace)
+            .context(&json!({ "id": 7 }));
+        let lhs = json!({ "id": 7, "name": "Ann  Lee", "tags": ["a  b", "c"] });
+        let rhs = json!({ "id": matchers::context_value(".id"), "name": "Ann Lee", "tags": ["a b", "d"] });
+
+        let borrowed = diff(&lhs, &rhs, &config).unwrap();
+        let owned = try_assert_json_matches(&lhs, &rhs, &config).unwrap_err();
+        assert_eq!(borrowed.len(), 1);
+        assert_eq!(owned.len(), 1);
+        assert_eq!(borrowed[0].path(), owned[0].path());
+        assert_eq!(borrowed[0].path().to_string(), ".tags[1]");
+        assert_eq!(borrowed[0].lhs(), owned[0].lhs());
+        assert_eq!(borrowed[0].to_string(), owned[0].to_string());
+        assert_eq!(
+            serde_json::to_value(&borrowed).unwrap(),
+            serde_json::to_value(&owned).unwrap()
+        );
+
+        let rhs = json!({ "id": 7, "name": "Ann Lee", "tags": ["a b", "c"] });
+        assert!(diff(&lhs, &rhs, &config).unwrap().is_empty());
+        assert!(try_assert_json_matches(&lhs, &rhs, &config).is_ok());
+    }
+
+    #[test]
+    fn array_modes() {
+        let actual = json!([1, 2, 3, 2]);
+        let matches = |expected: Value, array_mode: ArrayMode| {
+            [CompareMode::Strict, CompareMode::Inclusive].map(|compare_mode| {
+                let config = Config::new(compare_mode).array_mode(array_mode);
+                assert_json_matches_no_panic(&actual, &expected, &config).is_ok()
+            })
+        };
+
+        assert_eq!(matches(json!([1, 2, 3, 2]), ArrayMode::Ordered), [true; 2]);
+        assert_eq!(matches(json!([1, 2, 3]), ArrayMode::Ordered), [false; 2]);
+        assert_eq!(
+            matches(json!([2, 1, 2]), ArrayMode::UnorderedSubset),
+            [true; 2]
+        );
+        assert_eq!(
+            matches(json!([2, 2, 2]), ArrayMode::UnorderedSubset),
+            [false; 2]
+        );
+        assert_eq!(
+            matches(json!([2, 3, 2, 1]), ArrayMode::UnorderedExact),
+            [true; 2]
+        );
+        assert_eq!(
+            matches(json!([2, 3, 1]), ArrayMode::UnorderedExact),
+            [false; 2]
+        );
+        assert_eq!(matches(json!([1, 3, 2]), ArrayMode::Subsequence), [true; 2]);
+        assert_eq!(matches(json!([3, 1]), ArrayMode::Subsequence), [false; 2]);
+    }
+
+    #[test]
+    fn validate_config() {
+        assert!(Config::new(CompareMode::Inclusive)
+            .float_compare_mode(FloatCompareMode::Epsilon(0.01))
+            .array_sorting_mode(ArraySortingMode::Ignore)
+            .validate()
+            .is_empty());
+
+        let config = Config::new(CompareMode::Inclusive)
+            .float_compare_mode(FloatCompareMode::Epsilon(-0.01))
+            .string_compare_mode(StringCompareMode::EditDistance(0))
+            .array_sorting_mode(ArraySortingMode::Prefix)
+            .array_diff_mode(ArrayDiffMode::Sequence)
+            .max_depth(1)
+            .compare_mode_at(".a", CompareMode::Strict)
+            .compare_mode_at(".a", CompareMode::Inclusive);
+        assert_eq!(
+            config.validate(),
+            [
+                "float_compare_mode has epsilon -0.01, which never accepts different floats; use a positive epsilon",
+                "string_compare_mode EditDistance(0) behaves like StringCompareMode::Exact",
+                "array_diff_mode is ArrayDiffMode::Sequence, but has no effect since no arrays are compared with ArraySortingMode::Consider",
+                "compare mode for .a is set more than once, only the last one applies",
+                "compare mode for .a never applies, since values at depth 1 are compared as a whole by max_depth 1",
+                "compare mode for .a never applies, since values at depth 1 are compared as a whole by max_depth 1",
+            ]
+        );
+
+        let config = Config::new(CompareMode::Inclusive)
+            .array_sorting_mode(ArraySortingMode::Set)
+            .scalar_array_sorting_mode(ArraySortingMode::Prefix)
+            .container_array_sorting_mode(ArraySortingMode::Consider)
+            .max_depth(0)
+            .max_depth_mode(MaxDepthMode::Ignore)
+            .max_nodes(1);
+        assert_eq!(
+            config.validate(),
+            [
+                "max_depth 0 with MaxDepthMode::Ignore accepts any array or object; use a larger depth",
+                "max_nodes 1 fails every comparison, since both documents have at least one node",
+                "array_sorting_mode is overridden for all arrays by scalar_array_sorting_mode and container_array_sorting_mode",
+                "scalar_array_sorting_mode is ArraySortingMode::Prefix, which behaves like ArraySortingMode::Consider when arrays are compared in inclusive mode",
+            ]
+        );
+    }
+
+    #[test]
+    fn output_settings() {
+        let lhs = json!({ "a": 1, "b": 2, "c": 3 });
+        let rhs = json!({ "a": 2, "b": 3 });
+
+        let config = Config::new(CompareMode::Inclusive).verbosity(Verbosity::Quiet);
+        assert_output_eq(
+            assert_json_matches_no_panic(&lhs, &rhs, &config),
+            Err(r#"json atoms at path ".a" are not equal
+json atoms at path ".b" are not equal"#),
+        );
+
+        let config = config.max_diffs(Some(1));
+        assert_output_eq(
+            assert_json_matches_no_panic(&lhs, &rhs, &config),
+            Err(r#"json atoms at path ".a" are not equal
+... and 1 more differences"#),
+        );
+
+        let config = Config::new(CompareMode::Inclusive).verbosity(Verbosity::Verbose);
+        assert_output_eq(
+            assert_json_matches_no_panic(&json!({}), &json!({ "a": [1] }), &config),
+            Err(r#"json atom at path ".a" is missing from actual:
+    expected:
+        [
+          1
+        ]
+
+found 1 differences in total"#),
+        );
+
+        let config = Config::new(CompareMode::Strict).color(true);
+        assert_output_eq(
+            assert_json_matches_no_panic(&json!(1), &json!(2), &config),
+            Err("json atoms at path \"(root)\" are not equal:
+    \x1b[31mlhs\x1b[0m:
+        1
+    \x1b[32mrhs\x1b[0m:
+        2"),
+        );
+    }
+
     fn assert_output_eq(actual: Result<(), String>, expected: Result<(), &str>) {
         match (actual, expected) {
             (Ok(()), Ok(())) => {}
@@ -0,0 +1,261 @@
+use crate::diff::{Key, Path};
+use crate::paths::__to_value;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Constructs that [`assert_json_lint`](crate::assert_json_lint) reports anywhere in a document.
+///
+/// A new lint allows everything. Each method disallows one construct.
+///
+/// ```
+/// use serde_json_assert::{assert_json_lint_no_panic, Lint};
+/// use serde_json::json;
+///
+/// let lint = Lint::new().no_nulls().no_empty_strings().no_keys_matching("_*");
+///
+/// let error = assert_json_lint_no_panic(
+///     &json!({ "id": 1, "name": "", "tags": [null], "_rev": 3 }),
+///     &lint,
+/// )
+/// .unwrap_err();
+/// assert_eq!(
+///     error,
+///     r#"json value contains 3 disallowed values:
+///     ._rev: key matching "_*"
+///     .name: empty string
+///     .tags[0]: null"#
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lint {
+    nulls: bool,
+    empty_strings: bool,
+    empty_arrays: bool,
+    empty_objects: bool,
+    key_patterns: Vec<String>,
+}
+
+impl Lint {
+    /// Creates a lint that allows everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Disallow `null` values.
+    pub fn no_nulls(mut self) -> Self {
+        self.nulls = true;
+        self
+    }
+
+    /// Disallow empty strings.
+    pub fn no_empty_strings(mut self) -> Self {
+        self.empty_strings = true;
+        self
+    }
+
+    /// Disallow empty arrays.
+    pub fn no_empty_arrays(mut self) -> Self {
+        self.empty_arrays = true;
+        self
+    }
+
+    /// Disallow empty objects.
+    pub fn no_empty_objects(mut self) -> Self {
+        self.empty_objects = true;
+        self
+    }
+
+    /// Disallow empty arrays and empty objects.
+    pub fn no_empty_containers(self) -> Self {
+        self.no_empty_arrays().no_empty_objects()
+    }
+
+    /// Disallow object keys matching `pattern`, where `*` matches any text, such as `_*` for
+    /// private fields or `*password*`.
+    pub fn no_keys_matching(mut self, pattern: &str) -> Self {
+        self.key_patterns.push(pattern.to_string());
+        self
+    }
+
+    /// Returns the paths of the disallowed values of `value`, in document order.
+    fn violations(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = vec![];
+        self.visit(value, &mut vec![], &mut violations);
+        violations
+    }
+
+    fn visit(&self, value: &Value, keys: &mut Vec<Key>, violations: &mut Vec<Violation>) {
+        let mut violation = |rule| {
+            violations.push(Violation {
+                path: Path::from_keys(keys.clone()),
+                rule,
+            })
+        };
+        match value {
+            Value::Null if self.nulls => violation(Rule::Null),
+            Value::String(text) if self.empty_strings && text.is_empty() => {
+                violation(Rule::EmptyString)
+            }
+            Value::Array(items) if self.empty_arrays && items.is_empty() => {
+                violation(Rule::EmptyArray)
+            }
+            Value::Object(fields) if self.empty_objects && fields.is_empty() => {
+                violation(Rule::EmptyObject)
+            }
+            Value::Array(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    keys.push(Key::Idx(idx));
+                    self.visit(item, keys, violations);
+                    keys.pop();
+                }
+            }
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    keys.push(Key::Field(key.clone()));
+                    let pattern = self
+                        .key_patterns
+                        .iter()
+                        .find(|pattern| glob_matches(pattern, key));
+                    match pattern {
+                        Some(pattern) => violations.push(Violation {
+                            path: Path::from_keys(keys.clone()),
+                            rule: Rule::Key(pattern.clone()),
+                        }),
+                        None => self.visit(field, keys, violations),
+                    }
+                    keys.pop();
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+        }
+    }
+}
+
+struct Violation {
+    path: Path,
+    rule: Rule,
+}
+
+enum Rule {
+    Null,
+    EmptyString,
+    EmptyArray,
+    EmptyObject,
+    Key(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.rule {
+            Rule::Null => write!(f, "null"),
+            Rule::EmptyString => write!(f, "empty string"),
+            Rule::EmptyArray => write!(f, "empty array"),
+            Rule::EmptyObject => write!(f, "empty object"),
+            Rule::Key(pattern) => write!(f, "key matching {:?}", pattern),
+        }
+    }
+}
+
+/// Returns true if `text` matches `pattern`, where `*` matches any text.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        // The pattern has no `*`.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Checks `value` for the constructs disallowed by `lint` without panicking.
+///
+/// The error lists the path of every disallowed value. Values below a disallowed key aren't
+/// checked. See [`assert_json_lint`](crate::assert_json_lint) for the panicking version.
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized.
+#[track_caller]
+pub fn assert_json_lint_no_panic<T>(value: &T, lint: &Lint) -> Result<(), String>
+where
+    T: Serialize + ?Sized,
+{
+    let violations = lint.violations(&__to_value(value));
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut error = format!(
+        "json value contains {} disallowed value{}:",
+        violations.len(),
+        if violations.len() == 1 { "" } else { "s" }
+    );
+    for violation in violations {
+        error.push_str(&format!("\n    {}", violation));
+    }
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_every_violation() {
+        let value = json!({
+            "a": null,
+            "b": ["", [], {}, [null]],
+            "c": { "secret_token": "x", "password": null },
+            "d": {},
+        });
+
+        let lint = Lint::new()
+            .no_nulls()
+            .no_empty_strings()
+            .no_empty_containers()
+            .no_keys_matching("*pass*");
+        assert_eq!(
+            assert_json_lint_no_panic(&value, &lint).unwrap_err(),
+            r#"json value contains 7 disallowed values:
+    .a: null
+    .b[0]: empty string
+    .b[1]: empty array
+    .b[2]: empty object
+    .b[3][0]: null
+    .c.password: key matching "*pass*"
+    .d: empty object"#
+        );
+
+        assert_eq!(assert_json_lint_no_panic(&value, &Lint::new()), Ok(()));
+        let lint = Lint::new().no_empty_arrays().no_keys_matching("secret_*");
+        assert_eq!(
+            assert_json_lint_no_panic(&value, &lint).unwrap_err(),
+            "json value contains 2 disallowed values:\n    .b[1]: empty array\n    .c.secret_token: key matching \"secret_*\""
+        );
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("id", "id"));
+        assert!(!glob_matches("id", "ids"));
+        assert!(glob_matches("_*", "_rev"));
+        assert!(glob_matches("*_at", "created_at"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "aXbYc"));
+        assert!(!glob_matches("a*b*c", "aXcYb"));
+        assert!(!glob_matches("ab*ba", "aba"));
+    }
+}
@@ -0,0 +1,1572 @@
+//! Matchers that can be embedded in expected values.
+//!
+//! A matcher is a marker object placed somewhere in the expected (right hand side) JSON. When the
+//! comparison reaches a marker, the actual value at that position is checked by the matcher instead
+//! of being compared to the marker itself. Markers are objects with a `"$matcher"` field naming
+//! the matcher, so that key shouldn't be used in expected values for anything else.
+//!
+//! ```
+//! use serde_json_assert::{assert_json_eq, matchers};
+//! use serde_json::json;
+//!
+//! let user = json!({ "id": 1, "profile": { "name": "Bob", "age": 30 } });
+//!
+//! assert_json_eq!(
+//!     user,
+//!     json!({ "id": 1, "profile": matchers::partial(json!({ "name": "Bob" })) })
+//! );
+//! ```
+
+use crate::diff::Path;
+use crate::duration;
+use crate::minimize::get;
+use crate::url;
+use crate::Config;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+
+pub(crate) const MATCHER_KEY: &str = "$matcher";
+
+/// Matches an object or array that contains at least the given fields or items.
+///
+/// Objects may contain additional fields, and arrays additional trailing items, even when the
+/// comparison is strict. Only the object or array itself is open; nested values are compared using
+/// the mode of the comparison, unless they are partial as well. Other values are compared as if
+/// they weren't wrapped.
+///
+/// The [`json_partial!`](crate::json_partial) macro is usually more convenient.
+pub fn partial(expected: Value) -> Value {
+    json!({ MATCHER_KEY: "partial", "value": expected })
+}
+
+/// Matches any value except `unexpected`.
+///
+/// `unexpected` is compared using the settings of the comparison, so for example float epsilons
+/// apply, and it can contain other matchers.
+///
+/// ```
+/// use serde_json_assert::{assert_json_include, matchers};
+/// use serde_json::json;
+///
+/// assert_json_include!(
+///     actual: json!({ "status": "done", "retries": 2 }),
+///     expected: json!({ "status": matchers::not(json!("failed")) })
+/// );
+/// ```
+pub fn not(unexpected: Value) -> Value {
+    json!({ MATCHER_KEY: "not", "value": unexpected })
+}
+
+/// Matches any value except the given ones.
+///
+/// Like [`not`], but rejects several values at once.
+///
+/// ```
+/// use serde_json_assert::{assert_json_include, matchers};
+/// use serde_json::json;
+///
+/// assert_json_include!(
+///     actual: json!({ "status": "done" }),
+///     expected: json!({ "status": matchers::none_of([json!("failed"), json!("cancelled")]) })
+/// );
+/// ```
+pub fn none_of<I>(unexpected: I) -> Value
+where
+    I: IntoIterator<Item = Value>,
+{
+    json!({ MATCHER_KEY: "none_of", "values": unexpected.into_iter().collect::<Vec<_>>() })
+}
+
+/// Matches binary data equal to `data`, however it is encoded.
+///
+/// The actual value can be an array of byte values, or a base64 string using either the standard
+/// or the URL-safe alphabet, with or without padding. Use [`bytes_or_hex`] to accept hex strings
+/// as well.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(json!("aGk="), matchers::bytes("hi"));
+/// assert_json_eq!(json!([104, 105]), matchers::bytes("hi"));
+/// ```
+pub fn bytes(data: impl AsRef<[u8]>) -> Value {
+    json!({ MATCHER_KEY: "bytes", "value": data.as_ref(), "hex": false })
+}
+
+/// Like [`bytes`], but also accepts hex strings in either case.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(json!("6869"), matchers::bytes_or_hex("hi"));
+/// ```
+pub fn bytes_or_hex(data: impl AsRef<[u8]>) -> Value {
+    json!({ MATCHER_KEY: "bytes", "value": data.as_ref(), "hex": true })
+}
+
+/// Matches a string holding a URL equivalent to `expected`.
+///
+/// The scheme and host are compared case-insensitively, default ports such as `:443` for `https`
+/// may be omitted, and query parameters may appear in any order. Strings that aren't absolute URLs
+/// must be equal to `expected`. Use [`url_ignoring_trailing_slash`] if the path may end with
+/// `/` on only one side.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!("HTTPS://Example.com:443/search?page=2&q=json"),
+///     matchers::url("https://example.com/search?q=json&page=2")
+/// );
+/// ```
+pub fn url(expected: &str) -> Value {
+    json!({ MATCHER_KEY: "url", "value": expected, "ignore_trailing_slash": false })
+}
+
+/// Like [`url`], but also ignores trailing slashes in the path.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!("https://example.com/users/"),
+///     matchers::url_ignoring_trailing_slash("https://example.com/users")
+/// );
+/// ```
+pub fn url_ignoring_trailing_slash(expected: &str) -> Value {
+    json!({ MATCHER_KEY: "url", "value": expected, "ignore_trailing_slash": true })
+}
+
+/// Matches a string holding XML or HTML equal to `expected`, up to insignificant whitespace.
+///
+/// Whitespace before and after tags is ignored, as is leading and trailing whitespace. Any other
+/// run of whitespace, such as between words of text, is treated as a single space.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!("<ul>\n  <li>One\n    item</li>\n</ul>\n"),
+///     matchers::markup("<ul><li>One item</li></ul>")
+/// );
+/// ```
+pub fn markup(expected: &str) -> Value {
+    json!({ MATCHER_KEY: "markup", "value": expected })
+}
+
+/// Matches any value with the same shape as `example`.
+///
+/// Numbers, strings, booleans and null match any value of the same type. Objects match objects
+/// containing at least the fields of `example`, each like the example field, and arrays match
+/// arrays whose items are all like the first item of `example`. Matchers nested in `example` are
+/// applied as usual, so [`exactly`] can be used to require some values to be equal.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!({ "id": 7, "name": "Eve", "tags": ["a", "b"], "admin": true }),
+///     matchers::like(json!({ "id": 1, "name": "Bob", "tags": ["x"] }))
+/// );
+/// ```
+pub fn like(example: Value) -> Value {
+    json!({ MATCHER_KEY: "like", "value": example })
+}
+
+/// Matches an array whose items are all [`like`] `item`, with a length between `min` and `max`.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!([{ "id": 1 }, { "id": 2 }]),
+///     matchers::array_like(json!({ "id": 0 }), Some(1), None)
+/// );
+/// ```
+pub fn array_like(item: Value, min: Option<usize>, max: Option<usize>) -> Value {
+    json!({ MATCHER_KEY: "array_like", "value": item, "min": min, "max": max })
+}
+
+/// Matches any value of the given type.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers, matchers::JsonType};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!({ "id": 42, "ratio": 0.5 }),
+///     json!({
+///         "id": matchers::of_type(JsonType::Integer),
+///         "ratio": matchers::of_type(JsonType::Number),
+///     })
+/// );
+/// ```
+pub fn of_type(expected: JsonType) -> Value {
+    json!({ MATCHER_KEY: "of_type", "type": expected.name() })
+}
+
+/// Matches a string containing `substring`.
+pub fn contains(substring: &str) -> Value {
+    json!({ MATCHER_KEY: "contains", "value": substring })
+}
+
+/// Matches values equal to `expected`.
+///
+/// This is only useful within the examples of [`like`] and [`array_like`], where values would
+/// otherwise only be compared by their types.
+pub fn exactly(expected: Value) -> Value {
+    json!({ MATCHER_KEY: "exactly", "value": expected })
+}
+
+/// Matches an object containing at least the fields of `expected`, like Jest's
+/// `expect.objectContaining`.
+///
+/// This is the same as [`partial`], named for suites migrated from JavaScript.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!({ "id": 1, "name": "Bob", "tags": ["a", "b"] }),
+///     matchers::object_containing(json!({
+///         "name": matchers::any_string(),
+///         "tags": matchers::array_containing([json!("b")]),
+///     }))
+/// );
+/// ```
+pub fn object_containing(expected: Value) -> Value {
+    partial(expected)
+}
+
+/// Matches an array containing each of the `expected` items in any order, like Jest's
+/// `expect.arrayContaining`.
+///
+/// The array may contain other items as well, and one item may match several expected ones.
+/// Items are compared using the settings of the comparison, and can contain other matchers.
+pub fn array_containing<I>(expected: I) -> Value
+where
+    I: IntoIterator<Item = Value>,
+{
+    json!({ MATCHER_KEY: "array_containing", "values": expected.into_iter().collect::<Vec<_>>() })
+}
+
+/// Matches any string, like Jest's `expect.any(String)`.
+///
+/// This is the same as `of_type(JsonType::String)`.
+pub fn any_string() -> Value {
+    of_type(JsonType::String)
+}
+
+/// Matches any number, like Jest's `expect.any(Number)`.
+///
+/// This is the same as `of_type(JsonType::Number)`.
+pub fn any_number() -> Value {
+    of_type(JsonType::Number)
+}
+
+/// Matches any value, including `null`.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!({ "id": 1, "trace": { "span": "a1f" } }),
+///     json!({ "id": 1, "trace": matchers::any() })
+/// );
+/// ```
+pub fn any() -> Value {
+    json!({ MATCHER_KEY: "any" })
+}
+
+/// Matches strings containing a UUID in its canonical form, such as
+/// `"67e55044-10b1-426f-9247-bb680e5fe0c8"`, in either case.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!({ "id": "67E55044-10B1-426F-9247-BB680E5FE0C8" }),
+///     json!({ "id": matchers::uuid() })
+/// );
+/// ```
+pub fn uuid() -> Value {
+    json!({ MATCHER_KEY: "uuid" })
+}
+
+/// Matches strings matching the regular expression `pattern`.
+///
+/// The pattern isn't anchored, so use `^` and `$` to match whole strings. Failure messages show
+/// the pattern next to the actual string.
+///
+/// Requires the `regex` feature.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// assert_json_eq!(
+///     json!({ "id": "ord_3f2a9c01b7de" }),
+///     json!({ "id": matchers::regex("^ord_[0-9a-f]{12}$") })
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `pattern` isn't a valid regular expression.
+#[cfg(feature = "regex")]
+#[track_caller]
+pub fn regex(pattern: &str) -> Value {
+    if let Err(err) = regex::Regex::new(pattern) {
+        panic!("{}", err);
+    }
+    json!({ MATCHER_KEY: "regex", "pattern": pattern })
+}
+
+/// Matches values matching `expected`, and captures them under `name`.
+///
+/// The captured values are returned by [`assert_json_captures!`](crate::assert_json_captures) and
+/// [`check_json_captures`](crate::check_json_captures), which saves walking the actual document
+/// again to pick out generated values, such as an id to use in the next request. Values are only
+/// captured where `expected` matches. Other assertions ignore captures and only check `expected`.
+///
+/// ```
+/// use serde_json_assert::{assert_json_captures, matchers};
+/// use serde_json::json;
+///
+/// let response = json!({ "id": 17, "name": "Ann" });
+///
+/// let captures = assert_json_captures!(
+///     response,
+///     json!({ "id": matchers::capture("user_id", matchers::any_number()), "name": "Ann" })
+/// );
+/// assert_eq!(captures["user_id"], 17);
+/// ```
+pub fn capture(name: &str, expected: Value) -> Value {
+    json!({ MATCHER_KEY: "capture", "name": name, "value": expected })
+}
+
+/// Matches numbers equal to `expected`, written as a number or as a string in the format of any
+/// locale.
+///
+/// Strings may use either `.` or `,` as decimal separator, and group thousands with `.`, `,`,
+/// spaces or `'`. A string that reads as a number both ways, such as `"1,234"`, matches if either
+/// reading equals `expected`.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+///
+/// let price = || matchers::localized_number(1234.5);
+/// assert_json_eq!(
+///     json!(["1,234.50", "1.234,5", "1 234,5", "1'234.5", 1234.5]),
+///     json!([price(), price(), price(), price(), price()])
+/// );
+/// ```
+pub fn localized_number(expected: f64) -> Value {
+    json!({ MATCHER_KEY: "localized_number", "value": expected })
+}
+
+/// Matches durations equal to `expected`, in any of the formats of [`DurationFormat`].
+///
+/// Numbers are taken as seconds, and strings can be humanized or ISO 8601 durations, so `3600`,
+/// `"1h"`, `"60m"` and `"PT1H"` all match an hour. Use [`duration_in`] to limit the accepted
+/// formats.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers};
+/// use serde_json::json;
+/// use std::time::Duration;
+///
+/// let hour = || matchers::duration(Duration::from_secs(3600));
+/// assert_json_eq!(
+///     json!({ "ttl": "1h", "timeout": "PT60M", "interval": 3600 }),
+///     json!({ "ttl": hour(), "timeout": hour(), "interval": hour() })
+/// );
+/// ```
+pub fn duration(expected: Duration) -> Value {
+    duration_in(
+        expected,
+        [
+            DurationFormat::Seconds,
+            DurationFormat::Humanized,
+            DurationFormat::Iso8601,
+        ],
+    )
+}
+
+/// Matches durations equal to `expected` in one of the given formats.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, matchers, matchers::DurationFormat};
+/// use serde_json::json;
+/// use std::time::Duration;
+///
+/// assert_json_eq!(
+///     json!({ "delay": 1500, "retry": "1.5s" }),
+///     json!({
+///         "delay": matchers::duration_in(Duration::from_millis(1500), [DurationFormat::Milliseconds]),
+///         "retry": matchers::duration_in(Duration::from_millis(1500), [DurationFormat::Humanized]),
+///     })
+/// );
+/// ```
+pub fn duration_in<I>(expected: Duration, formats: I) -> Value
+where
+    I: IntoIterator<Item = DurationFormat>,
+{
+    let formats = formats
+        .into_iter()
+        .map(DurationFormat::name)
+        .collect::<Vec<_>>();
+    json!({
+        MATCHER_KEY: "duration",
+        "secs": expected.as_secs(),
+        "nanos": expected.subsec_nanos(),
+        "formats": formats,
+    })
+}
+
+/// A representation of durations accepted by [`duration_in`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DurationFormat {
+    /// A number of seconds, such as `3600` or `0.25`.
+    Seconds,
+    /// A number of milliseconds, such as `250`.
+    Milliseconds,
+    /// A string of numbers with units, such as `"1h"`, `"90s"` or `"1h 30min"`. The units are
+    /// `ns`, `us`, `ms`, `s`, `m`, `h`, `d` and `w`, and their usual long forms such as `"secs"`
+    /// or `"minutes"`.
+    Humanized,
+    /// An ISO 8601 duration string, such as `"PT1H30M"` or `"P1DT12H"`. Years and months aren't
+    /// supported, since their length varies.
+    Iso8601,
+}
+
+impl DurationFormat {
+    const ALL: [DurationFormat; 4] = [
+        DurationFormat::Seconds,
+        DurationFormat::Milliseconds,
+        DurationFormat::Humanized,
+        DurationFormat::Iso8601,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            DurationFormat::Seconds => "seconds",
+            DurationFormat::Milliseconds => "milliseconds",
+            DurationFormat::Humanized => "humanized",
+            DurationFormat::Iso8601 => "iso8601",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    /// Returns the duration that `value` represents in this format.
+    fn parse(self, value: &Value) -> Option<Duration> {
+        match (self, value) {
+            (DurationFormat::Seconds, Value::Number(number)) => {
+                duration::parse_number(number, 1_000_000_000)
+            }
+            (DurationFormat::Milliseconds, Value::Number(number)) => {
+                duration::parse_number(number, 1_000_000)
+            }
+            (DurationFormat::Humanized, Value::String(text)) => duration::parse_humanized(text),
+            (DurationFormat::Iso8601, Value::String(text)) => duration::parse_iso8601(text),
+            _ => None,
+        }
+    }
+}
+
+/// Matches values equal to the value at `path` in the expected document.
+///
+/// This relates a value of the actual document to another part of the expected one, such as an id
+/// that the response must echo from the request it answers. The reference is resolved before the
+/// comparison, so the referenced value is compared using the settings of the comparison, and shown
+/// as expected value in failure messages. A reference to a missing value, or to another
+/// reference, never matches.
+///
+/// ```
+/// use serde_json_assert::{assert_json_include, matchers};
+/// use serde_json::json;
+///
+/// assert_json_include!(
+///     actual: json!({ "request_id": "r-17", "echo": { "request_id": "r-17" } }),
+///     expected: json!({ "request_id": "r-17", "echo": { "request_id": matchers::same_as(".request_id") } })
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `path` can't be parsed.
+#[track_caller]
+pub fn same_as(path: &str) -> Value {
+    reference(path, "expected")
+}
+
+/// Matches values equal to the value at `path` in the context document of the comparison.
+///
+/// Like [`same_as`], but the value is looked up in the document set with
+/// [`Config::context`](crate::Config::context), such as the request that was sent.
+///
+/// ```
+/// use serde_json_assert::{assert_json_matches, matchers, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let request = json!({ "id": "r-17", "items": [1, 2] });
+/// let config = Config::new(CompareMode::Strict).context(&request);
+///
+/// assert_json_matches!(
+///     json!({ "request_id": "r-17", "count": 2 }),
+///     json!({ "request_id": matchers::context_value(".id"), "count": 2 }),
+///     &config
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `path` can't be parsed.
+#[track_caller]
+pub fn context_value(path: &str) -> Value {
+    reference(path, "context")
+}
+
+#[track_caller]
+fn reference(path: &str, document: &str) -> Value {
+    let path: Path = path.parse().unwrap_or_else(|err| panic!("{}", err));
+    json!({ MATCHER_KEY: "reference", "path": path.to_string(), "document": document })
+}
+
+/// The type of a JSON value, as checked by [`of_type`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JsonType {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool,
+    /// Any number.
+    Number,
+    /// A number without a fractional part or exponent.
+    Integer,
+    /// A number with a fractional part or exponent.
+    Decimal,
+    /// A string.
+    String,
+    /// An array.
+    Array,
+    /// An object.
+    Object,
+}
+
+impl JsonType {
+    const ALL: [JsonType; 8] = [
+        JsonType::Null,
+        JsonType::Bool,
+        JsonType::Number,
+        JsonType::Integer,
+        JsonType::Decimal,
+        JsonType::String,
+        JsonType::Array,
+        JsonType::Object,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            JsonType::Null => "null",
+            JsonType::Bool => "boolean",
+            JsonType::Number => "number",
+            JsonType::Integer => "integer",
+            JsonType::Decimal => "decimal",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Returns true if `value` is of this type.
+    pub fn is_type_of(self, value: &Value) -> bool {
+        match (self, value) {
+            (JsonType::Null, Value::Null)
+            | (JsonType::Bool, Value::Bool(_))
+            | (JsonType::Number, Value::Number(_))
+            | (JsonType::String, Value::String(_))
+            | (JsonType::Array, Value::Array(_))
+            | (JsonType::Object, Value::Object(_)) => true,
+            (JsonType::Integer, Value::Number(number)) => !number.is_f64(),
+            (JsonType::Decimal, Value::Number(number)) => number.is_f64(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Matcher<'a> {
+    Partial(&'a Value),
+    Not(&'a Value),
+    NoneOf(&'a [Value]),
+    Bytes {
+        expected: &'a [Value],
+        hex: bool,
+    },
+    Url {
+        expected: &'a str,
+        ignore_trailing_slash: bool,
+    },
+    Markup(&'a str),
+    Like(&'a Value),
+    ArrayLike {
+        item: &'a Value,
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    OfType(JsonType),
+    Contains(&'a str),
+    Exactly(&'a Value),
+    ArrayContaining(&'a [Value]),
+    Capture {
+        name: &'a str,
+        expected: &'a Value,
+    },
+    Duration {
+        expected: Duration,
+        formats: &'a [Value],
+    },
+    LocalizedNumber(f64),
+    Any,
+    Uuid,
+    #[cfg(feature = "regex")]
+    Regex(&'a str),
+    /// A reference that couldn't be resolved, see [`resolve_references`].
+    Reference {
+        path: &'a str,
+        context: bool,
+    },
+}
+
+impl<'a> Matcher<'a> {
+    /// Recognizes matcher markers in expected values.
+    pub(crate) fn parse(expected: &'a Value) -> Option<Self> {
+        let marker = expected.as_object()?;
+        let arg = |name: &str| marker.get(name);
+
+        match marker.get(MATCHER_KEY)?.as_str()? {
+            "partial" => Some(Matcher::Partial(arg("value")?)),
+            "not" => Some(Matcher::Not(arg("value")?)),
+            "none_of" => Some(Matcher::NoneOf(arg("values")?.as_array()?)),
+            "bytes" => Some(Matcher::Bytes {
+                expected: arg("value")?.as_array()?,
+                hex: arg("hex")?.as_bool()?,
+            }),
+            "url" => Some(Matcher::Url {
+                expected: arg("value")?.as_str()?,
+                ignore_trailing_slash: arg("ignore_trailing_slash")?.as_bool()?,
+            }),
+            "markup" => Some(Matcher::Markup(arg("value")?.as_str()?)),
+            "like" => Some(Matcher::Like(arg("value")?)),
+            "array_like" => {
+                let bound = |name: &str| match arg(name)? {
+                    Value::Null => Some(None),
+                    bound => Some(Some(usize::try_from(bound.as_u64()?).ok()?)),
+                };
+                Some(Matcher::ArrayLike {
+                    item: arg("value")?,
+                    min: bound("min")?,
+                    max: bound("max")?,
+                })
+            }
+            "of_type" => Some(Matcher::OfType(JsonType::from_name(
+                arg("type")?.as_str()?,
+            )?)),
+            "contains" => Some(Matcher::Contains(arg("value")?.as_str()?)),
+            "exactly" => Some(Matcher::Exactly(arg("value")?)),
+            "array_containing" => Some(Matcher::ArrayContaining(arg("values")?.as_array()?)),
+            "capture" => Some(Matcher::Capture {
+                name: arg("name")?.as_str()?,
+                expected: arg("value")?,
+            }),
+            "duration" => Some(Matcher::Duration {
+                expected: Duration::new(
+                    arg("secs")?.as_u64()?,
+                    u32::try_from(arg("nanos")?.as_u64()?).ok()?,
+                ),
+                formats: arg("formats")?.as_array()?,
+            }),
+            "localized_number" => Some(Matcher::LocalizedNumber(arg("value")?.as_f64()?)),
+            "any" => Some(Matcher::Any),
+            "uuid" => Some(Matcher::Uuid),
+            #[cfg(feature = "regex")]
+            "regex" => Some(Matcher::Regex(arg("pattern")?.as_str()?)),
+            "reference" => Some(Matcher::Reference {
+                path: arg("path")?.as_str()?,
+                context: match arg("document")?.as_str()? {
+                    "expected" => false,
+                    "context" => true,
+                    _ => return None,
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    /// Recognizes matcher markers in expected values, and the sentinel strings enabled by
+    /// [`Config::sentinels`].
+    pub(crate) fn find(expected: &'a Value, config: &Config) -> Option<Self> {
+        match expected {
+            Value::String(text) if config.sentinels => Matcher::sentinel(text),
+            _ => Matcher::parse(expected),
+        }
+    }
+
+    /// Recognizes the sentinel strings listed in [`Config::sentinels`].
+    fn sentinel(text: &'a str) -> Option<Self> {
+        #[cfg(feature = "regex")]
+        if let Some(pattern) = text.strip_prefix("$regex:") {
+            return Some(Matcher::Regex(pattern));
+        }
+        let matcher = match text {
+            "$any" => Matcher::Any,
+            "$uuid" => Matcher::Uuid,
+            "$null" => Matcher::OfType(JsonType::Null),
+            "$boolean" => Matcher::OfType(JsonType::Bool),
+            "$number" => Matcher::OfType(JsonType::Number),
+            "$integer" => Matcher::OfType(JsonType::Integer),
+            "$string" => Matcher::OfType(JsonType::String),
+            "$array" => Matcher::OfType(JsonType::Array),
+            "$object" => Matcher::OfType(JsonType::Object),
+            _ => return None,
+        };
+        Some(matcher)
+    }
+
+    /// Checks `actual` against matchers that only look at the value at their own position.
+    ///
+    /// Matchers that compare nested values are handled while diffing.
+    pub(crate) fn test(&self, actual: &Value) -> bool {
+        match *self {
+            Matcher::Partial(_)
+            | Matcher::Not(_)
+            | Matcher::NoneOf(_)
+            | Matcher::Like(_)
+            | Matcher::ArrayLike { .. }
+            | Matcher::Exactly(_)
+            | Matcher::ArrayContaining(_)
+            | Matcher::Capture { .. } => {
+                unreachable!("structural matchers are handled while diffing")
+            }
+            Matcher::Bytes { expected, hex } => {
+                let expected = byte_array(expected);
+                let actual = match actual {
+                    Value::Array(items) => byte_array(items),
+                    Value::String(text) => {
+                        let base64 = decode_base64(text);
+                        if hex && base64 != expected {
+                            decode_hex(text)
+                        } else {
+                            base64
+                        }
+                    }
+                    _ => None,
+                };
+                expected.is_some() && actual == expected
+            }
+            Matcher::Url {
+                expected,
+                ignore_trailing_slash,
+            } => match actual.as_str() {
+                Some(actual) if actual == expected => true,
+                Some(actual) => {
+                    let expected = url::normalize(expected, ignore_trailing_slash);
+                    expected.is_some() && url::normalize(actual, ignore_trailing_slash) == expected
+                }
+                None => false,
+            },
+            Matcher::Markup(expected) => actual
+                .as_str()
+                .is_some_and(|actual| normalize_markup(actual) == normalize_markup(expected)),
+            Matcher::OfType(expected) => expected.is_type_of(actual),
+            Matcher::Duration { expected, formats } => formats
+                .iter()
+                .filter_map(|format| DurationFormat::from_name(format.as_str()?))
+                .any(|format| format.parse(actual) == Some(expected)),
+            Matcher::LocalizedNumber(expected) => match actual {
+                Value::Number(number) => number.as_f64() == Some(expected),
+                Value::String(text) => localized_numbers(text).contains(&expected),
+                _ => false,
+            },
+            Matcher::Any => true,
+            Matcher::Uuid => actual.as_str().is_some_and(is_uuid),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(pattern) => match (actual.as_str(), regex::Regex::new(pattern)) {
+                (Some(actual), Ok(regex)) => regex.is_match(actual),
+                _ => false,
+            },
+            Matcher::Reference { .. } => false,
+            Matcher::Contains(substring) => actual
+                .as_str()
+                .is_some_and(|actual| actual.contains(substring)),
+        }
+    }
+}
+
+/// Replaces the reference markers in `expected` by the values they refer to, in `expected` itself
+/// or in `context`.
+///
+/// References that can't be resolved are left in place, and never match.
+pub(crate) fn resolve_references(expected: &mut Value, context: Option<&Value>) {
+    if !contains_references(expected) {
+        return;
+    }
+    let original = expected.clone();
+    resolve_in(expected, &original, context);
+}
+
+fn resolve_in(value: &mut Value, expected: &Value, context: Option<&Value>) {
+    if let Some(Matcher::Reference {
+        path,
+        context: in_context,
+    }) = Matcher::parse(value)
+    {
+        let document = if in_context { context } else { Some(expected) };
+        let target = path
+            .parse::<Path>()
+            .ok()
+            .zip(document)
+            .and_then(|(path, document)| get(document, path.keys()));
+        if let Some(target) = target.filter(|target| !contains_references(target)) {
+            *value = target.clone();
+        }
+        return;
+    }
+
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| resolve_in(item, expected, context)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| resolve_in(field, expected, context)),
+        _ => {}
+    }
+}
+
+pub(crate) fn contains_references(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.iter().any(contains_references),
+        Value::Object(fields) => {
+            fields
+                .get(MATCHER_KEY)
+                .is_some_and(|name| name == "reference")
+                || fields.values().any(contains_references)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if `value` is, or contains, a matcher marker.
+pub(crate) fn contains_markers(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.iter().any(contains_markers),
+        Value::Object(fields) => {
+            fields.contains_key(MATCHER_KEY) || fields.values().any(contains_markers)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if `text` is a UUID in its canonical hyphenated form.
+fn is_uuid(text: &str) -> bool {
+    let groups = text.split('-').collect::<Vec<_>>();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Removes whitespace next to tags and collapses other whitespace into single spaces.
+fn normalize_markup(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut words = text.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        normalized.push_str(word);
+        if let Some(next) = words.peek() {
+            if !word.ends_with('>') && !next.starts_with('<') {
+                normalized.push(' ');
+            }
+        }
+    }
+    normalized
+}
+
+/// Returns the numbers that `text` can be read as, with `.` or `,` as decimal separator.
+fn localized_numbers(text: &str) -> Vec<f64> {
+    let text = text.trim();
+    let (sign, body) = match text.strip_prefix(['-', '\u{2212}']) {
+        Some(body) => ("-", body),
+        None => ("", text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let mut numbers = vec![];
+    for decimal in [None, Some('.'), Some(',')] {
+        let (whole, fraction) = match decimal {
+            None => (body, ""),
+            Some(decimal) => match body.rsplit_once(decimal) {
+                Some(parts) => parts,
+                None => continue,
+            },
+        };
+        if decimal.is_some()
+            && (fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()))
+        {
+            continue;
+        }
+        let Some(whole) = ungrouped(whole, decimal) else {
+            continue;
+        };
+        if let Ok(number) = format!("{}{}.{}", sign, whole, fraction).parse::<f64>() {
+            if !numbers.contains(&number) {
+                numbers.push(number);
+            }
+        }
+    }
+    numbers
+}
+
+/// Returns the digits of `whole` without thousands separators, if they are grouped correctly and
+/// differ from the `decimal` separator.
+fn ungrouped(whole: &str, decimal: Option<char>) -> Option<String> {
+    let mut separators = whole.chars().filter(|c| !c.is_ascii_digit());
+    let Some(separator) = separators.next() else {
+        return (!whole.is_empty()).then(|| whole.to_string());
+    };
+    let valid = matches!(separator, '.' | ',' | ' ' | '\'' | '\u{a0}' | '\u{202f}')
+        && Some(separator) != decimal
+        && separators.all(|c| c == separator);
+    if !valid {
+        return None;
+    }
+
+    let mut groups = whole.split(separator);
+    let first = groups.next()?;
+    let grouped = (1..=3).contains(&first.len()) && groups.all(|group| group.len() == 3);
+    grouped.then(|| whole.replace(separator, ""))
+}
+
+fn byte_array(items: &[Value]) -> Option<Vec<u8>> {
+    items
+        .iter()
+        .map(|item| item.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+        .collect()
+}
+
+/// Decodes standard or URL-safe base64, with or without padding.
+pub(crate) fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let sextet = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 == 1 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+impl fmt::Display for Matcher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Matcher::Partial(expected) => {
+                write!(f, "{}", serde_json::to_string_pretty(expected).unwrap())
+            }
+            Matcher::Not(unexpected) => write!(f, "anything except {}", unexpected),
+            Matcher::NoneOf(unexpected) => {
+                write!(f, "none of {}", Value::from(unexpected.to_vec()))
+            }
+            Matcher::Bytes { expected, hex } => {
+                let encodings = if *hex {
+                    "base64, hex or a byte array"
+                } else {
+                    "base64 or a byte array"
+                };
+                write!(
+                    f,
+                    "bytes {} encoded as {}",
+                    Value::from(expected.to_vec()),
+                    encodings
+                )
+            }
+            Matcher::Url { expected, .. } => {
+                write!(f, "url equivalent to {}", Value::from(*expected))
+            }
+            Matcher::Markup(expected) => write!(f, "markup like {}", Value::from(*expected)),
+            Matcher::Like(example) => write!(f, "anything like {}", example),
+            Matcher::ArrayLike { item, min, max } => {
+                write!(f, "array of ")?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, "{} to {} ", min, max)?,
+                    (Some(min), None) => write!(f, "at least {} ", min)?,
+                    (None, Some(max)) => write!(f, "at most {} ", max)?,
+                    (None, None) => {}
+                }
+                write!(f, "items like {}", item)
+            }
+            Matcher::OfType(expected) => write!(f, "any {}", expected.name()),
+            Matcher::Contains(substring) => {
+                write!(f, "string containing {}", Value::from(*substring))
+            }
+            Matcher::Exactly(expected) => {
+                write!(f, "{}", serde_json::to_string_pretty(expected).unwrap())
+            }
+            Matcher::ArrayContaining(expected) => {
+                write!(f, "array containing {}", Value::from(expected.to_vec()))
+            }
+            Matcher::Duration { expected, formats } => {
+                let formats = formats
+                    .iter()
+                    .filter_map(|format| Some(DurationFormat::from_name(format.as_str()?)?.name()))
+                    .collect::<Vec<_>>();
+                write!(f, "duration of {:?} as {}", expected, formats.join(" or "))
+            }
+            Matcher::LocalizedNumber(expected) => {
+                write!(f, "number {} in any locale", Value::from(*expected))
+            }
+            Matcher::Any => write!(f, "anything"),
+            Matcher::Uuid => write!(f, "any uuid"),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(pattern) => match regex::Regex::new(pattern) {
+                Ok(_) => write!(f, "string matching regex `{}`", pattern),
+                Err(err) => write!(f, "string matching invalid regex `{}`: {}", pattern, err),
+            },
+            Matcher::Reference { path, context } => {
+                let document = if *context { "context" } else { "expected" };
+                write!(f, "value at path {:?} of the {} document", path, document)
+            }
+            Matcher::Capture { expected, .. } => match Matcher::parse(expected) {
+                Some(matcher) => write!(f, "{}", matcher),
+                None => write!(f, "{}", serde_json::to_string_pretty(expected).unwrap()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{compare, diff};
+    use crate::{ArrayMode, CompareMode, Config};
+
+    #[test]
+    fn parse_markers() {
+        let value = json!({ "a": 1 });
+        assert_eq!(
+            Matcher::parse(&partial(value.clone())),
+            Some(Matcher::Partial(&value))
+        );
+
+        assert_eq!(Matcher::parse(&value), None);
+        assert_eq!(Matcher::parse(&json!({ "$matcher": "unknown" })), None);
+        assert_eq!(Matcher::parse(&json!({ "$matcher": "partial" })), None);
+    }
+
+    #[test]
+    fn partial_objects_and_arrays() {
+        let config = Config::new(CompareMode::Strict);
+        let actual = json!({ "a": { "b": 1, "c": 2 }, "d": [1, 2, 3] });
+
+        let expected = json!({ "a": partial(json!({ "b": 1 })), "d": partial(json!([1, 2])) });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        // Only the marked node is open.
+        let expected = partial(json!({ "a": { "b": 1 } }));
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path().to_string(), ".a.c");
+
+        let expected = json!({ "a": { "b": 1, "c": 2 }, "d": partial(json!([1, 3])) });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+
+        let expected = json!({ "a": partial(json!({ "b": 1, "e": 3 })), "d": [1, 2, 3] });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+
+        // Unordered arrays only need to contain the expected items.
+        let config = config.array_mode(ArrayMode::UnorderedSubset);
+        let expected = json!({ "a": { "b": 1, "c": 2 }, "d": partial(json!([3, 1])) });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+    }
+
+    #[test]
+    fn negation() {
+        let config = Config::new(CompareMode::Strict);
+        let actual = json!({ "status": "done", "score": 1.0 });
+
+        let expected = json!({ "status": not(json!("failed")), "score": not(json!(2.0)) });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        let expected = json!({ "status": not(json!("done")), "score": 1.0 });
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path ".status" are not equal:
+    lhs:
+        "done"
+    rhs:
+        anything except "done""#
+        );
+
+        let expected = json!({ "status": none_of([json!("failed"), json!("done")]), "score": 1.0 });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+        let expected = json!({ "status": none_of([json!("failed")]), "score": 1.0 });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        // Negated values are compared using the settings of the comparison.
+        let config = config.float_compare_mode(crate::FloatCompareMode::Epsilon(0.01));
+        let expected = json!({ "status": "done", "score": not(json!(1.001)) });
+        assert_eq!(diff(&actual, &expected, &config).len(), 1);
+    }
+
+    #[test]
+    fn binary_data() {
+        let hi = Matcher::parse(&bytes("hi?")).map(|matcher| matcher.to_string());
+        assert_eq!(
+            hi.as_deref(),
+            Some("bytes [104,105,63] encoded as base64 or a byte array")
+        );
+
+        let expected = bytes([0xfb, 0xff, 0x01]);
+        let matcher = Matcher::parse(&expected).unwrap();
+        for actual in [json!("+/8B"), json!("-_8B"), json!([251, 255, 1])] {
+            assert!(matcher.test(&actual), "{}", actual);
+        }
+        for actual in [
+            json!("+/8C"),
+            json!("fbff01"),
+            json!([251, 255, 256]),
+            json!(1),
+        ] {
+            assert!(!matcher.test(&actual), "{}", actual);
+        }
+
+        let expected = bytes_or_hex([0xfb, 0xff, 0x01]);
+        let matcher = Matcher::parse(&expected).unwrap();
+        for actual in [json!("+/8B"), json!("fbff01"), json!("FBFF01")] {
+            assert!(matcher.test(&actual), "{}", actual);
+        }
+        assert!(!matcher.test(&json!("fbff0")));
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert_eq!(decode_base64("aA==").unwrap(), b"h");
+        assert_eq!(decode_base64("aGk").unwrap(), b"hi");
+        assert_eq!(decode_base64("aGk=").unwrap(), b"hi");
+        assert_eq!(decode_base64("aGkh").unwrap(), b"hi!");
+        assert_eq!(decode_base64("a"), None);
+        assert_eq!(decode_base64("a!=="), None);
+    }
+
+    #[test]
+    fn urls() {
+        let config = Config::new(CompareMode::Strict);
+        let actual = json!({ "self": "http://API.example.com:80/users/?page=2&sort=name" });
+
+        let expected = json!({ "self": url("http://api.example.com/users/?sort=name&page=2") });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        let expected = json!({ "self": url("http://api.example.com/users?sort=name&page=2") });
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path ".self" are not equal:
+    lhs:
+        "http://API.example.com:80/users/?page=2&sort=name"
+    rhs:
+        url equivalent to "http://api.example.com/users?sort=name&page=2""#
+        );
+
+        let expected = json!({
+            "self": url_ignoring_trailing_slash("http://api.example.com/users?sort=name&page=2")
+        });
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        let matcher = url("not a url");
+        let matcher = Matcher::parse(&matcher).unwrap();
+        assert!(matcher.test(&json!("not a url")));
+        assert!(!matcher.test(&json!("Not a url")));
+        assert!(!matcher.test(&json!(1)));
+    }
+
+    #[test]
+    fn markup_whitespace() {
+        assert_eq!(
+            normalize_markup("\n<p class=\"a\">\n\tHello,\n  <b>world</b> !</p>\n<br />\n"),
+            "<p class=\"a\">Hello,<b>world</b>!</p><br />"
+        );
+
+        let expected = markup("<div><p>Hello, world</p></div>");
+        let matcher = Matcher::parse(&expected).unwrap();
+        assert!(matcher.test(&json!("<div>\r\n  <p>Hello,  world</p>\r\n</div>")));
+        assert!(!matcher.test(&json!("<div><p>Hello,world</p></div>")));
+        assert!(!matcher.test(&json!("<div><p>Hello, world!</p></div>")));
+        assert!(!matcher.test(&json!(["<div><p>Hello, world</p></div>"])));
+    }
+
+    #[test]
+    fn likeness() {
+        let config = Config::new(CompareMode::Strict);
+        let actual = json!({
+            "id": 7,
+            "user": { "name": "Eve", "roles": ["admin", "dev"], "active": true },
+            "kind": "account",
+        });
+
+        let expected = like(json!({
+            "id": 1,
+            "user": { "name": "Bob", "roles": ["user"] },
+            "kind": exactly(json!("account")),
+        }));
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        for expected in [
+            json!({ "id": "1" }),
+            json!({ "user": { "roles": [1] } }),
+            json!({ "user": { "email": "bob@example.com" } }),
+            json!({ "kind": exactly(json!("user")) }),
+        ] {
+            assert_eq!(diff(&actual, &like(expected), &config).len(), 1);
+        }
+
+        let expected = json!({ "roles": array_like(json!(""), Some(1), Some(2)) });
+        assert_eq!(diff(&actual["user"], &expected, &config).len(), 2);
+        let expected = json!({
+            "name": "Eve",
+            "roles": array_like(json!(""), Some(1), Some(2)),
+            "active": true,
+        });
+        assert_eq!(diff(&actual["user"], &expected, &config), vec![]);
+        let expected = json!({
+            "name": "Eve",
+            "roles": array_like(json!(""), Some(3), None),
+            "active": true,
+        });
+        let diffs = diff(&actual["user"], &expected, &config);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path ".roles" are not equal:
+    lhs:
+        [
+          "admin",
+          "dev"
+        ]
+    rhs:
+        array of at least 3 items like """#
+        );
+    }
+
+    #[test]
+    fn types() {
+        let integer = of_type(JsonType::Integer);
+        let integer = Matcher::parse(&integer).unwrap();
+        assert!(integer.test(&json!(-1)));
+        assert!(!integer.test(&json!(1.0)));
+        assert_eq!(integer.to_string(), "any integer");
+
+        for kind in JsonType::ALL {
+            assert_eq!(JsonType::from_name(kind.name()), Some(kind));
+        }
+        assert!(JsonType::Decimal.is_type_of(&json!(1.5)));
+        assert!(JsonType::Number.is_type_of(&json!(1)));
+        assert!(!JsonType::Object.is_type_of(&json!([])));
+
+        let contains = contains("ell");
+        let contains = Matcher::parse(&contains).unwrap();
+        assert!(contains.test(&json!("hello")));
+        assert!(!contains.test(&json!("help")));
+    }
+
+    #[test]
+    fn jest_style_builders() {
+        let config = Config::new(CompareMode::Strict);
+        let actual = json!({ "id": 1, "name": "Bob", "scores": [3, 1, 2] });
+
+        let expected = object_containing(json!({
+            "id": any_number(),
+            "scores": array_containing([json!(2), json!(3), json!(2)]),
+        }));
+        assert_eq!(diff(&actual, &expected, &config), vec![]);
+
+        let expected = json!({
+            "id": any_string(),
+            "name": "Bob",
+            "scores": array_containing([json!(4)]),
+        });
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(
+            diffs[1].to_string(),
+            r#"json atoms at path ".scores" are not equal:
+    lhs:
+        [
+          3,
+          1,
+          2
+        ]
+    rhs:
+        array containing [4]"#
+        );
+
+        let expected = array_containing([json!(1)]);
+        assert_eq!(diff(&json!({ "a": 1 }), &expected, &config).len(), 1);
+    }
+
+    #[test]
+    fn captures() {
+        let config = Config::new(CompareMode::Inclusive);
+        let actual = json!({ "id": 7, "items": [{ "id": "a" }, { "id": "b" }], "name": "Bob" });
+        let expected = json!({
+            "id": capture("id", any_number()),
+            "items": [{ "id": capture("item", any_string()) }, { "id": capture("item", json!("b")) }],
+            "name": capture("name", any_number()),
+        });
+
+        let comparison = compare(&actual, &expected, &config);
+        assert_eq!(comparison.differences.len(), 1);
+        assert_eq!(
+            comparison.differences[0].to_string(),
+            r#"json atoms at path ".name" are not equal:
+    expected:
+        any number
+    actual:
+        "Bob""#
+        );
+        assert_eq!(
+            comparison.captures,
+            vec![
+                ("id".to_string(), json!(7)),
+                ("item".to_string(), json!("a")),
+                ("item".to_string(), json!("b")),
+            ]
+        );
+
+        // Values aren't captured by matchers that only probe them.
+        let expected = not(capture("id", json!(8)));
+        assert_eq!(compare(&json!(7), &expected, &config).captures, vec![]);
+    }
+
+    #[test]
+    fn references() {
+        let config = Config::new(CompareMode::Strict).context(&json!({ "id": 3 }));
+        let mut expected = json!({
+            "a": same_as(".b[1]"),
+            "b": [1, 2],
+            "c": context_value("id"),
+            "d": same_as(".a"),
+            "e": same_as(".missing"),
+        });
+        resolve_references(&mut expected, config.context.as_ref());
+        assert_eq!(expected["a"], json!(2));
+        assert_eq!(expected["c"], json!(3));
+        assert_eq!(expected["d"], same_as(".a"));
+
+        let actual = json!({ "a": 2, "b": [1, 2], "c": 3, "d": 2, "e": null });
+        let error = crate::assert_json_matches_no_panic(
+            &actual,
+            &json!({ "a": 2, "b": [1, 2], "c": 3, "d": 2, "e": same_as(".missing") }),
+            &config,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            r#"json atoms at path ".e" are not equal:
+    lhs:
+        null
+    rhs:
+        value at path ".missing" of the expected document"#
+        );
+    }
+
+    #[test]
+    fn durations() {
+        let config = Config::new(CompareMode::Strict);
+        let minute = duration(Duration::from_secs(60));
+        for actual in [
+            json!(60),
+            json!(60.0),
+            json!(6e1),
+            json!("1m"),
+            json!("PT1M"),
+        ] {
+            assert_eq!(diff(&actual, &minute, &config), vec![], "{}", actual);
+        }
+        for actual in [json!(-60), json!("61s"), json!("P1M"), json!(null)] {
+            assert_eq!(diff(&actual, &minute, &config).len(), 1, "{}", actual);
+        }
+
+        let expected = duration_in(Duration::from_millis(2500), [DurationFormat::Milliseconds]);
+        assert_eq!(diff(&json!(2500), &expected, &config), vec![]);
+        let diffs = diff(&json!("2.5s"), &expected, &config);
+        assert_eq!(
+            diffs[0].to_string(),
+            r#"json atoms at path "(root)" are not equal:
+    lhs:
+        "2.5s"
+    rhs:
+        duration of 2.5s as milliseconds"#
+        );
+    }
+
+    #[test]
+    fn localized_number_strings() {
+        let config = Config::new(CompareMode::Strict);
+        let expected = localized_number(1234.5);
+        for actual in [
+            json!("1234.5"),
+            json!("1234,50"),
+            json!("1,234.5"),
+            json!("1.234,5"),
+            json!(" 1 234,5 "),
+            json!("1\u{a0}234,5"),
+            json!("+1'234.5"),
+            json!(1234.5),
+        ] {
+            assert_eq!(diff(&actual, &expected, &config), vec![], "{}", actual);
+        }
+        for actual in [
+            json!("1.2345"),
+            json!("12,34.5"),
+            json!("1,234,5"),
+            json!("1.234.5"),
+            json!("1234.5.0"),
+            json!("1234."),
+            json!("-1234.5"),
+            json!("1234.5 EUR"),
+            json!(null),
+        ] {
+            assert_eq!(diff(&actual, &expected, &config).len(), 1, "{}", actual);
+        }
+
+        assert_eq!(localized_numbers("1,234"), [1234.0, 1.234]);
+        assert_eq!(localized_numbers("\u{2212}0,5"), [-0.5]);
+        assert_eq!(localized_numbers("1.234.567"), [1234567.0]);
+        assert_eq!(
+            Matcher::parse(&expected).unwrap().to_string(),
+            "number 1234.5 in any locale"
+        );
+    }
+
+    #[test]
+    fn any_and_uuid() {
+        let config = Config::new(CompareMode::Strict);
+        for actual in [json!(null), json!([1]), json!({ "a": 1 })] {
+            assert_eq!(diff(&actual, &any(), &config), vec![]);
+        }
+
+        let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        assert_eq!(diff(&json!(id), &uuid(), &config), vec![]);
+        for actual in [
+            json!(&id[1..]),
+            json!(id.replace('-', "")),
+            json!(id.replacen('e', "g", 1)),
+            json!(1),
+        ] {
+            assert_eq!(diff(&actual, &uuid(), &config).len(), 1, "{}", actual);
+        }
+        assert_eq!(Matcher::parse(&uuid()).unwrap().to_string(), "any uuid");
+    }
+
+    #[test]
+    fn sentinels() {
+        let actual = json!({ "id": "67e55044-10b1-426f-9247-bb680e5fe0c8", "n": 1.5, "tags": [] });
+        let expected = json!({ "id": "$uuid", "n": "$integer", "tags": "$any" });
+
+        let config = Config::new(CompareMode::Strict);
+        assert_eq!(diff(&actual, &expected, &config).len(), 3);
+
+        let config = config.sentinels(true);
+        let diffs = diff(&actual, &expected, &config);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].to_string(),
+            "json atoms at path \".n\" are not equal:\n    lhs:\n        1.5\n    rhs:\n        any integer"
+        );
+
+        // Unknown sentinels and markers inside strings are compared as usual.
+        assert_eq!(diff(&json!("$uuids"), &json!("$uuids"), &config), vec![]);
+        assert_eq!(diff(&json!("x"), &json!("$anything"), &config).len(), 1);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_strings() {
+        let config = Config::new(CompareMode::Strict).sentinels(true);
+        let pattern = "^ord_[0-9a-f]{4}$";
+        for expected in [regex(pattern), json!(format!("$regex:{}", pattern))] {
+            assert_eq!(diff(&json!("ord_3f2a"), &expected, &config), vec![]);
+            let diffs = diff(
+                &json!({ "id": "ord_3F2A" }),
+                &json!({ "id": expected }),
+                &config,
+            );
+            assert_eq!(
+                diffs[0].to_string(),
+                r#"json atoms at path ".id" are not equal:
+    lhs:
+        "ord_3F2A"
+    rhs:
+        string matching regex `^ord_[0-9a-f]{4}$`"#
+            );
+            assert_eq!(diff(&json!(1), &expected, &config).len(), 1);
+        }
+
+        let invalid = json!("$regex:(");
+        assert_eq!(diff(&json!("("), &invalid, &config).len(), 1);
+        assert!(Matcher::find(&invalid, &config)
+            .unwrap()
+            .to_string()
+            .starts_with("string matching invalid regex `(`"));
+    }
+}
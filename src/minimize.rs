@@ -0,0 +1,355 @@
+use crate::core_ext::Indent;
+use crate::diff::{diff, differs_at, Key, Path};
+use crate::render;
+use crate::{to_values, CompareMode, Config};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The smallest pair of subtrees that still exhibits every difference found by a comparison.
+///
+/// Created by [`minimal_failing_subtree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailingSubtree {
+    path: Path,
+    lhs: Value,
+    rhs: Value,
+    config: Config,
+}
+
+impl FailingSubtree {
+    /// Returns the path to the root of the extracted subtrees.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the pruned left-hand side, or "actual", subtree.
+    pub fn actual(&self) -> &Value {
+        &self.lhs
+    }
+
+    /// Get the pruned right-hand side, or "expected", subtree.
+    pub fn expected(&self) -> &Value {
+        &self.rhs
+    }
+}
+
+impl fmt::Display for FailingSubtree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json_to_string = |json: &Value| render::pretty(json, &self.path, &self.config);
+
+        writeln!(f, "minimal failing subtree at path \"{}\":", self.path)?;
+        match self.config.compare_mode {
+            CompareMode::Inclusive => {
+                writeln!(f, "    expected:")?;
+                writeln!(f, "{}", json_to_string(&self.rhs).indent(8))?;
+                writeln!(f, "    actual:")?;
+                write!(f, "{}", json_to_string(&self.lhs).indent(8))
+            }
+            CompareMode::Strict => {
+                writeln!(f, "    lhs:")?;
+                writeln!(f, "{}", json_to_string(&self.lhs).indent(8))?;
+                writeln!(f, "    rhs:")?;
+                write!(f, "{}", json_to_string(&self.rhs).indent(8))
+            }
+        }
+    }
+}
+
+/// Extracts the smallest pair of subtrees that still fails the comparison.
+///
+/// The extracted subtrees are rooted at the deepest path shared by all differences, and every
+/// sibling that compares equal is pruned away. They are built from the values each difference
+/// paired up, so items of arrays compared by key or regardless of order stay with their partner.
+/// Array items that are kept are moved to the front of their array, so indexes in the extracted
+/// subtrees may differ from the original documents. If the extracted subtrees would match, which
+/// can happen when pruning changes how array items are paired, the whole documents are returned.
+///
+/// Returns `None` if the two values match.
+///
+/// ```
+/// use serde_json_assert::{minimal_failing_subtree, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let actual = json!({
+///     "meta": { "page": 1 },
+///     "data": {
+///         "users": [
+///             { "id": 1, "name": "Bob", "admin": false },
+///             { "id": 2, "name": "Alice", "admin": true },
+///         ],
+///     },
+/// });
+/// let expected = json!({
+///     "meta": { "page": 1 },
+///     "data": {
+///         "users": [
+///             { "id": 1, "name": "Bob", "admin": true },
+///             { "id": 2, "name": "Eve", "admin": true },
+///         ],
+///     },
+/// });
+///
+/// let subtree =
+///     minimal_failing_subtree(&actual, &expected, &Config::new(CompareMode::Strict)).unwrap();
+///
+/// assert_eq!(subtree.path().to_string(), ".data.users");
+/// assert_eq!(subtree.actual(), &json!([{ "admin": false }, { "name": "Alice" }]));
+/// assert_eq!(subtree.expected(), &json!([{ "admin": true }, { "name": "Eve" }]));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn minimal_failing_subtree<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+) -> Option<FailingSubtree>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config).unwrap_or_else(|limit| panic!("{}", limit));
+
+    let diffs = diff(&lhs, &rhs, config);
+    if diffs.is_empty() {
+        return None;
+    }
+
+    let mut root = common_prefix(diffs.iter().map(|d| d.path().keys()));
+    // A value missing from one side is kept within its parent, so both subtrees exist.
+    while !root.is_empty()
+        && diffs.iter().any(|d| {
+            d.path().keys().len() == root.len() && (d.actual().is_none() || d.expected().is_none())
+        })
+    {
+        root.pop();
+    }
+
+    let mut tree = Node::Leaf(None, None);
+    for d in &diffs {
+        tree.insert(
+            &d.path().keys()[root.len()..],
+            d.actual().as_ref(),
+            d.expected().as_ref(),
+        );
+    }
+    let (pruned_lhs, pruned_rhs) = match tree.build() {
+        (Some(lhs), Some(rhs)) if differs_at(&lhs, &rhs, config, &root) => (lhs, rhs),
+        _ => {
+            root.clear();
+            (lhs, rhs)
+        }
+    };
+
+    Some(FailingSubtree {
+        path: Path::from_keys(root),
+        lhs: pruned_lhs,
+        rhs: pruned_rhs,
+        config: config.clone(),
+    })
+}
+
+fn common_prefix<'a>(mut paths: impl Iterator<Item = &'a [Key]>) -> Vec<Key> {
+    let mut prefix = paths.next().map(<[Key]>::to_vec).unwrap_or_default();
+    for path in paths {
+        let len = prefix.iter().zip(path).take_while(|(a, b)| a == b).count();
+        prefix.truncate(len);
+    }
+    prefix
+}
+
+pub(crate) fn get<'a>(value: &'a Value, keys: &[Key]) -> Option<&'a Value> {
+    keys.iter().try_fold(value, |value, key| match key {
+        Key::Idx(idx) => value.get(idx),
+        Key::Field(field) => value.get(field),
+    })
+}
+
+/// The differing values of a comparison, arranged by the keys of their paths.
+enum Node {
+    Leaf(Option<Value>, Option<Value>),
+    Object(BTreeMap<String, Node>),
+    Array(BTreeMap<(usize, Side), Node>),
+}
+
+/// The sides an array item is found on. Items found on one side only get their own slot, so
+/// they're never mixed up with a paired item reported at the same index.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Side {
+    Both,
+    Lhs,
+    Rhs,
+}
+
+impl Node {
+    fn insert(&mut self, keys: &[Key], lhs: Option<&Value>, rhs: Option<&Value>) {
+        let Some((key, rest)) = keys.split_first() else {
+            *self = Node::Leaf(lhs.cloned(), rhs.cloned());
+            return;
+        };
+        let child = match key {
+            Key::Field(field) => {
+                if !matches!(self, Node::Object(_)) {
+                    *self = Node::Object(BTreeMap::new());
+                }
+                let Node::Object(fields) = self else {
+                    unreachable!()
+                };
+                fields
+                    .entry(field.clone())
+                    .or_insert(Node::Leaf(None, None))
+            }
+            Key::Idx(idx) => {
+                if !matches!(self, Node::Array(_)) {
+                    *self = Node::Array(BTreeMap::new());
+                }
+                let Node::Array(items) = self else {
+                    unreachable!()
+                };
+                let side = match (lhs, rhs) {
+                    (Some(_), None) if rest.is_empty() => Side::Lhs,
+                    (None, Some(_)) if rest.is_empty() => Side::Rhs,
+                    _ => Side::Both,
+                };
+                items.entry((*idx, side)).or_insert(Node::Leaf(None, None))
+            }
+        };
+        child.insert(rest, lhs, rhs);
+    }
+
+    /// Returns the values on each side, which are `None` if they are missing.
+    fn build(self) -> (Option<Value>, Option<Value>) {
+        match self {
+            Node::Leaf(lhs, rhs) => (lhs, rhs),
+            Node::Object(fields) => {
+                let mut lhs = Map::new();
+                let mut rhs = Map::new();
+                for (field, child) in fields {
+                    let (child_lhs, child_rhs) = child.build();
+                    if let Some(child_lhs) = child_lhs {
+                        lhs.insert(field.clone(), child_lhs);
+                    }
+                    if let Some(child_rhs) = child_rhs {
+                        rhs.insert(field, child_rhs);
+                    }
+                }
+                (Some(Value::Object(lhs)), Some(Value::Object(rhs)))
+            }
+            Node::Array(items) => {
+                let mut lhs = vec![];
+                let mut rhs = vec![];
+                for child in items.into_values() {
+                    let (child_lhs, child_rhs) = child.build();
+                    lhs.extend(child_lhs);
+                    rhs.extend(child_rhs);
+                }
+                (Some(Value::Array(lhs)), Some(Value::Array(rhs)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matching_values_have_no_failing_subtree() {
+        let config = Config::new(CompareMode::Strict);
+        let value = json!({ "a": [1, 2, { "b": true }] });
+        assert_eq!(minimal_failing_subtree(&value, &value, &config), None);
+    }
+
+    #[test]
+    fn prunes_equal_siblings() {
+        let config = Config::new(CompareMode::Strict);
+        let lhs = json!({
+            "a": { "x": 1, "y": 2, "z": { "deep": [1, 2, 3] } },
+            "b": "unchanged",
+        });
+        let rhs = json!({
+            "a": { "x": 1, "y": 3, "z": { "deep": [1, 2, 4] } },
+            "b": "unchanged",
+        });
+
+        let subtree = minimal_failing_subtree(&lhs, &rhs, &config).unwrap();
+        assert_eq!(subtree.path().to_string(), ".a");
+        assert_eq!(subtree.actual(), &json!({ "y": 2, "z": { "deep": [3] } }));
+        assert_eq!(subtree.expected(), &json!({ "y": 3, "z": { "deep": [4] } }));
+
+        // the pruned subtrees still fail the comparison
+        assert!(minimal_failing_subtree(subtree.actual(), subtree.expected(), &config).is_some());
+    }
+
+    #[test]
+    fn single_difference_is_extracted_as_atoms() {
+        let config = Config::new(CompareMode::Inclusive);
+        let lhs = json!({ "a": { "b": { "c": 1 } }, "d": 2 });
+        let rhs = json!({ "a": { "b": { "c": 2 } } });
+
+        let subtree = minimal_failing_subtree(&lhs, &rhs, &config).unwrap();
+        assert_eq!(subtree.path().to_string(), ".a.b.c");
+        assert_eq!(subtree.actual(), &json!(1));
+        assert_eq!(subtree.expected(), &json!(2));
+    }
+
+    #[test]
+    fn missing_values_keep_their_parent() {
+        let config = Config::new(CompareMode::Inclusive);
+        let lhs = json!({ "a": { "x": 1 } });
+        let rhs = json!({ "a": { "x": 1, "y": 2 } });
+
+        let subtree = minimal_failing_subtree(&lhs, &rhs, &config).unwrap();
+        assert_eq!(subtree.path().to_string(), ".a");
+        assert_eq!(subtree.actual(), &json!({}));
+        assert_eq!(subtree.expected(), &json!({ "y": 2 }));
+        assert_eq!(
+            subtree.to_string(),
+            r#"minimal failing subtree at path ".a":
+    expected:
+        {
+          "y": 2
+        }
+    actual:
+        {}"#
+        );
+    }
+
+    #[test]
+    fn keeps_items_paired_by_key() {
+        let config = Config::new(CompareMode::Strict).match_array_by_key(".users", "id");
+        let lhs = json!({ "users": [
+            { "id": 3, "name": "Eve" },
+            { "id": 1, "name": "Ann" },
+            { "id": 2, "name": "Bob" },
+        ] });
+        let rhs = json!({ "users": [{ "id": 1, "name": "Ann" }, { "id": 2, "name": "Rob" }] });
+
+        let subtree = minimal_failing_subtree(&lhs, &rhs, &config).unwrap();
+        assert_eq!(subtree.path().to_string(), ".users");
+        assert_eq!(
+            subtree.actual(),
+            &json!([{ "id": 3, "name": "Eve" }, { "name": "Bob" }])
+        );
+        assert_eq!(subtree.expected(), &json!([{ "name": "Rob" }]));
+    }
+
+    #[test]
+    fn keeps_items_paired_regardless_of_order() {
+        let config = Config::new(CompareMode::Strict).array_mode(crate::ArrayMode::UnorderedExact);
+        let lhs = json!({ "tags": ["a", "b", "c"], "id": 1 });
+        let rhs = json!({ "tags": ["c", "d", "a"], "id": 1 });
+
+        let subtree = minimal_failing_subtree(&lhs, &rhs, &config).unwrap();
+        assert_eq!(subtree.path().to_string(), ".tags");
+        assert_eq!(subtree.actual(), &json!(["b"]));
+        assert_eq!(subtree.expected(), &json!(["d"]));
+        assert!(minimal_failing_subtree(subtree.actual(), subtree.expected(), &config).is_some());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::matchers::MATCHER_KEY;
+use crate::normalizers::Normalization;
+use crate::{Config, EnumMode, Key, LineEndingMode};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// Rewrites `value` into the canonical form required by `config` before it is compared.
+pub(crate) fn normalize(mut value: Value, config: &Config) -> Value {
+    if let EnumMode::Normalize { tag, content } = &config.enum_mode {
+        normalize_enums(&mut value, tag, content);
+    }
+    if !config.normalizers.is_empty() {
+        apply_normalizers(&mut value, &mut vec![], &config.normalizers);
+    }
+    value
+}
+
+/// Like [`normalize`], but only clones `value` if it has to be rewritten.
+pub(crate) fn normalized<'a>(value: &'a Value, config: &Config) -> Cow<'a, Value> {
+    match config.enum_mode {
+        EnumMode::Exact if config.normalizers.is_empty() => Cow::Borrowed(value),
+        _ => Cow::Owned(normalize(value.clone(), config)),
+    }
+}
+
+/// Applies the normalizers that match the path of each value, children first.
+fn apply_normalizers(value: &mut Value, keys: &mut Vec<Key>, normalizations: &[Normalization]) {
+    match value {
+        Value::Array(items) => {
+            for (idx, item) in items.iter_mut().enumerate() {
+                keys.push(Key::Idx(idx));
+                apply_normalizers(item, keys, normalizations);
+                keys.pop();
+            }
+        }
+        // Matchers are left alone, except for the values of partial matchers, which are compared
+        // as if they weren't wrapped.
+        Value::Object(marker) if marker.contains_key(MATCHER_KEY) => {
+            if marker[MATCHER_KEY] == "partial" {
+                if let Some(expected) = marker.get_mut("value") {
+                    apply_normalizers(expected, keys, normalizations);
+                }
+            }
+            return;
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                keys.push(Key::Field(key.clone()));
+                apply_normalizers(field, keys, normalizations);
+                keys.pop();
+            }
+        }
+        _ => {}
+    }
+
+    for normalization in normalizations.iter().filter(|n| n.applies_to(keys)) {
+        let normalizer = &normalization.normalizer;
+        match value {
+            Value::String(text) => normalizer.normalize_string(text),
+            Value::Number(number) => normalizer.normalize_number(number),
+            Value::Array(items) => normalizer.normalize_array(items),
+            Value::Object(fields) => normalizer.normalize_object(fields),
+            Value::Null | Value::Bool(_) => {}
+        }
+    }
+}
+
+/// Rewrites the line endings of `text` according to `mode`.
+pub(crate) fn line_endings(text: &str, mode: LineEndingMode) -> Cow<'_, str> {
+    match mode {
+        LineEndingMode::Exact => Cow::Borrowed(text),
+        LineEndingMode::Normalize if text.contains('\r') => Cow::Owned(text.replace("\r\n", "\n")),
+        LineEndingMode::Normalize => Cow::Borrowed(text),
+        LineEndingMode::NormalizeIgnoringTrailing => {
+            match line_endings(text, LineEndingMode::Normalize) {
+                Cow::Borrowed(text) => Cow::Borrowed(text.trim_end_matches('\n')),
+                Cow::Owned(text) => Cow::Owned(text.trim_end_matches('\n').to_owned()),
+            }
+        }
+    }
+}
+
+/// Converts internally and adjacently tagged enums into the externally tagged representation.
+fn normalize_enums(value: &mut Value, tag: &str, content: &str) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                normalize_enums(item, tag, content);
+            }
+        }
+        Value::Object(fields) => {
+            for field in fields.values_mut() {
+                normalize_enums(field, tag, content);
+            }
+
+            let variant = match fields.get(tag) {
+                Some(Value::String(variant)) => variant.clone(),
+                _ => return,
+            };
+            fields.remove(tag);
+
+            *value = if fields.is_empty() {
+                // Unit variant.
+                Value::String(variant)
+            } else if fields.len() == 1 && fields.contains_key(content) {
+                // Adjacently tagged.
+                let mut external = Map::new();
+                external.insert(variant, fields.remove(content).unwrap());
+                Value::Object(external)
+            } else {
+                // Internally tagged.
+                let mut external = Map::new();
+                external.insert(variant, Value::Object(std::mem::take(fields)));
+                Value::Object(external)
+            };
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    fn config() -> Config {
+        Config::new(CompareMode::Strict).enum_mode(EnumMode::Normalize {
+            tag: "t".to_string(),
+            content: "c".to_string(),
+        })
+    }
+
+    #[test]
+    fn normalizes_tagged_enums() {
+        let config = config();
+
+        let external = json!({ "Circle": { "radius": 1 } });
+        assert_eq!(normalize(external.clone(), &config), external);
+        assert_eq!(
+            normalize(json!({ "t": "Circle", "radius": 1 }), &config),
+            external
+        );
+        assert_eq!(
+            normalize(json!({ "t": "Circle", "c": { "radius": 1 } }), &config),
+            external
+        );
+
+        assert_eq!(
+            normalize(json!({ "t": "Id", "c": 7 }), &config),
+            json!({ "Id": 7 })
+        );
+        assert_eq!(normalize(json!({ "t": "Empty" }), &config), json!("Empty"));
+        assert_eq!(
+            normalize(json!([{ "t": 1 }, { "a": { "t": "Unit" } }]), &config),
+            json!([{ "t": 1 }, { "a": "Unit" }])
+        );
+    }
+
+    #[test]
+    fn exact_mode_leaves_values_alone() {
+        let config = Config::new(CompareMode::Strict);
+        let value = json!({ "t": "Circle", "c": 1 });
+        assert_eq!(normalize(value.clone(), &config), value);
+        assert!(matches!(normalized(&value, &config), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn normalizes_line_endings() {
+        let text = "a\r\nb\rc\r\n\n";
+        assert_eq!(line_endings(text, LineEndingMode::Exact), text);
+        assert_eq!(line_endings(text, LineEndingMode::Normalize), "a\nb\rc\n\n");
+        assert_eq!(
+            line_endings(text, LineEndingMode::NormalizeIgnoringTrailing),
+            "a\nb\rc"
+        );
+        assert_eq!(
+            line_endings("a\n", LineEndingMode::NormalizeIgnoringTrailing),
+            "a"
+        );
+    }
+}
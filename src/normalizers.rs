@@ -0,0 +1,223 @@
+//! Normalizers that rewrite values before they are compared.
+//!
+//! Normalizers are added to a [`Config`](crate::Config) with
+//! [`Config::normalizer`](crate::Config::normalizer) or
+//! [`Config::normalizer_at`](crate::Config::normalizer_at), and are applied in the order they were
+//! added to both sides of a comparison. Each normalizer only needs to implement the methods for the
+//! kinds of values it rewrites.
+//!
+//! ```
+//! use serde_json_assert::{assert_json_matches, normalizers, CompareMode, Config};
+//! use serde_json::json;
+//!
+//! let config = Config::new(CompareMode::Strict)
+//!     .normalizer(normalizers::CollapseWhitespace)
+//!     .normalizer_at(".email", normalizers::Lowercase);
+//!
+//! assert_json_matches!(
+//!     json!({ "name": " Bob  Smith ", "email": "Bob@Example.com" }),
+//!     json!({ "name": "Bob Smith", "email": "bob@example.com" }),
+//!     &config
+//! );
+//! ```
+
+use crate::pattern::PathPattern;
+use crate::timestamp;
+use crate::{normalize, Key, LineEndingMode};
+use serde_json::{Map, Number, Value};
+use std::fmt;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+/// Rewrites values into a canonical form before they are compared.
+///
+/// Arrays and objects are normalized after the values they contain.
+///
+/// Normalizers are shared between clones of a [`Config`](crate::Config), which is why they must be
+/// thread and unwind safe.
+pub trait Normalizer: fmt::Debug + Send + Sync + RefUnwindSafe {
+    /// Rewrites a string. Does nothing by default.
+    fn normalize_string(&self, _text: &mut String) {}
+
+    /// Rewrites a number. Does nothing by default.
+    fn normalize_number(&self, _number: &mut Number) {}
+
+    /// Rewrites an array. Does nothing by default.
+    fn normalize_array(&self, _items: &mut Vec<Value>) {}
+
+    /// Rewrites an object. Does nothing by default.
+    fn normalize_object(&self, _fields: &mut Map<String, Value>) {}
+}
+
+/// A [`Normalizer`] and the values it applies to.
+///
+/// Created by [`Config::normalizer`](crate::Config::normalizer) and
+/// [`Config::normalizer_at`](crate::Config::normalizer_at).
+#[derive(Debug, Clone)]
+pub struct Normalization {
+    /// The values the normalizer applies to. `None` applies it to every value.
+    pub pattern: Option<PathPattern>,
+    /// The normalizer.
+    pub normalizer: Arc<dyn Normalizer>,
+}
+
+impl Normalization {
+    pub(crate) fn applies_to(&self, keys: &[Key]) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches_keys(keys))
+    }
+}
+
+/// Normalizations are equal if they apply the same normalizer to the same values.
+impl PartialEq for Normalization {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && Arc::ptr_eq(&self.normalizer, &other.normalizer)
+    }
+}
+
+/// Converts strings to lowercase.
+#[derive(Debug, Copy, Clone)]
+pub struct Lowercase;
+
+impl Normalizer for Lowercase {
+    fn normalize_string(&self, text: &mut String) {
+        *text = text.to_lowercase();
+    }
+}
+
+/// Trims strings and replaces every run of whitespace in them with a single space.
+#[derive(Debug, Copy, Clone)]
+pub struct CollapseWhitespace;
+
+impl Normalizer for CollapseWhitespace {
+    fn normalize_string(&self, text: &mut String) {
+        *text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+}
+
+/// Rewrites line endings in strings according to a [`LineEndingMode`].
+///
+/// Unlike [`Config::line_ending_mode`](crate::Config::line_ending_mode), failure messages show the
+/// rewritten strings.
+#[derive(Debug, Copy, Clone)]
+pub struct LineEndings(pub LineEndingMode);
+
+impl Normalizer for LineEndings {
+    fn normalize_string(&self, text: &mut String) {
+        if let std::borrow::Cow::Owned(normalized) = normalize::line_endings(text, self.0) {
+            *text = normalized;
+        }
+    }
+}
+
+/// Converts integers to floats, so that for example `1` and `1.0` are equal.
+///
+/// Integers that can't be represented exactly as floats are rounded.
+#[derive(Debug, Copy, Clone)]
+pub struct NumbersAsFloats;
+
+impl Normalizer for NumbersAsFloats {
+    fn normalize_number(&self, number: &mut Number) {
+        if let Some(float) = number.as_f64().and_then(Number::from_f64) {
+            *number = float;
+        }
+    }
+}
+
+/// Converts RFC 3339 timestamps in strings to UTC, so that timestamps with different offsets are
+/// equal if they refer to the same instant.
+///
+/// Unlike [`Config::timestamps`](crate::Config::timestamps), failure messages show the rewritten
+/// timestamps.
+#[derive(Debug, Copy, Clone)]
+pub struct UtcTimestamps;
+
+impl Normalizer for UtcTimestamps {
+    fn normalize_string(&self, text: &mut String) {
+        if let Some(instant) = timestamp::parse(text) {
+            *text = instant.to_utc_string();
+        }
+    }
+}
+
+/// Sorts the items of arrays by their compact serialization, so that arrays holding the same items
+/// in a different order are equal.
+///
+/// Nested arrays are sorted before the arrays containing them. Failure messages show the sorted
+/// arrays. See also [`assert_json_eq_sorted`](crate::assert_json_eq_sorted).
+#[derive(Debug, Copy, Clone)]
+pub struct SortArrays;
+
+impl Normalizer for SortArrays {
+    fn normalize_array(&self, items: &mut Vec<Value>) {
+        items.sort_by_cached_key(|item| item.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff;
+    use crate::normalize::normalize;
+    use crate::{matchers, CompareMode, Config};
+    use serde_json::json;
+
+    #[test]
+    fn pipeline_runs_in_order() {
+        let value = json!({ "tags": [" B ", "a", "c  d"], "at": "2024-01-01T10:00:00.5+02:00" });
+
+        let config = Config::new(CompareMode::Strict)
+            .normalizer(CollapseWhitespace)
+            .normalizer(SortArrays)
+            .normalizer_at(".at", UtcTimestamps);
+        assert_eq!(
+            normalize(value.clone(), &config),
+            json!({ "tags": ["B", "a", "c d"], "at": "2024-01-01T08:00:00.5Z" })
+        );
+
+        let config = Config::new(CompareMode::Strict)
+            .normalizer(SortArrays)
+            .normalizer_at(".tags[*]", CollapseWhitespace)
+            .normalizer_at(".tags", Lowercase);
+        assert_eq!(
+            normalize(value, &config),
+            json!({ "tags": ["a", "b", "c d"], "at": "2024-01-01T10:00:00.5+02:00" })
+        );
+    }
+
+    #[test]
+    fn numbers_as_floats() {
+        let config = Config::new(CompareMode::Strict).normalizer(NumbersAsFloats);
+        let lhs = json!({ "a": 1, "b": [2.0, -3] });
+        let rhs = json!({ "a": 1.0, "b": [2, -3.0] });
+        assert_eq!(diff(&lhs, &rhs, &Config::new(CompareMode::Strict)).len(), 3);
+        assert_eq!(normalize(lhs, &config), normalize(rhs, &config));
+    }
+
+    #[test]
+    fn matcher_markers_are_left_alone() {
+        let config = Config::new(CompareMode::Strict).normalizer(Lowercase);
+        let expected = json!({
+            "a": matchers::partial(json!({ "b": "X" })),
+            "c": matchers::not(json!("Y")),
+        });
+        assert_eq!(
+            normalize(expected, &config),
+            json!({
+                "a": matchers::partial(json!({ "b": "x" })),
+                "c": matchers::not(json!("Y")),
+            })
+        );
+    }
+
+    #[test]
+    fn equality() {
+        let lowercase = Config::new(CompareMode::Strict).normalizer(Lowercase);
+        assert_eq!(lowercase.clone(), lowercase);
+        assert_ne!(
+            lowercase,
+            Config::new(CompareMode::Strict).normalizer(Lowercase)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::normalizers::Normalizer;
+use crate::{
+    ArrayDiffMode, ArrayMode, ArraySortingMode, CompareMode, Config, EnumMode, FloatCompareMode,
+    LineEndingMode, MaxDepthMode, NumericMode, OutputFormat, StringCompareMode, Verbosity,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Settings to layer on top of a [`Config`] with [`Config::merge`].
+///
+/// Every setting is left alone unless it's set here, even to its default. The methods are the
+/// same as those of [`Config`], and the settings that can be given several times, such as masked
+/// paths, are added to those of the config.
+///
+/// ```
+/// use serde_json_assert::{CompareMode, Config, ConfigOverrides};
+///
+/// let base = Config::new(CompareMode::Inclusive).fail_fast(true);
+/// let config = base.merge(&ConfigOverrides::new().fail_fast(false));
+/// assert!(!config.fail_fast);
+/// assert_eq!(config.compare_mode, CompareMode::Inclusive);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOverrides {
+    /// The overriding values, and the lists to add to those of the merged config.
+    pub(crate) config: Config,
+    /// The names of the fields of `config` that were set.
+    set: BTreeSet<&'static str>,
+}
+
+impl Default for ConfigOverrides {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! overrides {
+    ($($name:ident($($arg:ident: $ty:ty),*) => [$($field:ident),*];)*) => {
+        impl ConfigOverrides {
+            $(
+                #[doc = concat!("Override [`Config::", stringify!($name), "`].")]
+                #[track_caller]
+                pub fn $name(mut self, $($arg: $ty),*) -> Self {
+                    self.config = self.config.$name($($arg),*);
+                    $(self.set.insert(stringify!($field));)*
+                    self
+                }
+            )*
+        }
+    };
+}
+
+overrides! {
+    compare_mode(compare_mode: CompareMode) => [compare_mode];
+    numeric_mode(numeric_mode: NumericMode) => [numeric_mode];
+    float_compare_mode(float_compare_mode: FloatCompareMode) => [float_compare_mode];
+    verbosity(verbosity: Verbosity) => [verbosity];
+    max_diffs(max_diffs: Option<usize>) => [max_diffs];
+    color(color: bool) => [color];
+    output_format(output_format: OutputFormat) => [output_format];
+    numeric_keys(numeric_keys: bool) => [numeric_keys];
+    enum_mode(enum_mode: EnumMode) => [enum_mode];
+    mask_output(pattern: &str) => [];
+    max_depth(max_depth: usize) => [max_depth];
+    max_depth_mode(max_depth_mode: MaxDepthMode) => [max_depth_mode];
+    max_nodes(max_nodes: usize) => [max_nodes];
+    max_nesting(max_nesting: usize) => [max_nesting];
+    collapse_output(depth: usize) => [collapse_output];
+    compare_mode_at(pattern: &str, compare_mode: CompareMode) => [];
+    sentinels(sentinels: bool) => [sentinels];
+    match_array_by_key(pattern: &str, key: &str) => [];
+    object_compare_mode(compare_mode: CompareMode) => [object_compare_mode];
+    array_compare_mode(compare_mode: CompareMode) => [array_compare_mode];
+    report_extras(report_extras: bool) => [report_extras];
+    report_stats(report_stats: bool) => [report_stats];
+    fail_fast(fail_fast: bool) => [fail_fast];
+    summarize_type_mismatches(summarize: bool) => [summarize_type_mismatches];
+    long_string_threshold(bytes: usize) => [long_string_threshold];
+    float_precision(digits: usize) => [float_precision];
+    timestamps(timestamps: bool) => [timestamps];
+    line_ending_mode(line_ending_mode: LineEndingMode) => [line_ending_mode];
+    string_compare_mode(string_compare_mode: StringCompareMode) => [string_compare_mode];
+    array_mode(array_mode: ArrayMode) =>
+        [array_sorting_mode, array_diff_mode, array_compare_mode];
+    array_sorting_mode(array_sorting_mode: ArraySortingMode) => [array_sorting_mode];
+    scalar_array_sorting_mode(array_sorting_mode: ArraySortingMode) =>
+        [scalar_array_sorting_mode];
+    container_array_sorting_mode(array_sorting_mode: ArraySortingMode) =>
+        [container_array_sorting_mode];
+    array_diff_mode(array_diff_mode: ArrayDiffMode) => [array_diff_mode];
+}
+
+impl ConfigOverrides {
+    /// Create overrides that leave every setting alone.
+    pub fn new() -> Self {
+        Self {
+            config: Config::new(CompareMode::Strict),
+            set: BTreeSet::new(),
+        }
+    }
+
+    /// Returns true if the field of [`Config`] named `field` is overridden.
+    pub(crate) fn is_set(&self, field: &str) -> bool {
+        self.set.contains(field)
+    }
+
+    /// Override [`Config::ignore_paths`].
+    #[track_caller]
+    pub fn ignore_paths<I, P>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        self.config = self.config.ignore_paths(patterns);
+        self
+    }
+
+    /// Override [`Config::ignore_paths_matching`].
+    #[cfg(feature = "regex")]
+    pub fn ignore_paths_matching(mut self, regex: regex::Regex) -> Self {
+        self.config = self.config.ignore_paths_matching(regex);
+        self
+    }
+
+    /// Override [`Config::compare_with`].
+    #[track_caller]
+    pub fn compare_with<F>(mut self, pattern: &str, compare: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + std::panic::RefUnwindSafe + 'static,
+    {
+        self.config = self.config.compare_with(pattern, compare);
+        self
+    }
+
+    /// Override [`Config::normalizer`].
+    pub fn normalizer<N: Normalizer + 'static>(mut self, normalizer: N) -> Self {
+        self.config = self.config.normalizer(normalizer);
+        self
+    }
+
+    /// Override [`Config::normalizer_at`].
+    #[track_caller]
+    pub fn normalizer_at<N: Normalizer + 'static>(mut self, pattern: &str, normalizer: N) -> Self {
+        self.config = self.config.normalizer_at(pattern, normalizer);
+        self
+    }
+
+    /// Override [`Config::context`].
+    #[track_caller]
+    pub fn context<T: Serialize + ?Sized>(mut self, context: &T) -> Self {
+        self.config = self.config.context(context);
+        self.set.insert("context");
+        self
+    }
+}
@@ -0,0 +1,429 @@
+//! Checking responses against recorded [Pact](https://docs.pact.io) interactions.
+//!
+//! An [`Interaction`] is loaded from the JSON of a Pact file, and its expected response, with the
+//! matching rules applied, is compared to an actual response using the rest of this crate. The
+//! actual response is given as a JSON object with optional `status`, `headers` and `body` fields,
+//! like the response of the interaction.
+//!
+//! Response bodies and headers may contain values that weren't expected, but arrays must have the
+//! expected length unless a matching rule allows otherwise. Header names are case-insensitive.
+//!
+//! Both the version 2 and version 3 formats of matching rules are understood, with these rules:
+//!
+//! | Rule                                                | Matcher                    |
+//! | --------------------------------------------------- | -------------------------- |
+//! | `type`, optionally with `min` and `max`             | [`like`] or [`array_like`] |
+//! | `integer`, `decimal`, `number`, `boolean` or `null` | [`of_type`]                |
+//! | `include`                                           | [`contains`]               |
+//! | `equality`                                          | [`exactly`]                |
+//!
+//! Other rules, such as `regex` and `timestamp`, aren't supported, and loading an interaction using
+//! them fails.
+//!
+//! [`like`]: crate::matchers::like
+//! [`array_like`]: crate::matchers::array_like
+//! [`of_type`]: crate::matchers::of_type
+//! [`contains`]: crate::matchers::contains
+//! [`exactly`]: crate::matchers::exactly
+//!
+//! ```
+//! use serde_json_assert::pact::Interaction;
+//! use serde_json::json;
+//!
+//! let pact = json!({
+//!     "interactions": [{
+//!         "description": "a request for a user",
+//!         "request": { "method": "GET", "path": "/users/1" },
+//!         "response": {
+//!             "status": 200,
+//!             "headers": { "Content-Type": "application/json" },
+//!             "body": { "id": 1, "name": "Bob", "roles": ["admin"] },
+//!             "matchingRules": {
+//!                 "body": {
+//!                     "$.name": { "matchers": [{ "match": "type" }] },
+//!                     "$.roles": { "matchers": [{ "match": "type", "min": 1 }] }
+//!                 }
+//!             }
+//!         }
+//!     }]
+//! });
+//!
+//! let interaction = Interaction::find(&pact, "a request for a user").unwrap();
+//! interaction.assert_response(&json!({
+//!     "status": 200,
+//!     "headers": { "content-type": "application/json", "x-request-id": "abc" },
+//!     "body": { "id": 1, "name": "Eve", "roles": ["dev", "ops"], "active": true },
+//! }));
+//! ```
+
+use crate::matchers::{self, JsonType};
+use crate::normalizers::Normalizer;
+use crate::pattern::{parse_segments, Segment};
+use crate::{check_json_matches, CompareMode, Config, Key, Mismatch};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// A recorded interaction, whose response can be checked against actual responses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interaction {
+    description: String,
+    response: Value,
+}
+
+impl Interaction {
+    /// Loads an interaction from its JSON representation in a Pact file.
+    ///
+    /// Returns an error if the interaction has no response, or if its matching rules are invalid
+    /// or unsupported.
+    pub fn from_json(interaction: &Value) -> Result<Self, String> {
+        let description = interaction
+            .get("description")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned();
+        let response = interaction
+            .get("response")
+            .and_then(Value::as_object)
+            .ok_or_else(|| format!("Interaction {:?} has no response", description))?;
+
+        let mut expected = Map::new();
+        if let Some(status) = response.get("status") {
+            expected.insert("status".to_owned(), status.clone());
+        }
+        if let Some(headers) = response.get("headers").and_then(Value::as_object) {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| (name.to_lowercase(), value.clone()))
+                .collect();
+            expected.insert("headers".to_owned(), Value::Object(headers));
+        }
+        if let Some(body) = response.get("body") {
+            expected.insert("body".to_owned(), body.clone());
+        }
+        let mut expected = Value::Object(expected);
+
+        let mut rules = matching_rules(response.get("matchingRules"))
+            .map_err(|err| format!("Interaction {:?}: {}", description, err))?;
+        // Rules for nested values are applied first, so that the examples of rules for their
+        // ancestors contain the resulting matchers.
+        rules.sort_by_key(|(segments, _)| std::cmp::Reverse(segments.len()));
+        for (segments, rule) in rules {
+            apply_rule(&mut expected, &segments, &rule)
+                .map_err(|err| format!("Interaction {:?}: {}", description, err))?;
+        }
+
+        Ok(Interaction {
+            description,
+            response: expected,
+        })
+    }
+
+    /// Finds the interaction with the given description in the JSON of a Pact file, and loads it
+    /// with [`Interaction::from_json`].
+    pub fn find(pact: &Value, description: &str) -> Result<Self, String> {
+        let interaction = pact
+            .get("interactions")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .find(|interaction| interaction["description"] == description)
+            .ok_or_else(|| format!("No interaction described as {:?}", description))?;
+        Self::from_json(interaction)
+    }
+
+    /// Returns the description of the interaction.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the expected response, with the matching rules replaced by matchers.
+    pub fn expected_response(&self) -> &Value {
+        &self.response
+    }
+
+    /// Compares `actual` to the expected response without panicking.
+    #[track_caller]
+    pub fn check_response<T>(&self, actual: &T) -> Result<(), Mismatch>
+    where
+        T: Serialize,
+    {
+        let config = Config::new(CompareMode::Inclusive)
+            .array_compare_mode(CompareMode::Strict)
+            .normalizer_at(".headers", LowercaseKeys);
+        check_json_matches(actual, &self.response, &config).map(|_| ())
+    }
+
+    /// Compares `actual` to the expected response, panicking if they don't match.
+    #[track_caller]
+    pub fn assert_response<T>(&self, actual: &T)
+    where
+        T: Serialize,
+    {
+        if let Err(error) = self.check_response(actual) {
+            panic!(
+                "\nresponse doesn't match interaction {:?}:\n{}\n\nat {}",
+                self.description,
+                error,
+                std::panic::Location::caller()
+            );
+        }
+    }
+}
+
+/// Lowercases the keys of objects, as header names are case-insensitive.
+#[derive(Debug)]
+struct LowercaseKeys;
+
+impl Normalizer for LowercaseKeys {
+    fn normalize_object(&self, fields: &mut Map<String, Value>) {
+        *fields = std::mem::take(fields)
+            .into_iter()
+            .map(|(key, value)| (key.to_lowercase(), value))
+            .collect();
+    }
+}
+
+/// Collects the matching rules of a response as paths within the expected response.
+fn matching_rules(rules: Option<&Value>) -> Result<Vec<(Vec<Segment>, Value)>, String> {
+    let rules = match rules {
+        Some(Value::Object(rules)) => rules,
+        Some(_) => return Err("matching rules must be an object".to_owned()),
+        None => return Ok(vec![]),
+    };
+
+    let mut collected = vec![];
+    for (key, value) in rules {
+        match key.as_str() {
+            // Version 3 groups rules by the part of the response they apply to.
+            "body" => {
+                for (path, rule) in value.as_object().into_iter().flatten() {
+                    let mut segments = vec![Segment::Key(Key::Field("body".to_owned()))];
+                    segments.extend(rule_path(path)?);
+                    collected.push((segments, rule.clone()));
+                }
+            }
+            "header" => {
+                for (name, rule) in value.as_object().into_iter().flatten() {
+                    let segments = vec![
+                        Segment::Key(Key::Field("headers".to_owned())),
+                        Segment::Key(Key::Field(name.to_lowercase())),
+                    ];
+                    collected.push((segments, rule.clone()));
+                }
+            }
+            "status" => return Err("matching rules for the status aren't supported".to_owned()),
+            // Version 2 uses a single path starting with `$.body` or `$.headers`.
+            path => {
+                let mut segments = rule_path(path)?;
+                match segments.first() {
+                    Some(Segment::Key(Key::Field(field))) if field == "body" => {}
+                    Some(Segment::Key(Key::Field(field))) if field == "headers" => {
+                        if let Some(Segment::Key(Key::Field(name))) = segments.get_mut(1) {
+                            *name = name.to_lowercase();
+                        }
+                    }
+                    _ => return Err(format!("unsupported matching rule path {:?}", path)),
+                }
+                collected.push((segments, value.clone()));
+            }
+        }
+    }
+    Ok(collected)
+}
+
+/// Parses a JSONPath expression, which may quote keys with single quotes.
+fn rule_path(path: &str) -> Result<Vec<Segment>, String> {
+    parse_segments(path).or_else(|err| {
+        let double_quoted = path.replace("['", "[\"").replace("']", "\"]");
+        parse_segments(&double_quoted).map_err(|_| err)
+    })
+}
+
+/// Replaces the values at `segments` with matchers for `rule`.
+fn apply_rule(value: &mut Value, segments: &[Segment], rule: &Value) -> Result<(), String> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            *value = matcher_for(value, rule)?;
+            return Ok(());
+        }
+    };
+
+    match (segment, value) {
+        (Segment::Key(Key::Field(field)), Value::Object(fields)) => match fields.get_mut(field) {
+            Some(field) => apply_rule(field, rest, rule),
+            None => Ok(()),
+        },
+        (Segment::Key(Key::Idx(idx)), Value::Array(items)) => match items.get_mut(*idx) {
+            Some(item) => apply_rule(item, rest, rule),
+            None => Ok(()),
+        },
+        (Segment::Wildcard, Value::Object(fields)) => fields
+            .values_mut()
+            .try_for_each(|field| apply_rule(field, rest, rule)),
+        (Segment::Wildcard, Value::Array(items)) => items
+            .iter_mut()
+            .try_for_each(|item| apply_rule(item, rest, rule)),
+        _ => Ok(()),
+    }
+}
+
+/// Converts a matching rule for `example` into a matcher.
+fn matcher_for(example: &Value, rule: &Value) -> Result<Value, String> {
+    // Version 3 rules list their matchers, version 2 rules are a single matcher.
+    let matcher = match rule.get("matchers") {
+        Some(Value::Array(matchers)) if matchers.len() == 1 => &matchers[0],
+        Some(_) => return Err("combining several matching rules isn't supported".to_owned()),
+        None => rule,
+    };
+
+    let bound = |name: &str| {
+        matcher
+            .get(name)
+            .and_then(Value::as_u64)
+            .and_then(|bound| usize::try_from(bound).ok())
+    };
+    let kind = matcher.get("match").and_then(Value::as_str);
+    let example = example.clone();
+    Ok(match kind {
+        Some("type") | None if matcher.get("min").is_some() || matcher.get("max").is_some() => {
+            let item = match &example {
+                Value::Array(items) => items.first().cloned().unwrap_or(Value::Null),
+                _ => return Err("`min` and `max` only apply to arrays".to_owned()),
+            };
+            matchers::array_like(item, bound("min"), bound("max"))
+        }
+        Some("type") => matchers::like(example),
+        Some("integer") => matchers::of_type(JsonType::Integer),
+        Some("decimal") => matchers::of_type(JsonType::Decimal),
+        Some("number") => matchers::of_type(JsonType::Number),
+        Some("boolean") => matchers::of_type(JsonType::Bool),
+        Some("null") => matchers::of_type(JsonType::Null),
+        Some("include") => match matcher.get("value").and_then(Value::as_str) {
+            Some(substring) => matchers::contains(substring),
+            None => return Err("`include` rules need a string value".to_owned()),
+        },
+        Some("equality") => matchers::exactly(example),
+        Some(kind) => return Err(format!("the {:?} matching rule isn't supported", kind)),
+        None => return Err(format!("invalid matching rule {}", matcher)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn version_2_rules() {
+        let interaction = Interaction::from_json(&json!({
+            "description": "list orders",
+            "response": {
+                "status": 200,
+                "headers": { "Content-Type": "application/json" },
+                "body": { "orders": [{ "id": 1, "total": 9.5, "note": "gift" }] },
+                "matchingRules": {
+                    "$.body.orders": { "min": 1 },
+                    "$.body.orders[*].id": { "match": "integer" },
+                    "$.body.orders[*].note": { "match": "equality" },
+                    "$.headers.Content-Type": { "match": "include", "value": "json" }
+                }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            interaction.expected_response(),
+            &json!({
+                "status": 200,
+                "headers": { "content-type": matchers::contains("json") },
+                "body": {
+                    "orders": matchers::array_like(
+                        json!({
+                            "id": matchers::of_type(JsonType::Integer),
+                            "total": 9.5,
+                            "note": matchers::exactly(json!("gift")),
+                        }),
+                        Some(1),
+                        None
+                    )
+                }
+            })
+        );
+
+        let actual = json!({
+            "status": 200,
+            "headers": { "Content-Type": "application/json; charset=utf-8" },
+            "body": {
+                "orders": [
+                    { "id": 3, "total": 1.25, "note": "gift" },
+                    { "id": 4, "total": 20.0, "note": "gift" },
+                ]
+            }
+        });
+        assert!(interaction.check_response(&actual).is_ok());
+
+        let mut wrong_note = actual.clone();
+        wrong_note["body"]["orders"][1]["note"] = json!("none");
+        assert!(interaction.check_response(&wrong_note).is_err());
+
+        let mut no_orders = actual.clone();
+        no_orders["body"]["orders"] = json!([]);
+        assert!(interaction.check_response(&no_orders).is_err());
+
+        let mut wrong_status = actual;
+        wrong_status["status"] = json!(404);
+        assert!(interaction.check_response(&wrong_status).is_err());
+    }
+
+    #[test]
+    fn version_3_rules() {
+        let interaction = Interaction::from_json(&json!({
+            "response": {
+                "body": { "user": { "id": "a1", "tags": ["x", "y"] } },
+                "matchingRules": {
+                    "body": {
+                        "$['user']": { "matchers": [{ "match": "type" }], "combine": "AND" }
+                    },
+                    "header": {
+                        "ETag": { "matchers": [{ "match": "type" }] }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            interaction.expected_response(),
+            &json!({ "body": { "user": matchers::like(json!({ "id": "a1", "tags": ["x", "y"] })) } })
+        );
+        assert!(interaction
+            .check_response(&json!({ "body": { "user": { "id": "b2", "tags": [] } } }))
+            .is_ok());
+        assert!(interaction
+            .check_response(&json!({ "body": { "user": { "id": 2, "tags": [] } } }))
+            .is_err());
+    }
+
+    #[test]
+    fn unsupported_rules() {
+        let error = Interaction::from_json(&json!({
+            "description": "a",
+            "response": {
+                "body": { "id": "1" },
+                "matchingRules": { "$.body.id": { "match": "regex", "regex": "\\d+" } }
+            }
+        }))
+        .unwrap_err();
+        assert_eq!(
+            error,
+            r#"Interaction "a": the "regex" matching rule isn't supported"#
+        );
+
+        let pact = json!({ "interactions": [] });
+        assert_eq!(
+            Interaction::find(&pact, "b").unwrap_err(),
+            r#"No interaction described as "b""#
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use serde_json::Value;
+
+/// Parses JSON text into a [`Value`].
+///
+/// Uses simd-json when the `simd-json` feature is enabled, and serde_json otherwise.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn parse(json: &[u8]) -> Result<Value, String> {
+    serde_json::from_slice(json).map_err(|err| err.to_string())
+}
+
+/// Parses JSON text into a [`Value`].
+///
+/// Uses simd-json when the `simd-json` feature is enabled, and serde_json otherwise.
+#[cfg(feature = "simd-json")]
+pub(crate) fn parse(json: &[u8]) -> Result<Value, String> {
+    // simd-json parses in place, so it needs a buffer it is allowed to modify.
+    let mut buffer = json.to_vec();
+    simd_json::serde::from_slice(&mut buffer).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_json() {
+        assert_eq!(
+            parse(br#"{ "a": [1, -2, 3.5, "four", null, true] }"#),
+            Ok(json!({ "a": [1, -2, 3.5, "four", null, true] }))
+        );
+        assert_eq!(parse(b"18446744073709551615"), Ok(json!(u64::MAX)));
+        assert!(parse(b"{ \"a\": }").is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::diff::{diff, Difference, Key, Path};
+use crate::{to_values, Config};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// A JSON Patch operation, as defined by [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902).
+///
+/// Paths are JSON Pointers, see [`Path::to_json_pointer`]. Operations serialize to their standard
+/// JSON form, such as `{ "op": "replace", "path": "/a/0", "value": 1 }`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    /// Adds a value to an object, or inserts it into an array.
+    Add {
+        /// Where the value is added.
+        path: String,
+        /// The added value.
+        value: Value,
+    },
+    /// Removes a value.
+    Remove {
+        /// Where the value is removed.
+        path: String,
+    },
+    /// Replaces a value.
+    Replace {
+        /// Where the value is replaced.
+        path: String,
+        /// The new value.
+        value: Value,
+    },
+}
+
+impl Serialize for PatchOp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (op, path, value) = match self {
+            PatchOp::Add { path, value } => ("add", path, Some(value)),
+            PatchOp::Remove { path } => ("remove", path, None),
+            PatchOp::Replace { path, value } => ("replace", path, Some(value)),
+        };
+        let mut map = serializer.serialize_map(Some(2 + usize::from(value.is_some())))?;
+        map.serialize_entry("op", op)?;
+        map.serialize_entry("path", path)?;
+        if let Some(value) = value {
+            map.serialize_entry("value", value)?;
+        }
+        map.end()
+    }
+}
+
+impl Path {
+    /// Returns the path as a JSON Pointer, as defined by
+    /// [RFC 6901](https://www.rfc-editor.org/rfc/rfc6901), such as `/users/0/name`.
+    ///
+    /// The root is the empty string, and `~` and `/` in field names are escaped as `~0` and `~1`.
+    pub fn to_json_pointer(&self) -> String {
+        self.keys()
+            .iter()
+            .map(|key| match key {
+                Key::Idx(idx) => format!("/{}", idx),
+                Key::Field(field) => format!("/{}", field.replace('~', "~0").replace('/', "~1")),
+            })
+            .collect()
+    }
+}
+
+impl Difference {
+    /// Returns the JSON Patch operation that turns the lhs value of the difference into the rhs
+    /// value.
+    pub fn to_patch_op(&self) -> PatchOp {
+        let path = self.path().to_json_pointer();
+        match (self.lhs(), self.rhs()) {
+            (Some(_), Some(rhs)) => PatchOp::Replace {
+                path,
+                value: rhs.clone(),
+            },
+            (None, Some(rhs)) => PatchOp::Add {
+                path,
+                value: rhs.clone(),
+            },
+            (_, None) => PatchOp::Remove { path },
+        }
+    }
+}
+
+/// Compares two values and returns the differences as a JSON Patch that turns `lhs` into `rhs`.
+///
+/// The operations are ordered so they can be applied one after the other: values are added and
+/// replaced in document order, and then removed in reverse document order, so removing an array
+/// item doesn't shift the ones still to be removed.
+///
+/// The patch describes the differences found using `config`. It turns `lhs` into `rhs` for strict
+/// comparisons of ordered arrays. Other settings leave out what they allow, such as extra fields
+/// in inclusive mode, and matchers in `rhs` are copied into the patch as they are.
+///
+/// ```
+/// use serde_json_assert::{diff_as_json_patch, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let patch = diff_as_json_patch(
+///     &json!({ "name": "Ann", "tags": ["a", "b", "c"] }),
+///     &json!({ "name": "Bob", "tags": ["a"], "a/b": 1 }),
+///     &Config::new(CompareMode::Strict),
+/// );
+///
+/// assert_eq!(
+///     serde_json::to_value(&patch).unwrap(),
+///     json!([
+///         { "op": "add", "path": "/a~1b", "value": 1 },
+///         { "op": "replace", "path": "/name", "value": "Bob" },
+///         { "op": "remove", "path": "/tags/2" },
+///         { "op": "remove", "path": "/tags/1" },
+///     ])
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn diff_as_json_patch<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> Vec<PatchOp>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config).unwrap_or_else(|limit| panic!("{}", limit));
+    in_patch_order(&diff(&lhs, &rhs, config))
+        .into_iter()
+        .map(Difference::to_patch_op)
+        .collect()
+}
+
+/// Applies `differences` to `value`, turning the lhs of the comparison they were found by into
+/// its rhs.
+///
+/// This replays the changes found by [`try_assert_json_matches`](crate::try_assert_json_matches)
+/// or [`Mismatch::differences`](crate::Mismatch::differences): mismatched values are replaced by
+/// their rhs value, values missing from the lhs are added and values missing from the rhs are
+/// removed. The differences may be in any order. Like [`diff_as_json_patch`], this only restores
+/// the rhs exactly for strict comparisons of ordered arrays.
+///
+/// ```
+/// use serde_json_assert::{apply_differences, try_assert_json_matches, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let mut state = json!({ "replicas": 2, "labels": { "env": "dev" }, "ports": [80, 443] });
+/// let desired = json!({ "replicas": 3, "labels": {}, "ports": [80] });
+///
+/// let differences = try_assert_json_matches(&state, &desired, &Config::new(CompareMode::Strict))
+///     .unwrap_err();
+/// apply_differences(&mut state, &differences);
+/// assert_eq!(state, desired);
+/// ```
+pub fn apply_differences(value: &mut Value, differences: &[Difference]) {
+    for difference in in_patch_order(differences) {
+        set(value, difference.path().keys(), difference.rhs().cloned());
+    }
+}
+
+/// Orders differences so that applying them one after the other is valid: additions and
+/// replacements in document order, then removals in reverse document order.
+pub(crate) fn in_patch_order(differences: &[Difference]) -> Vec<&Difference> {
+    let (mut updates, mut removals): (Vec<_>, Vec<_>) = differences
+        .iter()
+        .partition(|difference| difference.rhs().is_some());
+    updates.sort_by(|lhs, rhs| compare_keys(lhs.path().keys(), rhs.path().keys()));
+    removals.sort_by(|lhs, rhs| compare_keys(rhs.path().keys(), lhs.path().keys()));
+    updates.extend(removals);
+    updates
+}
+
+/// Sets the value at `keys`, or removes it if `value` is `None`.
+///
+/// Array items past the end are appended. Values whose parent doesn't exist are left alone.
+pub(crate) fn set(document: &mut Value, keys: &[Key], value: Option<Value>) {
+    let Some((last, parents)) = keys.split_last() else {
+        *document = value.unwrap_or(Value::Null);
+        return;
+    };
+    let parent = parents.iter().try_fold(document, |value, key| match key {
+        Key::Idx(idx) => value.get_mut(idx),
+        Key::Field(field) => value.get_mut(field),
+    });
+    match (parent, last, value) {
+        (Some(Value::Object(fields)), Key::Field(field), Some(value)) => {
+            fields.insert(field.clone(), value);
+        }
+        (Some(Value::Object(fields)), Key::Field(field), None) => {
+            fields.remove(field);
+        }
+        (Some(Value::Array(items)), Key::Idx(idx), Some(value)) => match items.get_mut(*idx) {
+            Some(item) => *item = value,
+            None => items.push(value),
+        },
+        (Some(Value::Array(items)), Key::Idx(idx), None) if *idx < items.len() => {
+            items.remove(*idx);
+        }
+        _ => {}
+    }
+}
+
+/// Orders paths as they appear in documents, with fields sorted by name.
+pub(crate) fn compare_keys(lhs: &[Key], rhs: &[Key]) -> Ordering {
+    for (lhs, rhs) in lhs.iter().zip(rhs) {
+        let ordering = match (lhs, rhs) {
+            (Key::Idx(lhs), Key::Idx(rhs)) => lhs.cmp(rhs),
+            (Key::Field(lhs), Key::Field(rhs)) => lhs.cmp(rhs),
+            (Key::Idx(_), Key::Field(_)) => Ordering::Less,
+            (Key::Field(_), Key::Idx(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    lhs.len().cmp(&rhs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn json_pointers() {
+        let path: Path = r#".a["b/c"]["~"][3]"#.parse().unwrap();
+        assert_eq!(path.to_json_pointer(), "/a/b~1c/~0/3");
+        assert_eq!(Path::Root.to_json_pointer(), "");
+    }
+
+    #[test]
+    fn patches_arrays_in_order() {
+        let config = Config::new(CompareMode::Strict);
+        let patch = diff_as_json_patch(&json!([1, [2]]), &json!([0, [2, 3, 4], 5]), &config);
+        assert_eq!(
+            patch,
+            [
+                PatchOp::Replace {
+                    path: "/0".to_string(),
+                    value: json!(0)
+                },
+                PatchOp::Add {
+                    path: "/1/1".to_string(),
+                    value: json!(3)
+                },
+                PatchOp::Add {
+                    path: "/1/2".to_string(),
+                    value: json!(4)
+                },
+                PatchOp::Add {
+                    path: "/2".to_string(),
+                    value: json!(5)
+                },
+            ]
+        );
+
+        let patch = diff_as_json_patch(&json!({ "": [1, 2, 3] }), &json!(null), &config);
+        assert_eq!(
+            patch,
+            [PatchOp::Replace {
+                path: String::new(),
+                value: json!(null)
+            }]
+        );
+    }
+
+    #[test]
+    fn applies_differences() {
+        let config = Config::new(CompareMode::Strict);
+        let pairs = [
+            (
+                json!({ "a": [1, 2, 3], "b": 1 }),
+                json!({ "a": [3], "c": { "d": [] } }),
+            ),
+            (json!([[1], 2]), json!([[1, 2, 3], { "x": null }, 4])),
+            (json!({ "a": 1 }), json!([1])),
+        ];
+        for (lhs, rhs) in pairs {
+            let (mut value, _) = to_values(&lhs, &rhs, &config).unwrap();
+            let mut differences = diff(&lhs, &rhs, &config);
+            differences.reverse();
+            apply_differences(&mut value, &differences);
+            assert_eq!(value, rhs);
+        }
+    }
+}
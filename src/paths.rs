@@ -0,0 +1,508 @@
+use crate::diff::{diff, diff_with_extras, Difference, Key, Path};
+use crate::matchers::JsonType;
+use crate::minimize::get;
+use crate::normalize::{normalize, normalized};
+use crate::report::{Mismatch, Output};
+use crate::{check_nesting, Config};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Checks several `(path, expected)` pairs against a single JSON document.
+///
+/// Paths use the same format as failure messages, such as `.data.users[0].name` (see the
+/// [`FromStr`](std::str::FromStr) implementation of [`Path`]). Each expected value is compared with
+/// the value found at its path using `config`, and the differences from all pairs are collected
+/// into one [`Mismatch`] instead of stopping at the first failing pair. A path that doesn't exist
+/// in `actual` is reported as missing.
+///
+/// If all pairs match, the paths of values allowed without being expected are returned. They are
+/// only collected if [`Config::report_extras`] is set.
+///
+/// The actual document is only serialized once, no matter how many pairs are checked.
+///
+/// ```
+/// use serde_json_assert::{check_json_paths, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let actual = json!({ "user": { "id": 1, "roles": ["admin", "dev"] } });
+/// let config = Config::new(CompareMode::Strict);
+///
+/// let result = check_json_paths(
+///     &actual,
+///     [(".user.id", json!(1)), (".user.roles[1]", json!("ops")), (".user.name", json!("Bob"))],
+///     &config,
+/// );
+/// assert_eq!(result.unwrap_err().differences().len(), 2);
+/// ```
+///
+/// # Panics
+///
+/// Panics if one of the paths can't be parsed, or if a value can't be serialized.
+#[track_caller]
+pub fn check_json_paths<Actual, I, P, E>(
+    actual: &Actual,
+    assertions: I,
+    config: &Config,
+) -> Result<Vec<Path>, Mismatch>
+where
+    Actual: Serialize + ?Sized,
+    I: IntoIterator<Item = (P, E)>,
+    P: AsRef<str>,
+    E: Serialize,
+{
+    let exceeded = |limit| Mismatch::limit_exceeded(limit, Output::from_env(config));
+    check_nesting(&[("actual", &actual)], config).map_err(exceeded)?;
+    let actual = serde_json::to_value(actual).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't convert actual value to JSON. Serde error: {}",
+            err
+        )
+    });
+
+    let mut differences = vec![];
+    let mut extras = vec![];
+    for (path, expected) in assertions {
+        let path: Path = path
+            .as_ref()
+            .parse()
+            .unwrap_or_else(|err| panic!("{}", err));
+        check_nesting(&[("expected", &expected)], config).map_err(exceeded)?;
+        let expected = serde_json::to_value(expected).unwrap_or_else(|err| {
+            panic!(
+                "Couldn't convert expected value at path \"{}\" to JSON. Serde error: {}",
+                path, err
+            )
+        });
+        let expected = normalize(expected, config);
+
+        match get(&actual, path.keys()) {
+            Some(actual) => {
+                let actual = normalized(actual, config);
+                let (diffs, found_extras) = diff_with_extras(&actual, &expected, config);
+                differences.extend(diffs.into_iter().map(|diff| diff.nest_under(&path)));
+                extras.extend(
+                    found_extras
+                        .into_iter()
+                        .map(|extra| extra.nest_under(&path)),
+                );
+            }
+            None => differences.push(Difference::new(path, None, Some(expected), config.clone())),
+        }
+    }
+
+    if differences.is_empty() {
+        Ok(extras)
+    } else {
+        Err(Mismatch::new(differences, extras, Output::from_env(config)))
+    }
+}
+
+/// Checks several `(path, expected)` pairs against a single JSON document without panicking.
+///
+/// Returns a `Result` where the error is the message that would be passed to `panic!`. See
+/// [`check_json_paths`] for details.
+#[track_caller]
+pub fn assert_json_paths_no_panic<Actual, I, P, E>(
+    actual: &Actual,
+    assertions: I,
+    config: &Config,
+) -> Result<(), String>
+where
+    Actual: Serialize + ?Sized,
+    I: IntoIterator<Item = (P, E)>,
+    P: AsRef<str>,
+    E: Serialize,
+{
+    check_json_paths(actual, assertions, config)
+        .map(|_| ())
+        .map_err(|mismatch| mismatch.to_string())
+}
+
+/// Checks that at least one item of the array at `path` in `actual` matches `expected`, without
+/// panicking.
+///
+/// `path` uses the same format as failure messages, where `$` or an empty string is the root. Each
+/// item is compared with `expected` using `config`. If none of them matches, the error shows the
+/// differences from the closest item, which is the one with the fewest differences, with paths
+/// from the root of `actual`. See [`assert_json_any_item`](crate::assert_json_any_item) for the
+/// panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_any_item_no_panic, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let order = json!({ "lines": [{ "sku": "A-1", "qty": 1 }, { "sku": "B-2", "qty": 3 }] });
+///
+/// assert!(assert_json_any_item_no_panic(&order, ".lines", &json!({ "sku": "B-2" }), &config).is_ok());
+///
+/// let error = assert_json_any_item_no_panic(&order, ".lines", &json!({ "sku": "B-2", "qty": 2 }), &config)
+///     .unwrap_err();
+/// assert_eq!(
+///     error,
+///     r#"none of the 2 json array items at path ".lines" match the expected value, the closest one is item [1]:
+///
+/// json atoms at path ".lines[1].qty" are not equal:
+///     expected:
+///         2
+///     actual:
+///         3"#
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `path` can't be parsed, or if a value can't be serialized.
+#[track_caller]
+pub fn assert_json_any_item_no_panic<Actual, Expected>(
+    actual: &Actual,
+    path: &str,
+    expected: &Expected,
+    config: &Config,
+) -> Result<(), String>
+where
+    Actual: Serialize + ?Sized,
+    Expected: Serialize + ?Sized,
+{
+    let path: Path = path.parse().unwrap_or_else(|err| panic!("{}", err));
+    check_nesting(&[("actual", &actual), ("expected", &expected)], config)
+        .map_err(|limit| limit.to_string())?;
+    let actual = __to_value(actual);
+    let expected = normalize(__to_value(expected), config);
+
+    let Some(items) = get(&actual, path.keys()).and_then(Value::as_array) else {
+        return Err(format!("json value has no array at path \"{}\"", path));
+    };
+    if items.is_empty() {
+        return Err(format!(
+            "json array at path \"{}\" is empty, so none of its items match the expected value",
+            path
+        ));
+    }
+
+    let mut closest: Option<(usize, Vec<Difference>)> = None;
+    for (idx, item) in items.iter().enumerate() {
+        let differences = diff(&normalized(item, config), &expected, config);
+        if differences.is_empty() {
+            return Ok(());
+        }
+        if closest
+            .as_ref()
+            .is_none_or(|(_, closest)| differences.len() < closest.len())
+        {
+            closest = Some((idx, differences));
+        }
+    }
+
+    let (idx, differences) = closest.expect("the array isn't empty");
+    let item_path = Path::from_keys(path.keys().iter().cloned().chain([Key::Idx(idx)]).collect());
+    let differences = differences
+        .into_iter()
+        .map(|difference| difference.nest_under(&item_path))
+        .collect();
+    Err(format!(
+        "none of the {} json array items at path \"{}\" match the expected value, the closest one is item [{}]:\n\n{}",
+        items.len(),
+        path,
+        idx,
+        Mismatch::new(differences, vec![], Output::from_env(config))
+    ))
+}
+
+/// How many items of an array must match, see [`assert_json_matching_items_no_panic`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Quantity {
+    /// Exactly this many items.
+    Exactly(usize),
+    /// This many items or more.
+    AtLeast(usize),
+    /// This many items or fewer.
+    AtMost(usize),
+}
+
+impl Quantity {
+    fn allows(self, count: usize) -> bool {
+        match self {
+            Quantity::Exactly(expected) => count == expected,
+            Quantity::AtLeast(min) => count >= min,
+            Quantity::AtMost(max) => count <= max,
+        }
+    }
+}
+
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Quantity::Exactly(count) => write!(f, "exactly {}", count),
+            Quantity::AtLeast(count) => write!(f, "at least {}", count),
+            Quantity::AtMost(count) => write!(f, "at most {}", count),
+        }
+    }
+}
+
+/// Checks how many items of the array at `path` in `actual` match `expected`, without panicking.
+///
+/// `path` uses the same format as failure messages, where `$` or an empty string is the root. Each
+/// item is compared with `expected` using `config`, and the number of matching items must be
+/// allowed by `quantity`. The error lists the indexes of the items that matched. See
+/// [`assert_json_matching_items`](crate::assert_json_matching_items) for the panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_matching_items_no_panic, CompareMode, Config, Quantity};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Inclusive);
+/// let results = json!({ "hits": [
+///     { "id": 1, "status": "open" },
+///     { "id": 2, "status": "closed" },
+///     { "id": 3, "status": "open" },
+/// ] });
+/// let open = json!({ "status": "open" });
+///
+/// assert!(assert_json_matching_items_no_panic(&results, ".hits", &open, Quantity::AtLeast(2), &config).is_ok());
+/// assert_eq!(
+///     assert_json_matching_items_no_panic(&results, ".hits", &open, Quantity::Exactly(1), &config)
+///         .unwrap_err(),
+///     "expected exactly 1 of the 3 json array items at path \".hits\" to match the expected value, but 2 match: [0], [2]"
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `path` can't be parsed, or if a value can't be serialized.
+#[track_caller]
+pub fn assert_json_matching_items_no_panic<Actual, Expected>(
+    actual: &Actual,
+    path: &str,
+    expected: &Expected,
+    quantity: Quantity,
+    config: &Config,
+) -> Result<(), String>
+where
+    Actual: Serialize + ?Sized,
+    Expected: Serialize + ?Sized,
+{
+    let path: Path = path.parse().unwrap_or_else(|err| panic!("{}", err));
+    check_nesting(&[("actual", &actual), ("expected", &expected)], config)
+        .map_err(|limit| limit.to_string())?;
+    let actual = __to_value(actual);
+    let expected = normalize(__to_value(expected), config);
+
+    let Some(items) = get(&actual, path.keys()).and_then(Value::as_array) else {
+        return Err(format!("json value has no array at path \"{}\"", path));
+    };
+    let matching = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| diff(&normalized(item, config), &expected, config).is_empty())
+        .map(|(idx, _)| format!("[{}]", idx))
+        .collect::<Vec<_>>();
+    if quantity.allows(matching.len()) {
+        return Ok(());
+    }
+
+    let mut error = format!(
+        "expected {} of the {} json array items at path \"{}\" to match the expected value, but {} match",
+        quantity,
+        items.len(),
+        path,
+        matching.len()
+    );
+    if !matching.is_empty() {
+        error.push_str(&format!(": {}", matching.join(", ")));
+    }
+    Err(error)
+}
+
+/// Returns the paths of all leaves of `value`, in document order.
+///
+/// Leaves are the values that have no children: `null`, booleans, numbers, strings, and empty
+/// arrays and objects. The paths are the same [`Path`]s used in failure messages, so they can be
+/// compared with those of another document, or parsed back from their text.
+///
+/// ```
+/// use serde_json_assert::leaf_paths;
+/// use serde_json::json;
+///
+/// let paths = leaf_paths(&json!({ "user": { "id": 1, "roles": ["admin"], "tags": [] } }));
+/// let paths = paths.iter().map(ToString::to_string).collect::<Vec<_>>();
+/// assert_eq!(paths, [".user.id", ".user.roles[0]", ".user.tags"]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized.
+#[track_caller]
+pub fn leaf_paths<T: Serialize + ?Sized>(value: &T) -> Vec<Path> {
+    leaf_paths_with_types(value)
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Returns the paths of all leaves of `value` with their types, in document order.
+///
+/// See [`leaf_paths`] for which values are leaves. Numbers are reported as
+/// [`JsonType::Number`], whether or not they are integers.
+///
+/// ```
+/// use serde_json_assert::{leaf_paths_with_types, matchers::JsonType};
+/// use serde_json::json;
+///
+/// let leaves = leaf_paths_with_types(&json!({ "id": 1, "name": null, "tags": {} }));
+/// let types = leaves.iter().map(|(_, kind)| *kind).collect::<Vec<_>>();
+/// assert_eq!(types, [JsonType::Number, JsonType::Null, JsonType::Object]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized.
+#[track_caller]
+pub fn leaf_paths_with_types<T: Serialize + ?Sized>(value: &T) -> Vec<(Path, JsonType)> {
+    let value = __to_value(value);
+    let mut leaves = vec![];
+    collect_leaves(&value, &mut vec![], &mut leaves);
+    leaves
+}
+
+fn collect_leaves(value: &Value, keys: &mut Vec<Key>, leaves: &mut Vec<(Path, JsonType)>) {
+    let kind = match value {
+        Value::Array(items) if !items.is_empty() => {
+            for (idx, item) in items.iter().enumerate() {
+                keys.push(Key::Idx(idx));
+                collect_leaves(item, keys, leaves);
+                keys.pop();
+            }
+            return;
+        }
+        Value::Object(fields) if !fields.is_empty() => {
+            for (key, field) in fields {
+                keys.push(Key::Field(key.clone()));
+                collect_leaves(field, keys, leaves);
+                keys.pop();
+            }
+            return;
+        }
+        Value::Null => JsonType::Null,
+        Value::Bool(_) => JsonType::Bool,
+        Value::Number(_) => JsonType::Number,
+        Value::String(_) => JsonType::String,
+        Value::Array(_) => JsonType::Array,
+        Value::Object(_) => JsonType::Object,
+    };
+    leaves.push((Path::from_keys(keys.clone()), kind));
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __to_value<T: Serialize + ?Sized>(value: &T) -> Value {
+    serde_json::to_value(value)
+        .unwrap_or_else(|err| panic!("Couldn't convert value to JSON. Serde error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn collects_failures_from_every_pair() {
+        let actual = json!({ "a": { "b": [1, { "c": true }] }, "d": "x" });
+        let config = Config::new(CompareMode::Strict);
+
+        assert!(check_json_paths(
+            &actual,
+            [
+                ("$.a.b[0]", json!(1)),
+                (".a.b[1].c", json!(true)),
+                ("d", json!("x"))
+            ],
+            &config,
+        )
+        .is_ok());
+
+        let mismatch = check_json_paths(
+            &actual,
+            [
+                (".a.b[1]", json!({ "c": false })),
+                (".a.b[5]", json!(1)),
+                (".d", json!("x")),
+            ],
+            &config,
+        )
+        .unwrap_err();
+        let paths = mismatch
+            .differences()
+            .iter()
+            .map(|diff| diff.path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![".a.b[1].c", ".a.b[5]"]);
+
+        let missing = &mismatch.differences()[1];
+        assert_eq!(missing.path().keys()[2], Key::Idx(5));
+        assert_eq!(missing.actual(), &None);
+        assert_eq!(missing.expected(), &Some(json!(1)));
+    }
+
+    #[test]
+    fn uses_compare_mode_for_each_pair() {
+        let actual = json!({ "user": { "id": 1, "name": "Bob" } });
+        let expected = [(".user", json!({ "id": 1 }))];
+
+        let config = Config::new(CompareMode::Inclusive);
+        assert!(check_json_paths(&actual, expected.clone(), &config).is_ok());
+
+        let config = Config::new(CompareMode::Strict);
+        assert!(check_json_paths(&actual, expected, &config).is_err());
+    }
+
+    #[test]
+    fn returns_extras_of_every_pair() {
+        let actual = json!({ "user": { "id": 1, "name": "Bob" }, "tags": ["a", "b"] });
+        let config = Config::new(CompareMode::Inclusive).report_extras(true);
+
+        let extras = check_json_paths(
+            &actual,
+            [(".user", json!({ "id": 1 })), (".tags", json!(["a"]))],
+            &config,
+        )
+        .unwrap();
+        let extras = extras.iter().map(Path::to_string).collect::<Vec<_>>();
+        assert_eq!(extras, vec![".user.name", ".tags[1]"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid path \".a[x]\"")]
+    fn invalid_path_panics() {
+        let _ = check_json_paths(
+            &json!({}),
+            [(".a[x]", 1)],
+            &Config::new(CompareMode::Strict),
+        );
+    }
+
+    #[test]
+    fn lists_leaf_paths() {
+        let value = json!({ "a": [1, [], { "b": "x" }], "a.b": null, "c": {} });
+        let leaves = leaf_paths_with_types(&value)
+            .into_iter()
+            .map(|(path, kind)| (path.to_string(), kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            leaves,
+            [
+                (".a[0]".to_string(), JsonType::Number),
+                (".a[1]".to_string(), JsonType::Array),
+                (".a[2].b".to_string(), JsonType::String),
+                ("[\"a.b\"]".to_string(), JsonType::Null),
+                (".c".to_string(), JsonType::Object),
+            ]
+        );
+
+        for path in leaf_paths(&value) {
+            assert_eq!(path.to_string().parse::<Path>(), Ok(path));
+        }
+        assert_eq!(leaf_paths(&json!(true)), [Path::Root]);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::diff::{Key, KeyRef, Path};
+use std::{fmt, str::FromStr};
+
+/// A pattern matching paths to JSON values, such as `.credentials.*` or `.users[*].email`.
+///
+/// Patterns use the same format as [`Path`], where `*` (written as `.*` or `[*]`) matches any
+/// single field or index. A pattern also matches every path below the paths it matches, so
+/// `.credentials` matches `.credentials.token`.
+///
+/// ```
+/// use serde_json_assert::{Path, PathPattern};
+///
+/// let pattern: PathPattern = ".users[*].email".parse().unwrap();
+/// let path: Path = ".users[3].email".parse().unwrap();
+/// assert!(pattern.matches(&path));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    segments: Vec<Segment>,
+}
+
+impl PathPattern {
+    /// Returns true if the pattern matches `path`, or one of its ancestors.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.matches_keys(path.keys())
+    }
+
+    pub(crate) fn matches_keys(&self, keys: &[Key]) -> bool {
+        self.matches_refs(keys.iter().map(KeyRef::from))
+    }
+
+    pub(crate) fn matches_refs<'a>(&self, keys: impl IntoIterator<Item = KeyRef<'a>>) -> bool {
+        let mut keys = keys.into_iter();
+        self.segments.iter().all(|segment| {
+            keys.next().is_some_and(|key| match (segment, key) {
+                (Segment::Key(Key::Idx(expected)), KeyRef::Idx(idx)) => *expected == idx,
+                (Segment::Key(Key::Field(expected)), KeyRef::Field(field)) => expected == field,
+                (Segment::Key(_), _) => false,
+                (Segment::Wildcard, _) => true,
+            })
+        })
+    }
+
+    /// Returns the number of segments, which is the depth of the paths the pattern matches
+    /// exactly.
+    pub(crate) fn depth(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Ranks overlapping patterns. Longer patterns are more specific, and so are patterns with
+    /// fewer wildcards.
+    pub(crate) fn specificity(&self) -> (usize, usize) {
+        let literals = self
+            .segments
+            .iter()
+            .filter(|segment| matches!(segment, Segment::Key(_)))
+            .count();
+        (self.segments.len(), literals)
+    }
+}
+
+impl FromStr for PathPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Ok(PathPattern {
+            segments: parse_segments(pattern)?,
+        })
+    }
+}
+
+impl fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "$");
+        }
+        for segment in &self.segments {
+            match segment {
+                Segment::Key(key) => write!(f, "{}", key)?,
+                Segment::Wildcard => write!(f, ".*")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A regular expression matching the text of paths, such as `_at$`.
+///
+/// Paths are matched in the format used by failure messages, such as `.data.users[0].name`, where
+/// the root is `(root)`. Created by
+/// [`Config::ignore_paths_matching`](crate::Config::ignore_paths_matching).
+///
+/// Requires the `regex` feature.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct PathRegex(pub regex::Regex);
+
+#[cfg(feature = "regex")]
+impl PathRegex {
+    /// Returns true if the text of `path` matches the regular expression.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.0.is_match(&path.to_string())
+    }
+}
+
+/// Path regexes are equal if their regular expressions are written the same.
+#[cfg(feature = "regex")]
+impl PartialEq for PathRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Segment {
+    Key(Key),
+    Wildcard,
+}
+
+/// Parses a path or pattern into its segments. See the `FromStr` implementation of [`Path`].
+pub(crate) fn parse_segments(path: &str) -> Result<Vec<Segment>, String> {
+    let invalid = |reason: &str| format!("Invalid path {:?}: {}", path, reason);
+
+    let mut rest = path.trim();
+    if rest == "(root)" {
+        return Ok(vec![]);
+    }
+    rest = rest.strip_prefix('$').unwrap_or(rest);
+    let dotted;
+    if !rest.is_empty() && !rest.starts_with(['.', '[']) {
+        dotted = format!(".{}", rest);
+        rest = &dotted;
+    }
+
+    let mut segments = vec![];
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            if after_dot.starts_with("[\"") {
+                // A dot before a quoted field, as in `.["a.b"]`, is allowed.
+                rest = after_dot;
+                continue;
+            }
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            segments.push(match &after_dot[..end] {
+                "" => return Err(invalid("expected a field name after `.`")),
+                "*" => Segment::Wildcard,
+                field => Segment::Key(Key::Field(field.to_owned())),
+            });
+            rest = &after_dot[end..];
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            if after_bracket.starts_with('"') {
+                let end = quoted_len(after_bracket)
+                    .ok_or_else(|| invalid("unterminated quoted field"))?;
+                let field = serde_json::from_str(&after_bracket[..end])
+                    .map_err(|err| invalid(&err.to_string()))?;
+                segments.push(Segment::Key(Key::Field(field)));
+                rest = after_bracket[end..]
+                    .strip_prefix(']')
+                    .ok_or_else(|| invalid("expected `]` after quoted field"))?;
+            } else {
+                let end = after_bracket
+                    .find(']')
+                    .ok_or_else(|| invalid("expected `]` after index"))?;
+                segments.push(match after_bracket[..end].trim() {
+                    "*" => Segment::Wildcard,
+                    idx => {
+                        Segment::Key(Key::Idx(idx.parse().map_err(|_| {
+                            invalid("array indexes must be non-negative integers")
+                        })?))
+                    }
+                });
+                rest = &after_bracket[end + 1..];
+            }
+        } else {
+            return Err(invalid("expected `.` or `[`"));
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Returns the length of the JSON string literal at the start of `input`, including both quotes.
+fn quoted_len(input: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in input.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(idx + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        let pattern: PathPattern = pattern.parse().unwrap();
+        pattern.matches(&path.parse().unwrap())
+    }
+
+    #[test]
+    fn wildcards_and_ancestors() {
+        assert!(matches(".a.*", ".a.b"));
+        assert!(matches(".a.*", ".a[0].c"));
+        assert!(matches("$.a[*].b", ".a[2].b.c"));
+        assert!(matches(".a", ".a.b"));
+        assert!(matches("$", ".a"));
+        assert!(matches(r#"["*"]"#, r#"["*"]"#));
+
+        assert!(!matches(".a.*", ".a"));
+        assert!(!matches(".a[*].b", ".a[2].c"));
+        assert!(!matches(r#"["*"]"#, ".a"));
+        assert!(matches(r#".["a.b"]"#, r#"["a.b"].c"#));
+        assert!(!matches(r#"["a.b"]"#, ".a.b"));
+    }
+
+    #[test]
+    fn display() {
+        for pattern in [".a.*.b[1]", "$"] {
+            assert_eq!(pattern.parse::<PathPattern>().unwrap().to_string(), pattern);
+        }
+        assert!("[*".parse::<PathPattern>().is_err());
+
+        for (pattern, display) in [
+            (r#"["a.b"]["*"].*"#, r#"["a.b"]["*"].*"#),
+            (r#".["a.b"][""].c"#, r#"["a.b"][""].c"#),
+            (r#"["a"]["b c"]["[0]"]"#, r#".a["b c"]["[0]"]"#),
+            (r#"["\"quoted\""]"#, r#"["\"quoted\""]"#),
+        ] {
+            let parsed = pattern.parse::<PathPattern>().unwrap();
+            assert_eq!(parsed.to_string(), display);
+            assert_eq!(display.parse::<PathPattern>().unwrap(), parsed);
+        }
+        assert!(".a.*".parse::<Path>().is_err());
+    }
+}
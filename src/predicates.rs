@@ -0,0 +1,216 @@
+//! Integration with the [predicates](https://docs.rs/predicates) crate, as used by
+//! [assert_cmd](https://docs.rs/assert_cmd) and [assert_fs](https://docs.rs/assert_fs).
+//!
+//! The predicates compare JSON text, such as the output of a command or the contents of a file,
+//! to an expected value, and show the differences when they don't match.
+//!
+//! ```ignore
+//! use assert_cmd::Command;
+//! use serde_json::json;
+//! use serde_json_assert::predicates::json_include;
+//!
+//! Command::cargo_bin("my-cli")
+//!     .unwrap()
+//!     .arg("status")
+//!     .assert()
+//!     .success()
+//!     .stdout(json_include(json!({ "status": "ok" })));
+//! ```
+//!
+//! Requires the `predicates` feature.
+
+use crate::parse::parse;
+use crate::{assert_json_matches_no_panic, check_nesting, CompareMode, Config};
+use ::predicates_core::reflection::{Case, PredicateReflection, Product};
+use ::predicates_core::Predicate;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// A predicate that compares JSON values, or JSON text, to an expected value according to a
+/// [`Config`].
+///
+/// The value being checked is the left-hand side, or "actual", value of the comparison. Create
+/// one with [`json_eq`], [`json_include`] or [`json_matches`].
+///
+/// ```
+/// use predicates_core::Predicate;
+/// use serde_json::json;
+/// use serde_json_assert::predicates::json_include;
+///
+/// let predicate = json_include(json!({ "status": "ok" }));
+/// assert!(predicate.eval(r#"{ "status": "ok", "uptime": 120 }"#));
+/// assert!(predicate.eval(b"{ \"status\": \"ok\" }".as_slice()));
+/// assert!(!predicate.eval(&json!({ "status": "degraded" })));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPredicate {
+    expected: Value,
+    config: Config,
+}
+
+/// Matches values that are exactly equal to `expected` when serialized to JSON.
+///
+/// The predicates counterpart of [`assert_json_eq`](crate::assert_json_eq).
+#[track_caller]
+pub fn json_eq<T>(expected: T) -> JsonPredicate
+where
+    T: Serialize,
+{
+    json_matches(expected, Config::new(CompareMode::Strict))
+}
+
+/// Matches values that include `expected` when serialized to JSON.
+///
+/// The predicates counterpart of [`assert_json_include`](crate::assert_json_include).
+#[track_caller]
+pub fn json_include<T>(expected: T) -> JsonPredicate
+where
+    T: Serialize,
+{
+    json_matches(expected, Config::new(CompareMode::Inclusive))
+}
+
+/// Matches values that match `expected` according to `config` when serialized to JSON.
+///
+/// The predicates counterpart of [`assert_json_matches`](crate::assert_json_matches).
+///
+/// # Panics
+///
+/// Panics if `expected` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn json_matches<T>(expected: T, config: Config) -> JsonPredicate
+where
+    T: Serialize,
+{
+    check_nesting(&[("expected", &expected)], &config).unwrap_or_else(|limit| panic!("{}", limit));
+    let expected = serde_json::to_value(expected).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't convert expected value to JSON. Serde error: {}",
+            err
+        )
+    });
+    JsonPredicate { expected, config }
+}
+
+impl JsonPredicate {
+    fn check(&self, actual: &Value) -> Result<(), String> {
+        assert_json_matches_no_panic(actual, &self.expected, &self.config)
+    }
+
+    fn check_text(&self, text: &[u8]) -> Result<(), String> {
+        let actual = parse(text)
+            .map_err(|err| format!("Couldn't parse actual value as JSON. Error: {}", err))?;
+        self.check(&actual)
+    }
+
+    fn case(&self, expected: bool, result: Result<(), String>) -> Option<Case<'_>> {
+        if result.is_ok() != expected {
+            return None;
+        }
+        let case = Case::new(Some(self), result.is_ok());
+        Some(match result {
+            Ok(()) => case,
+            Err(error) => case.add_product(Product::new("differences", Indented(error))),
+        })
+    }
+}
+
+impl fmt::Display for JsonPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let relation = match self.config.compare_mode {
+            CompareMode::Inclusive => "includes",
+            CompareMode::Strict => "is equal to",
+        };
+        write!(f, "var {} json {}", relation, self.expected)
+    }
+}
+
+impl PredicateReflection for JsonPredicate {}
+
+impl Predicate<Value> for JsonPredicate {
+    fn eval(&self, variable: &Value) -> bool {
+        self.check(variable).is_ok()
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, variable: &Value) -> Option<Case<'a>> {
+        self.case(expected, self.check(variable))
+    }
+}
+
+impl Predicate<str> for JsonPredicate {
+    fn eval(&self, variable: &str) -> bool {
+        self.check_text(variable.as_bytes()).is_ok()
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, variable: &str) -> Option<Case<'a>> {
+        self.case(expected, self.check_text(variable.as_bytes()))
+    }
+}
+
+impl Predicate<[u8]> for JsonPredicate {
+    fn eval(&self, variable: &[u8]) -> bool {
+        self.check_text(variable).is_ok()
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, variable: &[u8]) -> Option<Case<'a>> {
+        self.case(expected, self.check_text(variable))
+    }
+}
+
+/// A multi-line product, starting on its own line so it lines up in the failure tree.
+struct Indented(String);
+
+impl fmt::Display for Indented {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matches_json() {
+        let actual = json!({ "a": 1, "b": [1, 2] });
+
+        assert!(json_include(json!({ "b": [1] })).eval(&actual));
+        assert!(json_eq(json!({ "a": 1, "b": [1, 2] })).eval(&actual));
+        assert!(!json_eq(json!({ "a": 1 })).eval(&actual));
+        assert!(json_eq(json!({ "a": 1 })).eval(r#"{ "a": 1 }"#));
+        assert!(!json_eq(json!({ "a": 1 })).eval(b"{ \"a\": ".as_slice()));
+        assert_eq!(
+            json_include(json!({ "a": 1 })).to_string(),
+            r#"var includes json {"a":1}"#
+        );
+    }
+
+    #[test]
+    fn reports_differences() {
+        let predicate = json_include(json!({ "a": 2 }));
+        let case = predicate.find_case(false, r#"{ "a": 1 }"#).unwrap();
+        assert!(!case.result());
+        let products = case.products().collect::<Vec<_>>();
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0].name(), "differences");
+        assert!(products[0]
+            .value()
+            .to_string()
+            .starts_with("\njson atoms at path \".a\" are not equal:"));
+
+        let case = predicate.find_case(false, "not json").unwrap();
+        assert!(case
+            .products()
+            .next()
+            .unwrap()
+            .value()
+            .to_string()
+            .starts_with("\nCouldn't parse actual value as JSON."));
+
+        assert!(predicate.find_case(true, r#"{ "a": 1 }"#).is_none());
+        assert!(predicate.find_case(true, &json!({ "a": 2 })).is_some());
+    }
+}
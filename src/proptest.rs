@@ -0,0 +1,254 @@
+//! Strategies and invariant checks for testing with [proptest](https://docs.rs/proptest).
+//!
+//! [`arb_value`] generates arbitrary JSON documents, and [`arb_value_pair`] generates pairs of
+//! documents that differ in known places. The `check_*` functions verify properties that every
+//! comparison should have, and return a [`TestCaseError`] so they can be used with `?` inside
+//! `proptest!`.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use serde_json_assert::proptest::{arb_value_pair, check_known_differences};
+//!
+//! proptest!(|(pair in arb_value_pair())| {
+//!     check_known_differences(&pair)?;
+//! });
+//! ```
+//!
+//! Requires the `proptest` feature.
+
+use crate::diff::{diff, Difference, Key, Path};
+use crate::{CompareMode, Config};
+use ::proptest::collection::{btree_map, vec};
+use ::proptest::prelude::*;
+use ::proptest::sample::Index;
+use ::proptest::test_runner::TestCaseError;
+use serde_json::{Number, Value};
+
+/// Generates arbitrary JSON scalars: null, booleans, integers, finite floats and short strings.
+pub fn arb_scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>()
+            .prop_filter_map("float isn't finite", Number::from_f64)
+            .prop_map(Value::Number),
+        "[a-z0-9 ]{0,8}".prop_map(Value::String),
+    ]
+}
+
+/// Generates arbitrary JSON documents up to a few levels deep.
+pub fn arb_value() -> impl Strategy<Value = Value> {
+    arb_scalar().prop_recursive(4, 32, 6, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..6).prop_map(Value::Array),
+            btree_map("[a-z]{1,4}", inner, 0..6)
+                .prop_map(|fields| Value::Object(fields.into_iter().collect())),
+        ]
+    })
+}
+
+/// Two JSON documents and the places where they differ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValuePair {
+    /// The original document.
+    pub lhs: Value,
+    /// A copy of `lhs` with some values changed, removed or added.
+    pub rhs: Value,
+    /// The paths of the differences between `lhs` and `rhs`, as reported by a strict
+    /// comparison, in no particular order.
+    pub differences: Vec<Path>,
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Replace(Value),
+    Remove,
+    Insert(String, Value),
+}
+
+fn arb_edit() -> impl Strategy<Value = Edit> {
+    prop_oneof![
+        arb_value().prop_map(Edit::Replace),
+        Just(Edit::Remove),
+        ("[a-z]{1,4}", arb_value()).prop_map(|(key, value)| Edit::Insert(key, value)),
+    ]
+}
+
+/// Generates pairs of documents where the second one differs from the first in up to four places.
+///
+/// Each edit replaces a value by a different scalar or a value of another type, removes a field
+/// from an object, or adds a field to an object.
+/// Edits never overlap, so each one results in exactly one difference, whose path is recorded in
+/// [`ValuePair::differences`].
+pub fn arb_value_pair() -> impl Strategy<Value = ValuePair> {
+    (arb_value(), vec((any::<Index>(), arb_edit()), 0..5)).prop_map(|(lhs, edits)| {
+        let mut nodes = vec![];
+        collect_paths(&lhs, &mut vec![], &mut nodes);
+
+        let mut rhs = lhs.clone();
+        let mut edited: Vec<Vec<Key>> = vec![];
+        for (index, edit) in edits {
+            let mut keys = nodes[index.index(nodes.len())].clone();
+            match edit {
+                Edit::Replace(value) if !overlaps(&keys, &edited) => {
+                    // Arrays and objects replaced by ones of the same kind differ in their items.
+                    let target = get_mut(&mut rhs, &keys).unwrap();
+                    if value == *target
+                        || (value.is_array() && target.is_array())
+                        || (value.is_object() && target.is_object())
+                    {
+                        continue;
+                    }
+                    *target = value;
+                }
+                Edit::Remove if !overlaps(&keys, &edited) => {
+                    let Some(Key::Field(key)) = keys.last() else {
+                        continue;
+                    };
+                    let parent = get_mut(&mut rhs, &keys[..keys.len() - 1]).unwrap();
+                    parent.as_object_mut().unwrap().remove(key);
+                }
+                // Fields can be added next to edited ones, but not below them.
+                Edit::Insert(key, value)
+                    if !edited.iter().any(|edited| keys.starts_with(edited)) =>
+                {
+                    let Value::Object(fields) = get_mut(&mut rhs, &keys).unwrap() else {
+                        continue;
+                    };
+                    keys.push(Key::Field(key.clone()));
+                    if fields.contains_key(&key) || overlaps(&keys, &edited) {
+                        continue;
+                    }
+                    fields.insert(key, value);
+                }
+                Edit::Replace(_) | Edit::Remove | Edit::Insert(..) => continue,
+            }
+            edited.push(keys);
+        }
+
+        ValuePair {
+            lhs,
+            rhs,
+            differences: edited.into_iter().map(Path::from_keys).collect(),
+        }
+    })
+}
+
+/// Returns true if `keys` is a prefix of one of the `edited` paths, or the other way around.
+fn overlaps(keys: &[Key], edited: &[Vec<Key>]) -> bool {
+    edited
+        .iter()
+        .any(|edited| edited.starts_with(keys) || keys.starts_with(edited))
+}
+
+fn collect_paths(value: &Value, keys: &mut Vec<Key>, paths: &mut Vec<Vec<Key>>) {
+    paths.push(keys.clone());
+    match value {
+        Value::Array(items) => {
+            for (idx, item) in items.iter().enumerate() {
+                keys.push(Key::Idx(idx));
+                collect_paths(item, keys, paths);
+                keys.pop();
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                keys.push(Key::Field(key.clone()));
+                collect_paths(field, keys, paths);
+                keys.pop();
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
+
+fn get_mut<'a>(value: &'a mut Value, keys: &[Key]) -> Option<&'a mut Value> {
+    keys.iter().try_fold(value, |value, key| match key {
+        Key::Idx(idx) => value.get_mut(*idx),
+        Key::Field(field) => value.get_mut(field),
+    })
+}
+
+/// Checks that a strict comparison of `pair` finds exactly its known differences.
+pub fn check_known_differences(pair: &ValuePair) -> Result<(), TestCaseError> {
+    let config = Config::new(CompareMode::Strict);
+    let mut found = paths(&diff(&pair.lhs, &pair.rhs, &config));
+    let mut expected = pair
+        .differences
+        .iter()
+        .map(Path::to_string)
+        .collect::<Vec<_>>();
+    found.sort();
+    expected.sort();
+    prop_assert_eq!(found, expected);
+    Ok(())
+}
+
+/// Checks that a strict comparison finds the same differences in both directions, with the sides
+/// of each difference swapped.
+pub fn check_strict_symmetry(lhs: &Value, rhs: &Value) -> Result<(), TestCaseError> {
+    let config = Config::new(CompareMode::Strict);
+    let mut forward = diff(lhs, rhs, &config)
+        .into_iter()
+        .map(|diff| {
+            (
+                diff.path().to_string(),
+                diff.actual().clone(),
+                diff.expected().clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut backward = diff(rhs, lhs, &config)
+        .into_iter()
+        .map(|diff| {
+            (
+                diff.path().to_string(),
+                diff.expected().clone(),
+                diff.actual().clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    forward.sort_by_cached_key(|diff| format!("{:?}", diff));
+    backward.sort_by_cached_key(|diff| format!("{:?}", diff));
+    prop_assert_eq!(forward, backward);
+    Ok(())
+}
+
+/// Checks that a strict comparison finds no differences if and only if the values are equal.
+pub fn check_empty_diff_iff_equal(lhs: &Value, rhs: &Value) -> Result<(), TestCaseError> {
+    let config = Config::new(CompareMode::Strict);
+    let diffs = diff(lhs, rhs, &config);
+    prop_assert_eq!(
+        diffs.is_empty(),
+        lhs == rhs,
+        "differences: {:?}",
+        paths(&diffs)
+    );
+    Ok(())
+}
+
+fn paths(diffs: &[Difference]) -> Vec<String> {
+    diffs.iter().map(|diff| diff.path().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn generated_differences_are_found(pair in arb_value_pair()) {
+            check_known_differences(&pair)?;
+            check_strict_symmetry(&pair.lhs, &pair.rhs)?;
+            check_empty_diff_iff_equal(&pair.lhs, &pair.rhs)?;
+        }
+
+        #[test]
+        fn values_equal_themselves(value in arb_value()) {
+            check_empty_diff_iff_equal(&value, &value.clone())?;
+        }
+    }
+}
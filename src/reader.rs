@@ -0,0 +1,123 @@
+use crate::parse::parse;
+use crate::report::Mismatch;
+use crate::{check_json_matches, Config};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Compares two JSON documents read from [`Read`]ers, panicking if they can't be read or parsed
+/// or don't match.
+///
+/// Both readers are read to the end and parsed, like
+/// [`assert_raw_json_matches_no_panic`](crate::assert_raw_json_matches_no_panic). This is
+/// convenient for comparing files, sockets or decompressed streams.
+///
+/// ```no_run
+/// use serde_json_assert::{assert_json_matches_reader, CompareMode, Config};
+/// use std::fs::File;
+///
+/// # fn main() -> std::io::Result<()> {
+/// assert_json_matches_reader(
+///     File::open("tests/fixtures/actual.json")?,
+///     File::open("tests/fixtures/expected.json")?,
+///     &Config::new(CompareMode::Strict),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[track_caller]
+pub fn assert_json_matches_reader<Lhs, Rhs>(lhs: Lhs, rhs: Rhs, config: &Config)
+where
+    Lhs: Read,
+    Rhs: Read,
+{
+    if let Err(error) = assert_json_matches_reader_no_panic(lhs, rhs, config) {
+        panic!("\n{}\n\nat {}", error, std::panic::Location::caller());
+    }
+}
+
+/// Compares two JSON documents read from [`Read`]ers without panicking.
+///
+/// Returns a [`ReaderError`] telling apart failures to read or parse either side from documents
+/// that don't match. See [`assert_json_matches_reader`] for the panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_matches_reader_no_panic, CompareMode, Config, ReaderError};
+///
+/// let config = Config::new(CompareMode::Inclusive);
+///
+/// let result = assert_json_matches_reader_no_panic("[1]".as_bytes(), "[2]".as_bytes(), &config);
+/// assert!(matches!(result, Err(ReaderError::Mismatch(_))));
+///
+/// let result = assert_json_matches_reader_no_panic("[1]".as_bytes(), "[1,".as_bytes(), &config);
+/// assert!(matches!(result, Err(ReaderError::ParseRhs(_))));
+/// ```
+pub fn assert_json_matches_reader_no_panic<Lhs, Rhs>(
+    mut lhs: Lhs,
+    mut rhs: Rhs,
+    config: &Config,
+) -> Result<(), ReaderError>
+where
+    Lhs: Read,
+    Rhs: Read,
+{
+    let mut lhs_buf = vec![];
+    lhs.read_to_end(&mut lhs_buf)
+        .map_err(ReaderError::ReadLhs)?;
+    let mut rhs_buf = vec![];
+    rhs.read_to_end(&mut rhs_buf)
+        .map_err(ReaderError::ReadRhs)?;
+
+    let lhs = parse(&lhs_buf).map_err(ReaderError::ParseLhs)?;
+    let rhs = parse(&rhs_buf).map_err(ReaderError::ParseRhs)?;
+    check_json_matches(&lhs, &rhs, config)
+        .map(|_| ())
+        .map_err(ReaderError::Mismatch)
+}
+
+/// Why comparing documents read by [`assert_json_matches_reader_no_panic`] failed.
+///
+/// It displays as the message [`assert_json_matches_reader`] panics with.
+#[derive(Debug)]
+pub enum ReaderError {
+    /// The left hand side couldn't be read.
+    ReadLhs(io::Error),
+    /// The right hand side couldn't be read.
+    ReadRhs(io::Error),
+    /// The left hand side isn't valid JSON, with the parser's error message.
+    ParseLhs(String),
+    /// The right hand side isn't valid JSON, with the parser's error message.
+    ParseRhs(String),
+    /// The documents were parsed, but don't match.
+    Mismatch(Mismatch),
+}
+
+impl fmt::Display for ReaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReaderError::ReadLhs(err) => {
+                write!(f, "Couldn't read left hand side. IO error: {}", err)
+            }
+            ReaderError::ReadRhs(err) => {
+                write!(f, "Couldn't read right hand side. IO error: {}", err)
+            }
+            ReaderError::ParseLhs(err) => {
+                write!(f, "Couldn't parse left hand side as JSON. Error: {}", err)
+            }
+            ReaderError::ParseRhs(err) => {
+                write!(f, "Couldn't parse right hand side as JSON. Error: {}", err)
+            }
+            ReaderError::Mismatch(mismatch) => write!(f, "{}", mismatch),
+        }
+    }
+}
+
+impl Error for ReaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReaderError::ReadLhs(err) | ReaderError::ReadRhs(err) => Some(err),
+            ReaderError::Mismatch(mismatch) => Some(mismatch),
+            ReaderError::ParseLhs(_) | ReaderError::ParseRhs(_) => None,
+        }
+    }
+}
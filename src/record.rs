@@ -0,0 +1,180 @@
+use crate::{check_json_matches, Config, Mismatch};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// Compares `actual` to a value recorded in the file at `recording`, recording it first if needed.
+///
+/// If the file doesn't exist, or the `JSON_ASSERT_RECORD` environment variable is set to anything
+/// but `0` or `false`, `actual` is written to the file as pretty printed JSON and the check
+/// succeeds. Otherwise `actual` is compared to the recorded value using `config`, which works like
+/// comparing it to an expected value.
+///
+/// When the `CI` environment variable is set the same way, as most CI services do, missing
+/// recordings aren't created and the check panics instead, so a recording that wasn't committed
+/// can't make the test pass. `JSON_ASSERT_RECORD` still records them.
+///
+/// The [`assert_json_recorded!`](crate::assert_json_recorded) macro names recordings after the
+/// current test.
+///
+/// ```
+/// use serde_json_assert::{check_json_recording, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let recording = std::env::temp_dir().join("serde-json-assert-doc-recording.json");
+/// // Made by a previous run.
+/// # std::fs::write(&recording, r#"{ "score": 0.5 }"#).unwrap();
+/// let config = Config::new(CompareMode::Strict).float_compare_mode(
+///     serde_json_assert::FloatCompareMode::Epsilon(0.01),
+/// );
+///
+/// check_json_recording(&recording, &json!({ "score": 0.501 }), &config).unwrap();
+/// assert!(check_json_recording(&recording, &json!({ "score": 0.6 }), &config).is_err());
+/// ```
+///
+/// # Panics
+///
+/// Panics if the recording can't be read, parsed or written, or is missing on CI.
+#[track_caller]
+pub fn check_json_recording<T>(
+    recording: impl AsRef<Path>,
+    actual: &T,
+    config: &Config,
+) -> Result<(), Mismatch>
+where
+    T: Serialize,
+{
+    let mode = if env_flag("JSON_ASSERT_RECORD") {
+        Record::Always
+    } else if env_flag("CI") {
+        Record::Never
+    } else {
+        Record::IfMissing
+    };
+    check_recording(recording.as_ref(), actual, config, mode)
+}
+
+/// Whether the environment variable `name` is set to something other than `0` or `false`.
+fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| !value.is_empty() && value != "0" && value != "false")
+}
+
+/// When [`check_json_recording`] writes the recording instead of comparing to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Record {
+    Always,
+    IfMissing,
+    Never,
+}
+
+#[track_caller]
+fn check_recording<T>(
+    recording: &Path,
+    actual: &T,
+    config: &Config,
+    mode: Record,
+) -> Result<(), Mismatch>
+where
+    T: Serialize,
+{
+    let missing = !recording.exists();
+    if missing && mode == Record::Never {
+        panic!(
+            "Recording {} doesn't exist and isn't created on CI, run the test with JSON_ASSERT_RECORD=1 and commit it",
+            recording.display()
+        );
+    }
+    if mode == Record::Always || missing {
+        let actual = serde_json::to_string_pretty(actual).unwrap_or_else(|err| {
+            panic!(
+                "Couldn't convert actual value to JSON. Serde error: {}",
+                err
+            )
+        });
+        if let Some(dir) = recording.parent() {
+            fs::create_dir_all(dir).unwrap_or_else(|err| {
+                panic!("Couldn't create directory {}: {}", dir.display(), err)
+            });
+        }
+        fs::write(recording, actual + "\n").unwrap_or_else(|err| {
+            panic!("Couldn't write recording {}: {}", recording.display(), err)
+        });
+        eprintln!("recorded {}", recording.display());
+        return Ok(());
+    }
+
+    let recorded = fs::read_to_string(recording)
+        .unwrap_or_else(|err| panic!("Couldn't read recording {}: {}", recording.display(), err));
+    let recorded: serde_json::Value = serde_json::from_str(&recorded)
+        .unwrap_or_else(|err| panic!("Couldn't parse recording {}: {}", recording.display(), err));
+    check_json_matches(actual, &recorded, config).map(|_| ())
+}
+
+/// Returns the path of the recording for the current test, in `tests/recordings` of the crate
+/// at `manifest_dir`.
+#[doc(hidden)]
+#[track_caller]
+pub fn __recording_path(manifest_dir: &str) -> PathBuf {
+    // The test harness runs every test on a thread named after it.
+    let thread = std::thread::current();
+    let test = match thread.name() {
+        Some(name) if name != "main" => name,
+        _ => panic!("Recordings can only be named after tests run by the standard test harness, use `check_json_recording` instead"),
+    };
+    Path::new(manifest_dir)
+        .join("tests")
+        .join("recordings")
+        .join(format!("{}.json", test.replace("::", "__")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn records_then_replays() {
+        let dir = env::temp_dir().join(format!("serde-json-assert-{}", std::process::id()));
+        let recording = dir.join("nested").join("value.json");
+        let config = Config::new(CompareMode::Inclusive);
+
+        let recorded = json!({ "id": 1, "tags": ["a"] });
+        assert!(check_recording(&recording, &recorded, &config, Record::IfMissing).is_ok());
+        assert_eq!(
+            fs::read_to_string(&recording).unwrap(),
+            serde_json::to_string_pretty(&recorded).unwrap() + "\n"
+        );
+
+        let actual = json!({ "id": 1, "tags": ["a"], "extra": true });
+        assert!(check_recording(&recording, &actual, &config, Record::Never).is_ok());
+        let actual = json!({ "id": 2, "tags": ["a"] });
+        assert!(check_recording(&recording, &actual, &config, Record::IfMissing).is_err());
+
+        // Forcing replaces the recording.
+        assert!(check_recording(&recording, &actual, &config, Record::Always).is_ok());
+        assert!(check_recording(&recording, &actual, &config, Record::IfMissing).is_ok());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't created on CI")]
+    fn missing_recording_fails_on_ci() {
+        let recording = env::temp_dir().join("serde-json-assert-missing-recording.json");
+        let _ = check_recording(
+            &recording,
+            &json!(1),
+            &Config::new(CompareMode::Strict),
+            Record::Never,
+        );
+    }
+
+    #[test]
+    fn named_after_test() {
+        assert_eq!(
+            __recording_path("/crate"),
+            Path::new("/crate/tests/recordings/record__tests__named_after_test.json")
+        );
+    }
+}
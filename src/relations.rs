@@ -0,0 +1,355 @@
+use crate::arrays::{natural_order, Aggregate};
+use crate::diff::{diff, floats_equal, Key, Path};
+use crate::minimize::get;
+use crate::paths::__to_value;
+use crate::pattern::{parse_segments, Segment};
+use crate::{check_nesting, Config};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// How the two sides of a constraint checked by
+/// [`assert_json_relation!`](crate::assert_json_relation) relate to each other.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Relation {
+    /// The values are equal. Numbers are compared by value according to the
+    /// [`FloatCompareMode`](crate::FloatCompareMode), other values like in
+    /// [`assert_json_matches!`](crate::assert_json_matches).
+    Equal,
+    /// The values aren't equal, in the sense of [`Relation::Equal`].
+    NotEqual,
+    /// The lhs is ordered before the rhs.
+    Less,
+    /// The lhs is ordered before the rhs, or equal to it.
+    LessOrEqual,
+    /// The lhs is ordered after the rhs.
+    Greater,
+    /// The lhs is ordered after the rhs, or equal to it.
+    GreaterOrEqual,
+}
+
+impl Relation {
+    fn symbol(self) -> &'static str {
+        match self {
+            Relation::Equal => "==",
+            Relation::NotEqual => "!=",
+            Relation::Less => "<",
+            Relation::LessOrEqual => "<=",
+            Relation::Greater => ">",
+            Relation::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// One side of a constraint.
+enum Operand {
+    /// The value at a path.
+    Path(Path),
+    /// An aggregate of the numbers matching a pattern.
+    Aggregate(Aggregate, Vec<Segment>),
+    /// The number of values matching a pattern.
+    Count(Vec<Segment>),
+}
+
+impl Operand {
+    fn parse(text: &str) -> Result<Self, String> {
+        let function = text
+            .split_once('(')
+            .and_then(|(name, rest)| Some((name.trim(), rest.strip_suffix(')')?)));
+        let Some((name, pattern)) = function else {
+            return text.parse().map(Operand::Path);
+        };
+        let aggregate = match name {
+            "sum" => Aggregate::Sum,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            "avg" => Aggregate::Average,
+            "count" => return Ok(Operand::Count(parse_segments(pattern)?)),
+            _ => return text.parse().map(Operand::Path),
+        };
+        Ok(Operand::Aggregate(aggregate, parse_segments(pattern)?))
+    }
+
+    fn evaluate(&self, actual: &Value, text: &str) -> Result<Value, String> {
+        match self {
+            Operand::Path(path) => get(actual, path.keys())
+                .cloned()
+                .ok_or_else(|| format!("json atom at path \"{}\" is missing", path)),
+            Operand::Aggregate(aggregate, segments) => {
+                let mut numbers = vec![];
+                for (path, value) in select(actual, segments)? {
+                    match value.as_f64() {
+                        Some(number) => numbers.push(number),
+                        None => {
+                            return Err(format!(
+                                "json atom at path \"{}\" is not a number:\n    {}",
+                                path, value
+                            ))
+                        }
+                    }
+                }
+                aggregate
+                    .apply(&numbers)
+                    .map(Value::from)
+                    .ok_or_else(|| format!("\"{}\" is undefined, because no values match", text))
+            }
+            Operand::Count(segments) => Ok(Value::from(select(actual, segments)?.len())),
+        }
+    }
+}
+
+/// Checks a constraint relating two values of the same document, without panicking.
+///
+/// Each side is either a path, such as `.start_date`, or a function of the values matching a
+/// pattern such as `.items[*].price`: `sum(..)`, `min(..)`, `max(..)` and `avg(..)` of numbers,
+/// or `count(..)` of any values. Numbers are ordered by value and strings by their bytes, which
+/// suits ISO 8601 dates. Returns a `Result` where the error is the message that would be passed
+/// to `panic!`, showing both sides and their values.
+///
+/// ```
+/// use serde_json_assert::{assert_json_relation_no_panic, CompareMode, Config, Relation};
+/// use serde_json::json;
+///
+/// let order = json!({
+///     "total": 30,
+///     "items": [{ "price": 10 }, { "price": 19.5 }],
+///     "start_date": "2024-05-01",
+///     "end_date": "2024-04-01",
+/// });
+/// let config = Config::new(CompareMode::Strict);
+///
+/// assert_eq!(
+///     assert_json_relation_no_panic(&order, ".total", Relation::Equal, "sum(.items[*].price)", &config)
+///         .unwrap_err(),
+///     r#"json constraint ".total == sum(.items[*].price)" is not satisfied:
+///     .total:               30
+///     sum(.items[*].price): 29.5"#
+/// );
+/// assert!(
+///     assert_json_relation_no_panic(&order, ".start_date", Relation::LessOrEqual, ".end_date", &config)
+///         .is_err()
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be parsed, or if `actual` can't be serialized.
+#[track_caller]
+pub fn assert_json_relation_no_panic<T>(
+    actual: &T,
+    lhs: &str,
+    relation: Relation,
+    rhs: &str,
+    config: &Config,
+) -> Result<(), String>
+where
+    T: Serialize + ?Sized,
+{
+    check_nesting(&[("actual", &actual)], config).map_err(|limit| limit.to_string())?;
+    let actual = __to_value(actual);
+    let (lhs, rhs) = (lhs.trim(), rhs.trim());
+    let lhs_value = Operand::parse(lhs)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .evaluate(&actual, lhs)?;
+    let rhs_value = Operand::parse(rhs)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .evaluate(&actual, rhs)?;
+
+    let constraint = format!("{} {} {}", lhs, relation.symbol(), rhs);
+    let ordering = natural_order(&lhs_value, &rhs_value);
+    let is_satisfied = match relation {
+        Relation::Equal => are_equal(&lhs_value, &rhs_value, ordering, config),
+        Relation::NotEqual => !are_equal(&lhs_value, &rhs_value, ordering, config),
+        Relation::Less | Relation::LessOrEqual | Relation::Greater | Relation::GreaterOrEqual => {
+            let Some(ordering) = ordering else {
+                return Err(format!(
+                    "json constraint \"{}\" can't be checked, because the values can't be ordered:{}",
+                    constraint,
+                    values(lhs, &lhs_value, rhs, &rhs_value)
+                ));
+            };
+            match relation {
+                Relation::Less => ordering == Ordering::Less,
+                Relation::LessOrEqual => ordering != Ordering::Greater,
+                Relation::Greater => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }
+        }
+    };
+
+    if is_satisfied {
+        Ok(())
+    } else {
+        Err(format!(
+            "json constraint \"{}\" is not satisfied:{}",
+            constraint,
+            values(lhs, &lhs_value, rhs, &rhs_value)
+        ))
+    }
+}
+
+fn are_equal(lhs: &Value, rhs: &Value, ordering: Option<Ordering>, config: &Config) -> bool {
+    match (lhs.as_f64(), rhs.as_f64()) {
+        (Some(lhs_number), Some(rhs_number)) => {
+            ordering == Some(Ordering::Equal)
+                || floats_equal(lhs_number, rhs_number, config.float_compare_mode)
+        }
+        _ => diff(lhs, rhs, config).is_empty(),
+    }
+}
+
+/// Lists both sides of a constraint with their values, aligned.
+fn values(lhs: &str, lhs_value: &Value, rhs: &str, rhs_value: &Value) -> String {
+    let width = lhs.len().max(rhs.len()) + 1;
+    format!(
+        "\n    {:width$} {}\n    {:width$} {}",
+        format!("{}:", lhs),
+        lhs_value,
+        format!("{}:", rhs),
+        rhs_value,
+        width = width
+    )
+}
+
+/// Returns the values matching the pattern `segments`, with their paths.
+fn select<'a>(actual: &'a Value, segments: &[Segment]) -> Result<Vec<(Path, &'a Value)>, String> {
+    let mut selected = vec![];
+    select_into(actual, segments, &mut vec![], &mut selected)?;
+    Ok(selected)
+}
+
+fn select_into<'a>(
+    value: &'a Value,
+    segments: &[Segment],
+    keys: &mut Vec<Key>,
+    selected: &mut Vec<(Path, &'a Value)>,
+) -> Result<(), String> {
+    let Some((segment, rest)) = segments.split_first() else {
+        selected.push((Path::from_keys(keys.clone()), value));
+        return Ok(());
+    };
+
+    let children: Vec<(Key, &Value)> = match (segment, value) {
+        (Segment::Key(key), _) => {
+            let child = get(value, std::slice::from_ref(key));
+            keys.push(key.clone());
+            let Some(child) = child else {
+                return Err(format!(
+                    "json atom at path \"{}\" is missing",
+                    Path::from_keys(keys.clone())
+                ));
+            };
+            select_into(child, rest, keys, selected)?;
+            keys.pop();
+            return Ok(());
+        }
+        (Segment::Wildcard, Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| (Key::Idx(idx), item))
+            .collect(),
+        (Segment::Wildcard, Value::Object(fields)) => fields
+            .iter()
+            .map(|(field, value)| (Key::Field(field.clone()), value))
+            .collect(),
+        (Segment::Wildcard, _) => {
+            return Err(format!(
+                "json atom at path \"{}\" is not an array or object:\n    {}",
+                Path::from_keys(keys.clone()),
+                value
+            ))
+        }
+    };
+    for (key, child) in children {
+        keys.push(key);
+        select_into(child, rest, keys, selected)?;
+        keys.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompareMode, FloatCompareMode};
+    use serde_json::json;
+
+    fn check(actual: &Value, lhs: &str, relation: Relation, rhs: &str) -> Result<(), String> {
+        let config = Config::new(CompareMode::Strict);
+        assert_json_relation_no_panic(actual, lhs, relation, rhs, &config)
+    }
+
+    #[test]
+    fn relations() {
+        let actual = json!({
+            "total": 29.5,
+            "count": 2,
+            "items": [{ "price": 10 }, { "price": 19.5 }],
+            "start": "2024-01-01",
+            "end": "2024-02-01",
+            "tags": { "a": [1], "b": [1] },
+        });
+
+        check(&actual, ".total", Relation::Equal, "sum(.items[*].price)").unwrap();
+        check(&actual, ".count", Relation::Equal, "count(.items[*])").unwrap();
+        check(
+            &actual,
+            "max(.items[*].price)",
+            Relation::Greater,
+            "min(.items.*.price)",
+        )
+        .unwrap();
+        check(&actual, "avg(.items[*].price)", Relation::Less, ".total").unwrap();
+        check(&actual, ".start", Relation::Less, ".end").unwrap();
+        check(&actual, ".start", Relation::LessOrEqual, ".start").unwrap();
+        check(&actual, ".tags.a", Relation::Equal, ".tags.b").unwrap();
+        check(&actual, ".tags.a", Relation::NotEqual, ".items").unwrap();
+
+        assert_eq!(
+            check(&actual, ".end", Relation::GreaterOrEqual, ".count").unwrap_err(),
+            r#"json constraint ".end >= .count" can't be checked, because the values can't be ordered:
+    .end:   "2024-02-01"
+    .count: 2"#
+        );
+        assert_eq!(
+            check(&actual, "sum(.items[*].cost)", Relation::Equal, ".total").unwrap_err(),
+            r#"json atom at path ".items[0].cost" is missing"#
+        );
+        assert_eq!(
+            check(&actual, "min(.tags.c[*])", Relation::Equal, ".total").unwrap_err(),
+            r#"json atom at path ".tags.c" is missing"#
+        );
+        assert_eq!(
+            check(&actual, "max(.items[5])", Relation::Equal, ".total").unwrap_err(),
+            r#"json atom at path ".items[5]" is missing"#
+        );
+        assert_eq!(
+            check(&json!({ "a": [] }), "max(.a[*])", Relation::Equal, ".a").unwrap_err(),
+            r#""max(.a[*])" is undefined, because no values match"#
+        );
+    }
+
+    #[test]
+    fn float_compare_mode() {
+        let actual = json!({ "total": 0.3, "items": [0.1, 0.2] });
+        let config = Config::new(CompareMode::Strict);
+        assert_json_relation_no_panic(
+            &actual,
+            ".total",
+            Relation::Equal,
+            "sum(.items[*])",
+            &config,
+        )
+        .unwrap_err();
+
+        let config = config.float_compare_mode(FloatCompareMode::Epsilon(1e-9));
+        assert_json_relation_no_panic(
+            &actual,
+            ".total",
+            Relation::Equal,
+            "sum(.items[*])",
+            &config,
+        )
+        .unwrap();
+    }
+}
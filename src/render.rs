@@ -0,0 +1,436 @@
+use crate::diff::{Key, Path};
+use crate::matchers::Matcher;
+use crate::Config;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
+/// Placeholder shown instead of values masked by [`Config::mask_output`].
+pub(crate) const MASK: &str = "***";
+
+/// Pretty prints `value`, found at `path`, for failure messages.
+///
+/// The output matches [`serde_json::to_string_pretty`], except that values matching one of the
+/// masked patterns in `config` are replaced by [`MASK`], arrays and objects nested deeper than
+/// [`Config::collapse_output`] are collapsed, long strings are summarized, and matchers are
+/// described rather than printed as JSON.
+pub(crate) fn pretty(value: &Value, path: &Path, config: &Config) -> String {
+    render(value, path.keys(), config, false, config.float_precision)
+}
+
+/// Like [`pretty`], but prints floats with as many digits as needed to identify them exactly,
+/// whatever [`Config::float_precision`] is.
+pub(crate) fn pretty_exact(value: &Value, path: &Path, config: &Config) -> String {
+    render(value, path.keys(), config, false, None)
+}
+
+/// Like [`pretty`], but prints `value`, found at `keys`, on a single line.
+pub(crate) fn compact(value: &Value, keys: &[Key], config: &Config) -> String {
+    render(value, keys, config, true, config.float_precision)
+}
+
+fn render(
+    value: &Value,
+    keys: &[Key],
+    config: &Config,
+    compact: bool,
+    float_precision: Option<usize>,
+) -> String {
+    let mut renderer = Renderer {
+        config,
+        keys: keys.to_vec(),
+        compact,
+        float_precision,
+        out: String::new(),
+    };
+    renderer.value(value, 0);
+    renderer.out
+}
+
+struct Renderer<'a> {
+    config: &'a Config,
+    keys: Vec<Key>,
+    compact: bool,
+    float_precision: Option<usize>,
+    out: String,
+}
+
+impl Renderer<'_> {
+    fn value(&mut self, value: &Value, depth: usize) {
+        if is_masked(&self.keys, self.config) {
+            self.out.push_str(MASK);
+            return;
+        }
+
+        match Matcher::find(value, self.config) {
+            Some(
+                Matcher::Partial(expected)
+                | Matcher::Exactly(expected)
+                | Matcher::Capture { expected, .. },
+            ) => return self.value(expected, depth),
+            Some(matcher) => return write!(self.out, "{}", matcher).unwrap(),
+            None => {}
+        }
+
+        let is_empty = match value {
+            Value::Array(items) => items.is_empty(),
+            Value::Object(fields) => fields.is_empty(),
+            _ => true,
+        };
+        if !is_empty
+            && self
+                .config
+                .collapse_output
+                .is_some_and(|collapse_depth| depth >= collapse_depth)
+        {
+            return self.out.push_str(&collapsed(value));
+        }
+
+        match value {
+            Value::Array(items) if !items.is_empty() => {
+                self.out.push('[');
+                for (idx, item) in items.iter().enumerate() {
+                    self.separator(idx, depth + 1);
+                    self.keys.push(Key::Idx(idx));
+                    self.value(item, depth + 1);
+                    self.keys.pop();
+                }
+                self.newline(depth);
+                self.out.push(']');
+            }
+            Value::Object(fields) if !fields.is_empty() => {
+                self.out.push('{');
+                for (idx, (key, field)) in fields.iter().enumerate() {
+                    self.separator(idx, depth + 1);
+                    write!(self.out, "{}: ", Value::from(key.as_str())).unwrap();
+                    self.keys.push(Key::Field(key.clone()));
+                    self.value(field, depth + 1);
+                    self.keys.pop();
+                }
+                self.newline(depth);
+                self.out.push('}');
+            }
+            Value::String(text) if is_long_string(text, self.config) => {
+                write!(self.out, "{}", summarize_string(text)).unwrap()
+            }
+            Value::Number(number) if number.is_f64() && self.float_precision.is_some() => {
+                let digits = self.float_precision.unwrap();
+                let float = number.as_f64().expect("float value");
+                self.out.push_str(&format_float(float, digits))
+            }
+            _ => write!(self.out, "{}", value).unwrap(),
+        }
+    }
+
+    fn separator(&mut self, idx: usize, depth: usize) {
+        if idx > 0 {
+            self.out.push(',');
+            if self.compact {
+                self.out.push(' ');
+            }
+        }
+        self.newline(depth);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if self.compact {
+            return;
+        }
+        self.out.push('\n');
+        for _ in 0..depth {
+            self.out.push_str("  ");
+        }
+    }
+}
+
+/// Formats `float` with at most `digits` significant digits, in the style of serde_json.
+fn format_float(float: f64, digits: usize) -> String {
+    let scientific = format!("{:.*e}", digits - 1, float);
+    let (mantissa, exponent) = scientific.split_once('e').expect("exponent");
+    let exponent: i32 = exponent.parse().expect("integer exponent");
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+    let digits = digits.trim_end_matches('0');
+    let digits = if digits.is_empty() { "0" } else { digits };
+
+    if !(-5..=15).contains(&exponent) {
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", rest)
+        };
+        return format!("{}{}{}e{}", sign, first, rest, exponent);
+    }
+
+    let (whole, fraction) = if exponent >= 0 {
+        let whole_len = exponent as usize + 1;
+        if digits.len() > whole_len {
+            (digits[..whole_len].to_string(), &digits[whole_len..])
+        } else {
+            (format!("{:0<width$}", digits, width = whole_len), "")
+        }
+    } else {
+        let zeros = "0".repeat((-exponent - 1) as usize);
+        return format!("{}0.{}{}", sign, zeros, digits);
+    };
+    let fraction = if fraction.is_empty() { "0" } else { fraction };
+    format!("{}{}.{}", sign, whole, fraction)
+}
+
+/// Returns true if the value at `keys` is hidden by [`Config::mask_output`].
+pub(crate) fn is_masked(keys: &[Key], config: &Config) -> bool {
+    config
+        .mask_output
+        .iter()
+        .any(|pattern| pattern.matches_keys(keys))
+}
+
+/// Returns true if `text` is longer than [`Config::long_string_threshold`].
+pub(crate) fn is_long_string(text: &str, config: &Config) -> bool {
+    config
+        .long_string_threshold
+        .is_some_and(|threshold| text.len() > threshold)
+}
+
+/// Describes a long string by its length and hash instead of printing it.
+fn summarize_string(text: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!(
+        "<string of {} bytes, hash {:016x}>",
+        text.len(),
+        hasher.finish()
+    )
+}
+
+/// Returns the byte offset of the first difference between two strings.
+pub(crate) fn first_difference(lhs: &str, rhs: &str) -> usize {
+    lhs.bytes()
+        .zip(rhs.bytes())
+        .position(|(lhs, rhs)| lhs != rhs)
+        .unwrap_or_else(|| lhs.len().min(rhs.len()))
+}
+
+/// A line in the output of [`line_diff`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Above this many line pairs, changed lines are shown as removed and then added without looking
+/// for common lines between them.
+const MAX_LINE_DIFF_CELLS: usize = 1_000_000;
+
+/// Computes the lines removed from `old` and added in `new`, based on their longest common
+/// subsequence of lines.
+pub(crate) fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old = old.split('\n').collect::<Vec<_>>();
+    let new = new.split('\n').collect::<Vec<_>>();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| Line::Same(line))
+        .collect::<Vec<_>>();
+    if old_changed.len() * new_changed.len() > MAX_LINE_DIFF_CELLS {
+        lines.extend(old_changed.iter().map(|line| Line::Removed(line)));
+        lines.extend(new_changed.iter().map(|line| Line::Added(line)));
+    } else {
+        // common[i][j] is the length of the longest common subsequence of old_changed[i..] and
+        // new_changed[j..].
+        let width = new_changed.len() + 1;
+        let mut common = vec![0_usize; (old_changed.len() + 1) * width];
+        for i in (0..old_changed.len()).rev() {
+            for j in (0..new_changed.len()).rev() {
+                common[i * width + j] = if old_changed[i] == new_changed[j] {
+                    common[(i + 1) * width + j + 1] + 1
+                } else {
+                    common[(i + 1) * width + j].max(common[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_changed.len() || j < new_changed.len() {
+            if i < old_changed.len() && j < new_changed.len() && old_changed[i] == new_changed[j] {
+                lines.push(Line::Same(old_changed[i]));
+                i += 1;
+                j += 1;
+            } else if j == new_changed.len()
+                || (i < old_changed.len()
+                    && common[(i + 1) * width + j] >= common[i * width + j + 1])
+            {
+                lines.push(Line::Removed(old_changed[i]));
+                i += 1;
+            } else {
+                lines.push(Line::Added(new_changed[j]));
+                j += 1;
+            }
+        }
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| Line::Same(line)),
+    );
+    lines
+}
+
+/// Shows a non-empty array or object by the number of values nested in it.
+pub(crate) fn collapsed(value: &Value) -> String {
+    let nodes = count_nodes(value) - 1;
+    let brackets = if value.is_array() { "[...]" } else { "{...}" };
+    let noun = if nodes == 1 { "node" } else { "nodes" };
+    format!("{} ({} {})", brackets, nodes, noun)
+}
+
+/// Counts `value` and every value nested in it.
+fn count_nodes(value: &Value) -> usize {
+    1 + match value {
+        Value::Array(items) => items.iter().map(count_nodes).sum(),
+        Value::Object(fields) => fields.values().map(count_nodes).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn matches_serde_json_pretty_printing() {
+        let config = Config::new(CompareMode::Strict);
+        let value = json!({
+            "a": [1, 2.5, [], {}],
+            "b\"": { "c": null, "d": "text\n" },
+            "e": true
+        });
+        assert_eq!(
+            pretty(&value, &Path::Root, &config),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn masks_values() {
+        let config = Config::new(CompareMode::Strict)
+            .mask_output(".credentials.*")
+            .mask_output(".users[*].email");
+        let value = json!({
+            "credentials": { "token": "secret", "user": "bob" },
+            "users": [{ "id": 1, "email": "bob@example.com" }],
+        });
+        assert_eq!(
+            pretty(&value, &Path::Root, &config),
+            r#"{
+  "credentials": {
+    "token": ***,
+    "user": ***
+  },
+  "users": [
+    {
+      "email": ***,
+      "id": 1
+    }
+  ]
+}"#
+        );
+
+        let path = ".credentials.token".parse().unwrap();
+        assert_eq!(pretty(&json!("secret"), &path, &config), "***");
+    }
+
+    #[test]
+    fn collapses_deep_values() {
+        let config = Config::new(CompareMode::Strict).collapse_output(1);
+        let value = json!({ "a": { "b": [1, 2], "c": null }, "d": [], "e": [{}] });
+        assert_eq!(
+            pretty(&value, &Path::Root, &config),
+            r#"{
+  "a": {...} (4 nodes),
+  "d": [],
+  "e": [...] (1 node)
+}"#
+        );
+
+        let config = config.collapse_output(0);
+        assert_eq!(pretty(&value, &Path::Root, &config), "{...} (8 nodes)");
+        assert_eq!(pretty(&json!(1), &Path::Root, &config), "1");
+    }
+
+    #[test]
+    fn summarizes_long_strings() {
+        let config = Config::new(CompareMode::Strict).long_string_threshold(4);
+        let value = json!(["abcd", "abcde"]);
+        let rendered = pretty(&value, &Path::Root, &config);
+        assert!(rendered.contains(r#""abcd""#), "{}", rendered);
+        assert!(!rendered.contains("abcde"), "{}", rendered);
+        assert!(
+            rendered.contains("<string of 5 bytes, hash "),
+            "{}",
+            rendered
+        );
+
+        assert_eq!(first_difference("abcd", "abxd"), 2);
+        assert_eq!(first_difference("abc", "abcd"), 3);
+    }
+
+    #[test]
+    fn formats_floats() {
+        assert_eq!(format_float(0.6666666666666666, 4), "0.6667");
+        assert_eq!(format_float(0.1, 17), "0.10000000000000001");
+        assert_eq!(format_float(-1234.5678, 3), "-1230.0");
+        assert_eq!(format_float(2.5, 6), "2.5");
+        assert_eq!(format_float(9.99999, 3), "10.0");
+        assert_eq!(format_float(0.000123456, 2), "0.00012");
+        assert_eq!(format_float(1.5e-7, 3), "1.5e-7");
+        assert_eq!(format_float(6.02214076e23, 4), "6.022e23");
+        assert_eq!(format_float(0.0, 3), "0.0");
+
+        let config = Config::new(CompareMode::Strict).float_precision(3);
+        assert_eq!(
+            pretty(&json!([1, 1.0, 1.23456]), &Path::Root, &config),
+            "[\n  1,\n  1.0,\n  1.23\n]"
+        );
+    }
+
+    #[test]
+    fn diffs_lines() {
+        assert_eq!(
+            line_diff("a\nb\nc\nd", "a\nc\nx\nd"),
+            vec![
+                Line::Same("a"),
+                Line::Removed("b"),
+                Line::Same("c"),
+                Line::Added("x"),
+                Line::Same("d"),
+            ]
+        );
+        assert_eq!(
+            line_diff("a\n", "a"),
+            vec![Line::Same("a"), Line::Removed("")]
+        );
+        assert_eq!(
+            line_diff("a", "b\na"),
+            vec![Line::Added("b"), Line::Same("a")]
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use crate::diff::{Difference, Path};
+use crate::stats::{Limit, Stats};
+use crate::summary::TypeSummary;
+use crate::tree::Tree;
+use crate::{Config, OutputFormat, Verbosity};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::{env, error::Error, fmt};
+
+pub(crate) const LHS_COLOR: &str = "\x1b[31m";
+pub(crate) const RHS_COLOR: &str = "\x1b[32m";
+const RESET_COLOR: &str = "\x1b[0m";
+
+/// How failure messages are rendered, after applying environment variable overrides.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Output {
+    pub(crate) verbosity: Verbosity,
+    pub(crate) max_diffs: Option<usize>,
+    pub(crate) color: bool,
+    pub(crate) format: OutputFormat,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self {
+            verbosity: Verbosity::Normal,
+            max_diffs: None,
+            color: false,
+            format: OutputFormat::List,
+        }
+    }
+}
+
+impl Output {
+    /// Reads the output settings of `config`, overridden by the `JSON_ASSERT_VERBOSITY`,
+    /// `JSON_ASSERT_MAX_DIFFS`, `JSON_ASSERT_FORMAT` and `NO_COLOR` environment variables.
+    pub(crate) fn from_env(config: &Config) -> Self {
+        Self::from_vars(config, |name| env::var(name).ok())
+    }
+
+    fn from_vars(config: &Config, var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut output = Self {
+            verbosity: config.verbosity,
+            max_diffs: config.max_diffs,
+            color: config.color,
+            format: config.output_format,
+        };
+
+        if let Some(verbosity) = var("JSON_ASSERT_VERBOSITY") {
+            match verbosity.trim().to_ascii_lowercase().as_str() {
+                "quiet" | "0" => output.verbosity = Verbosity::Quiet,
+                "normal" | "1" => output.verbosity = Verbosity::Normal,
+                "verbose" | "2" => output.verbosity = Verbosity::Verbose,
+                _ => {}
+            }
+        }
+        if let Some(max_diffs) = var("JSON_ASSERT_MAX_DIFFS") {
+            match max_diffs.trim().parse::<usize>() {
+                Ok(0) => output.max_diffs = None,
+                Ok(max_diffs) => output.max_diffs = Some(max_diffs),
+                Err(_) => {}
+            }
+        }
+        if let Some(format) = var("JSON_ASSERT_FORMAT") {
+            match format.trim().to_ascii_lowercase().as_str() {
+                "list" => output.format = OutputFormat::List,
+                "tree" => output.format = OutputFormat::Tree,
+                _ => {}
+            }
+        }
+        // See https://no-color.org
+        if var("NO_COLOR").is_some_and(|no_color| !no_color.is_empty()) {
+            output.color = false;
+        }
+
+        output
+    }
+
+    pub(crate) fn paint<'a>(&self, text: &'a str, color: &'a str) -> Painted<'a> {
+        Painted {
+            text,
+            color: if self.color { Some(color) } else { None },
+        }
+    }
+}
+
+pub(crate) struct Painted<'a> {
+    text: &'a str,
+    color: Option<&'a str>,
+}
+
+impl fmt::Display for Painted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.color {
+            Some(color) => write!(f, "{}{}{}", color, self.text, RESET_COLOR),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// The differences found by a failed comparison.
+///
+/// Both the [`Display`](fmt::Display) and [`Debug`] representations are the human-readable message
+/// that the assertion macros panic with. That keeps failures readable in frameworks that print
+/// results with `{:?}`, such as `assert2::check!` or a test returning `Result`.
+///
+/// Returned by [`check_json_matches`](crate::check_json_matches).
+#[derive(Clone, PartialEq)]
+pub struct Mismatch {
+    differences: Vec<Difference>,
+    extras: Vec<Path>,
+    stats: Option<Box<Stats>>,
+    /// Type mismatches shown once instead of once per array item.
+    summaries: Vec<TypeSummary>,
+    /// Set if the documents weren't compared because they were too large or too deeply nested.
+    limit: Option<Box<Limit>>,
+    /// The lhs document, kept to render the differences as a tree.
+    document: Option<Box<Value>>,
+    output: Output,
+}
+
+impl Mismatch {
+    pub(crate) fn new(differences: Vec<Difference>, extras: Vec<Path>, output: Output) -> Self {
+        Self {
+            differences,
+            extras,
+            stats: None,
+            summaries: vec![],
+            limit: None,
+            document: None,
+            output,
+        }
+    }
+
+    /// A mismatch for documents that weren't compared because they exceed a limit of the config.
+    pub(crate) fn limit_exceeded(limit: Limit, output: Output) -> Self {
+        Self {
+            limit: Some(Box::new(limit)),
+            ..Self::new(vec![], vec![], output)
+        }
+    }
+
+    pub(crate) fn with_stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(Box::new(stats));
+        self
+    }
+
+    pub(crate) fn with_summaries(mut self, summaries: Vec<TypeSummary>) -> Self {
+        self.summaries = summaries;
+        self
+    }
+
+    /// Keeps the lhs document if it's needed by the output format, only copying a borrowed
+    /// document then.
+    pub(crate) fn with_document(mut self, document: Cow<'_, Value>) -> Self {
+        if self.output.format == OutputFormat::Tree {
+            self.document = Some(Box::new(document.into_owned()));
+        }
+        self
+    }
+
+    /// Returns the differences that caused the comparison to fail.
+    ///
+    /// This is empty if the documents weren't compared because they have more nodes than
+    /// [`Config::max_nodes`](crate::Config::max_nodes) allows.
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+
+    /// Returns the paths of values that were allowed without being expected.
+    ///
+    /// Only collected if [`Config::report_extras`](crate::Config::report_extras) is set.
+    pub fn extras(&self) -> &[Path] {
+        &self.extras
+    }
+
+    /// Consumes the mismatch, returning the differences that caused the comparison to fail.
+    pub fn into_differences(self) -> Vec<Difference> {
+        self.differences
+    }
+}
+
+/// Mismatches are serialized as objects with the list of `differences`, see the `Serialize`
+/// implementation of [`Difference`], and the `extras` allowed without being expected. Documents
+/// that weren't compared because of [`Config::max_nodes`](crate::Config::max_nodes) have no
+/// differences, and their `error` is the failure message.
+///
+/// ```
+/// use serde_json_assert::{check_json_matches, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Strict);
+/// let mismatch = check_json_matches(&json!([1, 2]), &json!([1]), &config).unwrap_err();
+/// assert_eq!(
+///     serde_json::to_value(&mismatch).unwrap(),
+///     json!({
+///         "differences": [
+///             { "path": "[1]", "kind": "added", "compare_mode": "strict", "lhs": 2 },
+///         ],
+///         "extras": [],
+///     })
+/// );
+/// ```
+impl Serialize for Mismatch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = 2 + usize::from(self.limit.is_some());
+        let mut map = serializer.serialize_map(Some(len))?;
+        map.serialize_entry("differences", &self.differences)?;
+        map.serialize_entry("extras", &self.extras)?;
+        if let Some(limit) = &self.limit {
+            map.serialize_entry("error", &limit.to_string())?;
+        }
+        map.end()
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = match self.output.verbosity {
+            Verbosity::Quiet => "\n",
+            Verbosity::Normal | Verbosity::Verbose => "\n\n",
+        };
+        if let Some(limit) = &self.limit {
+            return write!(f, "{}", limit);
+        }
+        let remaining = self
+            .differences
+            .iter()
+            .filter(|difference| {
+                !self
+                    .summaries
+                    .iter()
+                    .any(|summary| summary.covers(difference))
+            })
+            .collect::<Vec<_>>();
+        // A summary counts as a single entry.
+        let total = self.summaries.len() + remaining.len();
+        let shown = self.output.max_diffs.map_or(total, |max| max.min(total));
+
+        if let Some(document) = &self.document {
+            let tree = Tree {
+                document,
+                differences: &self.differences,
+                output: &self.output,
+            };
+            write!(f, "{}", tree)?;
+        } else {
+            let summaries = self.summaries.len().min(shown);
+            for (idx, summary) in self.summaries[..summaries].iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "{}", separator)?;
+                }
+                write!(f, "{}", summary)?;
+            }
+            for (idx, difference) in remaining[..shown - summaries].iter().enumerate() {
+                if idx + summaries > 0 {
+                    write!(f, "{}", separator)?;
+                }
+                difference.fmt_with(f, &self.output)?;
+            }
+        }
+        if self.document.is_none() && shown < total {
+            write!(f, "{}... and {} more differences", separator, total - shown)?;
+        }
+        if self.output.verbosity == Verbosity::Verbose {
+            write!(
+                f,
+                "{}found {} differences in total",
+                separator,
+                self.differences.len()
+            )?;
+        }
+        if !self.extras.is_empty() {
+            write!(f, "{}{}", separator, Extras(&self.extras))?;
+        }
+        if let Some(stats) = &self.stats {
+            write!(f, "{}{}", separator, stats)?;
+        }
+        Ok(())
+    }
+}
+
+/// A note listing values that were allowed without being expected.
+pub(crate) struct Extras<'a>(pub(crate) &'a [Path]);
+
+impl fmt::Display for Extras<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "note: actual contains values that weren't expected:")?;
+        for path in self.0 {
+            write!(f, "\n    {}", path)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n{}", self)
+    }
+}
+
+impl Error for Mismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+
+    fn output_with_vars(config: &Config, vars: &[(&str, &str)]) -> Output {
+        Output::from_vars(config, |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn env_vars_override_config() {
+        let config = Config::new(CompareMode::Strict)
+            .verbosity(Verbosity::Verbose)
+            .max_diffs(Some(3))
+            .color(true);
+
+        assert_eq!(
+            output_with_vars(&config, &[]),
+            Output {
+                verbosity: Verbosity::Verbose,
+                max_diffs: Some(3),
+                color: true,
+                format: OutputFormat::List,
+            }
+        );
+        assert_eq!(
+            output_with_vars(
+                &config,
+                &[
+                    ("JSON_ASSERT_VERBOSITY", "Quiet"),
+                    ("JSON_ASSERT_MAX_DIFFS", "0"),
+                    ("JSON_ASSERT_FORMAT", "Tree"),
+                    ("NO_COLOR", "1"),
+                ]
+            ),
+            Output {
+                verbosity: Verbosity::Quiet,
+                max_diffs: None,
+                color: false,
+                format: OutputFormat::Tree,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_env_vars_are_ignored() {
+        let config = Config::new(CompareMode::Strict).color(true);
+        assert_eq!(
+            output_with_vars(
+                &config,
+                &[
+                    ("JSON_ASSERT_VERBOSITY", "loud"),
+                    ("JSON_ASSERT_MAX_DIFFS", "many"),
+                    ("JSON_ASSERT_FORMAT", "graph"),
+                    ("NO_COLOR", ""),
+                ]
+            ),
+            Output {
+                verbosity: Verbosity::Normal,
+                max_diffs: None,
+                color: true,
+                format: OutputFormat::List,
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_document_only_for_tree_output() {
+        let document = serde_json::json!({ "a": 1 });
+        let output = |format| Output {
+            format,
+            ..output_with_vars(&Config::new(CompareMode::Strict), &[])
+        };
+
+        let mismatch = Mismatch::new(vec![], vec![], output(OutputFormat::List))
+            .with_document(Cow::Borrowed(&document));
+        assert_eq!(mismatch.document, None);
+
+        let mismatch = Mismatch::new(vec![], vec![], output(OutputFormat::Tree))
+            .with_document(Cow::Borrowed(&document));
+        assert_eq!(mismatch.document.as_deref(), Some(&document));
+    }
+}
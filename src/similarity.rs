@@ -0,0 +1,194 @@
+use crate::diff::{diff, Difference};
+use crate::report::{Mismatch, Output};
+use crate::{to_values, Config};
+use serde::Serialize;
+use serde_json::Value;
+
+/// The number of differences listed in the failure messages of [`assert_json_similar_no_panic`].
+const MAX_CONTRIBUTORS: usize = 5;
+
+/// Returns how similar two JSON values are, from 0 for completely different values to 1 for
+/// matching values.
+///
+/// Both documents are seen as their leaves: scalars and empty arrays or objects. The score is the
+/// fraction of the leaves of both documents that aren't part of a difference found using `config`.
+/// A single changed field in a large document barely lowers the score, while a changed subtree
+/// lowers it in proportion to its size.
+///
+/// ```
+/// use serde_json_assert::{json_similarity, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Strict);
+/// let similarity = json_similarity(
+///     &json!({ "a": 1, "b": [1, 2, 3], "c": "x" }),
+///     &json!({ "a": 1, "b": [1, 2, 4], "c": "x" }),
+///     &config,
+/// );
+/// assert_eq!(similarity, 0.8);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn json_similarity<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> f64
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config).unwrap_or_else(|limit| panic!("{}", limit));
+    Similarity::of(&lhs, &rhs, config).score()
+}
+
+/// Compares two JSON values without panicking, succeeding if their similarity is at least
+/// `threshold`.
+///
+/// The similarity is computed like [`json_similarity`]. On failure, the error shows the
+/// similarity and the differences that lowered it the most, with the share of the documents each
+/// of them accounts for. See [`assert_json_similar`](crate::assert_json_similar) for the
+/// panicking version.
+///
+/// ```
+/// use serde_json_assert::{assert_json_similar_no_panic, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let config = Config::new(CompareMode::Strict);
+/// let error = assert_json_similar_no_panic(
+///     &json!({ "label": "cat", "boxes": [[1, 2], [3, 4]] }),
+///     &json!({ "label": "dog", "boxes": [[1, 2], [3, 5]] }),
+///     0.9,
+///     &config,
+/// )
+/// .unwrap_err();
+/// assert!(error.starts_with(
+///     r#"json values are 60.00% similar, less than the required 90.00%. The largest contributors are:
+///     20.00% at path ".boxes[1][1]"
+///     20.00% at path ".label""#
+/// ));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `threshold` isn't between 0 and 1.
+#[track_caller]
+pub fn assert_json_similar_no_panic<Lhs, Rhs>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    threshold: f64,
+    config: &Config,
+) -> Result<(), String>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    assert!(
+        (0.0..=1.0).contains(&threshold),
+        "similarity threshold must be between 0 and 1, got {}",
+        threshold
+    );
+    let (lhs, rhs) = to_values(lhs, rhs, config).map_err(|limit| limit.to_string())?;
+    let similarity = Similarity::of(&lhs, &rhs, config);
+    let score = similarity.score();
+    if score >= threshold {
+        return Ok(());
+    }
+
+    let mut contributors = similarity
+        .differences
+        .into_iter()
+        .map(|difference| (weight(&difference), difference))
+        .collect::<Vec<_>>();
+    // Stable, so equally large contributors stay in the order they were found.
+    contributors.sort_by(|(lhs, _), (rhs, _)| rhs.cmp(lhs));
+    contributors.truncate(MAX_CONTRIBUTORS);
+
+    let mut error = format!(
+        "json values are {:.2}% similar, less than the required {:.2}%. The largest contributors are:",
+        score * 100.0,
+        threshold * 100.0
+    );
+    for (weight, difference) in &contributors {
+        error.push_str(&format!(
+            "\n    {:.2}% at path \"{}\"",
+            *weight as f64 / similarity.leaves as f64 * 100.0,
+            difference.path()
+        ));
+    }
+    let differences = contributors
+        .into_iter()
+        .map(|(_, difference)| difference)
+        .collect();
+    let mismatch = Mismatch::new(differences, vec![], Output::from_env(config));
+    error.push_str(&format!("\n\n{}", mismatch));
+    Err(error)
+}
+
+struct Similarity {
+    differences: Vec<Difference>,
+    /// The number of leaves of both documents.
+    leaves: usize,
+}
+
+impl Similarity {
+    fn of(lhs: &Value, rhs: &Value, config: &Config) -> Self {
+        Self {
+            differences: diff(lhs, rhs, config),
+            leaves: leaves(lhs) + leaves(rhs),
+        }
+    }
+
+    fn score(&self) -> f64 {
+        let different = self.differences.iter().map(weight).sum::<usize>();
+        1.0 - (different as f64 / self.leaves as f64).min(1.0)
+    }
+}
+
+/// Returns the number of leaves involved in `difference`, on both sides.
+fn weight(difference: &Difference) -> usize {
+    [difference.actual(), difference.expected()]
+        .into_iter()
+        .flatten()
+        .map(leaves)
+        .sum()
+}
+
+/// Returns the number of scalars and empty arrays or objects in `value`.
+fn leaves(value: &Value) -> usize {
+    match value {
+        Value::Array(items) if !items.is_empty() => items.iter().map(leaves).sum(),
+        Value::Object(fields) if !fields.is_empty() => fields.values().map(leaves).sum(),
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn scores_similarity() {
+        let config = Config::new(CompareMode::Strict);
+        let score = |lhs: Value, rhs: Value| Similarity::of(&lhs, &rhs, &config).score();
+
+        assert_eq!(score(json!({ "a": [1, {}] }), json!({ "a": [1, {}] })), 1.0);
+        assert_eq!(score(json!(1), json!("1")), 0.0);
+        assert_eq!(
+            score(json!({ "a": 1, "b": 2 }), json!({ "a": 1 })),
+            1.0 - 1.0 / 3.0
+        );
+        assert_eq!(
+            score(json!({ "a": [1, 2], "b": 3 }), json!({ "a": 1, "b": 3 })),
+            1.0 - 3.0 / 5.0
+        );
+
+        let config = Config::new(CompareMode::Inclusive);
+        assert_eq!(
+            Similarity::of(&json!({ "a": 1, "b": 2 }), &json!({ "a": 1 }), &config).score(),
+            1.0
+        );
+    }
+}
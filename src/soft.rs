@@ -0,0 +1,154 @@
+use crate::core_ext::Indent;
+use std::panic::Location;
+
+/// Collects the failures of several JSON checks, to report them all at once.
+///
+/// Long tests often check many documents, and an assertion only reports the first mismatch. The
+/// `check_json_*` macros record failures in a `JsonChecks` instead of panicking, and
+/// [`JsonChecks::finalize`] panics once with every recorded failure and where it happened.
+///
+/// ```should_panic
+/// use serde_json_assert::{check_json_eq, check_json_include, JsonChecks};
+/// use serde_json::json;
+///
+/// let mut checks = JsonChecks::new();
+/// check_json_eq!(checks, json!({ "status": "ok" }), json!({ "status": "ok" }));
+/// check_json_include!(checks, actual: json!({ "id": 1, "name": "Ann" }), expected: json!({ "id": 2 }));
+/// check_json_eq!(checks, json!([1, 2]), json!([1, 3]), "the ids of page {}", 1);
+///
+/// // Panics with both failures.
+/// checks.finalize();
+/// ```
+///
+/// Failures that were never reported by `finalize` are reported when the `JsonChecks` is dropped,
+/// unless the thread is already panicking.
+#[derive(Debug, Default)]
+pub struct JsonChecks {
+    checks: usize,
+    failures: Vec<(&'static Location<'static>, String)>,
+}
+
+impl JsonChecks {
+    /// Creates a collector without any checks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the outcome of a check, where the error is a failure message. Returns true if the
+    /// check passed.
+    ///
+    /// This is what the `check_json_*` macros call, with the result of a `_no_panic` function, and
+    /// can be used to record the outcome of any other check.
+    #[track_caller]
+    pub fn record(&mut self, result: Result<(), String>) -> bool {
+        self.checks += 1;
+        match result {
+            Ok(()) => true,
+            Err(error) => {
+                self.failures.push((Location::caller(), error));
+                false
+            }
+        }
+    }
+
+    /// Returns the failure messages recorded so far.
+    pub fn failures(&self) -> impl Iterator<Item = &str> {
+        self.failures.iter().map(|(_, error)| error.as_str())
+    }
+
+    /// Returns true if no check has failed so far.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Panics with every recorded failure, if any check failed.
+    #[track_caller]
+    pub fn finalize(mut self) {
+        if let Some(message) = self.take_message() {
+            panic!("\n{}\n\nat {}", message, Location::caller());
+        }
+    }
+
+    fn take_message(&mut self) -> Option<String> {
+        if self.failures.is_empty() {
+            return None;
+        }
+        let mut message = format!(
+            "{} of {} json checks failed:",
+            self.failures.len(),
+            self.checks
+        );
+        for (location, error) in std::mem::take(&mut self.failures) {
+            message.push_str(&format!("\n\nat {}:\n{}", location, error.indent(4)));
+        }
+        Some(message)
+    }
+}
+
+impl Drop for JsonChecks {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(message) = self.take_message() {
+            panic!(
+                "\n{}\n\nnote: JsonChecks was dropped without calling finalize",
+                message
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_json_matches_no_panic, CompareMode, Config};
+    use serde_json::json;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn reports_all_failures() {
+        let config = Config::new(CompareMode::Strict);
+        let mut checks = JsonChecks::new();
+        assert!(checks.record(assert_json_matches_no_panic(&json!(1), &json!(1), &config)));
+        assert!(!checks.record(assert_json_matches_no_panic(&json!(1), &json!(2), &config)));
+        assert!(!checks.record(Err("custom failure".to_string())));
+        assert!(!checks.passed());
+        assert_eq!(checks.failures().count(), 2);
+
+        let line = line!() - 5;
+        let message = catch_unwind(AssertUnwindSafe(|| checks.finalize()))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        let expected = format!(
+            r#"
+2 of 3 json checks failed:
+
+at src/soft.rs:{}:25:
+    json atoms at path "(root)" are not equal:
+        lhs:
+            1
+        rhs:
+            2
+
+at src/soft.rs:{}:25:
+    custom failure"#,
+            line,
+            line + 1
+        );
+        assert!(message.starts_with(&expected), "{}", message);
+
+        JsonChecks::new().finalize();
+    }
+
+    #[test]
+    fn reports_failures_when_dropped() {
+        let result = catch_unwind(|| {
+            let mut checks = JsonChecks::new();
+            checks.record(Err("dropped".to_string()));
+        });
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.ends_with("note: JsonChecks was dropped without calling finalize"));
+    }
+}
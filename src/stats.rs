@@ -0,0 +1,359 @@
+use crate::Config;
+use serde_json::Value;
+use std::{fmt, io};
+
+/// The size and shape of both documents of a failed comparison.
+///
+/// Shown at the end of failure messages if [`Config::report_stats`](crate::Config::report_stats)
+/// is set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Stats {
+    pub(crate) lhs: DocumentStats,
+    pub(crate) rhs: DocumentStats,
+    pub(crate) compared: usize,
+}
+
+impl Stats {
+    pub(crate) fn new(lhs: &Value, rhs: &Value, compared: usize) -> Self {
+        Self {
+            lhs: DocumentStats::of(lhs),
+            rhs: DocumentStats::of(rhs),
+            compared,
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stats:")?;
+        write!(f, "\n    lhs:      {}", self.lhs)?;
+        write!(f, "\n    rhs:      {}", self.rhs)?;
+        write!(f, "\n    compared: {}", Count(self.compared, "node"))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct DocumentStats {
+    /// The number of values in the document, including arrays and objects.
+    pub(crate) nodes: usize,
+    /// The nesting depth of the document, where a scalar has depth 0.
+    pub(crate) depth: usize,
+    /// The length of the document serialized without whitespace.
+    pub(crate) bytes: usize,
+}
+
+impl DocumentStats {
+    pub(crate) fn of(value: &Value) -> Self {
+        let mut stats = Self {
+            nodes: 0,
+            depth: 0,
+            bytes: 0,
+        };
+        stats.visit(value, 0);
+
+        let mut counter = ByteCounter(0);
+        // Writing to a counter can't fail, and values always serialize.
+        let _ = serde_json::to_writer(&mut counter, value);
+        stats.bytes = counter.0;
+        stats
+    }
+
+    fn visit(&mut self, value: &Value, depth: usize) {
+        self.nodes += 1;
+        self.depth = self.depth.max(depth);
+        match value {
+            Value::Array(items) => items.iter().for_each(|item| self.visit(item, depth + 1)),
+            Value::Object(fields) => fields
+                .values()
+                .for_each(|field| self.visit(field, depth + 1)),
+            Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+        }
+    }
+}
+
+impl fmt::Display for DocumentStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, depth {}, {}",
+            Count(self.nodes, "node"),
+            self.depth,
+            Count(self.bytes, "byte")
+        )
+    }
+}
+
+/// The sizes of two documents that have more nodes than [`Config::max_nodes`] allows.
+///
+/// [`Config::max_nodes`]: crate::Config::max_nodes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct NodeLimit {
+    pub(crate) max_nodes: usize,
+    pub(crate) lhs: usize,
+    pub(crate) rhs: usize,
+}
+
+impl NodeLimit {
+    /// Returns the sizes of both documents if they have more than `max_nodes` nodes together.
+    ///
+    /// Documents are only counted up to the limit, so this stays cheap for huge documents.
+    pub(crate) fn check(lhs: &Value, rhs: &Value, max_nodes: usize) -> Option<Self> {
+        let lhs = count_nodes(lhs, max_nodes);
+        let rhs = count_nodes(rhs, max_nodes);
+        (lhs + rhs > max_nodes).then_some(Self {
+            max_nodes,
+            lhs,
+            rhs,
+        })
+    }
+}
+
+impl fmt::Display for NodeLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "json documents have more than {} in total, the limit set by Config::max_nodes, so they weren't compared:",
+            Count(self.max_nodes, "node")
+        )?;
+        for (side, nodes) in [("lhs", self.lhs), ("rhs", self.rhs)] {
+            if nodes > self.max_nodes {
+                write!(
+                    f,
+                    "\n    {}: more than {}",
+                    side,
+                    Count(self.max_nodes, "node")
+                )?;
+            } else {
+                write!(f, "\n    {}: {}", side, Count(nodes, "node"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The depths of documents nested more deeply than [`Config::max_nesting`] allows.
+///
+/// [`Config::max_nesting`]: crate::Config::max_nesting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NestingLimit {
+    pub(crate) max_nesting: usize,
+    /// The depth of each document, by the name it's shown with.
+    pub(crate) depths: Vec<(&'static str, usize)>,
+}
+
+impl NestingLimit {
+    /// Returns the depths of both documents if either is nested more than `max_nesting` levels.
+    ///
+    /// Comparing documents walks them recursively, so this is checked up front without recursion
+    /// to fail with a message instead of overflowing the stack.
+    pub(crate) fn check(lhs: &Value, rhs: &Value, max_nesting: usize) -> Option<Self> {
+        Self::of(
+            max_nesting,
+            vec![
+                ("lhs", nesting_depth(lhs, max_nesting)),
+                ("rhs", nesting_depth(rhs, max_nesting)),
+            ],
+        )
+    }
+
+    /// Returns `depths` if any of them is more than `max_nesting` levels.
+    pub(crate) fn of(max_nesting: usize, depths: Vec<(&'static str, usize)>) -> Option<Self> {
+        depths
+            .iter()
+            .any(|&(_, depth)| depth > max_nesting)
+            .then_some(Self {
+                max_nesting,
+                depths,
+            })
+    }
+}
+
+impl fmt::Display for NestingLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "json {} nested more than {} deep, the limit set by Config::max_nesting, so {} compared:",
+            if self.depths.len() == 1 { "document is" } else { "documents are" },
+            Count(self.max_nesting, "level"),
+            if self.depths.len() == 1 { "it wasn't" } else { "they weren't" },
+        )?;
+        for &(side, depth) in &self.depths {
+            if depth > self.max_nesting {
+                write!(
+                    f,
+                    "\n    {}: more than {}",
+                    side,
+                    Count(self.max_nesting, "level")
+                )?;
+            } else {
+                write!(f, "\n    {}: {}", side, Count(depth, "level"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A limit that kept documents from being compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Limit {
+    Nodes(NodeLimit),
+    Nesting(NestingLimit),
+}
+
+impl Limit {
+    /// Returns the first limit of `config` that `lhs` and `rhs` exceed, if any.
+    pub(crate) fn check(lhs: &Value, rhs: &Value, config: &Config) -> Option<Self> {
+        config
+            .max_nodes
+            .and_then(|max_nodes| NodeLimit::check(lhs, rhs, max_nodes))
+            .map(Limit::Nodes)
+            .or_else(|| {
+                config
+                    .max_nesting
+                    .and_then(|max_nesting| NestingLimit::check(lhs, rhs, max_nesting))
+                    .map(Limit::Nesting)
+            })
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Nodes(limit) => write!(f, "{}", limit),
+            Limit::Nesting(limit) => write!(f, "{}", limit),
+        }
+    }
+}
+
+/// Measures how deeply `value` is nested, stopping as soon as it is deeper than `limit`.
+fn nesting_depth(value: &Value, limit: usize) -> usize {
+    let mut deepest = 0;
+    let mut stack = vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        deepest = deepest.max(depth);
+        if deepest > limit {
+            break;
+        }
+        match value {
+            Value::Array(items) => stack.extend(items.iter().map(|item| (item, depth + 1))),
+            Value::Object(fields) => stack.extend(fields.values().map(|field| (field, depth + 1))),
+            Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+        }
+    }
+    deepest
+}
+
+/// Counts the nodes of `value`, stopping as soon as there are more than `limit`.
+fn count_nodes(value: &Value, limit: usize) -> usize {
+    let mut count = 0;
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        count += 1;
+        if count > limit {
+            break;
+        }
+        match value {
+            Value::Array(items) => stack.extend(items),
+            Value::Object(fields) => stack.extend(fields.values()),
+            Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+        }
+    }
+    count
+}
+
+struct Count(usize, &'static str);
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Count(count, noun) = *self;
+        write!(f, "{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+    }
+}
+
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn counts_nodes_depth_and_bytes() {
+        let stats = DocumentStats::of(&json!({ "a": [1, { "b": null }], "c": "x" }));
+        assert_eq!(
+            stats,
+            DocumentStats {
+                nodes: 6,
+                depth: 3,
+                bytes: 28,
+            }
+        );
+        assert_eq!(stats.to_string(), "6 nodes, depth 3, 28 bytes");
+
+        let stats = DocumentStats::of(&json!({}));
+        assert_eq!(stats.to_string(), "1 node, depth 0, 2 bytes");
+    }
+
+    #[test]
+    fn limits_nodes() {
+        let small = json!([1, 2]);
+        let large = json!([[1, 2, 3], { "a": [4, 5] }]);
+        assert_eq!(NodeLimit::check(&small, &small, 6), None);
+        assert_eq!(
+            NodeLimit::check(&small, &small, 5).unwrap().to_string(),
+            "json documents have more than 5 nodes in total, the limit set by Config::max_nodes, so they weren't compared:\n    lhs: 3 nodes\n    rhs: 3 nodes"
+        );
+        assert_eq!(
+            NodeLimit::check(&small, &large, 4).unwrap().to_string(),
+            "json documents have more than 4 nodes in total, the limit set by Config::max_nodes, so they weren't compared:\n    lhs: 3 nodes\n    rhs: more than 4 nodes"
+        );
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let shallow = json!([1, { "a": 2 }]);
+        let deep = json!({ "a": [[1], { "b": [2] }] });
+        assert_eq!(NestingLimit::check(&shallow, &deep, 4), None);
+        assert_eq!(
+            NestingLimit::check(&shallow, &deep, 3).unwrap().to_string(),
+            "json documents are nested more than 3 levels deep, the limit set by Config::max_nesting, so they weren't compared:\n    lhs: 2 levels\n    rhs: more than 3 levels"
+        );
+        assert_eq!(
+            NestingLimit::check(&json!(1), &json!([1]), 0).unwrap().to_string(),
+            "json documents are nested more than 0 levels deep, the limit set by Config::max_nesting, so they weren't compared:\n    lhs: 0 levels\n    rhs: more than 0 levels"
+        );
+        assert_eq!(
+            NestingLimit::of(2, vec![("actual", 3)]).unwrap().to_string(),
+            "json document is nested more than 2 levels deep, the limit set by Config::max_nesting, so it wasn't compared:\n    actual: more than 2 levels"
+        );
+    }
+
+    #[test]
+    fn rejects_pathologically_deep_documents() {
+        let mut deep = json!(1);
+        for _ in 0..1_000 {
+            deep = json!([deep]);
+        }
+        let config = Config::new(crate::CompareMode::Strict);
+        assert_eq!(
+            Limit::check(&deep, &deep, &config),
+            Some(Limit::Nesting(NestingLimit {
+                max_nesting: 512,
+                depths: vec![("lhs", 513), ("rhs", 513)],
+            }))
+        );
+        let matched = std::panic::catch_unwind(|| crate::is_json_match(&deep, &deep, &config));
+        assert!(matched.is_err());
+    }
+}
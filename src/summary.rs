@@ -0,0 +1,179 @@
+use crate::diff::{Difference, DifferenceKind, Key};
+use crate::CompareMode;
+use serde_json::Value;
+use std::fmt;
+
+/// A type mismatch repeated across the items of one or more arrays, shown once in failure
+/// messages instead of once per item.
+///
+/// Shown if [`Config::summarize_type_mismatches`](crate::Config::summarize_type_mismatches) is
+/// set.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TypeSummary {
+    /// The path of the mismatched values, with array indexes replaced by `[*]`.
+    pattern: String,
+    lhs_type: &'static str,
+    rhs_type: &'static str,
+    /// The number of mismatched values.
+    count: usize,
+    /// The number of values at the pattern in the lhs document.
+    total: usize,
+    compare_mode: CompareMode,
+}
+
+impl TypeSummary {
+    /// Returns true if `difference` is one of the summarized mismatches.
+    pub(crate) fn covers(&self, difference: &Difference) -> bool {
+        group(difference).is_some_and(|(pattern, lhs_type, rhs_type)| {
+            pattern == self.pattern && lhs_type == self.lhs_type && rhs_type == self.rhs_type
+        })
+    }
+}
+
+impl fmt::Display for TypeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (lhs_label, rhs_label) = match self.compare_mode {
+            CompareMode::Inclusive => ("actual", "expected"),
+            CompareMode::Strict => ("lhs", "rhs"),
+        };
+        write!(
+            f,
+            "json values at path \"{}\" are {} in {} but {} in {} for {} of {} items",
+            self.pattern,
+            self.lhs_type,
+            lhs_label,
+            self.rhs_type,
+            rhs_label,
+            self.count,
+            self.total
+        )
+    }
+}
+
+/// Summarizes type mismatches found at the same path in at least two items of arrays in `lhs`.
+pub(crate) fn summarize(differences: &[Difference], lhs: &Value) -> Vec<TypeSummary> {
+    let mut summaries: Vec<(TypeSummary, &[Key])> = vec![];
+    for difference in differences {
+        let Some((pattern, lhs_type, rhs_type)) = group(difference) else {
+            continue;
+        };
+        match summaries.iter_mut().find(|(summary, _)| {
+            summary.pattern == pattern
+                && summary.lhs_type == lhs_type
+                && summary.rhs_type == rhs_type
+        }) {
+            Some((summary, _)) => summary.count += 1,
+            None => summaries.push((
+                TypeSummary {
+                    pattern,
+                    lhs_type,
+                    rhs_type,
+                    count: 1,
+                    total: 0,
+                    compare_mode: difference.config().compare_mode,
+                },
+                difference.path().keys(),
+            )),
+        }
+    }
+
+    summaries
+        .into_iter()
+        .filter(|(summary, _)| summary.count > 1)
+        .map(|(mut summary, keys)| {
+            summary.total = count_matching(lhs, keys);
+            summary
+        })
+        .collect()
+}
+
+/// Returns the path pattern and the types of a type mismatch inside an array.
+fn group(difference: &Difference) -> Option<(String, &'static str, &'static str)> {
+    let keys = difference.path().keys();
+    if !keys.iter().any(|key| matches!(key, Key::Idx(_))) {
+        return None;
+    }
+    if difference.kind() != DifferenceKind::TypeMismatch {
+        return None;
+    }
+    let lhs_type = type_name(difference.lhs()?);
+    let rhs_type = type_name(difference.rhs()?);
+
+    let pattern = keys
+        .iter()
+        .map(|key| match key {
+            Key::Idx(_) => "[*]".to_string(),
+            Key::Field(_) => key.to_string(),
+        })
+        .collect();
+    Some((pattern, lhs_type, rhs_type))
+}
+
+/// Counts the values in `value` at `keys`, where any array index matches every item.
+fn count_matching(value: &Value, keys: &[Key]) -> usize {
+    match (keys.split_first(), value) {
+        (None, _) => 1,
+        (Some((Key::Idx(_), rest)), Value::Array(items)) => {
+            items.iter().map(|item| count_matching(item, rest)).sum()
+        }
+        (Some((Key::Field(field), rest)), Value::Object(fields)) => fields
+            .get(field)
+            .map_or(0, |field| count_matching(field, rest)),
+        _ => 0,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff;
+    use crate::Config;
+    use serde_json::json;
+
+    #[test]
+    fn summarizes_repeated_type_mismatches() {
+        let lhs = json!({
+            "items": [
+                { "price": "1.5", "tags": ["a"] },
+                { "price": "2" },
+                { "price": 3 },
+                { "name": "x" },
+            ],
+            "total": "6.5",
+        });
+        let rhs = json!({
+            "items": [
+                { "price": 1.5, "tags": [1] },
+                { "price": 2 },
+                { "price": 3 },
+                { "name": "x" },
+            ],
+            "total": 6.5,
+        });
+        let config = Config::new(CompareMode::Strict);
+        let differences = diff(&lhs, &rhs, &config);
+        let summaries = summarize(&differences, &lhs);
+
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(
+            summaries[0].to_string(),
+            r#"json values at path ".items[*].price" are string in lhs but number in rhs for 2 of 3 items"#
+        );
+        let covered = differences
+            .iter()
+            .filter(|difference| summaries[0].covers(difference))
+            .count();
+        assert_eq!(covered, 2);
+    }
+}
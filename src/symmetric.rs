@@ -0,0 +1,136 @@
+use crate::diff::{compare, Difference, Path};
+use crate::minimize::get;
+use crate::{to_values, Config};
+use serde::Serialize;
+use serde_json::Value;
+
+/// The differences between two values in both directions, found by [`diff_symmetric`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetricDiff {
+    only_in_lhs: Vec<(Path, Value)>,
+    only_in_rhs: Vec<(Path, Value)>,
+    mismatches: Vec<Difference>,
+}
+
+impl SymmetricDiff {
+    /// Returns the values of the lhs that have no counterpart in the rhs, with their paths.
+    pub fn only_in_lhs(&self) -> &[(Path, Value)] {
+        &self.only_in_lhs
+    }
+
+    /// Returns the values of the rhs that have no counterpart in the lhs, with their paths.
+    pub fn only_in_rhs(&self) -> &[(Path, Value)] {
+        &self.only_in_rhs
+    }
+
+    /// Returns the differences between values present on both sides.
+    pub fn mismatches(&self) -> &[Difference] {
+        &self.mismatches
+    }
+
+    /// Returns true if the values match in both directions.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_lhs.is_empty() && self.only_in_rhs.is_empty() && self.mismatches.is_empty()
+    }
+}
+
+/// Compares two values once, returning what is missing from each side and the values that differ.
+///
+/// Values only in the lhs are found whatever the compare mode: in inclusive mode, they are the
+/// values allowed without being expected, as listed by
+/// [`Config::report_extras`](crate::Config::report_extras). This gives the picture of comparing
+/// the values in both directions without running the comparison twice.
+///
+/// ```
+/// use serde_json_assert::{diff_symmetric, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let diff = diff_symmetric(
+///     &json!({ "id": 1, "name": "Ann", "admin": true }),
+///     &json!({ "id": 2, "name": "Ann", "email": "ann@example.com" }),
+///     &Config::new(CompareMode::Inclusive),
+/// );
+///
+/// assert_eq!(diff.only_in_lhs()[0].0.to_string(), ".admin");
+/// assert_eq!(diff.only_in_rhs()[0].1, json!("ann@example.com"));
+/// assert_eq!(diff.mismatches()[0].path().to_string(), ".id");
+/// ```
+///
+/// # Panics
+///
+/// Panics if either value can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn diff_symmetric<Lhs, Rhs>(lhs: &Lhs, rhs: &Rhs, config: &Config) -> SymmetricDiff
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+{
+    let config = config.clone().report_extras(true);
+    let (lhs, rhs) = to_values(lhs, rhs, &config).unwrap_or_else(|limit| panic!("{}", limit));
+    let comparison = compare(&lhs, &rhs, &config);
+
+    let mut only_in_lhs = vec![];
+    let mut only_in_rhs = vec![];
+    let mut mismatches = vec![];
+    for difference in comparison.differences {
+        match (difference.actual(), difference.expected()) {
+            (Some(lhs), None) => only_in_lhs.push((difference.path().clone(), lhs.clone())),
+            (None, Some(rhs)) => only_in_rhs.push((difference.path().clone(), rhs.clone())),
+            _ => mismatches.push(difference),
+        }
+    }
+    for path in comparison.extras {
+        if let Some(value) = get(&lhs, path.keys()) {
+            only_in_lhs.push((path, value.clone()));
+        }
+    }
+
+    SymmetricDiff {
+        only_in_lhs,
+        only_in_rhs,
+        mismatches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn finds_both_directions() {
+        let lhs = json!({ "a": 1, "b": { "c": [1, 2, 3] }, "d": null });
+        let rhs = json!({ "a": 2, "b": { "c": [1, 2], "e": true } });
+
+        for compare_mode in [CompareMode::Strict, CompareMode::Inclusive] {
+            let diff = diff_symmetric(&lhs, &rhs, &Config::new(compare_mode));
+            let paths = |entries: &[(Path, Value)]| {
+                entries
+                    .iter()
+                    .map(|(path, value)| (path.to_string(), value.clone()))
+                    .collect::<Vec<_>>()
+            };
+            let mut only_in_lhs = paths(diff.only_in_lhs());
+            only_in_lhs.sort_by(|a, b| a.0.cmp(&b.0));
+            assert_eq!(
+                only_in_lhs,
+                [
+                    (".b.c[2]".to_string(), json!(3)),
+                    (".d".to_string(), json!(null))
+                ],
+                "{:?}",
+                compare_mode
+            );
+            assert_eq!(
+                paths(diff.only_in_rhs()),
+                [(".b.e".to_string(), json!(true))]
+            );
+            assert_eq!(diff.mismatches().len(), 1);
+            assert_eq!(diff.mismatches()[0].path().to_string(), ".a");
+        }
+
+        assert!(diff_symmetric(&lhs, &lhs, &Config::new(CompareMode::Strict)).is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::diff::{Key, Path};
+use crate::paths::__to_value;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// An expected JSON document with `{{name}}` placeholders, filled in before comparison.
+///
+/// Fixtures shared by several tests often only differ in a few ids. Instead of replacing them by
+/// hand, write them as placeholders and set their values in each test.
+///
+/// A string that consists of a single placeholder is replaced by its value, whatever its type, so
+/// `"{{id}}"` becomes `42` and not `"42"`. Placeholders inside longer strings and in object keys
+/// are replaced by the text of their value: strings are inserted as they are, other values as
+/// JSON.
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, Template};
+/// use serde_json::json;
+///
+/// let template = Template::new(&json!({
+///     "id": "{{user_id}}",
+///     "links": { "self": "/users/{{user_id}}" },
+///     "roles": "{{roles}}",
+/// }));
+///
+/// let expected = template.set("user_id", 42).set("roles", ["admin"]).fill();
+/// assert_json_eq!(
+///     expected,
+///     json!({ "id": 42, "links": { "self": "/users/42" }, "roles": ["admin"] })
+/// );
+/// ```
+///
+/// Placeholders without a value are an error, see [`Template::try_fill`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    document: Value,
+    values: HashMap<String, Value>,
+}
+
+impl Template {
+    /// Creates a template from a document containing placeholders.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `document` can't be serialized.
+    #[track_caller]
+    pub fn new<T: Serialize + ?Sized>(document: &T) -> Self {
+        Self {
+            document: __to_value(document),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Sets the value of the placeholder `{{name}}`, replacing any previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` can't be serialized.
+    #[track_caller]
+    pub fn set<T: Serialize>(mut self, name: &str, value: T) -> Self {
+        self.values.insert(name.to_string(), __to_value(&value));
+        self
+    }
+
+    /// Returns the document with all placeholders replaced by their values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some placeholders have no value, listing them.
+    #[track_caller]
+    pub fn fill(&self) -> Value {
+        self.try_fill()
+            .unwrap_or_else(|error| panic!("\n{}\n\nat {}", error, std::panic::Location::caller()))
+    }
+
+    /// Returns the document with all placeholders replaced by their values, without panicking.
+    ///
+    /// The error lists the placeholders that have no value, and where they are.
+    ///
+    /// ```
+    /// use serde_json_assert::Template;
+    /// use serde_json::json;
+    ///
+    /// let template = Template::new(&json!({ "id": "{{id}}", "owner": { "{{role}}": "{{name}}" } }));
+    /// let error = template.set("name", "Ann").try_fill().unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     r#"json template has placeholders without a value:
+    ///     {{id}} at path ".id"
+    ///     {{role}} at path ".owner""#
+    /// );
+    /// ```
+    pub fn try_fill(&self) -> Result<Value, String> {
+        let mut unresolved = vec![];
+        let filled = self.fill_value(&self.document, &mut vec![], &mut unresolved);
+        if unresolved.is_empty() {
+            return Ok(filled);
+        }
+
+        let mut error = "json template has placeholders without a value:".to_string();
+        for (name, path) in unresolved {
+            error.push_str(&format!("\n    {{{{{}}}}} at path \"{}\"", name, path));
+        }
+        Err(error)
+    }
+
+    fn fill_value(
+        &self,
+        value: &Value,
+        keys: &mut Vec<Key>,
+        unresolved: &mut Vec<(String, Path)>,
+    ) -> Value {
+        match value {
+            Value::String(text) => {
+                if let Some(name) = whole_placeholder(text) {
+                    if let Some(value) = self.values.get(name) {
+                        return value.clone();
+                    }
+                }
+                Value::String(self.fill_str(text, keys, unresolved))
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, item)| {
+                        keys.push(Key::Idx(idx));
+                        let item = self.fill_value(item, keys, unresolved);
+                        keys.pop();
+                        item
+                    })
+                    .collect(),
+            ),
+            Value::Object(fields) => {
+                let mut filled = Map::new();
+                for (key, field) in fields {
+                    let key = self.fill_str(key, keys, unresolved);
+                    keys.push(Key::Field(key.clone()));
+                    let field = self.fill_value(field, keys, unresolved);
+                    keys.pop();
+                    filled.insert(key, field);
+                }
+                Value::Object(filled)
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => value.clone(),
+        }
+    }
+
+    /// Replaces the placeholders in `text` by the text of their values.
+    fn fill_str(&self, text: &str, keys: &[Key], unresolved: &mut Vec<(String, Path)>) -> String {
+        let mut filled = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some((before, name, after)) = next_placeholder(rest) {
+            filled.push_str(before);
+            match self.values.get(name) {
+                Some(Value::String(value)) => filled.push_str(value),
+                Some(value) => filled.push_str(&value.to_string()),
+                None => {
+                    filled.push_str(&rest[before.len()..rest.len() - after.len()]);
+                    unresolved.push((name.to_string(), Path::from_keys(keys.to_vec())));
+                }
+            }
+            rest = after;
+        }
+        filled.push_str(rest);
+        filled
+    }
+}
+
+/// Returns the name of the placeholder if `text` consists of nothing else.
+fn whole_placeholder(text: &str) -> Option<&str> {
+    match next_placeholder(text) {
+        Some(("", name, "")) => Some(name),
+        _ => None,
+    }
+}
+
+/// Splits `text` around its first placeholder, returning the text before it, the trimmed name and
+/// the text after it.
+fn next_placeholder(text: &str) -> Option<(&str, &str, &str)> {
+    let start = text.find("{{")?;
+    let len = text[start + 2..].find("}}")?;
+    let name = &text[start + 2..start + 2 + len];
+    Some((&text[..start], name.trim(), &text[start + 4 + len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn fills_placeholders() {
+        let template = Template::new(&json!({
+            "id": "{{ id }}",
+            "ids": ["{{id}}", "{{id}}-{{id}}", "{{missing"],
+            "{{key}}": { "name": "{{name}}" },
+            "null": "{{nothing}}",
+        }))
+        .set("id", 7)
+        .set("key", "user")
+        .set("name", "Ann")
+        .set("nothing", ());
+
+        assert_eq!(
+            template.fill(),
+            json!({
+                "id": 7,
+                "ids": [7, "7-7", "{{missing"],
+                "user": { "name": "Ann" },
+                "null": null,
+            })
+        );
+    }
+
+    #[test]
+    fn reports_unresolved_placeholders() {
+        let template =
+            Template::new(&json!([{ "a": "x{{one}}y{{two}}" }, "{{one}}"])).set("two", 2);
+        assert_eq!(
+            template.try_fill().unwrap_err(),
+            r#"json template has placeholders without a value:
+    {{one}} at path "[0].a"
+    {{one}} at path "[1]""#
+        );
+    }
+}
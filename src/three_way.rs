@@ -0,0 +1,450 @@
+use crate::core_ext::Indent;
+use crate::diff::{diff, Key, Path};
+use crate::minimize::get;
+use crate::patch::{compare_keys, set};
+use crate::paths::__to_value;
+use crate::{check_nesting, CompareMode, Config};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// How a path was changed by the two versions compared by [`three_way_diff`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Only ours changed the value.
+    Ours,
+    /// Only theirs changed the value.
+    Theirs,
+    /// Both versions changed the value in the same way.
+    Both,
+    /// Both versions changed the value, or values nested in each other, differently.
+    Conflict,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            ChangeKind::Ours => "changed in ours",
+            ChangeKind::Theirs => "changed in theirs",
+            ChangeKind::Both => "changed the same way in both",
+            ChangeKind::Conflict => "conflicting changes",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// A path changed by one or both versions, found by [`three_way_diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    path: Path,
+    kind: ChangeKind,
+    base: Option<Value>,
+    ours: Option<Value>,
+    theirs: Option<Value>,
+}
+
+impl Change {
+    /// Returns the path of the changed value.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns which versions changed the value.
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    /// Returns the value in the base document, or `None` if it was added.
+    pub fn base(&self) -> Option<&Value> {
+        self.base.as_ref()
+    }
+
+    /// Returns the value in our version, or `None` if it is missing from it.
+    pub fn ours(&self) -> Option<&Value> {
+        self.ours.as_ref()
+    }
+
+    /// Returns the value in their version, or `None` if it is missing from it.
+    pub fn theirs(&self) -> Option<&Value> {
+        self.theirs.as_ref()
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)?;
+        let sides: &[(&str, &Option<Value>)] = match self.kind {
+            ChangeKind::Ours => &[("base", &self.base), ("ours", &self.ours)],
+            ChangeKind::Theirs => &[("base", &self.base), ("theirs", &self.theirs)],
+            ChangeKind::Both => &[("base", &self.base), ("both", &self.ours)],
+            ChangeKind::Conflict => &[
+                ("base", &self.base),
+                ("ours", &self.ours),
+                ("theirs", &self.theirs),
+            ],
+        };
+        for (side, value) in sides {
+            match value {
+                Some(value) => write!(f, "\n    {:<8}{}", format!("{}:", side), value)?,
+                None => write!(f, "\n    {:<8}(missing)", format!("{}:", side))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The changes made by two versions of a base document, found by [`three_way_diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreeWayDiff {
+    changes: Vec<Change>,
+}
+
+impl ThreeWayDiff {
+    /// Returns the changes, in document order.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Returns the changes that conflict.
+    pub fn conflicts(&self) -> impl Iterator<Item = &Change> {
+        self.changes
+            .iter()
+            .filter(|change| change.kind == ChangeKind::Conflict)
+    }
+
+    /// Returns the change at `path`, if there is one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` isn't a valid path, see [`Path`].
+    #[track_caller]
+    pub fn at(&self, path: &str) -> Option<&Change> {
+        let path = path
+            .parse::<Path>()
+            .unwrap_or_else(|error| panic!("{}", error));
+        self.changes.iter().find(|change| change.path == path)
+    }
+
+    /// Returns true if neither version changed the base document.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for ThreeWayDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "json versions don't change the base document");
+        }
+        write!(
+            f,
+            "json versions change the base document at {} paths:",
+            self.changes.len()
+        )?;
+        for change in &self.changes {
+            write!(f, "\n\n{}", change.indent(4))?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two versions derived from a base document, classifying each changed path by the
+/// versions that changed it.
+///
+/// Each version is compared to `base` using `config`, with the version as the lhs (or actual
+/// value). A path changed by only one version is [`ChangeKind::Ours`] or [`ChangeKind::Theirs`].
+/// A path changed by both is [`ChangeKind::Both`] if the changed values are equal, and
+/// [`ChangeKind::Conflict`] otherwise. Changes to a value and to values nested in it conflict,
+/// and are reported once at the outer path.
+///
+/// ```
+/// use serde_json_assert::{three_way_diff, ChangeKind, CompareMode, Config};
+/// use serde_json::json;
+///
+/// let base = json!({ "version": 1, "name": "app", "port": 80 });
+/// let ours = json!({ "version": 2, "name": "app", "port": 8080 });
+/// let theirs = json!({ "version": 2, "name": "service", "port": 443 });
+///
+/// let changes = three_way_diff(&base, &ours, &theirs, &Config::new(CompareMode::Strict));
+/// assert_eq!(changes.at(".version").unwrap().kind(), ChangeKind::Both);
+/// assert_eq!(changes.at(".name").unwrap().kind(), ChangeKind::Theirs);
+/// assert_eq!(
+///     changes.conflicts().map(|change| change.path().to_string()).collect::<Vec<_>>(),
+///     [".port"]
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if any of the documents can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn three_way_diff<Base, Ours, Theirs>(
+    base: &Base,
+    ours: &Ours,
+    theirs: &Theirs,
+    config: &Config,
+) -> ThreeWayDiff
+where
+    Base: Serialize + ?Sized,
+    Ours: Serialize + ?Sized,
+    Theirs: Serialize + ?Sized,
+{
+    check_nesting(
+        &[("base", &base), ("ours", &ours), ("theirs", &theirs)],
+        config,
+    )
+    .unwrap_or_else(|limit| panic!("{}", limit));
+    let base = __to_value(base);
+    let ours = __to_value(ours);
+    let theirs = __to_value(theirs);
+    let changed_paths = |version: &Value| -> Vec<Vec<Key>> {
+        diff(version, &base, config)
+            .iter()
+            .map(|difference| difference.path().keys().to_vec())
+            .collect()
+    };
+    let ours_paths = changed_paths(&ours);
+    let theirs_paths = changed_paths(&theirs);
+
+    let mut classified = vec![];
+    let mut theirs_handled = vec![false; theirs_paths.len()];
+    for ours_path in &ours_paths {
+        let mut kind = ChangeKind::Ours;
+        let mut path = ours_path.as_slice();
+        for (idx, theirs_path) in theirs_paths.iter().enumerate() {
+            if !theirs_path.starts_with(ours_path) && !ours_path.starts_with(theirs_path) {
+                continue;
+            }
+            theirs_handled[idx] = true;
+            kind = if kind == ChangeKind::Ours
+                && theirs_path == ours_path
+                && get(&ours, ours_path) == get(&theirs, theirs_path)
+            {
+                ChangeKind::Both
+            } else {
+                ChangeKind::Conflict
+            };
+            if theirs_path.len() < path.len() {
+                path = theirs_path;
+            }
+        }
+        classified.push((path.to_vec(), kind));
+    }
+    for (theirs_path, handled) in theirs_paths.into_iter().zip(theirs_handled) {
+        if !handled {
+            classified.push((theirs_path, ChangeKind::Theirs));
+        }
+    }
+
+    classified.sort_by(|(a, _), (b, _)| compare_keys(a, b));
+    // Several changes in ours can conflict with the same change in theirs.
+    classified.dedup();
+
+    let changes = classified
+        .into_iter()
+        .map(|(keys, kind)| Change {
+            base: get(&base, &keys).cloned(),
+            ours: get(&ours, &keys).cloned(),
+            theirs: get(&theirs, &keys).cloned(),
+            path: Path::from_keys(keys),
+            kind,
+        })
+        .collect();
+    ThreeWayDiff { changes }
+}
+
+/// How [`merge_json`] resolves conflicting changes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep our value.
+    OursWins,
+    /// Take their value.
+    TheirsWins,
+    /// Fail, listing the conflicts.
+    FailOnConflict,
+}
+
+/// Merges two versions derived from a base document.
+///
+/// Our version is taken as is, and the changes made by their version are applied to it. Changes
+/// are found by [`three_way_diff`] comparing values exactly, and conflicting changes are resolved
+/// according to `strategy`. Items added to the end of an array by their version are appended, even
+/// if our version changed the length of the array.
+///
+/// ```
+/// use serde_json_assert::{merge_json, MergeStrategy};
+/// use serde_json::json;
+///
+/// let base = json!({ "name": "app", "port": 80, "tags": ["a"] });
+/// let ours = json!({ "name": "app", "port": 8080, "tags": ["a"] });
+/// let theirs = json!({ "name": "service", "port": 443, "tags": ["a", "b"] });
+///
+/// let merged = merge_json(&base, &ours, &theirs, MergeStrategy::OursWins).unwrap();
+/// assert_eq!(merged, json!({ "name": "service", "port": 8080, "tags": ["a", "b"] }));
+///
+/// let error = merge_json(&base, &ours, &theirs, MergeStrategy::FailOnConflict).unwrap_err();
+/// assert_eq!(
+///     error,
+///     "json versions can't be merged, they conflict at 1 path:
+///
+///     .port: conflicting changes
+///         base:   80
+///         ours:   8080
+///         theirs: 443"
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if any of the documents can't be serialized.
+#[track_caller]
+pub fn merge_json<Base, Ours, Theirs>(
+    base: &Base,
+    ours: &Ours,
+    theirs: &Theirs,
+    strategy: MergeStrategy,
+) -> Result<Value, String>
+where
+    Base: Serialize + ?Sized,
+    Ours: Serialize + ?Sized,
+    Theirs: Serialize + ?Sized,
+{
+    let config = Config::new(CompareMode::Strict);
+    check_nesting(
+        &[("base", &base), ("ours", &ours), ("theirs", &theirs)],
+        &config,
+    )
+    .map_err(|limit| limit.to_string())?;
+    let changes = three_way_diff(base, ours, theirs, &config);
+    let conflicts = changes.conflicts().collect::<Vec<_>>();
+    if strategy == MergeStrategy::FailOnConflict && !conflicts.is_empty() {
+        let mut error = format!(
+            "json versions can't be merged, they conflict at {} path{}:",
+            conflicts.len(),
+            if conflicts.len() == 1 { "" } else { "s" }
+        );
+        for conflict in conflicts {
+            error.push_str(&format!("\n\n{}", conflict.indent(4)));
+        }
+        return Err(error);
+    }
+
+    let theirs_changes = changes
+        .changes
+        .into_iter()
+        .filter(|change| match change.kind {
+            ChangeKind::Theirs => true,
+            ChangeKind::Conflict => strategy == MergeStrategy::TheirsWins,
+            ChangeKind::Ours | ChangeKind::Both => false,
+        });
+    let (updates, removals): (Vec<_>, Vec<_>) =
+        theirs_changes.partition(|change| change.theirs.is_some());
+
+    let mut merged = __to_value(ours);
+    // Values are set in document order, so added array items are appended in order, and removed
+    // in reverse order, so removing an item doesn't shift the ones still to be removed.
+    for change in updates {
+        set(&mut merged, change.path.keys(), change.theirs);
+    }
+    for change in removals.into_iter().rev() {
+        set(&mut merged, change.path.keys(), None);
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn classifies_changes() {
+        let base = json!({ "a": { "b": 1, "c": 2 }, "d": [1, 2], "e": 5 });
+        let ours = json!({ "a": { "b": 10, "c": 2 }, "d": [1, 3], "f": true });
+        let theirs = json!({ "a": null, "d": [1, 3], "e": 5, "g": "x" });
+        let changes = three_way_diff(&base, &ours, &theirs, &Config::new(CompareMode::Strict));
+
+        let kinds = changes
+            .changes()
+            .iter()
+            .map(|change| (change.path().to_string(), change.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (".a".to_string(), ChangeKind::Conflict),
+                (".d[1]".to_string(), ChangeKind::Both),
+                (".e".to_string(), ChangeKind::Ours),
+                (".f".to_string(), ChangeKind::Ours),
+                (".g".to_string(), ChangeKind::Theirs),
+            ]
+        );
+
+        let conflict = changes.at(".a").unwrap();
+        assert_eq!(conflict.base(), Some(&json!({ "b": 1, "c": 2 })));
+        assert_eq!(conflict.ours(), Some(&json!({ "b": 10, "c": 2 })));
+        assert_eq!(conflict.theirs(), Some(&json!(null)));
+        assert_eq!(changes.at(".e").unwrap().ours(), None);
+        assert_eq!(changes.conflicts().count(), 1);
+    }
+
+    #[test]
+    fn display() {
+        let config = Config::new(CompareMode::Strict);
+        let changes = three_way_diff(
+            &json!({ "a": 1, "b": 2 }),
+            &json!({ "a": 3, "b": 2 }),
+            &json!({ "a": 4 }),
+            &config,
+        );
+        assert_eq!(
+            changes.to_string(),
+            r#"json versions change the base document at 2 paths:
+
+    .a: conflicting changes
+        base:   1
+        ours:   3
+        theirs: 4
+
+    .b: changed in theirs
+        base:   2
+        theirs: (missing)"#
+        );
+
+        let base = json!([1]);
+        assert!(three_way_diff(&base, &base, &base, &config).is_empty());
+    }
+
+    #[test]
+    fn merges_versions() {
+        let base = json!({ "a": 1, "b": [1, 2, 3], "c": { "d": 1 }, "e": true });
+        let ours = json!({ "a": 2, "b": [1, 2, 3], "c": { "d": 1 }, "e": true, "f": 1 });
+        let theirs = json!({ "a": 3, "b": [1], "c": { "d": 1, "g": 2 } });
+
+        assert_eq!(
+            merge_json(&base, &ours, &theirs, MergeStrategy::OursWins),
+            Ok(json!({ "a": 2, "b": [1], "c": { "d": 1, "g": 2 }, "f": 1 }))
+        );
+        assert_eq!(
+            merge_json(&base, &ours, &theirs, MergeStrategy::TheirsWins),
+            Ok(json!({ "a": 3, "b": [1], "c": { "d": 1, "g": 2 }, "f": 1 }))
+        );
+
+        let theirs = json!({ "a": 1, "b": [1, 2, 3, 4, 5], "c": null, "e": true });
+        let ours = json!({ "a": 1, "b": [1, 2], "c": { "d": 2 }, "e": true });
+        assert_eq!(
+            merge_json(&base, &ours, &theirs, MergeStrategy::TheirsWins),
+            Ok(json!({ "a": 1, "b": [1, 2, 4, 5], "c": null, "e": true }))
+        );
+        assert_eq!(
+            merge_json(
+                &json!(1),
+                &json!(1),
+                &json!(2),
+                MergeStrategy::FailOnConflict
+            ),
+            Ok(json!(2))
+        );
+    }
+}
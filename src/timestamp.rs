@@ -0,0 +1,210 @@
+/// A point in time, as seconds and nanoseconds since the Unix epoch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Instant {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Instant {
+    /// Formats the instant as an RFC 3339 timestamp in UTC, with as many fractional digits as
+    /// needed.
+    pub(crate) fn to_utc_string(self) -> String {
+        let (days, secs_of_day) = (self.secs.div_euclid(86_400), self.secs.rem_euclid(86_400));
+        let (year, month, day) = civil_from_days(days);
+        let mut text = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60
+        );
+        if self.nanos > 0 {
+            let fraction = format!("{:09}", self.nanos);
+            text.push('.');
+            text.push_str(fraction.trim_end_matches('0'));
+        }
+        text.push('Z');
+        text
+    }
+}
+
+/// Parses an RFC 3339 timestamp, such as `2024-01-01T10:00:00.5+02:00`.
+///
+/// Lowercase `t` and `z`, and a space instead of `T`, are accepted as allowed by RFC 3339. Leap
+/// seconds aren't supported.
+pub(crate) fn parse(text: &str) -> Option<Instant> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 20 {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = text.get(range)?;
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let expect = |idx: usize, allowed: &[u8]| allowed.contains(bytes.get(idx)?).then_some(());
+
+    let year = number(0..4)?;
+    expect(4, b"-")?;
+    let month = number(5..7)?;
+    expect(7, b"-")?;
+    let day = number(8..10)?;
+    expect(10, b"Tt ")?;
+    let hour = number(11..13)?;
+    expect(13, b":")?;
+    let minute = number(14..16)?;
+    expect(16, b":")?;
+    let second = number(17..19)?;
+
+    let mut rest = &text[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        // Digits beyond nanosecond precision are ignored.
+        let digits = &fraction[..len.min(9)];
+        nanos = digits.parse::<u32>().ok()? * 10_u32.pow(9 - digits.len() as u32);
+        rest = &fraction[len..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes().first()? {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return None;
+            }
+            let hours = rest[1..3].parse::<i64>().ok()?;
+            let minutes = rest[4..6].parse::<i64>().ok()?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day < 1 || day > days_in_month || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    Some(Instant { secs, nanos })
+}
+
+/// Returns the number of days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_instant_in_different_offsets() {
+        let utc = parse("2024-01-01T08:00:00Z").unwrap();
+        assert_eq!(parse("2024-01-01T10:00:00+02:00"), Some(utc));
+        assert_eq!(parse("2023-12-31T23:30:00-08:30"), Some(utc));
+        assert_eq!(parse("2024-01-01 08:00:00.000z"), Some(utc));
+        assert_ne!(parse("2024-01-01T08:00:00.001Z"), Some(utc));
+        assert_eq!(
+            parse("2024-01-01T08:00:00.5Z"),
+            parse("2024-01-01T08:00:00.500000000000Z")
+        );
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(
+            parse("1970-01-01T00:00:00Z"),
+            Some(Instant { secs: 0, nanos: 0 })
+        );
+        assert_eq!(
+            parse("1969-12-31T23:59:59.25Z"),
+            Some(Instant {
+                secs: -1,
+                nanos: 250_000_000
+            })
+        );
+        assert_eq!(parse("2000-03-01T00:00:00Z").unwrap().secs, 951_868_800);
+    }
+
+    #[test]
+    fn formats_in_utc() {
+        for (text, utc) in [
+            ("2024-01-01T10:00:00+02:00", "2024-01-01T08:00:00Z"),
+            ("2024-03-01T01:30:00.250+02:00", "2024-02-29T23:30:00.25Z"),
+            (
+                "1969-12-31T23:59:59.000000001Z",
+                "1969-12-31T23:59:59.000000001Z",
+            ),
+            ("0000-01-01T00:00:00Z", "0000-01-01T00:00:00Z"),
+        ] {
+            assert_eq!(parse(text).unwrap().to_utc_string(), utc);
+        }
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        for text in [
+            "2024-01-01",
+            "2024-01-01T08:00:00",
+            "2024-02-30T08:00:00Z",
+            "2023-02-29T08:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T08:00:00.Z",
+            "2024-01-01T08:00:00+2:00",
+            "2024-01-01T08:00:00+0200",
+            "2024-01-01T08:00:00 Z",
+            "+024-01-01T08:00:00Z",
+        ] {
+            assert_eq!(parse(text), None, "{}", text);
+        }
+        assert!(parse("2024-02-29T08:00:00Z").is_some());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::depth::Measure;
+use crate::paths::__to_value;
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+
+/// Values that can be compared as JSON without being serialized.
+///
+/// The assertion macros, such as [`assert_json_matches!`](crate::assert_json_matches), compare
+/// types implementing this trait as they are, instead of serializing them into a copy first. This
+/// saves a deep copy of both documents when they already are [`Value`]s. Values of other types
+/// are serialized as usual. Note that the `_no_panic` functions always serialize their inputs,
+/// since they are generic over [`Serialize`].
+///
+/// ```
+/// use serde_json_assert::{assert_json_eq, ToJsonValue};
+/// use serde_json::{json, Value};
+/// use std::borrow::Cow;
+///
+/// struct Response {
+///     body: Value,
+/// }
+///
+/// impl ToJsonValue for Response {
+///     fn to_json_value(&self) -> Cow<'_, Value> {
+///         Cow::Borrowed(&self.body)
+///     }
+/// }
+///
+/// let response = Response { body: json!({ "id": 1 }) };
+/// assert_json_eq!(response, json!({ "id": 1 }));
+/// ```
+pub trait ToJsonValue {
+    /// Returns the value as JSON, borrowing it if possible.
+    fn to_json_value(&self) -> Cow<'_, Value>;
+}
+
+impl ToJsonValue for Value {
+    fn to_json_value(&self) -> Cow<'_, Value> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<T: ToJsonValue + ?Sized> ToJsonValue for &T {
+    fn to_json_value(&self) -> Cow<'_, Value> {
+        (**self).to_json_value()
+    }
+}
+
+impl<T: ToJsonValue + ?Sized> ToJsonValue for Box<T> {
+    fn to_json_value(&self) -> Cow<'_, Value> {
+        (**self).to_json_value()
+    }
+}
+
+// The macros pick the conversion with method resolution, which tries `__ViaValue` on `&__Json`
+// before auto-referencing it to find `__ViaSerialize`. This selects `ToJsonValue` when the type
+// implements it, and falls back to serializing otherwise.
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct __Json<'a, T: ?Sized>(pub &'a T);
+
+/// A document given to the assertion macros. Values of types that aren't JSON yet are only
+/// serialized once they're known not to be nested too deeply.
+#[doc(hidden)]
+pub struct __Document<'a>(Document<'a>);
+
+enum Document<'a> {
+    Json(Cow<'a, Value>),
+    Serialize(&'a dyn Serializable),
+}
+
+/// A value that can be measured before it's serialized.
+trait Serializable: Measure {
+    fn to_value(&self) -> Value;
+}
+
+impl<T: Serialize + ?Sized> Serializable for T {
+    #[track_caller]
+    fn to_value(&self) -> Value {
+        __to_value(self)
+    }
+}
+
+impl<'a> __Document<'a> {
+    #[track_caller]
+    pub(crate) fn into_json(self) -> Cow<'a, Value> {
+        match self.0 {
+            Document::Json(value) => value,
+            Document::Serialize(value) => Cow::Owned(value.to_value()),
+        }
+    }
+}
+
+impl Measure for __Document<'_> {
+    fn depth(&self, limit: usize) -> usize {
+        match &self.0 {
+            Document::Json(value) => value.depth(limit),
+            Document::Serialize(value) => value.depth(limit),
+        }
+    }
+}
+
+impl fmt::Debug for __Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Document::Json(value) => f.debug_tuple("__Document").field(value).finish(),
+            Document::Serialize(_) => f.debug_tuple("__Document").finish_non_exhaustive(),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub trait __ViaValue {
+    fn __json(&self) -> __Document<'_>;
+}
+
+impl<T: ToJsonValue + ?Sized> __ViaValue for __Json<'_, T> {
+    fn __json(&self) -> __Document<'_> {
+        __Document(Document::Json(self.0.to_json_value()))
+    }
+}
+
+#[doc(hidden)]
+pub trait __ViaSerialize {
+    fn __json(&self) -> __Document<'_>;
+}
+
+impl<T: Serialize + ?Sized> __ViaSerialize for &__Json<'_, T> {
+    fn __json(&self) -> __Document<'_> {
+        __Document(Document::Serialize(&self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // The borrows select the conversion, as in the macros.
+    #[allow(clippy::needless_borrow)]
+    #[test]
+    fn borrows_values() {
+        let value = json!({ "a": [1, 2] });
+        assert!(matches!(
+            (&__Json(&value)).__json().into_json(),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            (&__Json(&&value)).__json().into_json(),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            (&__Json(&Box::new(value.clone()))).__json().into_json(),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            (&__Json(&vec![1, 2])).__json().into_json(),
+            Cow::<Value>::Owned(json!([1, 2]))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::diff::{Difference, Key};
+use crate::render;
+use crate::report::{Output, LHS_COLOR, RHS_COLOR};
+use crate::{CompareMode, Config};
+use serde_json::Value;
+use std::fmt;
+
+/// Failure messages in the [`OutputFormat::Tree`](crate::OutputFormat::Tree) format.
+///
+/// The lhs document is printed with one value per line. Containers holding differences are
+/// expanded, other containers are collapsed, and the differences themselves are shown at their
+/// path with a marker in the first column.
+pub(crate) struct Tree<'a> {
+    pub(crate) document: &'a Value,
+    pub(crate) differences: &'a [Difference],
+    pub(crate) output: &'a Output,
+}
+
+impl fmt::Display for Tree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(config) = self.differences.first().map(Difference::config) else {
+            return Ok(());
+        };
+        let (lhs_label, rhs_label) = match config.compare_mode {
+            CompareMode::Inclusive => ("actual", "expected"),
+            CompareMode::Strict => ("lhs", "rhs"),
+        };
+        write!(
+            f,
+            "tree (~ changed, + only in {}, - only in {}):",
+            lhs_label, rhs_label
+        )?;
+
+        let mut printer = Printer {
+            f,
+            config,
+            differences: self.differences,
+            output: self.output,
+            rhs_label,
+            keys: vec![],
+        };
+        printer.node("", Some(self.document), 0)
+    }
+}
+
+struct Printer<'a, 'f> {
+    f: &'a mut fmt::Formatter<'f>,
+    config: &'a Config,
+    differences: &'a [Difference],
+    output: &'a Output,
+    rhs_label: &'static str,
+    keys: Vec<Key>,
+}
+
+impl Printer<'_, '_> {
+    /// Prints the node at `self.keys`, whose value in the lhs document is `value`, after
+    /// `prefix`.
+    fn node(&mut self, prefix: &str, value: Option<&Value>, depth: usize) -> fmt::Result {
+        let differences = self.differences;
+        let at_node = differences
+            .iter()
+            .filter(|diff| diff.path().keys() == self.keys.as_slice())
+            .collect::<Vec<_>>();
+        if !at_node.is_empty() {
+            for diff in at_node {
+                match (diff.actual(), diff.expected()) {
+                    (Some(lhs), Some(rhs)) => {
+                        let line = format!(
+                            "{}{} ({}: {})",
+                            prefix,
+                            self.compact(lhs),
+                            self.rhs_label,
+                            self.compact(rhs)
+                        );
+                        self.line('~', None, &line, depth)?;
+                    }
+                    (Some(lhs), None) => {
+                        let line = format!("{}{}", prefix, self.compact(lhs));
+                        self.line('+', Some(LHS_COLOR), &line, depth)?;
+                    }
+                    (None, Some(rhs)) => {
+                        let line = format!("{}{}", prefix, self.compact(rhs));
+                        self.line('-', Some(RHS_COLOR), &line, depth)?;
+                    }
+                    (None, None) => {}
+                }
+            }
+            return Ok(());
+        }
+
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let has_nested = differences
+            .iter()
+            .any(|diff| diff.path().keys().starts_with(&self.keys));
+        match value {
+            Value::Array(items) if has_nested => {
+                self.line(' ', None, &format!("{}[", prefix), depth)?;
+                let len = items.len().max(self.child_count());
+                for idx in 0..len {
+                    self.child(Key::Idx(idx), items.get(idx), depth + 1)?;
+                }
+                self.line(' ', None, "]", depth)
+            }
+            Value::Object(fields) if has_nested => {
+                self.line(' ', None, &format!("{}{{", prefix), depth)?;
+                for (field, value) in fields {
+                    self.child(Key::Field(field.clone()), Some(value), depth + 1)?;
+                }
+                for field in self.missing_fields(value) {
+                    self.child(Key::Field(field), None, depth + 1)?;
+                }
+                self.line(' ', None, "}", depth)
+            }
+            Value::Array(items) if !items.is_empty() => {
+                let line = format!("{}{}", prefix, render::collapsed(value));
+                self.line(' ', None, &line, depth)
+            }
+            Value::Object(fields) if !fields.is_empty() => {
+                let line = format!("{}{}", prefix, render::collapsed(value));
+                self.line(' ', None, &line, depth)
+            }
+            _ => {
+                let line = format!("{}{}", prefix, self.compact(value));
+                self.line(' ', None, &line, depth)
+            }
+        }
+    }
+
+    fn child(&mut self, key: Key, value: Option<&Value>, depth: usize) -> fmt::Result {
+        let prefix = match &key {
+            Key::Field(field) => format!("{}: ", Value::from(field.as_str())),
+            Key::Idx(_) => String::new(),
+        };
+        self.keys.push(key);
+        let result = self.node(&prefix, value, depth);
+        self.keys.pop();
+        result
+    }
+
+    /// Returns the number of items of the array at `self.keys` according to the differences,
+    /// which include items only found in the rhs.
+    fn child_count(&self) -> usize {
+        self.differences
+            .iter()
+            .filter_map(|diff| match diff.path().keys().get(self.keys.len()) {
+                Some(Key::Idx(idx)) if diff.path().keys().starts_with(&self.keys) => Some(idx + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the fields of the object at `self.keys` that only exist in the rhs.
+    fn missing_fields(&self, value: &Value) -> Vec<String> {
+        let mut fields: Vec<String> = vec![];
+        for diff in self.differences {
+            let keys = diff.path().keys();
+            if let Some(Key::Field(field)) = keys.get(self.keys.len()) {
+                if keys.starts_with(&self.keys)
+                    && value.get(field).is_none()
+                    && !fields.contains(field)
+                {
+                    fields.push(field.clone());
+                }
+            }
+        }
+        fields
+    }
+
+    fn compact(&self, value: &Value) -> String {
+        render::compact(value, &self.keys, self.config)
+    }
+
+    fn line(&mut self, marker: char, color: Option<&str>, text: &str, depth: usize) -> fmt::Result {
+        let line = format!("{}   {}{}", marker, "  ".repeat(depth), text);
+        match color {
+            Some(color) => write!(self.f, "\n{}", self.output.paint(&line, color)),
+            None => write!(self.f, "\n{}", line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assert_json_matches_no_panic, CompareMode, Config, OutputFormat};
+    use serde_json::json;
+
+    #[test]
+    fn marks_differences_in_context() {
+        let config = Config::new(CompareMode::Strict).output_format(OutputFormat::Tree);
+        let error = assert_json_matches_no_panic(
+            &json!({ "a": [1, 2, { "b": true }], "c": "x", "d": null, "e": [] }),
+            &json!({ "a": [1, 3], "c": "x", "e": [], "f": { "g": 1 } }),
+            &config,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            r#"tree (~ changed, + only in lhs, - only in rhs):
+    {
+      "a": [
+        1
+~       2 (rhs: 3)
++       {"b": true}
+      ]
+      "c": "x"
++     "d": null
+      "e": []
+-     "f": {"g": 1}
+    }"#
+        );
+    }
+
+    #[test]
+    fn masks_values() {
+        let config = Config::new(CompareMode::Strict)
+            .output_format(OutputFormat::Tree)
+            .mask_output(".token");
+        let error = assert_json_matches_no_panic(
+            &json!({ "token": "a", "id": 1 }),
+            &json!({ "token": "b", "id": 2 }),
+            &config,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            r#"tree (~ changed, + only in lhs, - only in rhs):
+    {
+~     "id": 1 (rhs: 2)
+~     "token": *** (rhs: ***)
+    }"#
+        );
+    }
+
+    #[test]
+    fn changed_root() {
+        let config = Config::new(CompareMode::Strict).output_format(OutputFormat::Tree);
+        let error = assert_json_matches_no_panic(&json!([1]), &json!({}), &config).unwrap_err();
+        assert_eq!(
+            error,
+            "tree (~ changed, + only in lhs, - only in rhs):\n~   [1] (rhs: {})"
+        );
+    }
+}
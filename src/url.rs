@@ -0,0 +1,151 @@
+/// Rewrites an absolute URL into a canonical form, so that equivalent URLs compare equal.
+///
+/// The scheme and host are lowercased, default ports are removed, an empty path becomes `/` and
+/// query parameters are sorted. Percent-encoding is left untouched. Returns `None` if `text`
+/// isn't an absolute URL with an authority, such as `https://example.com/path`.
+pub(crate) fn normalize(text: &str, ignore_trailing_slash: bool) -> Option<String> {
+    let (scheme, rest) = text.split_once("://")?;
+    let mut chars = scheme.chars();
+    if !chars.next()?.is_ascii_alphabetic()
+        || !chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    {
+        return None;
+    }
+    let scheme = scheme.to_ascii_lowercase();
+
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, ""),
+    };
+
+    let (userinfo, host_port) = match authority.rsplit_once('@') {
+        Some((userinfo, host_port)) => (Some(userinfo), host_port),
+        None => (None, authority),
+    };
+    // The port separator must come after the closing bracket of IPv6 hosts.
+    let (host, port) = match host_port.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, Some(port)),
+        _ => (host_port, None),
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let port = match port {
+        None | Some("") => None,
+        Some(port) => match (port.parse::<u16>().ok()?, default_port(&scheme)) {
+            (port, Some(default)) if port == default => None,
+            (port, _) => Some(port),
+        },
+    };
+
+    let mut path = if path.is_empty() { "/" } else { path };
+    if ignore_trailing_slash {
+        path = path.trim_end_matches('/');
+    }
+
+    let mut normalized = format!("{}://", scheme);
+    if let Some(userinfo) = userinfo {
+        normalized.push_str(userinfo);
+        normalized.push('@');
+    }
+    normalized.push_str(&host.to_ascii_lowercase());
+    if let Some(port) = port {
+        normalized.push_str(&format!(":{}", port));
+    }
+    normalized.push_str(path);
+    if let Some(query) = query {
+        let mut params = query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .collect::<Vec<_>>();
+        // Sorting is stable, so repeated parameters keep their relative order.
+        params.sort_by_key(|param| param.split('=').next());
+        normalized.push('?');
+        normalized.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        normalized.push('#');
+        normalized.push_str(fragment);
+    }
+    Some(normalized)
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equivalent_urls() {
+        let canonical = Some("https://example.com/a/b?x=1&y=2#top".to_owned());
+        for url in [
+            "https://example.com/a/b?x=1&y=2#top",
+            "HTTPS://Example.COM/a/b?y=2&x=1#top",
+            "https://example.com:443/a/b?x=1&y=2&#top",
+        ] {
+            assert_eq!(normalize(url, false), canonical, "{}", url);
+        }
+
+        assert_eq!(
+            normalize("http://example.com", false),
+            normalize("http://example.com:80/", false)
+        );
+        assert_eq!(
+            normalize("http://user@[::1]:8080/A?b=2&a=1&b=1", false).as_deref(),
+            Some("http://user@[::1]:8080/A?a=1&b=2&b=1")
+        );
+        assert_ne!(
+            normalize("http://example.com/A", false),
+            normalize("http://example.com/a", false)
+        );
+        assert_ne!(
+            normalize("http://example.com:8080", false),
+            normalize("http://example.com", false)
+        );
+    }
+
+    #[test]
+    fn trailing_slash() {
+        assert_ne!(
+            normalize("http://example.com/a/", false),
+            normalize("http://example.com/a", false)
+        );
+        assert_eq!(
+            normalize("http://example.com/a/", true),
+            normalize("http://example.com/a", true)
+        );
+        assert_eq!(
+            normalize("http://example.com/", true),
+            normalize("http://example.com", true)
+        );
+    }
+
+    #[test]
+    fn invalid_urls() {
+        for text in [
+            "example.com",
+            "/a/b",
+            "1http://a",
+            "http:///a",
+            "http://a:b/",
+        ] {
+            assert_eq!(normalize(text, false), None, "{}", text);
+        }
+    }
+}
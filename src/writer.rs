@@ -0,0 +1,178 @@
+use crate::diff::{diff, Difference};
+use crate::{to_values, Config};
+use serde::Serialize;
+use std::io;
+
+/// Receives the differences found by a comparison one at a time.
+///
+/// Failure messages hold every difference in a single string, which is unwieldy for documents with
+/// thousands of them. A `DiffWriter` gets each difference on its own instead, so a report can be
+/// streamed to a file or collected by a test logger. See [`write_json_diff`].
+///
+/// Implementations are provided for [`String`], for any [`io::Write`] through [`IoDiffWriter`],
+/// and for the output captured by the test harness through [`PrintDiffWriter`].
+pub trait DiffWriter {
+    /// Writes the next difference, in the order the differences were found.
+    fn write_difference(&mut self, difference: &Difference) -> io::Result<()>;
+
+    /// Called once after the last difference, even if there were none. Does nothing by default.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: DiffWriter + ?Sized> DiffWriter for &mut W {
+    fn write_difference(&mut self, difference: &Difference) -> io::Result<()> {
+        (**self).write_difference(difference)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// Appends each difference, separated by an empty line like in failure messages.
+impl DiffWriter for String {
+    fn write_difference(&mut self, difference: &Difference) -> io::Result<()> {
+        if !self.is_empty() {
+            self.push_str("\n\n");
+        }
+        self.push_str(&difference.to_string());
+        Ok(())
+    }
+}
+
+/// Writes differences to an [`io::Write`], such as a file, separated by an empty line.
+///
+/// Nothing is buffered, so wrap slow writers in an [`io::BufWriter`]. The writer is flushed once
+/// the last difference has been written.
+#[derive(Debug)]
+pub struct IoDiffWriter<W> {
+    writer: W,
+    written: bool,
+}
+
+impl<W: io::Write> IoDiffWriter<W> {
+    /// Creates a `DiffWriter` writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            written: false,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: io::Write> DiffWriter for IoDiffWriter<W> {
+    fn write_difference(&mut self, difference: &Difference) -> io::Result<()> {
+        if self.written {
+            writeln!(self.writer)?;
+        }
+        writeln!(self.writer, "{}", difference)?;
+        self.written = true;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Prints each difference to stdout as soon as it's received.
+///
+/// The test harness captures the output of each test and only shows it for failing tests, so this
+/// logs differences without making a test fail.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrintDiffWriter;
+
+impl DiffWriter for PrintDiffWriter {
+    fn write_difference(&mut self, difference: &Difference) -> io::Result<()> {
+        println!("{}\n", difference);
+        Ok(())
+    }
+}
+
+/// Compares two JSON values and passes the differences to `writer` one at a time, instead of
+/// collecting them in a failure message.
+///
+/// Returns the number of differences written. Every difference is written, regardless of
+/// [`Config::max_diffs`].
+///
+/// ```
+/// use serde_json_assert::{write_json_diff, CompareMode, Config, IoDiffWriter};
+/// use serde_json::json;
+///
+/// let mut writer = IoDiffWriter::new(vec![]);
+/// let count = write_json_diff(
+///     &json!({ "a": 1, "b": 2 }),
+///     &json!({ "a": 2, "b": 2 }),
+///     &Config::new(CompareMode::Strict),
+///     &mut writer,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(count, 1);
+/// assert_eq!(
+///     String::from_utf8(writer.into_inner()).unwrap(),
+///     "json atoms at path \".a\" are not equal:\n    lhs:\n        1\n    rhs:\n        2\n"
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `lhs` or `rhs` can't be serialized or is nested too deeply, see
+/// [`Config::max_nesting`].
+#[track_caller]
+pub fn write_json_diff<Lhs, Rhs, W>(
+    lhs: &Lhs,
+    rhs: &Rhs,
+    config: &Config,
+    mut writer: W,
+) -> io::Result<usize>
+where
+    Lhs: Serialize,
+    Rhs: Serialize,
+    W: DiffWriter,
+{
+    let (lhs, rhs) = to_values(lhs, rhs, config).unwrap_or_else(|limit| panic!("{}", limit));
+    let differences = diff(&lhs, &rhs, config);
+    for difference in &differences {
+        writer.write_difference(difference)?;
+    }
+    writer.finish()?;
+    Ok(differences.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompareMode;
+    use serde_json::json;
+
+    #[test]
+    fn writes_differences() {
+        let config = Config::new(CompareMode::Strict);
+        let (lhs, rhs) = (json!({ "a": 1, "b": [2] }), json!({ "a": 2, "c": null }));
+
+        let mut report = String::new();
+        assert_eq!(
+            write_json_diff(&lhs, &rhs, &config, &mut report).unwrap(),
+            3
+        );
+        let message = crate::assert_json_matches_no_panic(&lhs, &rhs, &config).unwrap_err();
+        assert_eq!(report, message);
+
+        let mut writer = IoDiffWriter::new(vec![]);
+        write_json_diff(&lhs, &rhs, &config, &mut writer).unwrap();
+        assert_eq!(writer.into_inner(), format!("{}\n", message).into_bytes());
+
+        assert_eq!(
+            write_json_diff(&lhs, &lhs, &config, PrintDiffWriter).unwrap(),
+            0
+        );
+    }
+}
@@ -1,8 +1,16 @@
 use serde::Serialize;
 use serde_json::json;
 use serde_json_assert::{
-    assert_json_contains, assert_json_eq, assert_json_include, assert_json_matches,
-    assert_json_matches_no_panic, CompareMode, Config, FloatCompareMode, NumericMode,
+    assert_json_any_item, assert_json_contains, assert_json_each, assert_json_eq,
+    assert_json_eq_sorted, assert_json_include, assert_json_matches, assert_json_matches_any,
+    assert_json_matches_no_panic, assert_json_matches_reader, assert_json_matches_reader_no_panic,
+    assert_json_matching_items, assert_json_paths, assert_json_paths_no_panic, check_json,
+    check_json_eq, check_json_include, check_json_matches, check_json_paths,
+    debug_assert_json_contains, debug_assert_json_eq, debug_assert_json_include,
+    debug_assert_json_matches, diff, diff_as_json_patch, diff_symmetric, explain_json,
+    is_json_match, json_partial, json_similarity, merge_json, minimal_failing_subtree,
+    three_way_diff, write_json_diff, ArrayMode, CompareMode, CompiledExpectation, Config,
+    FloatCompareMode, IoDiffWriter, JsonChecks, MergeStrategy, NumericMode, ReaderError,
 };
 
 #[test]
@@ -337,7 +345,7 @@ fn ignore_array_sorting_with_inclusive_comparisons() {
             "c": "foo"
         }
     ]);
-    let config = Config::new(CompareMode::Inclusive).consider_array_sorting(false);
+    let config = Config::new(CompareMode::Inclusive).array_mode(ArrayMode::UnorderedSubset);
     assert_json_matches!(&actual, &expected, &config);
 }
 
@@ -368,7 +376,7 @@ fn can_fail_ignore_array_sorting_with_strict_comparisons() {
             "c": "foo"
         }
     ]);
-    let config = Config::new(CompareMode::Strict).consider_array_sorting(false);
+    let config = Config::new(CompareMode::Strict).array_mode(ArrayMode::UnorderedExact);
     assert_json_matches!(&actual, &expected, &config);
 }
 
@@ -431,7 +439,7 @@ fn assert_json_eq_can_fail_with_message() {
 
 #[test]
 fn assert_json_matches_can_fail_with_message() {
-    let config = Config::new(CompareMode::Strict).consider_array_sorting(false);
+    let config = Config::new(CompareMode::Strict).array_mode(ArrayMode::UnorderedExact);
     let result = std::panic::catch_unwind(|| {
         assert_json_matches!(
             json!({ "a": { "b": true } }),
@@ -449,3 +457,559 @@ fn assert_json_matches_can_fail_with_message() {
     let msg = error.downcast_ref::<String>().unwrap();
     assert!(msg.contains("The 'matches' assert failed because of 'reasons'"));
 }
+
+#[test]
+fn check_json_matches_debug_output_is_readable() {
+    let config = Config::new(CompareMode::Inclusive);
+    assert!(check_json_matches(&json!({ "a": 1, "b": 2 }), &json!({ "b": 2 }), &config).is_ok());
+
+    let result = check_json_matches(&json!({ "a": 1 }), &json!({ "a": 2 }), &config);
+    assert_eq!(
+        format!("{:?}", result),
+        r#"Err(
+json atoms at path ".a" are not equal:
+    expected:
+        2
+    actual:
+        1)"#
+    );
+}
+
+#[track_caller]
+fn assert_user_id(user: serde_json::Value, id: i32) {
+    assert_json_include!(actual: user, expected: json!({ "id": id }));
+}
+
+#[test]
+fn failure_message_includes_caller_location() {
+    let line = line!() + 2;
+    let result = std::panic::catch_unwind(|| {
+        assert_user_id(json!({ "id": 1 }), 2);
+    });
+
+    let error = result.unwrap_err();
+    let msg = error.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.ends_with(&format!("at {}:{}:9", file!(), line)),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn inline_config_settings() {
+    assert_json_matches!(json!({ "a": 1 }), json!({ "a": 1 }), mode = strict);
+    assert_json_matches!(
+        json!({ "a": [3, 1, 2], "b": 1 }),
+        json!({ "a": [1, 2] }),
+        mode = inclusive,
+        arrays = unordered
+    );
+    assert_json_matches!(
+        json!({ "a": 1, "b": 0.30000001 }),
+        json!({ "a": 1.0, "b": 0.3 }),
+        numeric = float,
+        float_epsilon = 1e-6,
+    );
+    const TOLERANCE: f64 = 0.01;
+    assert_json_matches!(
+        json!({ "b": 0.301 }),
+        json!({ "b": 0.3 }),
+        float_epsilon = TOLERANCE / 5.0,
+        mode = inclusive,
+    );
+    assert_json_matches!(
+        json!({ "log": ["created", "paid", "shipped"] }),
+        json!({ "log": ["created", "paid"] }),
+        arrays = prefix,
+    );
+    assert_json_matches!(
+        std::collections::BTreeMap::from([(1, "a"), (10, "b")]),
+        json!({ "1.0": "a", "10.0": "b" }),
+        keys = numeric,
+    );
+
+    let result = std::panic::catch_unwind(|| {
+        assert_json_matches!(
+            json!({ "a": 1, "b": 2 }),
+            json!({ "a": 2, "b": 3 }),
+            mode = inclusive,
+            verbosity = quiet,
+            max_diffs = 1
+        );
+    });
+    let error = result.unwrap_err();
+    let msg = error.downcast_ref::<String>().unwrap();
+    assert!(msg.starts_with(
+        r#"
+json atoms at path ".a" are not equal
+... and 1 more differences"#
+    ));
+}
+
+#[test]
+#[should_panic]
+fn inline_config_defaults_to_strict() {
+    assert_json_matches!(
+        json!({ "a": 1, "b": 2 }),
+        json!({ "a": 1 }),
+        numeric = float
+    );
+}
+
+#[cfg(feature = "raw_value")]
+#[test]
+fn raw_values_on_either_side() {
+    use serde_json::value::RawValue;
+
+    let raw = RawValue::from_string(r#"{ "a": [1, 2], "b": "c" }"#.to_string()).unwrap();
+
+    assert_json_eq!(&raw, json!({ "a": [1, 2], "b": "c" }));
+    assert_json_eq!(json!({ "a": [1, 2], "b": "c" }), &raw);
+    assert_json_include!(actual: &raw, expected: json!({ "a": [1] }));
+
+    let other = RawValue::from_string(r#"{ "a": [1, 3] }"#.to_string()).unwrap();
+    let config = Config::new(CompareMode::Inclusive);
+    assert_eq!(
+        assert_json_matches_no_panic(&raw, &other, &config).unwrap_err(),
+        r#"json atoms at path ".a[1]" are not equal:
+    expected:
+        3
+    actual:
+        2"#
+    );
+}
+
+#[test]
+fn compare_readers() {
+    let config = Config::new(CompareMode::Inclusive);
+    let actual = std::io::Cursor::new(r#"{ "a": 1, "b": [1, 2] }"#);
+    let expected = r#"{ "b": [1] }"#.as_bytes();
+    assert_json_matches_reader(actual, expected, &config);
+
+    let result =
+        assert_json_matches_reader_no_panic(r#"{ "a": 1 }"#.as_bytes(), "[1,".as_bytes(), &config);
+    let error = result.unwrap_err();
+    assert!(matches!(error, ReaderError::ParseRhs(_)));
+    assert!(error
+        .to_string()
+        .starts_with("Couldn't parse right hand side as JSON."));
+
+    struct FailingReader;
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("connection reset"))
+        }
+    }
+    let error =
+        assert_json_matches_reader_no_panic(FailingReader, "{}".as_bytes(), &config).unwrap_err();
+    assert!(matches!(error, ReaderError::ReadLhs(_)));
+    assert_eq!(
+        error.to_string(),
+        "Couldn't read left hand side. IO error: connection reset"
+    );
+
+    let error = assert_json_matches_reader_no_panic("[1]".as_bytes(), "[2]".as_bytes(), &config)
+        .unwrap_err();
+    assert!(matches!(error, ReaderError::Mismatch(_)));
+}
+
+#[test]
+#[should_panic(expected = "Couldn't parse left hand side as JSON.")]
+fn compare_readers_panics() {
+    let config = Config::new(CompareMode::Strict);
+    assert_json_matches_reader("{".as_bytes(), "{}".as_bytes(), &config);
+}
+
+#[test]
+fn assert_paths() {
+    let response = json!({ "data": { "users": [{ "id": 1, "name": "Bob" }] }, "total": 1 });
+    assert_json_paths!(response, {
+        ".data.users[0].id" => 1,
+        "$.data.users[0]" => json!({ "id": 1, "name": "Bob" }),
+        "total" => 1,
+    });
+
+    let result = std::panic::catch_unwind(|| {
+        assert_json_paths!(response, { ".total" => 2, ".data.users[1].id" => 2 });
+    });
+    assert!(result.is_err());
+
+    let error = assert_json_paths_no_panic(
+        &response,
+        [(".total", json!(2)), (".data.users[1].id", json!(2))],
+        &Config::new(CompareMode::Strict),
+    )
+    .unwrap_err();
+    assert_eq!(
+        error,
+        r#"json atoms at path ".total" are not equal:
+    lhs:
+        1
+    rhs:
+        2
+
+json atom at path ".data.users[1].id" is missing from lhs"#
+    );
+}
+
+#[test]
+fn partial_expectations() {
+    let actual = json!({
+        "id": 1,
+        "user": { "name": "Bob", "age": 30, "tags": ["a", "b", "c"] },
+        "items": [{ "id": 1, "extra": true }, { "id": 2 }],
+    });
+    let name = "Bob";
+
+    assert_json_eq!(
+        actual,
+        json_partial!({
+            "id": 1,
+            "user": { "name": name, "tags": ["a", ..], .. },
+            "items": [{ "id": 1, .. }, ..],
+        })
+    );
+
+    // Nodes without `..` stay strict.
+    let result = assert_json_matches_no_panic(
+        &actual,
+        &json_partial!({ "user": { "name": "Bob", "tags": ["a", ..] }, .. }),
+        &Config::new(CompareMode::Strict),
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        r#"json atom at path ".user.age" is missing from rhs"#
+    );
+
+    assert_eq!(
+        json_partial!([1, -2, { "a": [] }]),
+        json!([1, -2, { "a": [] }])
+    );
+    assert_eq!(json_partial!({}), json!({}));
+    assert_eq!(json_partial!("x"), json!("x"));
+}
+
+#[test]
+fn masked_output() {
+    let config = Config::new(CompareMode::Strict)
+        .mask_output(".credentials")
+        .mask_output(".users[*].email");
+
+    let result = assert_json_matches_no_panic(
+        &json!({ "credentials": { "token": "a" }, "users": [{ "email": "x@example.com" }] }),
+        &json!({ "credentials": { "token": "b" }, "users": [] }),
+        &config,
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        r#"json atoms at path ".credentials.token" are not equal:
+    lhs:
+        ***
+    rhs:
+        ***
+
+json atom at path ".users[0]" is missing from rhs"#
+    );
+
+    let result = assert_json_matches_no_panic(
+        &json!({ "users": [{ "email": "x@example.com", "id": 1 }] }),
+        &json!({ "users": 1 }),
+        &config,
+    );
+    assert!(result.unwrap_err().contains(r#""email": ***"#));
+}
+
+#[test]
+fn debug_assertions() {
+    debug_assert_json_eq!(json!({ "a": 1 }), json!({ "a": 1 }));
+    debug_assert_json_include!(actual: json!({ "a": 1, "b": 2 }), expected: json!({ "a": 1 }));
+    debug_assert_json_contains!(container: json!([1, 2]), contained: json!([2]));
+    debug_assert_json_matches!(json!([2, 1]), json!([1, 2]), arrays = unordered);
+
+    let result = std::panic::catch_unwind(|| {
+        debug_assert_json_eq!(json!({ "a": 1 }), json!({ "a": 2 }), "invariant broken");
+    });
+    assert_eq!(result.is_err(), cfg!(debug_assertions));
+}
+
+#[test]
+fn sorted_arrays() {
+    assert_json_eq_sorted!(
+        json!([[2, 1], { "a": [true, false] }]),
+        json!([{ "a": [false, true] }, [1, 2]])
+    );
+
+    let result = std::panic::catch_unwind(|| {
+        assert_json_eq_sorted!(json!({ "ids": [3, 1] }), json!({ "ids": [1, 2] }));
+    });
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with(
+        r#"
+json atoms at path ".ids[1]" are not equal:
+    lhs:
+        3
+    rhs:
+        2"#
+    ));
+}
+
+#[test]
+fn any_alternative() {
+    assert_json_matches_any!(json!({ "a": 2 }), [json!({ "a": 1 }), json!({ "a": 2 })]);
+
+    let result = std::panic::catch_unwind(|| {
+        assert_json_matches_any!(
+            json!({ "id": 1, "tags": ["x"] }),
+            [
+                json!({ "id": 2, "name": "x" }),
+                json!({ "id": 1, "tags": ["y"] })
+            ],
+            &Config::new(CompareMode::Inclusive)
+        );
+    });
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with(
+        r#"
+json value doesn't match any of 2 alternatives, the closest one is alternative 2:
+
+json atoms at path ".tags[0]" are not equal:"#
+    ));
+}
+
+#[test]
+fn each_item() {
+    assert_json_each!(json!([]), json!({ "a": 1 }));
+    assert_json_each!(
+        json!([{ "a": 1, "b": 2 }, { "a": 1.0 }]),
+        json!({ "a": 1 }),
+        &Config::new(CompareMode::Inclusive).numeric_mode(NumericMode::AssumeFloat)
+    );
+
+    let result = std::panic::catch_unwind(|| {
+        assert_json_each!(
+            json!([{ "a": 1 }, { "a": 1, "b": 2 }, [1]]),
+            json!({ "a": 1 }),
+            &Config::new(CompareMode::Strict)
+        );
+    });
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with(
+        r#"
+2 of 3 json array items don't match the expected value:
+
+item [1]:
+    json atom at path ".b" is missing from rhs
+
+item [2]:
+    json atoms at path "(root)" are not equal:"#
+    ));
+
+    let body = json!({ "items": [{ "status": "active", "id": 1 }, { "status": "active" }] });
+    assert_json_each!(actual: body["items"], template: json!({ "status": "active" }));
+    assert_json_each!(
+        actual: body["items"],
+        template: json!({ "status": "active" }),
+        config: &Config::new(CompareMode::Inclusive),
+    );
+    let result = std::panic::catch_unwind(|| {
+        assert_json_each!(
+            actual: body["items"],
+            template: json!({ "status": "active" }),
+            config: &Config::new(CompareMode::Strict),
+        )
+    });
+    assert!(result.is_err());
+
+    let result = std::panic::catch_unwind(|| assert_json_each!(json!({ "a": 1 }), json!(1)));
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with("\njson value isn't an array"));
+}
+
+#[test]
+fn any_item() {
+    let actual = json!({ "a": [[1, 2], [3, 4]], "b": [{ "c": [{ "d": 1 }, { "d": 2, "e": 3 }] }] });
+    assert_json_any_item!(actual, "$.a", json!([3, 4]));
+    assert_json_any_item!(actual, ".b[0].c", json!({ "e": 3 }));
+    assert_json_any_item!(
+        actual,
+        ".a[1]",
+        json!(4.0),
+        &Config::new(CompareMode::Strict).numeric_mode(NumericMode::AssumeFloat)
+    );
+
+    let messages = [".a[0][0]", ".b", ".b[0].c"].map(|path| {
+        let result = std::panic::catch_unwind(|| {
+            assert_json_any_item!(actual, path, json!({ "d": 2, "e": 4 }));
+        });
+        *result.unwrap_err().downcast::<String>().unwrap()
+    });
+    assert!(messages[0].starts_with("\njson value has no array at path \".a[0][0]\""));
+    assert!(messages[1].starts_with(
+        r#"
+none of the 1 json array items at path ".b" match the expected value, the closest one is item [0]:"#
+    ));
+    assert!(messages[2].starts_with(
+        r#"
+none of the 2 json array items at path ".b[0].c" match the expected value, the closest one is item [1]:
+
+json atoms at path ".b[0].c[1].e" are not equal:"#
+    ));
+}
+
+#[test]
+fn matching_items() {
+    let actual = json!({ "a": [{ "b": 1 }, { "b": 2 }, { "b": 1, "c": 0 }] });
+    assert_json_matching_items!(actual, ".a", json!({ "b": 1 }), exactly = 2);
+    assert_json_matching_items!(actual, ".a", json!({ "b": 3 }), exactly = 0);
+    assert_json_matching_items!(
+        actual,
+        ".a",
+        json!({ "b": 1 }),
+        at_most = 1,
+        &Config::new(CompareMode::Strict)
+    );
+
+    let result = std::panic::catch_unwind(|| {
+        assert_json_matching_items!(actual, ".a", json!({ "b": 3 }), at_least = 1);
+    });
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with(
+        r#"
+expected at least 1 of the 3 json array items at path ".a" to match the expected value, but 0 match
+"#
+    ));
+}
+
+#[test]
+fn soft_checks() {
+    let mut checks = JsonChecks::new();
+    assert!(check_json_eq!(checks, json!([1]), json!([1])));
+    assert!(check_json_include!(checks, expected: json!({}), actual: json!({ "a": 1 })));
+    assert!(check_json!(
+        checks,
+        json!(1.0),
+        json!(1),
+        &Config::new(CompareMode::Strict).numeric_mode(NumericMode::AssumeFloat)
+    ));
+    assert!(
+        !check_json_include!(checks, actual: json!({}), expected: json!({ "a": 1 }), "user {}", 7)
+    );
+    assert!(!check_json_eq!(checks, json!(1), json!(2)));
+
+    let failures = checks.failures().collect::<Vec<_>>();
+    assert_eq!(failures.len(), 2);
+    assert!(failures[0].starts_with("user 7\n\njson atom at path \".a\" is missing from actual"));
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| checks.finalize()));
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with("\n2 of 5 json checks failed:\n\nat tests/integration_test.rs:"));
+}
+
+#[derive(Serialize)]
+struct Nested {
+    child: Option<Box<Nested>>,
+}
+
+#[test]
+fn rejects_documents_nested_too_deeply_before_walking_them() {
+    let mut deep = serde_json::Value::Null;
+    let mut nested = Nested { child: None };
+    for _ in 0..200_000 {
+        deep = serde_json::Value::Array(vec![deep]);
+        nested = Nested {
+            child: Some(Box::new(nested)),
+        };
+    }
+    let shallow = json!([1]);
+    let config = Config::new(CompareMode::Strict);
+    let exceeded = |message: &str| message.contains("nested more than 512 levels deep");
+
+    assert!(exceeded(
+        &assert_json_matches_no_panic(&deep, &shallow, &config).unwrap_err()
+    ));
+    assert!(exceeded(
+        &assert_json_matches_no_panic(&shallow, &nested, &config).unwrap_err()
+    ));
+    assert!(exceeded(
+        &diff(&deep, &shallow, &config).unwrap_err().to_string()
+    ));
+    assert!(exceeded(
+        &check_json_matches(&deep, &shallow, &config)
+            .unwrap_err()
+            .to_string()
+    ));
+    assert!(exceeded(
+        &check_json_paths(&deep, [("[0]", &shallow)], &config)
+            .unwrap_err()
+            .to_string()
+    ));
+    assert!(exceeded(
+        &check_json_paths(&shallow, [("[0]", &nested)], &config)
+            .unwrap_err()
+            .to_string()
+    ));
+    assert!(exceeded(
+        &CompiledExpectation::new(&shallow, &config)
+            .diff(&deep)
+            .unwrap_err()
+            .to_string()
+    ));
+    assert!(exceeded(
+        &merge_json(&shallow, &deep, &shallow, MergeStrategy::OursWins).unwrap_err()
+    ));
+
+    let panicking: [(&str, &dyn Fn()); 11] = [
+        ("assert_json_eq!", &|| assert_json_eq!(deep, shallow)),
+        ("assert_json_eq! on a struct", &|| {
+            assert_json_eq!(nested, shallow)
+        }),
+        ("is_json_match", &|| {
+            is_json_match(&deep, &shallow, &config);
+        }),
+        ("explain_json", &|| {
+            explain_json(&deep, &shallow, &config);
+        }),
+        ("diff_symmetric", &|| {
+            diff_symmetric(&deep, &shallow, &config);
+        }),
+        ("diff_as_json_patch", &|| {
+            diff_as_json_patch(&deep, &shallow, &config);
+        }),
+        ("json_similarity", &|| {
+            json_similarity(&deep, &shallow, &config);
+        }),
+        ("minimal_failing_subtree", &|| {
+            minimal_failing_subtree(&deep, &shallow, &config);
+        }),
+        ("three_way_diff", &|| {
+            three_way_diff(&shallow, &deep, &shallow, &config);
+        }),
+        ("write_json_diff", &|| {
+            let _ = write_json_diff(&deep, &shallow, &config, IoDiffWriter::new(vec![]));
+        }),
+        ("CompiledExpectation::new", &|| {
+            CompiledExpectation::new(&nested, &config);
+        }),
+    ];
+    for (name, check) in panicking {
+        let error = std::panic::catch_unwind(std::panic::AssertUnwindSafe(check)).unwrap_err();
+        assert!(
+            exceeded(error.downcast_ref::<String>().unwrap()),
+            "{}",
+            name
+        );
+    }
+
+    // Dropping the documents recursively would overflow the stack.
+    let mut values = vec![deep];
+    while let Some(value) = values.pop() {
+        if let serde_json::Value::Array(items) = value {
+            values.extend(items);
+        }
+    }
+    let mut child = nested.child.take();
+    while let Some(mut nested) = child {
+        child = nested.child.take();
+    }
+}